@@ -0,0 +1,79 @@
+// Copyright © 2022, Electron Labs
+
+//! Two-lane field multiplication and the BN254 public input MSM against
+//! arkworks.
+//!
+//! ```sh
+//! cargo bench --bench simd --features simd
+//! ```
+
+use ark_bn254::{Fq, Fr, G1Affine, G1Projective};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::PrimeField;
+use ark_std::UniformRand;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use electron_rs::verifier::core::simd;
+
+const INPUT_COUNTS: [usize; 4] = [1, 8, 32, 128];
+
+fn bench_mul(c: &mut Criterion) {
+    let mut rng = ark_std::test_rng();
+    let mut group = c.benchmark_group("simd/mul");
+
+    let a = [Fq::rand(&mut rng), Fq::rand(&mut rng)];
+    let b = [Fq::rand(&mut rng), Fq::rand(&mut rng)];
+    group.bench_function("fq/arkworks", |bench| {
+        bench.iter(|| [black_box(a[0]) * b[0], black_box(a[1]) * b[1]])
+    });
+    group.bench_function("fq/mul2", |bench| {
+        bench.iter(|| simd::mul2(black_box(a), b))
+    });
+
+    let a = [Fr::rand(&mut rng), Fr::rand(&mut rng)];
+    let b = [Fr::rand(&mut rng), Fr::rand(&mut rng)];
+    group.bench_function("fr/arkworks", |bench| {
+        bench.iter(|| [black_box(a[0]) * b[0], black_box(a[1]) * b[1]])
+    });
+    group.bench_function("fr/mul2", |bench| {
+        bench.iter(|| simd::mul2(black_box(a), b))
+    });
+
+    group.finish();
+}
+
+fn arkworks_prepare_inputs(gamma_abc_g1: &[G1Affine], pub_inputs: &[Fr]) -> G1Projective {
+    let mut prepared_inputs = gamma_abc_g1[0].into_projective();
+    for (input, base) in pub_inputs.iter().zip(&gamma_abc_g1[1..]) {
+        prepared_inputs += &base.mul(input.into_repr());
+    }
+    prepared_inputs
+}
+
+fn bench_prepare_inputs(c: &mut Criterion) {
+    let mut rng = ark_std::test_rng();
+    let mut group = c.benchmark_group("simd/prepare_inputs");
+    group.sample_size(10);
+
+    for &num_inputs in INPUT_COUNTS.iter() {
+        let gamma_abc_g1: Vec<G1Affine> = (0..=num_inputs)
+            .map(|_| G1Projective::rand(&mut rng).into_affine())
+            .collect();
+        let pub_inputs: Vec<Fr> = (0..num_inputs).map(|_| Fr::rand(&mut rng)).collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("arkworks", num_inputs),
+            &pub_inputs,
+            |b, pub_inputs| b.iter(|| arkworks_prepare_inputs(&gamma_abc_g1, pub_inputs)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("simd", num_inputs),
+            &pub_inputs,
+            |b, pub_inputs| b.iter(|| simd::prepare_inputs(&gamma_abc_g1, pub_inputs)),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_mul, bench_prepare_inputs);
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+// Copyright © 2022, Electron Labs
+
+//! Key preparation, storage size and verification cost by number of public
+//! inputs. Keys and proofs are generated natively before measuring.
+//!
+//! ```sh
+//! cargo bench --bench verifier
+//! ```
+
+use borsh::BorshSerialize;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use electron_rs::verifier::near::{
+    get_prepared_verifying_key, parse_verification_key, proof_to_borsh, verify_proof,
+    verify_proof_borsh,
+};
+use electron_testkit::wide::Wide;
+
+const INPUT_COUNTS: [usize; 5] = [1, 8, 32, 128, 512];
+
+fn bench_verifier(c: &mut Criterion) {
+    let mut group = c.benchmark_group("groth16");
+    group.sample_size(10);
+
+    for &num_inputs in INPUT_COUNTS.iter() {
+        let mut wide = Wide::setup(num_inputs);
+        let vkey_str = wide.vkey_json();
+        let (proof_str, inputs_str) = wide.prove();
+
+        let pvk =
+            get_prepared_verifying_key(parse_verification_key(vkey_str.clone()).unwrap()).unwrap();
+        println!(
+            "groth16/storage/{}: prepared key {} bytes",
+            num_inputs,
+            pvk.try_to_vec().unwrap().len()
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("prepare_key", num_inputs),
+            &vkey_str,
+            |b, vkey_str| {
+                b.iter(|| {
+                    get_prepared_verifying_key(parse_verification_key(vkey_str.clone()).unwrap())
+                        .unwrap()
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("verify_json", num_inputs),
+            &(proof_str.clone(), inputs_str.clone()),
+            |b, (proof_str, inputs_str)| {
+                b.iter(|| verify_proof(pvk.clone(), proof_str.clone(), inputs_str.clone()).unwrap())
+            },
+        );
+
+        let borsh_input = proof_to_borsh(proof_str, inputs_str).unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("verify_borsh", num_inputs),
+            &borsh_input,
+            |b, (proof, inputs)| {
+                b.iter(|| verify_proof_borsh(pvk.clone(), proof.clone(), inputs.clone()).unwrap())
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_verifier);
+criterion_main!(benches);
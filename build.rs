@@ -0,0 +1,19 @@
+// Copyright © 2022, Electron Labs
+
+//! With the `ffi` feature, generates the C header of `ffi` into `OUT_DIR`,
+//! so that a build fails when cbindgen can't read `src/ffi.rs`. The header
+//! in `include/` is written by `cargo xtask ffi-header`.
+
+fn main() {
+    #[cfg(feature = "ffi")]
+    write_ffi_header();
+}
+
+#[cfg(feature = "ffi")]
+fn write_ffi_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    cbindgen::generate(&crate_dir)
+        .expect("Unable to generate the C header")
+        .write_to_file(std::path::Path::new(&out_dir).join("electron_rs.h"));
+}
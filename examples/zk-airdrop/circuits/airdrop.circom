@@ -0,0 +1,51 @@
+pragma circom 2.0.0;
+
+include "circomlib/circuits/poseidon.circom";
+include "circomlib/circuits/mux1.circom";
+
+// Proves that Poseidon(secret) is a leaf of the Merkle tree with root
+// `root`, without revealing which leaf, and exposes Poseidon(secret, 1) as
+// the nullifier. `recipient` is only bound to the proof, so that a claim
+// seen in the mempool can't be resubmitted for another account.
+//
+// Public inputs, in order: root, nullifier, recipient.
+template Airdrop(levels) {
+    signal input root;
+    signal input nullifier;
+    signal input recipient;
+    signal input secret;
+    signal input pathElements[levels];
+    signal input pathIndices[levels];
+
+    component leaf = Poseidon(1);
+    leaf.inputs[0] <== secret;
+
+    component nullifierHash = Poseidon(2);
+    nullifierHash.inputs[0] <== secret;
+    nullifierHash.inputs[1] <== 1;
+    nullifier === nullifierHash.out;
+
+    component muxes[levels];
+    component hashers[levels];
+    signal hashes[levels + 1];
+    hashes[0] <== leaf.out;
+    for (var i = 0; i < levels; i++) {
+        pathIndices[i] * (1 - pathIndices[i]) === 0;
+        muxes[i] = MultiMux1(2);
+        muxes[i].c[0][0] <== hashes[i];
+        muxes[i].c[0][1] <== pathElements[i];
+        muxes[i].c[1][0] <== pathElements[i];
+        muxes[i].c[1][1] <== hashes[i];
+        muxes[i].s <== pathIndices[i];
+        hashers[i] = Poseidon(2);
+        hashers[i].inputs[0] <== muxes[i].out[0];
+        hashers[i].inputs[1] <== muxes[i].out[1];
+        hashes[i + 1] <== hashers[i].out;
+    }
+    root === hashes[levels];
+
+    signal recipientSquare;
+    recipientSquare <== recipient * recipient;
+}
+
+component main {public [root, nullifier, recipient]} = Airdrop(20);
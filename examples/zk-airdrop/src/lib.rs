@@ -0,0 +1,241 @@
+// Copyright © 2022, Electron Labs
+
+//! A zk-gated airdrop on NEAR.
+//!
+//! The owner publishes the root of a Merkle tree of eligible secrets. An
+//! account claims by proving, with `circuits/airdrop.circom`, that it knows
+//! a secret in the tree, without revealing which. The proof exposes a
+//! nullifier derived from the secret, so each secret claims once, and is
+//! bound to the claiming account, so a claim can't be front-run.
+//!
+//! The contract keeps the key in a `VkeyRegistry`, spends nullifiers with a
+//! `NullifierSet`, and logs NEP-297 events for indexers.
+
+use ark_ff::PrimeField;
+use electron_rs::components::nullifier::NullifierSet;
+use electron_rs::components::registry::VkeyRegistry;
+use electron_rs::contract::unwrap_or_panic;
+use electron_rs::hash::limbs::LimbLayout;
+use electron_rs::schema::InputSchema;
+use electron_rs::verifier::near::multi::log_verification;
+use electron_rs::verifier::near::{fr_from_str, fr_to_string};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, Balance, PanicOnDefault, Promise};
+use sha2::{Digest, Sha256};
+
+/// Registry id of the claim circuit.
+pub const CIRCUIT_ID: &str = "airdrop";
+
+/// Public inputs of `circuits/airdrop.circom`, in circuit order.
+pub struct ClaimInputs {
+    pub merkle_root: String,
+    pub nullifier: String,
+    pub recipient: String,
+}
+
+impl ClaimInputs {
+    pub const NULLIFIER_INDEX: usize = 1;
+}
+
+impl InputSchema for ClaimInputs {
+    const LEN: usize = 3;
+
+    fn write_inputs(&self, out: &mut Vec<String>) {
+        out.push(self.merkle_root.clone());
+        out.push(self.nullifier.clone());
+        out.push(self.recipient.clone());
+    }
+
+    fn input_name(index: usize) -> Option<&'static str> {
+        ["merkle_root", "nullifier", "recipient"]
+            .get(index)
+            .copied()
+    }
+}
+
+/// The `recipient` input of a claim by `account`: the sha256 of the account
+/// id reduced into the field, as a decimal. Provers compute it the same way.
+pub fn recipient_input(account: &AccountId) -> String {
+    let digest = Sha256::digest(account.as_str().as_bytes());
+    fr_to_string(ark_bn254::Fr::from_be_bytes_mod_order(&digest))
+}
+
+#[near_bindgen]
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
+pub struct Airdrop {
+    owner: AccountId,
+    merkle_root: String,
+    amount: Balance,
+    registry: VkeyRegistry,
+    nullifiers: NullifierSet,
+}
+
+#[near_bindgen]
+impl Airdrop {
+    /// Registers the verification key of the claim circuit. Each claim
+    /// transfers `amount` yoctoNEAR, paid from the contract balance.
+    #[init]
+    pub fn new(owner_id: AccountId, vkey: String, merkle_root: String, amount: U128) -> Self {
+        let mut registry = VkeyRegistry::new(b"r".to_vec());
+        unwrap_or_panic(registry.register_json(CIRCUIT_ID, vkey, LimbLayout::default()));
+        let mut airdrop = Airdrop {
+            owner: owner_id,
+            merkle_root: String::new(),
+            amount: amount.0,
+            registry,
+            nullifiers: NullifierSet::new(b"n".to_vec()),
+        };
+        airdrop.store_merkle_root(merkle_root);
+        airdrop
+    }
+
+    /// Replaces the root, e.g. to add eligible secrets. Owner only.
+    pub fn set_merkle_root(&mut self, merkle_root: String) {
+        if env::predecessor_account_id() != self.owner {
+            env::panic_str("Only the owner can set the merkle root");
+        }
+        self.store_merkle_root(merkle_root);
+    }
+
+    /// Verifies a claim of the predecessor account and transfers the
+    /// airdrop to it. Panics if the nullifier was spent or the proof
+    /// doesn't verify.
+    pub fn claim(&mut self, proof: String, nullifier: String) -> Promise {
+        let account = env::predecessor_account_id();
+        let inputs = ClaimInputs {
+            merkle_root: self.merkle_root.clone(),
+            nullifier: nullifier.clone(),
+            recipient: recipient_input(&account),
+        };
+        let entry = self.registry.get(CIRCUIT_ID).unwrap();
+        let verified = unwrap_or_panic(inputs.to_public_inputs_json().and_then(|inputs_json| {
+            self.nullifiers.verify_and_consume(
+                entry.pvk.clone(),
+                proof,
+                inputs_json,
+                ClaimInputs::NULLIFIER_INDEX,
+            )
+        }));
+        log_verification(CIRCUIT_ID, &entry.pvk, verified);
+        if !verified {
+            env::panic_str("Proof does not verify");
+        }
+
+        env::log_str(&format!(
+            "EVENT_JSON:{{\"standard\":\"zk-airdrop\",\"version\":\"1.0.0\",\"event\":\"claim\",\"data\":[{{\"account_id\":\"{}\",\"nullifier\":\"{}\",\"amount\":\"{}\"}}]}}",
+            account, nullifier, self.amount
+        ));
+        Promise::new(account).transfer(self.amount)
+    }
+
+    pub fn is_claimed(&self, nullifier: String) -> bool {
+        let nullifier = unwrap_or_panic(fr_from_str(nullifier).map_err(Into::into));
+        self.nullifiers.is_spent(&nullifier)
+    }
+
+    pub fn merkle_root(&self) -> String {
+        self.merkle_root.clone()
+    }
+
+    pub fn amount(&self) -> U128 {
+        U128(self.amount)
+    }
+}
+
+impl Airdrop {
+    /// Stores the root as a canonical decimal, so hex roots work too.
+    fn store_merkle_root(&mut self, merkle_root: String) {
+        self.merkle_root = fr_to_string(unwrap_or_panic(
+            fr_from_str(merkle_root).map_err(Into::into),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use electron_testkit::wide::Wide;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    const ROOT: &str = "12345";
+    const NULLIFIER: &str = "678";
+
+    fn context(account: &str) -> AccountId {
+        let account: AccountId = account.parse().unwrap();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(account.clone())
+            .build());
+        account
+    }
+
+    /// A proof of a claim by `account`. `Wide` stands in for the claim
+    /// circuit: it has the same three public inputs, which is all the
+    /// contract sees.
+    fn claim_proof(wide: &mut Wide, account: &AccountId) -> String {
+        let inputs = ClaimInputs {
+            merkle_root: ROOT.to_string(),
+            nullifier: NULLIFIER.to_string(),
+            recipient: recipient_input(account),
+        };
+        let (proof, _) = wide.prove_inputs(&inputs.to_field_elements().unwrap());
+        proof
+    }
+
+    fn airdrop(wide: &Wide) -> Airdrop {
+        Airdrop::new(
+            "owner.near".parse().unwrap(),
+            wide.vkey_json(),
+            ROOT.to_string(),
+            U128(10),
+        )
+    }
+
+    #[test]
+    fn test_claim() {
+        let mut wide = Wide::setup(ClaimInputs::LEN);
+        let alice = context("alice.near");
+        let mut contract = airdrop(&wide);
+        let proof = claim_proof(&mut wide, &alice);
+
+        assert!(!contract.is_claimed(NULLIFIER.to_string()));
+        contract.claim(proof.clone(), NULLIFIER.to_string());
+        assert!(contract.is_claimed(NULLIFIER.to_string()));
+        let logs = get_logs();
+        assert!(logs[0].contains("\"event\":\"proof_verified\""));
+        assert!(logs[1].contains("\"account_id\":\"alice.near\",\"nullifier\":\"678\""));
+
+        let again = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            contract.claim(proof, NULLIFIER.to_string())
+        }));
+        assert!(again.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Proof does not verify")]
+    fn test_claim_is_bound_to_account() {
+        let mut wide = Wide::setup(ClaimInputs::LEN);
+        let alice = context("alice.near");
+        let mut contract = airdrop(&wide);
+        let proof = claim_proof(&mut wide, &alice);
+
+        context("mallory.near");
+        contract.claim(proof, NULLIFIER.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Proof does not verify")]
+    fn test_claim_against_old_root() {
+        let mut wide = Wide::setup(ClaimInputs::LEN);
+        let alice = context("alice.near");
+        let mut contract = airdrop(&wide);
+        let proof = claim_proof(&mut wide, &alice);
+
+        context("owner.near");
+        contract.set_merkle_root("0x1f".to_string());
+        assert_eq!(contract.merkle_root(), "31");
+        context("alice.near");
+        contract.claim(proof, NULLIFIER.to_string());
+    }
+}
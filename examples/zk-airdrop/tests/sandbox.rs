@@ -0,0 +1,78 @@
+// Copyright © 2022, Electron Labs
+
+//! The airdrop deployed to a NEAR sandbox. Build the contract first:
+//!
+//! ```sh
+//! cargo build -p zk-airdrop --target wasm32-unknown-unknown --release
+//! cargo test -p zk-airdrop -- --ignored
+//! ```
+
+use electron_rs::schema::InputSchema;
+use electron_testkit::wide::Wide;
+use near_workspaces::types::NearToken;
+use serde_json::json;
+use zk_airdrop::{recipient_input, ClaimInputs};
+
+const WASM: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../target/wasm32-unknown-unknown/release/zk_airdrop.wasm"
+);
+
+#[tokio::test]
+#[ignore = "needs the contract built for wasm32 and a NEAR sandbox"]
+async fn test_claim_in_sandbox() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker.dev_deploy(&std::fs::read(WASM)?).await?;
+    let owner = worker.dev_create_account().await?;
+    let alice = worker.dev_create_account().await?;
+
+    let mut wide = Wide::setup(ClaimInputs::LEN);
+    contract
+        .call("new")
+        .args_json(json!({
+            "owner_id": owner.id(),
+            "vkey": wide.vkey_json(),
+            "merkle_root": "12345",
+            "amount": NearToken::from_near(1).as_yoctonear().to_string(),
+        }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let inputs = ClaimInputs {
+        merkle_root: "12345".to_string(),
+        nullifier: "678".to_string(),
+        recipient: recipient_input(&alice.id().as_str().parse()?),
+    };
+    let (proof, _) = wide.prove_inputs(&inputs.to_field_elements()?);
+
+    let before = alice.view_account().await?.balance;
+    let claim = alice
+        .call(contract.id(), "claim")
+        .args_json(json!({ "proof": proof, "nullifier": "678" }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(claim.is_success(), "{:?}", claim);
+    assert!(claim
+        .logs()
+        .iter()
+        .any(|log| log.contains("\"event\":\"claim\"")));
+    assert!(alice.view_account().await?.balance > before);
+
+    let claimed: bool = contract
+        .view("is_claimed")
+        .args_json(json!({ "nullifier": "678" }))
+        .await?
+        .json()?;
+    assert!(claimed);
+
+    let again = alice
+        .call(contract.id(), "claim")
+        .args_json(json!({ "proof": proof, "nullifier": "678" }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(again.is_failure());
+    Ok(())
+}
@@ -0,0 +1,55 @@
+#ifndef ELECTRON_RS_H
+#define ELECTRON_RS_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Prepares verification key json. Returns the borsh encoding of the
+// prepared key and writes its length to `out_len`, or returns null on
+// error. Free the buffer with `electron_free_buffer`.
+//
+// # Safety
+//
+// `vkey_json` must be a nul-terminated string and `out_len` must be null
+// or valid for writes.
+uint8_t *electron_prepare_vkey(const char *vkey_json, size_t *out_len);
+
+// Verifies circom proof and public inputs json against a key from
+// `electron_prepare_vkey`. Returns 1 if the proof verifies, 0 if it
+// doesn't and -1 on error.
+//
+// # Safety
+//
+// `pvk` must point to `pvk_len` readable bytes, and `proof_json` and
+// `inputs_json` must be nul-terminated strings.
+int electron_verify_proof(const uint8_t *pvk,
+                          size_t pvk_len,
+                          const char *proof_json,
+                          const char *inputs_json);
+
+// Frees a buffer returned by `electron_prepare_vkey`.
+//
+// # Safety
+//
+// `buf` must come from `electron_prepare_vkey`, with the length it
+// returned, and must not be used afterwards.
+void electron_free_buffer(uint8_t *buf, size_t len);
+
+// Returns the message of the last error on this thread, or null if there
+// was none. Free it with `electron_free_string`.
+char *electron_last_error(void);
+
+// Frees a string returned by `electron_last_error`.
+//
+// # Safety
+//
+// `s` must come from `electron_last_error` and must not be used
+// afterwards.
+void electron_free_string(char *s);
+
+#endif /* ELECTRON_RS_H */
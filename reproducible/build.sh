@@ -0,0 +1,18 @@
+#!/usr/bin/env bash
+# Copyright © 2022, Electron Labs
+#
+# Usage: build.sh <contract-dir>
+# Builds the contract wasm inside the pinned image and prints its sha256.
+set -euo pipefail
+
+CONTRACT_DIR=$(cd "${1:-.}" && pwd)
+IMAGE=electron-rs-reproducible
+
+docker build -t "$IMAGE" "$(dirname "$0")"
+docker run --rm \
+    -v "$CONTRACT_DIR":/contract \
+    -v "$CONTRACT_DIR/target/reproducible":/target \
+    "$IMAGE"
+
+find "$CONTRACT_DIR/target/reproducible/wasm32-unknown-unknown/release" \
+    -maxdepth 1 -name '*.wasm' -exec sha256sum {} \;
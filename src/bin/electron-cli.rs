@@ -0,0 +1,147 @@
+// Copyright © 2022, Electron Labs
+
+//! Command line tools for preparing contract calls, built with the `cli`
+//! feature.
+//!
+//! `vkey prepare <file>` prints the borsh-serialized `PreparedVerifyingKey`
+//! of a snarkjs verification key as base64, the encoding `near call` takes
+//! for `Base64VecU8` arguments, such as a key setter taking the prepared
+//! key rather than the json:
+//!
+//! ```sh
+//! near call $CONTRACT set_prepared_key "{\"key\": \"$(electron-cli vkey prepare verification_key.json)\"}" --accountId $OWNER
+//! ```
+//!
+//! `verify --vkey <file> --proof <file> --public <file>` verifies snarkjs
+//! files with the code contracts run, so badly formatted proofs show up
+//! before they cost gas. It exits with 1 if the proof does not verify.
+
+use anyhow::{Context, Result};
+use borsh::BorshSerialize;
+use electron_rs::verifier::core::VerifierError;
+use electron_rs::verifier::near::{prepare_verification_key, verify_proof};
+use std::env;
+use std::fs;
+use std::process::exit;
+
+const USAGE: &str = "Usage:
+    electron-cli vkey prepare <verification_key.json>
+    electron-cli verify --vkey <verification_key.json> --proof <proof.json> --public <public.json>";
+
+fn read(path: &str) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))
+}
+
+/// Reads the verification key json at `path` and returns its prepared key
+/// as base64.
+fn vkey_prepare(path: &str) -> Result<String> {
+    let pvk = prepare_verification_key(read(path)?)?;
+    Ok(base64::encode(pvk.try_to_vec()?))
+}
+
+/// Reads the paths of `verify`, whose flags come in any order.
+fn verify_args<'a>(args: &[&'a str]) -> Option<(&'a str, &'a str, &'a str)> {
+    let (mut vkey, mut proof, mut public) = (None, None, None);
+    for pair in args.chunks(2) {
+        match pair {
+            ["--vkey", path] => vkey = Some(*path),
+            ["--proof", path] => proof = Some(*path),
+            ["--public", path] => public = Some(*path),
+            _ => return None,
+        }
+    }
+    Some((vkey?, proof?, public?))
+}
+
+/// Verifies the proof and public inputs json against the verification key
+/// json, as `verifier::near::verify_proof` does on chain.
+fn verify(vkey_path: &str, proof_path: &str, public_path: &str) -> Result<String> {
+    let pvk = prepare_verification_key(read(vkey_path)?)?;
+    if !verify_proof(pvk, read(proof_path)?, read(public_path)?)? {
+        return Err(VerifierError::VerificationFailed.into());
+    }
+    Ok("Proof verifies".to_string())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let res = match args.as_slice() {
+        ["vkey", "prepare", path] => vkey_prepare(path),
+        ["verify", flags @ ..] => match verify_args(flags) {
+            Some((vkey, proof, public)) => verify(vkey, proof, public),
+            None => {
+                eprintln!("{}", USAGE);
+                exit(2);
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    };
+    match res {
+        Ok(out) => println!("{}", out),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshDeserialize;
+    use electron_rs::verifier::near::PreparedVerifyingKey;
+
+    const VKEY: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/testkit/fixtures/bn128/verification_key.json"
+    );
+    const PROOF: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/testkit/fixtures/bn128/proof.json"
+    );
+    const PUBLIC: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/testkit/fixtures/bn128/public.json"
+    );
+
+    #[test]
+    fn test_vkey_prepare() {
+        let encoded = vkey_prepare(VKEY).unwrap();
+        let pvk = PreparedVerifyingKey::try_from_slice(&base64::decode(encoded).unwrap()).unwrap();
+        let expected = prepare_verification_key(fs::read_to_string(VKEY).unwrap()).unwrap();
+        assert_eq!(pvk, expected);
+    }
+
+    #[test]
+    fn test_vkey_prepare_missing_file() {
+        let err = vkey_prepare("missing.json").unwrap_err();
+        assert_eq!(err.to_string(), "Failed to read missing.json");
+    }
+
+    #[test]
+    fn test_verify() {
+        assert_eq!(verify(VKEY, PROOF, PUBLIC).unwrap(), "Proof verifies");
+
+        let wrong = std::env::temp_dir().join("electron-cli-wrong-public.json");
+        fs::write(&wrong, read(PUBLIC).unwrap().replacen("\"1\"", "\"2\"", 1)).unwrap();
+        let err = verify(VKEY, PROOF, wrong.to_str().unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "Proof does not verify");
+    }
+
+    #[test]
+    fn test_verify_args() {
+        assert_eq!(
+            verify_args(&["--public", "p.json", "--vkey", "v.json", "--proof", "x.json"]),
+            Some(("v.json", "x.json", "p.json"))
+        );
+        assert_eq!(
+            verify_args(&["--vkey", "v.json", "--proof", "x.json"]),
+            None
+        );
+        assert_eq!(verify_args(&["--vkey", "v.json", "--proof"]), None);
+    }
+}
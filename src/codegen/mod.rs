@@ -0,0 +1,7 @@
+// Copyright © 2022, Electron Labs
+
+//! Verifiers for other chains generated from the keys this crate verifies
+//! against, so that both sides of a bridge check proofs against the same
+//! key. Code generation runs off chain, so this module is native only.
+
+pub mod solidity;
@@ -0,0 +1,233 @@
+// Copyright © 2022, Electron Labs
+
+//! Generation of a Solidity Groth16 verifier for a BN254 key.
+//!
+//! The contract checks proofs with the `ecAdd`, `ecMul` and `ecPairing`
+//! precompiles of EIP-196 and EIP-197 and exposes
+//! `verifyProof(a, b, c, input)` with the argument layout of the snarkjs
+//! verifier: `b` lists the coordinates of its G2 point with the imaginary
+//! part first, as the pairing precompile reads them.
+
+use crate::verifier::near::{repr_to_string, VerificationKeyJson};
+use anyhow::Result;
+use ark_ff::{PrimeField, Zero};
+use std::fmt::Write;
+
+/// The prime order of the BN254 scalar field.
+const SCALAR_FIELD: &str =
+    "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+/// The prime order of the BN254 base field.
+const BASE_FIELD: &str =
+    "21888242871839275222246405745257275088696311157297823662689037894645226208583";
+
+fn fq(fq: ark_bn254::Fq) -> String {
+    repr_to_string(&fq.into_repr().0)
+}
+
+fn g1_constants(out: &mut String, name: &str, point: &ark_bn254::G1Affine) {
+    let (x, y) = if point.is_zero() {
+        (ark_bn254::Fq::zero(), ark_bn254::Fq::zero())
+    } else {
+        (point.x, point.y)
+    };
+    writeln!(out, "    uint256 constant {}_X = {};", name, fq(x)).unwrap();
+    writeln!(out, "    uint256 constant {}_Y = {};", name, fq(y)).unwrap();
+}
+
+fn g2_constants(out: &mut String, name: &str, point: &ark_bn254::G2Affine) {
+    let zero = ark_bn254::Fq2::zero();
+    let (x, y) = if point.is_zero() {
+        (zero, zero)
+    } else {
+        (point.x, point.y)
+    };
+    for (suffix, value) in [("X1", x.c1), ("X0", x.c0), ("Y1", y.c1), ("Y0", y.c0)] {
+        writeln!(
+            out,
+            "    uint256 constant {}_{} = {};",
+            name,
+            suffix,
+            fq(value)
+        )
+        .unwrap();
+    }
+}
+
+/// Writes the four words of a G2 constant into `p`, starting at `start`.
+fn g2_words(out: &mut String, name: &str, start: usize) {
+    for (i, suffix) in ["X1", "X0", "Y1", "Y0"].iter().enumerate() {
+        writeln!(out, "        p[{}] = {}_{};", start + i, name, suffix).unwrap();
+    }
+}
+
+/// Generates the source of a `Verifier` contract for a verification key.
+///
+/// # Errors
+/// VerifierError::InvalidFieldElement
+///
+/// This function will return an error if a field element of the key fails
+/// to parse.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::codegen::solidity::solidity_verifier;
+/// use electron_rs::verifier::near::parse_verification_key;
+///
+/// let vkey = parse_verification_key(electron_testkit::circom::vkey().to_string()).unwrap();
+/// let source = solidity_verifier(vkey).unwrap();
+/// assert!(source.contains("contract Verifier"));
+/// ```
+pub fn solidity_verifier(vkey: VerificationKeyJson) -> Result<String> {
+    let vk: ark_groth16::VerifyingKey<ark_bn254::Bn254> = vkey.try_into()?;
+    Ok(solidity_verifier_for_key(&vk))
+}
+
+/// Like `solidity_verifier`, for a parsed key.
+pub fn solidity_verifier_for_key(vk: &ark_groth16::VerifyingKey<ark_bn254::Bn254>) -> String {
+    let num_inputs = vk.gamma_abc_g1.len() - 1;
+    let mut out = String::new();
+    out.push_str(
+        "// SPDX-License-Identifier: MIT
+// Generated by electron-rs from a circom verification key. Do not edit.
+pragma solidity ^0.8.0;
+
+/// Groth16 verifier for BN254, using the EIP-196 and EIP-197 precompiles.
+contract Verifier {
+",
+    );
+    writeln!(out, "    uint256 constant R = {};", SCALAR_FIELD).unwrap();
+    writeln!(out, "    uint256 constant Q = {};", BASE_FIELD).unwrap();
+    out.push('\n');
+    g1_constants(&mut out, "ALPHA", &vk.alpha_g1);
+    g2_constants(&mut out, "BETA", &vk.beta_g2);
+    g2_constants(&mut out, "GAMMA", &vk.gamma_g2);
+    g2_constants(&mut out, "DELTA", &vk.delta_g2);
+    for (i, point) in vk.gamma_abc_g1.iter().enumerate() {
+        g1_constants(&mut out, &format!("IC{}", i), point);
+    }
+
+    out.push_str(
+        "
+    /// Returns whether the proof is valid for the public inputs. The
+    /// coordinates of `b` are in the precompile order, imaginary parts
+    /// first.
+    function verifyProof(
+        uint256[2] calldata a,
+        uint256[2][2] calldata b,
+        uint256[2] calldata c",
+    );
+    if num_inputs > 0 {
+        write!(out, ",\n        uint256[{}] calldata input", num_inputs).unwrap();
+    }
+    out.push_str(
+        "
+    ) public view returns (bool) {
+        uint256[2] memory acc = [IC0_X, IC0_Y];
+",
+    );
+    for i in 0..num_inputs {
+        writeln!(
+            out,
+            "        require(input[{i}] < R, \"input {i} is not in the scalar field\");
+        acc = ecAdd(acc, ecMul([IC{j}_X, IC{j}_Y], input[{i}]));",
+            i = i,
+            j = i + 1
+        )
+        .unwrap();
+    }
+    out.push_str(
+        "
+        // e(-A, B) * e(alpha, beta) * e(acc, gamma) * e(C, delta) == 1
+        uint256[24] memory p;
+        p[0] = a[0];
+        p[1] = (Q - (a[1] % Q)) % Q;
+        p[2] = b[0][0];
+        p[3] = b[0][1];
+        p[4] = b[1][0];
+        p[5] = b[1][1];
+        p[6] = ALPHA_X;
+        p[7] = ALPHA_Y;
+",
+    );
+    g2_words(&mut out, "BETA", 8);
+    out.push_str(
+        "        p[12] = acc[0];
+        p[13] = acc[1];
+",
+    );
+    g2_words(&mut out, "GAMMA", 14);
+    out.push_str(
+        "        p[18] = c[0];
+        p[19] = c[1];
+",
+    );
+    g2_words(&mut out, "DELTA", 20);
+    out.push_str(
+        "
+        uint256[1] memory result;
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 8, p, 768, result, 32)
+        }
+        return ok && result[0] == 1;
+    }
+
+    function ecAdd(uint256[2] memory p1, uint256[2] memory p2)
+        internal
+        view
+        returns (uint256[2] memory r)
+    {
+        uint256[4] memory input = [p1[0], p1[1], p2[0], p2[1]];
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 6, input, 128, r, 64)
+        }
+        require(ok, \"ecAdd failed\");
+    }
+
+    function ecMul(uint256[2] memory p1, uint256 s) internal view returns (uint256[2] memory r) {
+        uint256[3] memory input = [p1[0], p1[1], s];
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 7, input, 96, r, 64)
+        }
+        require(ok, \"ecMul failed\");
+    }
+}
+",
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::test_circuit;
+    use crate::verifier::near::{fixtures, parse_verification_key};
+
+    #[test]
+    fn test_constants_match_key() {
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        let vk: ark_groth16::VerifyingKey<ark_bn254::Bn254> = vkey.clone().try_into().unwrap();
+        let source = solidity_verifier(vkey).unwrap();
+
+        let alpha_x = format!("uint256 constant ALPHA_X = {};", fq(vk.alpha_g1.x));
+        assert!(source.contains(&alpha_x));
+        let beta_x1 = format!("uint256 constant BETA_X1 = {};", fq(vk.beta_g2.x.c1));
+        assert!(source.contains(&beta_x1));
+        assert!(source.contains("uint256[21] calldata input"));
+        assert!(source.contains("acc = ecAdd(acc, ecMul([IC21_X, IC21_Y], input[20]));"));
+        assert!(!source.contains("IC22_X"));
+    }
+
+    #[test]
+    fn test_key_without_inputs() {
+        let (mut vk, _, _) = test_circuit::prove::<ark_bn254::Bn254>(3, 5);
+        vk.gamma_abc_g1.truncate(1);
+        let source = solidity_verifier_for_key(&vk);
+        assert!(!source.contains("calldata input"));
+        assert!(!source.contains("ecMul(["));
+        assert_eq!(source.matches('{').count(), source.matches('}').count());
+    }
+}
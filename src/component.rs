@@ -0,0 +1,81 @@
+// Copyright © 2022, Electron Labs
+
+//! A WebAssembly component exporting the verifier through the WIT interface
+//! in `wit/electron.wit`, so hosts other than blockchains, such as
+//! verification sidecars or wasm plugin systems, embed the code the
+//! contracts run.
+//!
+//! Prepared keys cross the interface in their borsh encoding, the bytes
+//! contracts store, and errors are returned as their message.
+
+use crate::verifier::near::{self, PreparedVerifyingKey};
+use borsh::{BorshDeserialize, BorshSerialize};
+use exports::electron::verifier::groth16::Guest;
+
+wit_bindgen::generate!({
+    world: "verifier",
+    path: "wit",
+});
+
+/// The exports of the `verifier` world.
+pub struct Component;
+
+fn prepared_key(bytes: &[u8]) -> Result<PreparedVerifyingKey, String> {
+    PreparedVerifyingKey::try_from_slice(bytes).map_err(|e| e.to_string())
+}
+
+impl Guest for Component {
+    fn prepare(vkey: String) -> Result<Vec<u8>, String> {
+        let pvk = near::prepare_verification_key(vkey).map_err(|e| e.to_string())?;
+        Ok(pvk.try_to_vec().unwrap())
+    }
+
+    fn fingerprint(prepared_key_bytes: Vec<u8>) -> Result<String, String> {
+        Ok(hex::encode(
+            prepared_key(&prepared_key_bytes)?.fingerprint(),
+        ))
+    }
+
+    fn verify(prepared_key_bytes: Vec<u8>, proof: String, inputs: String) -> Result<bool, String> {
+        let pvk = prepared_key(&prepared_key_bytes)?;
+        near::verify_proof(pvk, proof, inputs).map_err(|e| e.to_string())
+    }
+}
+
+export!(Component);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::fixtures;
+
+    #[test]
+    fn test_same_path_as_contract() {
+        let pvk = Component::prepare(fixtures::vkey().to_string()).unwrap();
+        let expected = near::prepare_verification_key(fixtures::vkey().to_string()).unwrap();
+        assert_eq!(pvk, expected.try_to_vec().unwrap());
+        assert_eq!(
+            Component::fingerprint(pvk.clone()).unwrap(),
+            hex::encode(expected.fingerprint())
+        );
+
+        assert!(Component::verify(
+            pvk.clone(),
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string()
+        )
+        .unwrap());
+        assert!(!Component::verify(
+            pvk,
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().replacen("\"1\"", "\"2\"", 1)
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_errors_are_messages() {
+        assert!(Component::prepare("{}".to_string()).is_err());
+        assert!(Component::fingerprint(vec![1, 2, 3]).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+// Copyright © 2022, Electron Labs
+
+//! Non-transferable attestation tokens minted after a successful
+//! verification.
+//!
+//! Tokens follow the NEP-171 `Token` shape with NEP-177 metadata, and mints
+//! are logged as NEP-297 `nft_mint` events, but the component exposes no
+//! transfer method: a token stays with the account that proved the
+//! statement. The proof must bind that account through
+//! [`account_input`](crate::components::meta_tx::account_input), otherwise
+//! anyone who sees the proof in the mempool could mint the token first.
+
+use crate::components::meta_tx::account_input;
+use crate::components::ComponentError;
+use crate::verifier::near::public_inputs::PublicInputs;
+use crate::verifier::near::{verify_proof, PreparedVerifyingKey};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::json;
+use near_sdk::{env, AccountId, IntoStorageKey};
+use sha2::{Digest, Sha256};
+
+/// NEP-177 token metadata.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub media: Option<String>,
+    pub media_hash: Option<String>,
+    pub copies: Option<u64>,
+    pub issued_at: Option<String>,
+    pub expires_at: Option<String>,
+    pub starts_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub extra: Option<String>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<String>,
+}
+
+/// NEP-171 token.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Token {
+    pub token_id: String,
+    pub owner_id: AccountId,
+    pub metadata: Option<TokenMetadata>,
+}
+
+/// Token id of the attestation for a circuit fingerprint and input
+/// commitment.
+pub fn attestation_token_id(fingerprint: &[u8; 32], commitment: &[u8; 32]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(fingerprint);
+    hasher.update(commitment);
+    hex::encode(hasher.finalize())
+}
+
+/// Metadata recording the circuit fingerprint and input commitment in
+/// `extra`.
+pub fn attestation_metadata(fingerprint: &[u8; 32], commitment: &[u8; 32]) -> TokenMetadata {
+    TokenMetadata {
+        title: Some("Verified proof attestation".to_string()),
+        description: None,
+        media: None,
+        media_hash: None,
+        copies: Some(1),
+        issued_at: Some(env::block_timestamp().to_string()),
+        expires_at: None,
+        starts_at: None,
+        updated_at: None,
+        extra: Some(
+            json!({
+                "circuit": hex::encode(fingerprint),
+                "inputs": hex::encode(commitment),
+            })
+            .to_string(),
+        ),
+        reference: None,
+        reference_hash: None,
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AttestationTokens {
+    tokens: LookupMap<String, Token>,
+}
+
+impl AttestationTokens {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        AttestationTokens {
+            tokens: LookupMap::new(prefix),
+        }
+    }
+
+    /// Verifies a proof whose public input at `account_index` binds the
+    /// predecessor and mints an attestation token to it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the proof or inputs fail to
+    /// parse, if the proof is bound to another account, if it does not
+    /// verify, or if the statement has already been attested.
+    pub fn verify_and_mint(
+        &mut self,
+        pvk: PreparedVerifyingKey,
+        proof_str: String,
+        pub_inputs_str: String,
+        account_index: usize,
+    ) -> Result<Token> {
+        let owner_id = env::predecessor_account_id();
+        let inputs = PublicInputs::parse(pub_inputs_str.clone())?;
+        if inputs.as_slice().get(account_index) != Some(&account_input(&owner_id)) {
+            return Err(ComponentError::AccountMismatch.into());
+        }
+        let fingerprint = pvk.fingerprint();
+        let commitment = inputs.message_id();
+        let token_id = attestation_token_id(&fingerprint, &commitment);
+        if self.tokens.contains_key(&token_id) {
+            return Err(ComponentError::DuplicateToken(token_id).into());
+        }
+        if !verify_proof(pvk, proof_str, pub_inputs_str)? {
+            return Err(ComponentError::VerificationFailed.into());
+        }
+
+        let token = Token {
+            token_id,
+            owner_id,
+            metadata: Some(attestation_metadata(&fingerprint, &commitment)),
+        };
+        self.tokens.insert(&token.token_id, &token);
+        let event = json!({
+            "standard": "nep171",
+            "version": "1.0.0",
+            "event": "nft_mint",
+            "data": [{ "owner_id": token.owner_id, "token_ids": [token.token_id] }],
+        });
+        env::log_str(&format!("EVENT_JSON:{}", event));
+        Ok(token)
+    }
+
+    pub fn nft_token(&self, token_id: &str) -> Option<Token> {
+        self.tokens.get(&token_id.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::{get_prepared_verifying_key, parse_verification_key};
+    use ark_ff::One;
+    use electron_testkit::multiplier::Multiplier;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn context(account: &str) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(account.parse().unwrap())
+            .block_timestamp(42)
+            .build());
+    }
+
+    fn proof_for(account: &str) -> (PreparedVerifyingKey, String, String) {
+        let mut multiplier = Multiplier::setup();
+        let vkey = parse_verification_key(multiplier.vkey_json()).unwrap();
+        let account = account_input(&account.parse().unwrap());
+        let (proof, inputs) = multiplier.prove_fr(account, ark_bn254::Fr::one());
+        (get_prepared_verifying_key(vkey).unwrap(), proof, inputs)
+    }
+
+    #[test]
+    fn test_attestation_token_id() {
+        let id = attestation_token_id(&[1u8; 32], &[2u8; 32]);
+        assert_eq!(id.len(), 64);
+        assert_ne!(id, attestation_token_id(&[2u8; 32], &[1u8; 32]));
+    }
+
+    #[test]
+    fn test_attestation_metadata() {
+        let metadata = attestation_metadata(&[0u8; 32], &[0xffu8; 32]);
+        let extra: near_sdk::serde_json::Value =
+            near_sdk::serde_json::from_str(&metadata.extra.unwrap()).unwrap();
+        assert_eq!(extra["circuit"], hex::encode([0u8; 32]));
+        assert_eq!(extra["inputs"], hex::encode([0xffu8; 32]));
+        assert_eq!(metadata.copies, Some(1));
+    }
+
+    #[test]
+    fn test_verify_and_mint() {
+        context("alice.near");
+        let mut tokens = AttestationTokens::new(b"t".to_vec());
+        let (pvk, proof, inputs) = proof_for("alice.near");
+        let token = tokens
+            .verify_and_mint(pvk.clone(), proof.clone(), inputs.clone(), 0)
+            .unwrap();
+        assert_eq!(token.owner_id.as_str(), "alice.near");
+        assert_eq!(tokens.nft_token(&token.token_id), Some(token.clone()));
+
+        let event = json!({
+            "standard": "nep171",
+            "version": "1.0.0",
+            "event": "nft_mint",
+            "data": [{ "owner_id": "alice.near", "token_ids": [token.token_id] }],
+        });
+        assert_eq!(get_logs(), vec![format!("EVENT_JSON:{}", event)]);
+
+        let err = tokens.verify_and_mint(pvk, proof, inputs, 0).err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ComponentError>(),
+            Some(ComponentError::DuplicateToken(id)) if *id == token.token_id
+        ));
+    }
+
+    #[test]
+    fn test_mint_for_other_account() {
+        context("bob.near");
+        let mut tokens = AttestationTokens::new(b"t".to_vec());
+        let (pvk, proof, inputs) = proof_for("alice.near");
+        let err = tokens.verify_and_mint(pvk, proof, inputs, 0).err().unwrap();
+        assert_eq!(err.to_string(), "Proof is not bound to the sender account");
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright © 2022, Electron Labs
+
+//! Challenge-response freshness for proofs.
+//!
+//! The contract issues a random challenge to an account, the prover includes
+//! it as a public input, and [`ChallengeStore::verify_challenge_proof`]
+//! checks both the proof and that it is bound to the live challenge. A
+//! challenge is consumed by the first proof that verifies against it.
+
+use crate::components::ComponentError;
+use crate::verifier::core::RandomnessSource;
+use crate::verifier::near::batch::EnvSeed;
+use crate::verifier::near::public_inputs::PublicInputs;
+use crate::verifier::near::{fr_from_str, fr_to_string, verify_proof, PreparedVerifyingKey};
+use anyhow::Result;
+use ark_ff::PrimeField;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::{env, AccountId, IntoStorageKey};
+use sha2::{Digest, Sha256};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct Challenge {
+    pub value: String,
+    pub expires_at: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ChallengeStore {
+    ttl: u64,
+    challenges: LookupMap<AccountId, Challenge>,
+}
+
+impl ChallengeStore {
+    /// Challenges expire `ttl` nanoseconds after they are issued.
+    pub fn new<S: IntoStorageKey>(prefix: S, ttl: u64) -> Self {
+        ChallengeStore {
+            ttl,
+            challenges: LookupMap::new(prefix),
+        }
+    }
+
+    /// Issues a fresh challenge to the predecessor account, replacing any
+    /// previous one, and returns it as a decimal field element.
+    pub fn issue(&mut self) -> Challenge {
+        self.issue_with(&EnvSeed)
+    }
+
+    /// `issue` with the challenge derived from `source` instead of the
+    /// block random seed.
+    pub fn issue_with<R: RandomnessSource + ?Sized>(&mut self, source: &R) -> Challenge {
+        let account = env::predecessor_account_id();
+        let mut hasher = Sha256::new();
+        hasher.update(source.random_seed());
+        hasher.update(account.as_str().as_bytes());
+        hasher.update(env::block_height().to_le_bytes());
+        let value = ark_bn254::Fr::from_be_bytes_mod_order(&hasher.finalize());
+
+        let challenge = Challenge {
+            value: fr_to_string(value),
+            expires_at: env::block_timestamp().saturating_add(self.ttl),
+        };
+        self.challenges.insert(&account, &challenge);
+        challenge
+    }
+
+    pub fn get(&self, account: &AccountId) -> Option<Challenge> {
+        self.challenges.get(account)
+    }
+
+    /// Verifies a proof whose public input at `challenge_index` must equal
+    /// the live challenge of the predecessor account, and consumes the
+    /// challenge.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the account has no live
+    /// challenge, if the proof is not bound to it, or if the proof fails to
+    /// parse or verify.
+    pub fn verify_challenge_proof(
+        &mut self,
+        pvk: PreparedVerifyingKey,
+        proof_str: String,
+        pub_inputs_str: String,
+        challenge_index: usize,
+    ) -> Result<()> {
+        let account = env::predecessor_account_id();
+        let challenge = self
+            .challenges
+            .get(&account)
+            .ok_or(ComponentError::NoChallenge)?;
+        if env::block_timestamp() >= challenge.expires_at {
+            return Err(ComponentError::ChallengeExpired.into());
+        }
+
+        let inputs = PublicInputs::parse(pub_inputs_str.clone())?;
+        let bound = inputs.as_slice().get(challenge_index).copied();
+        if bound != Some(fr_from_str(challenge.value)?) {
+            return Err(ComponentError::ChallengeMismatch.into());
+        }
+        if !verify_proof(pvk, proof_str, pub_inputs_str)? {
+            return Err(ComponentError::VerificationFailed.into());
+        }
+
+        self.challenges.remove(&account);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::FixedSeed;
+    use crate::verifier::near::{fixtures, get_prepared_verifying_key, parse_verification_key};
+    use ark_ff::One;
+    use electron_testkit::multiplier::Multiplier;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn context(timestamp: u64) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("alice.near".parse().unwrap())
+            .block_timestamp(timestamp)
+            .build());
+    }
+
+    #[test]
+    fn test_issue_challenge() {
+        context(100);
+        let mut store = ChallengeStore::new(b"c".to_vec(), 50);
+        let challenge = store.issue();
+        assert_eq!(challenge.expires_at, 150);
+        assert_eq!(store.get(&"alice.near".parse().unwrap()), Some(challenge));
+    }
+
+    #[test]
+    fn test_issue_challenge_with_source() {
+        context(100);
+        let mut store = ChallengeStore::new(b"c".to_vec(), 50);
+        let first = store.issue_with(&FixedSeed([1; 32]));
+        assert_eq!(store.issue_with(&FixedSeed([1; 32])), first);
+        assert_ne!(store.issue_with(&FixedSeed([2; 32])), first);
+    }
+
+    #[test]
+    fn test_issue_challenge_without_expiry() {
+        context(100);
+        let mut store = ChallengeStore::new(b"c".to_vec(), u64::MAX);
+        assert_eq!(store.issue().expires_at, u64::MAX);
+    }
+
+    #[test]
+    fn test_verify_challenge_proof() {
+        context(100);
+        let mut store = ChallengeStore::new(b"c".to_vec(), 50);
+        let challenge = store.issue();
+
+        let mut multiplier = Multiplier::setup();
+        let vkey = parse_verification_key(multiplier.vkey_json()).unwrap();
+        let pvk = get_prepared_verifying_key(vkey).unwrap();
+        let value = fr_from_str(challenge.value).unwrap();
+        let (proof, inputs) = multiplier.prove_fr(value, ark_bn254::Fr::one());
+        store
+            .verify_challenge_proof(pvk.clone(), proof.clone(), inputs.clone(), 0)
+            .unwrap();
+        assert!(store.get(&"alice.near".parse().unwrap()).is_none());
+
+        let err = store
+            .verify_challenge_proof(pvk, proof, inputs, 0)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ComponentError>(),
+            Some(ComponentError::NoChallenge)
+        ));
+    }
+
+    #[test]
+    fn test_challenge_mismatch() {
+        context(100);
+        let mut store = ChallengeStore::new(b"c".to_vec(), 50);
+        store.issue();
+        let err = store
+            .verify_challenge_proof(
+                fixtures::pvk(),
+                fixtures::proof().to_string(),
+                fixtures::public_inputs().to_string(),
+                0,
+            )
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Proof is not bound to the issued challenge"
+        );
+    }
+
+    #[test]
+    fn test_challenge_expired() {
+        context(100);
+        let mut store = ChallengeStore::new(b"c".to_vec(), 50);
+        store.issue();
+        context(150);
+        let err = store
+            .verify_challenge_proof(
+                fixtures::pvk(),
+                fixtures::proof().to_string(),
+                fixtures::public_inputs().to_string(),
+                0,
+            )
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "Challenge expired");
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright © 2022, Electron Labs
+
+//! Freezes a circuit when someone proves it is compromised.
+//!
+//! Every circuit of a [`VkeyRegistry`] can be paired with the key of a
+//! compromise circuit, for instance one proving knowledge of the setup
+//! toxic waste or of two distinct witnesses for one statement. A valid
+//! compromise proof freezes the circuit in the registry, and
+//! [`VkeyRegistry::verify_proof`] fails for it from then on, without
+//! waiting for the owner to react.
+//!
+//! ```ignore
+//! pub fn report_compromise(&mut self, circuit_id: String, proof: String, inputs: String) -> bool {
+//!     self.breaker
+//!         .report_compromise(&mut self.registry, &circuit_id, proof, inputs)
+//!         .unwrap()
+//! }
+//! ```
+
+use crate::components::registry::VkeyRegistry;
+use crate::components::ComponentError;
+use crate::verifier::near::{verify_proof, PreparedVerifyingKey};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, AccountId, IntoStorageKey};
+
+/// Who froze a circuit and when.
+#[derive(Serialize, BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Freeze {
+    pub reporter: AccountId,
+    pub timestamp: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct CircuitBreaker {
+    compromise_keys: LookupMap<String, PreparedVerifyingKey>,
+}
+
+impl CircuitBreaker {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        CircuitBreaker {
+            compromise_keys: LookupMap::new(prefix),
+        }
+    }
+
+    /// Sets the key of the compromise circuit guarding `circuit_id`. Only
+    /// call this from owner methods.
+    pub fn set_compromise_key(&mut self, circuit_id: &str, pvk: &PreparedVerifyingKey) {
+        self.compromise_keys.insert(&circuit_id.to_string(), pvk);
+    }
+
+    /// Checks a proof against the compromise key of `circuit_id` and freezes
+    /// the circuit in `registry` if it verifies. Returns whether the circuit
+    /// was frozen by this call.
+    ///
+    /// # Errors
+    /// ComponentError::NoCompromiseKey
+    ///
+    /// This function will also return an error if the proof or inputs fail
+    /// to parse.
+    pub fn report_compromise(
+        &self,
+        registry: &mut VkeyRegistry,
+        circuit_id: &str,
+        proof_str: String,
+        pub_inputs_str: String,
+    ) -> Result<bool> {
+        let id = circuit_id.to_string();
+        let pvk = self
+            .compromise_keys
+            .get(&id)
+            .ok_or_else(|| ComponentError::NoCompromiseKey(id.clone()))?;
+        if registry.is_frozen(&id) || !verify_proof(pvk, proof_str, pub_inputs_str)? {
+            return Ok(false);
+        }
+
+        let freeze = Freeze {
+            reporter: env::predecessor_account_id(),
+            timestamp: env::block_timestamp(),
+        };
+        registry.freeze(&id, &freeze);
+        env::log_str(&format!(
+            "Circuit {} frozen on a compromise proof from {}",
+            id, freeze.reporter
+        ));
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::limbs::LimbLayout;
+    use crate::verifier::near::fixtures;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn test_compromise_proof_freezes_circuit() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("alice.near".parse().unwrap())
+            .build());
+        let mut registry = VkeyRegistry::new(b"r".to_vec());
+        registry
+            .register_json(
+                "transfer",
+                fixtures::vkey().to_string(),
+                LimbLayout::default(),
+            )
+            .unwrap();
+        // The fixture circuit stands in for the compromise circuit.
+        let mut breaker = CircuitBreaker::new(b"b".to_vec());
+        let proof = fixtures::proof().to_string();
+        let inputs = fixtures::public_inputs().to_string();
+        assert!(breaker
+            .report_compromise(&mut registry, "transfer", proof.clone(), inputs.clone())
+            .is_err());
+
+        breaker.set_compromise_key("transfer", &fixtures::pvk());
+        assert!(registry
+            .verify_proof("transfer", proof.clone(), inputs.clone())
+            .unwrap());
+        assert!(!breaker
+            .report_compromise(
+                &mut registry,
+                "transfer",
+                proof.clone(),
+                "[\"1\"]".to_string()
+            )
+            .unwrap_or(false));
+        assert!(!registry.is_frozen("transfer"));
+
+        assert!(breaker
+            .report_compromise(&mut registry, "transfer", proof.clone(), inputs.clone())
+            .unwrap());
+        assert_eq!(
+            registry.frozen("transfer").unwrap().reporter.as_str(),
+            "alice.near"
+        );
+        assert_eq!(
+            registry
+                .verify_proof("transfer", proof.clone(), inputs.clone())
+                .err()
+                .unwrap()
+                .to_string(),
+            "Circuit transfer is frozen"
+        );
+        assert!(!breaker
+            .report_compromise(&mut registry, "transfer", proof.clone(), inputs.clone())
+            .unwrap());
+
+        registry.unfreeze("transfer");
+        assert!(registry.verify_proof("transfer", proof, inputs).unwrap());
+    }
+}
@@ -0,0 +1,296 @@
+// Copyright © 2022, Electron Labs
+
+//! Proof batches committed by a Merkle root and verified on demand.
+//!
+//! A relayer posts only the root of a batch of `(proof, inputs)` leaves
+//! with [`CommittedBatches::commit`]. Individual leaves are verified later,
+//! when someone needs them settled or disputed, by presenting the leaf and
+//! its Merkle path to [`CommittedBatches::verify_leaf`]. The leaf encoding
+//! and tree functions are plain sha256 so relayers compute the same roots
+//! off chain.
+//!
+//! Leaves are hashed as `sha256(0x00 || len(proof) as u32 le || proof ||
+//! inputs)` and nodes as `sha256(0x01 || left || right)`. A level with an
+//! odd number of nodes is padded with a zero hash.
+
+use crate::components::ComponentError;
+use crate::verifier::near::{verify_proof, PreparedVerifyingKey};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::{env, AccountId, IntoStorageKey};
+use sha2::{Digest, Sha256};
+
+const LEAF_TAG: u8 = 0;
+const NODE_TAG: u8 = 1;
+
+/// Hash of a `(proof, inputs)` leaf.
+pub fn leaf_hash(proof_str: &str, pub_inputs_str: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_TAG]);
+    hasher.update((proof_str.len() as u32).to_le_bytes());
+    hasher.update(proof_str.as_bytes());
+    hasher.update(pub_inputs_str.as_bytes());
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_TAG]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| node_hash(&pair[0], pair.get(1).unwrap_or(&[0u8; 32])))
+        .collect()
+}
+
+/// Merkle root of the leaf hashes, or the zero hash for an empty batch.
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = leaves.to_vec();
+    if level.is_empty() {
+        return [0u8; 32];
+    }
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Sibling hashes from leaf `index` up to the root, or `None` if `index` is
+/// out of range.
+pub fn merkle_path(leaves: &[[u8; 32]], index: usize) -> Option<Vec<[u8; 32]>> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let mut path = Vec::new();
+    let mut level = leaves.to_vec();
+    let mut index = index;
+    while level.len() > 1 {
+        path.push(*level.get(index ^ 1).unwrap_or(&[0u8; 32]));
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(path)
+}
+
+/// Whether `path` proves `leaf` at `index` under `root`.
+pub fn verify_merkle_path(root: &[u8; 32], leaf: &[u8; 32], index: u32, path: &[[u8; 32]]) -> bool {
+    if path.len() >= 32 || (index as u64) >> path.len() != 0 {
+        return false;
+    }
+    let node = path
+        .iter()
+        .enumerate()
+        .fold(*leaf, |node, (level, sibling)| {
+            if (index >> level) & 1 == 0 {
+                node_hash(&node, sibling)
+            } else {
+                node_hash(sibling, &node)
+            }
+        });
+    node == *root
+}
+
+/// A batch root posted by a relayer.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct BatchCommitment {
+    pub relayer: AccountId,
+    pub root: [u8; 32],
+    pub size: u32,
+    pub committed_at: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct CommittedBatches {
+    circuit: [u8; 32],
+    next_id: u64,
+    batches: LookupMap<u64, BatchCommitment>,
+    results: LookupMap<(u64, u32), bool>,
+}
+
+impl CommittedBatches {
+    /// Batches of proofs for the circuit with the given key fingerprint.
+    pub fn new<S: IntoStorageKey>(prefix: S, circuit: [u8; 32]) -> Self {
+        let prefix = prefix.into_storage_key();
+        CommittedBatches {
+            circuit,
+            next_id: 0,
+            batches: LookupMap::new([prefix.as_slice(), &b"b"[..]].concat()),
+            results: LookupMap::new([prefix.as_slice(), &b"r"[..]].concat()),
+        }
+    }
+
+    /// Records the root of a batch of `size` leaves from the predecessor
+    /// and returns its id.
+    pub fn commit(&mut self, root: [u8; 32], size: u32) -> u64 {
+        let id = self.next_id;
+        self.batches.insert(
+            &id,
+            &BatchCommitment {
+                relayer: env::predecessor_account_id(),
+                root,
+                size,
+                committed_at: env::block_timestamp(),
+            },
+        );
+        self.next_id += 1;
+        id
+    }
+
+    pub fn batch(&self, batch_id: u64) -> Option<BatchCommitment> {
+        self.batches.get(&batch_id)
+    }
+
+    /// Checks that the leaf is in the batch and verifies its proof,
+    /// recording the result. A leaf whose proof or inputs fail to parse is
+    /// committed all the same, so it is recorded as invalid and the parse
+    /// error is logged. A leaf is only verified once; later calls return the
+    /// recorded result.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `pvk` is not the key of the
+    /// batches' circuit, or if the batch is unknown or the leaf is not in
+    /// it.
+    pub fn verify_leaf(
+        &mut self,
+        pvk: PreparedVerifyingKey,
+        batch_id: u64,
+        index: u32,
+        proof_str: String,
+        pub_inputs_str: String,
+        path: &[[u8; 32]],
+    ) -> Result<bool> {
+        if pvk.fingerprint() != self.circuit {
+            return Err(ComponentError::FingerprintMismatch.into());
+        }
+        let batch = self
+            .batches
+            .get(&batch_id)
+            .ok_or(ComponentError::UnknownBatch(batch_id))?;
+        let leaf = leaf_hash(&proof_str, &pub_inputs_str);
+        if index >= batch.size || !verify_merkle_path(&batch.root, &leaf, index, path) {
+            return Err(ComponentError::NotInBatch { batch_id, index }.into());
+        }
+        if let Some(valid) = self.results.get(&(batch_id, index)) {
+            return Ok(valid);
+        }
+        let valid = match verify_proof(pvk, proof_str, pub_inputs_str) {
+            Ok(valid) => valid,
+            Err(err) => {
+                env::log_str(&format!(
+                    "Leaf {} of batch {} is invalid: {}",
+                    index, batch_id, err
+                ));
+                false
+            }
+        };
+        self.results.insert(&(batch_id, index), &valid);
+        Ok(valid)
+    }
+
+    /// Whether a leaf verified, or `None` if it hasn't been verified yet.
+    pub fn result(&self, batch_id: u64, index: u32) -> Option<bool> {
+        self.results.get(&(batch_id, index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::fixtures;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn test_merkle_paths() {
+        let leaves: Vec<[u8; 32]> = (0..5u8).map(|i| [i; 32]).collect();
+        let root = merkle_root(&leaves);
+        for (i, leaf) in leaves.iter().enumerate() {
+            let path = merkle_path(&leaves, i).unwrap();
+            assert_eq!(path.len(), 3);
+            assert!(verify_merkle_path(&root, leaf, i as u32, &path));
+            assert!(!verify_merkle_path(&root, leaf, i as u32 ^ 1, &path));
+        }
+        assert!(merkle_path(&leaves, 5).is_none());
+        assert_eq!(merkle_root(&leaves[..1]), leaves[0]);
+    }
+
+    #[test]
+    fn test_verify_leaf() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("relayer.near".parse().unwrap())
+            .build());
+        let pvk = fixtures::pvk();
+        let proof = fixtures::proof().to_string();
+        let valid_inputs = fixtures::public_inputs().to_string();
+        let invalid_inputs = valid_inputs.replacen("\"1\"", "\"2\"", 1);
+        let leaves = vec![
+            leaf_hash(&proof, &valid_inputs),
+            leaf_hash(&proof, &invalid_inputs),
+        ];
+
+        let mut batches = CommittedBatches::new(b"c".to_vec(), pvk.fingerprint());
+        let id = batches.commit(merkle_root(&leaves), 2);
+        assert_eq!(batches.batch(id).unwrap().relayer.as_str(), "relayer.near");
+
+        let path = merkle_path(&leaves, 1).unwrap();
+        assert!(!batches
+            .verify_leaf(pvk.clone(), id, 1, proof.clone(), invalid_inputs, &path)
+            .unwrap());
+        assert_eq!(batches.result(id, 1), Some(false));
+        assert_eq!(batches.result(id, 0), None);
+
+        // A valid leaf with the wrong path is not in the batch.
+        assert!(batches
+            .verify_leaf(
+                pvk.clone(),
+                id,
+                0,
+                proof.clone(),
+                valid_inputs.clone(),
+                &path
+            )
+            .is_err());
+        let path = merkle_path(&leaves, 0).unwrap();
+        assert!(batches
+            .verify_leaf(pvk, id, 0, proof, valid_inputs, &path)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_malformed_leaf_is_invalid() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("relayer.near".parse().unwrap())
+            .build());
+        let pvk = fixtures::pvk();
+        let proof = fixtures::proof().to_string();
+        let inputs = fixtures::public_inputs().to_string();
+        let leaves = vec![
+            leaf_hash(&proof, &inputs),
+            leaf_hash("{}", &inputs),
+            leaf_hash(&proof, "[\"not a number\"]"),
+        ];
+
+        let mut batches = CommittedBatches::new(b"c".to_vec(), pvk.fingerprint());
+        let id = batches.commit(merkle_root(&leaves), 3);
+        let path = merkle_path(&leaves, 1).unwrap();
+        assert!(!batches
+            .verify_leaf(pvk.clone(), id, 1, "{}".to_string(), inputs, &path)
+            .unwrap());
+        assert_eq!(batches.result(id, 1), Some(false));
+        assert!(get_logs()[0].starts_with("Leaf 1 of batch 0 is invalid"));
+
+        let path = merkle_path(&leaves, 2).unwrap();
+        let malformed = "[\"not a number\"]".to_string();
+        assert!(!batches
+            .verify_leaf(pvk, id, 2, proof, malformed, &path)
+            .unwrap());
+        assert_eq!(batches.result(id, 2), Some(false));
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright © 2022, Electron Labs
+
+use crate::components::ComponentError;
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, Balance};
+
+/// Attached-deposit fee charged for a verification call against a circuit.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeeSchedule {
+    pub base_fee: U128,
+    pub per_input_fee: U128,
+}
+
+impl FeeSchedule {
+    pub fn new(base_fee: Balance, per_input_fee: Balance) -> Self {
+        FeeSchedule {
+            base_fee: U128(base_fee),
+            per_input_fee: U128(per_input_fee),
+        }
+    }
+
+    pub fn fee_for(&self, num_inputs: usize) -> Balance {
+        self.base_fee
+            .0
+            .saturating_add(self.per_input_fee.0.saturating_mul(num_inputs as Balance))
+    }
+
+    /// Checks that the attached deposit covers the fee for `num_inputs`
+    /// public inputs.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the attached deposit is lower
+    /// than the fee.
+    pub fn charge(&self, num_inputs: usize) -> Result<Balance> {
+        let required = self.fee_for(num_inputs);
+        let attached = env::attached_deposit();
+        if attached < required {
+            return Err(ComponentError::InsufficientFee { required, attached }.into());
+        }
+        Ok(required)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn test_fee_for() {
+        let schedule = FeeSchedule::new(100, 10);
+        assert_eq!(schedule.fee_for(0), 100);
+        assert_eq!(schedule.fee_for(21), 310);
+    }
+
+    #[test]
+    fn test_charge() {
+        let schedule = FeeSchedule::new(100, 10);
+        testing_env!(VMContextBuilder::new().attached_deposit(120).build());
+        assert_eq!(schedule.charge(2).unwrap(), 120);
+        assert_eq!(
+            schedule.charge(3).err().unwrap().to_string(),
+            "Insufficient fee: attached 120, required 130"
+        );
+    }
+}
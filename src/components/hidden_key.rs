@@ -0,0 +1,274 @@
+// Copyright © 2022, Electron Labs
+
+//! Circuits whose key stays hidden until it is unveiled.
+//!
+//! The owner stores a key blinded with
+//! [`Blinding`](crate::verifier::core::blinding::Blinding), which verifies
+//! proofs blinded with the same seed, and the `sha256` of the original
+//! key's fingerprint followed by the seed. Neither shows which audited
+//! circuit the key belongs to. Unveiling reveals the key and the seed: the
+//! contract checks the commitment and that the key blinds to the stored one,
+//! then rotates the key into the registry, where it verifies ordinary
+//! proofs.
+//!
+//! ```ignore
+//! #[private]
+//! pub fn hide(&mut self, circuit_id: String, key: Base64VecU8) {
+//!     // The borsh encoding of `hide_verification_key`, run off chain.
+//!     self.hidden.hide(&circuit_id, &HiddenKey::try_from_slice(&key.0).unwrap());
+//! }
+//!
+//! #[private]
+//! pub fn unveil(&mut self, circuit_id: String, vkey: String, seed: [u8; 32]) {
+//!     self.hidden
+//!         .unveil(&mut self.registry, &circuit_id, vkey, &seed, LimbLayout::default())
+//!         .unwrap();
+//! }
+//! ```
+//!
+//! Provers need the seed to blind their proofs, so it is only hidden from
+//! everyone else. The number of public inputs of the key is not hidden.
+
+use crate::components::registry::{CircuitEntry, VkeyRegistry};
+use crate::components::ComponentError;
+use crate::hash::limbs::LimbLayout;
+use crate::verifier::core::blinding::Blinding;
+use crate::verifier::near::{
+    get_prepared_verifying_key, parse_verification_key, verify_proof, PreparedVerifyingKey,
+};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::{env, IntoStorageKey};
+use sha2::{Digest, Sha256};
+
+/// A blinded prepared key and the commitment to the key it was blinded
+/// from.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct HiddenKey {
+    pub pvk: PreparedVerifyingKey,
+    pub commitment: [u8; 32],
+}
+
+/// The `sha256` of a key fingerprint followed by the blinding seed.
+pub fn key_commitment(fingerprint: &[u8; 32], seed: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(fingerprint);
+    hasher.update(seed);
+    hasher.finalize().into()
+}
+
+/// Blinds circom verification key json with `seed`, off chain or in tests.
+///
+/// # Errors
+///
+/// This function will return an error in the same cases as
+/// `prepare_verification_key`.
+pub fn hide_verification_key(vkey_str: String, seed: &[u8; 32]) -> Result<HiddenKey> {
+    let vkey = parse_verification_key(vkey_str)?;
+    let pvk = get_prepared_verifying_key(vkey.clone())?;
+    let vk: ark_groth16::VerifyingKey<ark_bn254::Bn254> = vkey.try_into()?;
+    let blinded = Blinding::<ark_bn254::Bn254>::from_seed(seed).blind_verifying_key(&vk);
+    Ok(HiddenKey {
+        pvk: ark_groth16::prepare_verifying_key(&blinded).into(),
+        commitment: key_commitment(&pvk.fingerprint(), seed),
+    })
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct HiddenKeys {
+    keys: LookupMap<String, HiddenKey>,
+}
+
+impl HiddenKeys {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        HiddenKeys {
+            keys: LookupMap::new(prefix),
+        }
+    }
+
+    /// Stores the hidden key of `circuit_id`, replacing any previous one.
+    /// Only call this from owner methods.
+    pub fn hide(&mut self, circuit_id: &str, key: &HiddenKey) {
+        self.keys.insert(&circuit_id.to_string(), key);
+    }
+
+    pub fn get(&self, circuit_id: &str) -> Option<HiddenKey> {
+        self.keys.get(&circuit_id.to_string())
+    }
+
+    /// Verifies a blinded proof against the hidden key of `circuit_id`.
+    ///
+    /// # Errors
+    /// ComponentError::UnknownCircuit
+    ///
+    /// This function will also return an error if the proof or inputs fail
+    /// to parse.
+    pub fn verify_proof(
+        &self,
+        circuit_id: &str,
+        proof_str: String,
+        pub_inputs_str: String,
+    ) -> Result<bool> {
+        let key = self
+            .get(circuit_id)
+            .ok_or_else(|| ComponentError::UnknownCircuit(circuit_id.to_string()))?;
+        verify_proof(key.pvk, proof_str, pub_inputs_str)
+    }
+
+    /// Checks the unveiled key and seed against the hidden key of
+    /// `circuit_id`, then rotates the key into `registry` and forgets the
+    /// hidden key. Returns the entry the key replaced, if the circuit was
+    /// already registered. Only call this from owner methods.
+    ///
+    /// # Errors
+    /// ComponentError::UnknownCircuit, ComponentError::FingerprintMismatch,
+    /// ComponentError::BlindingMismatch
+    ///
+    /// This function will also return an error if the key fails to parse.
+    pub fn unveil(
+        &mut self,
+        registry: &mut VkeyRegistry,
+        circuit_id: &str,
+        vkey_str: String,
+        seed: &[u8; 32],
+        layout: LimbLayout,
+    ) -> Result<Option<CircuitEntry>> {
+        let key = self
+            .get(circuit_id)
+            .ok_or_else(|| ComponentError::UnknownCircuit(circuit_id.to_string()))?;
+        let pvk = get_prepared_verifying_key(parse_verification_key(vkey_str.clone())?)?;
+        if key_commitment(&pvk.fingerprint(), seed) != key.commitment {
+            return Err(ComponentError::FingerprintMismatch.into());
+        }
+        if hide_verification_key(vkey_str, seed)?.pvk != key.pvk {
+            return Err(ComponentError::BlindingMismatch.into());
+        }
+
+        self.keys.remove(&circuit_id.to_string());
+        env::log_str(&format!(
+            "Circuit {} unveiled with key {}",
+            circuit_id,
+            hex::encode(pvk.fingerprint())
+        ));
+        Ok(registry.rotate(circuit_id, pvk, layout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::multi::AnyPreparedVerifyingKey;
+    use crate::verifier::near::{fixtures, parse_proof};
+    use electron_testkit::multiplier::Multiplier;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    const SEED: [u8; 32] = [7; 32];
+
+    fn blind_proof(proof_str: String, seed: &[u8; 32]) -> String {
+        let proof = parse_proof(proof_str).unwrap();
+        let blinded = Blinding::<ark_bn254::Bn254>::from_seed(seed).blind_proof(&proof);
+        electron_testkit::json::proof_json(&blinded)
+    }
+
+    #[test]
+    fn test_unveil_rotates_key() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut registry = VkeyRegistry::new(b"r".to_vec());
+        registry
+            .register_json("age", fixtures::vkey().to_string(), LimbLayout::default())
+            .unwrap();
+        let mut multiplier = Multiplier::setup();
+        let key = hide_verification_key(multiplier.vkey_json(), &SEED).unwrap();
+        assert_ne!(key.pvk, fixtures::pvk());
+        let mut hidden = HiddenKeys::new(b"h".to_vec());
+        hidden.hide("age", &key);
+
+        let (proof, inputs) = multiplier.prove(3, 5);
+        assert!(hidden
+            .verify_proof("age", blind_proof(proof.clone(), &SEED), inputs.clone())
+            .unwrap());
+        assert!(!hidden
+            .verify_proof("age", proof.clone(), inputs.clone())
+            .unwrap());
+        assert!(!hidden
+            .verify_proof("age", blind_proof(proof.clone(), &[8; 32]), inputs.clone())
+            .unwrap());
+
+        let previous = hidden
+            .unveil(
+                &mut registry,
+                "age",
+                multiplier.vkey_json(),
+                &SEED,
+                LimbLayout::default(),
+            )
+            .unwrap();
+        assert_eq!(
+            previous.unwrap().pvk,
+            AnyPreparedVerifyingKey::from(fixtures::pvk())
+        );
+        assert!(registry
+            .verify_proof("age", proof.clone(), inputs.clone())
+            .unwrap());
+        assert!(hidden.get("age").is_none());
+        assert_eq!(
+            hidden
+                .verify_proof("age", proof, inputs)
+                .err()
+                .unwrap()
+                .to_string(),
+            "Unknown circuit age"
+        );
+    }
+
+    #[test]
+    fn test_unveil_rejects_other_keys() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut registry = VkeyRegistry::new(b"r".to_vec());
+        let multiplier = Multiplier::setup();
+        let key = hide_verification_key(multiplier.vkey_json(), &SEED).unwrap();
+        let mut hidden = HiddenKeys::new(b"h".to_vec());
+        hidden.hide("mul", &key);
+
+        let mut unveil = |vkey: String, seed: &[u8; 32]| {
+            hidden
+                .unveil(&mut registry, "mul", vkey, seed, LimbLayout::default())
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        let mismatch = "Verifying key does not match the expected fingerprint";
+        assert_eq!(unveil(fixtures::vkey().to_string(), &SEED), mismatch);
+        assert_eq!(unveil(multiplier.vkey_json(), &[8; 32]), mismatch);
+
+        // The commitment opens, but the stored key was blinded with another
+        // seed.
+        hidden.hide(
+            "mul",
+            &HiddenKey {
+                pvk: hide_verification_key(multiplier.vkey_json(), &[8; 32])
+                    .unwrap()
+                    .pvk,
+                commitment: key.commitment,
+            },
+        );
+        assert_eq!(
+            hidden
+                .unveil(
+                    &mut registry,
+                    "mul",
+                    multiplier.vkey_json(),
+                    &SEED,
+                    LimbLayout::default()
+                )
+                .err()
+                .unwrap()
+                .to_string(),
+            "Verifying key does not match the blinded key"
+        );
+        assert!(registry.is_empty());
+        assert!(hidden.get("mul").is_some());
+    }
+}
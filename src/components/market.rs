@@ -0,0 +1,227 @@
+// Copyright © 2022, Electron Labs
+
+//! Escrowed bounties for outsourced proving.
+//!
+//! A requester escrows the attached deposit for a proof of a given circuit
+//! and public inputs. The first relayer whose proof verifies in
+//! [`ProofMarket::verify_and_settle`] receives the reward; the bounty is
+//! removed before the transfer is scheduled so it can only be paid once.
+
+use crate::components::ComponentError;
+use crate::verifier::near::{verify_proof, PreparedVerifyingKey};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::{env, AccountId, Balance, IntoStorageKey, Promise};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct Bounty {
+    pub requester: AccountId,
+    pub circuit: [u8; 32],
+    pub pub_inputs: String,
+    pub reward: Balance,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ProofMarket {
+    next_id: u64,
+    bounties: LookupMap<u64, Bounty>,
+}
+
+impl ProofMarket {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        ProofMarket {
+            next_id: 0,
+            bounties: LookupMap::new(prefix),
+        }
+    }
+
+    /// Escrows the attached deposit for a proof of `pub_inputs_str` against
+    /// the circuit with the given key fingerprint, and returns the bounty id.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no deposit is attached.
+    pub fn post_bounty(&mut self, circuit: [u8; 32], pub_inputs_str: String) -> Result<u64> {
+        let reward = env::attached_deposit();
+        if reward == 0 {
+            return Err(ComponentError::InsufficientFee {
+                required: 1,
+                attached: 0,
+            }
+            .into());
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.bounties.insert(
+            &id,
+            &Bounty {
+                requester: env::predecessor_account_id(),
+                circuit,
+                pub_inputs: pub_inputs_str,
+                reward,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Verifies `proof_str` for the bounty and pays the reward to the
+    /// predecessor account.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the bounty doesn't exist, if
+    /// `pvk` is not the key of the bounty circuit, or if the proof does not
+    /// verify. The bounty stays open in every error case.
+    pub fn verify_and_settle(
+        &mut self,
+        bounty_id: u64,
+        pvk: PreparedVerifyingKey,
+        proof_str: String,
+    ) -> Result<Promise> {
+        let bounty = self
+            .bounties
+            .get(&bounty_id)
+            .ok_or(ComponentError::UnknownBounty(bounty_id))?;
+        if pvk.fingerprint() != bounty.circuit {
+            return Err(ComponentError::FingerprintMismatch.into());
+        }
+        if !verify_proof(pvk, proof_str, bounty.pub_inputs)? {
+            return Err(ComponentError::VerificationFailed.into());
+        }
+        self.bounties.remove(&bounty_id);
+        Ok(Promise::new(env::predecessor_account_id()).transfer(bounty.reward))
+    }
+
+    /// Refunds an unclaimed bounty to its requester.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the bounty doesn't exist or the
+    /// predecessor is not the requester.
+    pub fn cancel_bounty(&mut self, bounty_id: u64) -> Result<Promise> {
+        let bounty = self
+            .bounties
+            .get(&bounty_id)
+            .ok_or(ComponentError::UnknownBounty(bounty_id))?;
+        if env::predecessor_account_id() != bounty.requester {
+            return Err(ComponentError::Unauthorized.into());
+        }
+        self.bounties.remove(&bounty_id);
+        Ok(Promise::new(bounty.requester).transfer(bounty.reward))
+    }
+
+    pub fn get_bounty(&self, bounty_id: u64) -> Option<Bounty> {
+        self.bounties.get(&bounty_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::fixtures;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn context(account: &str, deposit: Balance) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(account.parse().unwrap())
+            .attached_deposit(deposit)
+            .build());
+    }
+
+    #[test]
+    fn test_post_and_cancel_bounty() {
+        let mut market = ProofMarket::new(b"m".to_vec());
+        context("alice.near", 1_000);
+        let id = market
+            .post_bounty([1u8; 32], "[\"1\"]".to_string())
+            .unwrap();
+        assert_eq!(market.get_bounty(id).unwrap().reward, 1_000);
+
+        context("bob.near", 0);
+        assert!(market.cancel_bounty(id).is_err());
+
+        context("alice.near", 0);
+        market.cancel_bounty(id).unwrap();
+        assert!(market.get_bounty(id).is_none());
+    }
+
+    #[test]
+    fn test_post_bounty_without_deposit() {
+        let mut market = ProofMarket::new(b"m".to_vec());
+        context("alice.near", 0);
+        assert!(market.post_bounty([1u8; 32], "[]".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_verify_and_settle() {
+        let pvk = fixtures::pvk();
+        let mut market = ProofMarket::new(b"m".to_vec());
+        context("alice.near", 1_000);
+        let id = market
+            .post_bounty(pvk.fingerprint(), fixtures::public_inputs().to_string())
+            .unwrap();
+
+        context("bob.near", 0);
+        market
+            .verify_and_settle(id, pvk.clone(), fixtures::proof().to_string())
+            .unwrap();
+        assert!(market.get_bounty(id).is_none());
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id.as_str(), "bob.near");
+        assert!(matches!(
+            receipts[0].actions.as_slice(),
+            [VmAction::Transfer { deposit: 1_000 }]
+        ));
+
+        let err = market
+            .verify_and_settle(id, pvk, fixtures::proof().to_string())
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), format!("Unknown bounty {}", id));
+    }
+
+    #[test]
+    fn test_settle_with_other_circuit() {
+        let mut market = ProofMarket::new(b"m".to_vec());
+        context("alice.near", 1_000);
+        let id = market
+            .post_bounty([1u8; 32], fixtures::public_inputs().to_string())
+            .unwrap();
+
+        context("bob.near", 0);
+        let err = market
+            .verify_and_settle(id, fixtures::pvk(), fixtures::proof().to_string())
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ComponentError>(),
+            Some(ComponentError::FingerprintMismatch)
+        ));
+        assert!(market.get_bounty(id).is_some());
+    }
+
+    #[test]
+    fn test_settle_with_invalid_proof() {
+        let pvk = fixtures::pvk();
+        let mut market = ProofMarket::new(b"m".to_vec());
+        context("alice.near", 1_000);
+        let inputs = fixtures::public_inputs().replacen("\"1\"", "\"2\"", 1);
+        let id = market.post_bounty(pvk.fingerprint(), inputs).unwrap();
+
+        context("bob.near", 0);
+        let err = market
+            .verify_and_settle(id, pvk, fixtures::proof().to_string())
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ComponentError>(),
+            Some(ComponentError::VerificationFailed)
+        ));
+        assert_eq!(market.get_bounty(id).unwrap().reward, 1_000);
+        assert!(get_created_receipts().is_empty());
+    }
+}
@@ -0,0 +1,327 @@
+// Copyright © 2022, Electron Labs
+
+//! Cross-chain messages that become executable once a zk-verified root
+//! containing them is registered.
+//!
+//! The sending contract keeps an [`Outbox`]. Every message gets the next
+//! nonce, and its id is leaf `nonce` of the outbox Merkle tree, whose root
+//! a light client on the other chain proves. The receiving contract keeps
+//! an [`Inbox`]: [`Inbox::register_root`] verifies a proof carrying a root
+//! as two limbs of its public inputs, and [`Inbox::execute`] accepts a
+//! message with its Merkle path to a registered root, once per message id.
+//!
+//! ```ignore
+//! pub fn relay(&mut self, message: Message, root: [u8; 32], path: Vec<[u8; 32]>) {
+//!     self.inbox.execute(&message, &root, &path).unwrap();
+//!     self.handle(message.payload);
+//! }
+//! ```
+//!
+//! The tree hashes nodes as `committed_batch` does, and message ids are
+//! `sha256(0x00 || borsh(message))`.
+
+use crate::components::committed_batch::{merkle_path, merkle_root, verify_merkle_path};
+use crate::components::ComponentError;
+use crate::hash::limbs::LimbLayout;
+use crate::verifier::near::multi::AnyPreparedVerifyingKey;
+use crate::verifier::near::public_inputs::PublicInputs;
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, LookupSet, Vector};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::{json, Value};
+use near_sdk::{env, IntoStorageKey};
+use sha2::{Digest, Sha256};
+use std::convert::TryFrom;
+
+const MESSAGE_TAG: u8 = 0;
+
+/// Logs a NEP-297 event. The recipient is any string, so events are built
+/// with serde rather than by formatting.
+fn log_event(event: &str, data: Value) {
+    let event = json!({
+        "standard": "electron-messaging",
+        "version": "1.0.0",
+        "event": event,
+        "data": [data],
+    });
+    env::log_str(&format!("EVENT_JSON:{}", event));
+}
+
+/// A message between chains. Accounts are strings, since the other chain
+/// may not use NEAR account ids.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Message {
+    pub nonce: u64,
+    pub sender: String,
+    pub recipient: String,
+    pub payload: Vec<u8>,
+}
+
+impl Message {
+    /// The id of the message, which is also its leaf in the outbox tree.
+    pub fn id(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update([MESSAGE_TAG]);
+        hasher.update(self.try_to_vec().unwrap());
+        hasher.finalize().into()
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct Outbox {
+    ids: Vector<[u8; 32]>,
+}
+
+impl Outbox {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        Outbox {
+            ids: Vector::new(prefix),
+        }
+    }
+
+    /// Appends a message from the predecessor account and logs a
+    /// `message_sent` event.
+    pub fn send(&mut self, recipient: String, payload: Vec<u8>) -> Message {
+        let message = Message {
+            nonce: self.ids.len(),
+            sender: env::predecessor_account_id().to_string(),
+            recipient,
+            payload,
+        };
+        let id = message.id();
+        self.ids.push(&id);
+        log_event(
+            "message_sent",
+            json!({
+                "nonce": message.nonce,
+                "id": hex::encode(id),
+                "recipient": message.recipient,
+            }),
+        );
+        message
+    }
+
+    pub fn len(&self) -> u64 {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Root of the tree of every message sent so far. Reads every id, so
+    /// relayers compute it off chain for large outboxes.
+    pub fn root(&self) -> [u8; 32] {
+        merkle_root(&self.ids.to_vec())
+    }
+
+    /// Merkle path of message `nonce` under `root()`, or `None` if no such
+    /// message was sent.
+    pub fn path(&self, nonce: u64) -> Option<Vec<[u8; 32]>> {
+        merkle_path(&self.ids.to_vec(), usize::try_from(nonce).ok()?)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct Inbox {
+    root_index: usize,
+    layout: LimbLayout,
+    roots: LookupMap<[u8; 32], u64>,
+    executed: LookupSet<[u8; 32]>,
+}
+
+impl Inbox {
+    /// An inbox taking roots from public inputs `root_index` and
+    /// `root_index + 1`, as limbs in `layout`.
+    pub fn new<S: IntoStorageKey>(prefix: S, root_index: usize, layout: LimbLayout) -> Self {
+        let prefix = prefix.into_storage_key();
+        Inbox {
+            root_index,
+            layout,
+            roots: LookupMap::new([prefix.as_slice(), &b"r"[..]].concat()),
+            executed: LookupSet::new([prefix.as_slice(), &b"e"[..]].concat()),
+        }
+    }
+
+    /// Verifies a proof of the other chain's outbox root and registers the
+    /// root, returning it.
+    ///
+    /// # Errors
+    /// ComponentError::RootIndexOutOfRange, ComponentError::VerificationFailed
+    ///
+    /// This function will also return an error if the proof or inputs fail
+    /// to parse, or if the limbs of the root don't fit in 128 bits.
+    pub fn register_root(
+        &mut self,
+        pvk: impl Into<AnyPreparedVerifyingKey>,
+        proof_str: String,
+        pub_inputs_str: String,
+    ) -> Result<[u8; 32]> {
+        let inputs = PublicInputs::parse(pub_inputs_str.clone())?;
+        let limbs = inputs
+            .as_slice()
+            .get(self.root_index..self.root_index + 2)
+            .ok_or(ComponentError::RootIndexOutOfRange(self.root_index))?;
+        let root = self.layout.join(&[limbs[0], limbs[1]])?;
+        if !pvk.into().verify_proof(proof_str, pub_inputs_str)? {
+            return Err(ComponentError::VerificationFailed.into());
+        }
+        self.roots.insert(&root, &env::block_timestamp());
+        Ok(root)
+    }
+
+    pub fn is_root(&self, root: &[u8; 32]) -> bool {
+        self.roots.contains_key(root)
+    }
+
+    /// Accepts a message included under a registered root and marks it
+    /// executed, logging a `message_executed` event. Returns the message
+    /// id.
+    ///
+    /// # Errors
+    /// ComponentError::UnknownRoot, ComponentError::MessageNotInRoot,
+    /// ComponentError::MessageExecuted
+    pub fn execute(
+        &mut self,
+        message: &Message,
+        root: &[u8; 32],
+        path: &[[u8; 32]],
+    ) -> Result<[u8; 32]> {
+        if !self.is_root(root) {
+            return Err(ComponentError::UnknownRoot.into());
+        }
+        let id = message.id();
+        let index = u32::try_from(message.nonce).map_err(|_| ComponentError::MessageNotInRoot)?;
+        if !verify_merkle_path(root, &id, index, path) {
+            return Err(ComponentError::MessageNotInRoot.into());
+        }
+        if !self.executed.insert(&id) {
+            return Err(ComponentError::MessageExecuted.into());
+        }
+        log_event(
+            "message_executed",
+            json!({ "nonce": message.nonce, "id": hex::encode(id) }),
+        );
+        Ok(id)
+    }
+
+    pub fn is_executed(&self, id: &[u8; 32]) -> bool {
+        self.executed.contains(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::limbs::digest_to_limbs;
+    use crate::verifier::near::prepare_verification_key;
+    use electron_testkit::wide::Wide;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn outbox_with(count: u8) -> Outbox {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bridge.near".parse().unwrap())
+            .build());
+        let mut outbox = Outbox::new(b"o".to_vec());
+        for i in 0..count {
+            outbox.send("0xrecipient".to_string(), vec![i]);
+        }
+        outbox
+    }
+
+    fn last_event() -> Value {
+        let logs = get_logs();
+        let event = logs.last().unwrap().strip_prefix("EVENT_JSON:").unwrap();
+        near_sdk::serde_json::from_str(event).unwrap()
+    }
+
+    #[test]
+    fn test_recipient_is_escaped() {
+        let mut outbox = outbox_with(0);
+        let recipient = "0x\",\"event\":\"message_executed";
+        outbox.send(recipient.to_string(), vec![]);
+        let event = last_event();
+        assert_eq!(event["event"], "message_sent");
+        assert_eq!(event["data"][0]["recipient"], recipient);
+    }
+
+    fn message(nonce: u8) -> Message {
+        Message {
+            nonce: nonce as u64,
+            sender: "bridge.near".to_string(),
+            recipient: "0xrecipient".to_string(),
+            payload: vec![nonce],
+        }
+    }
+
+    #[test]
+    fn test_outbox() {
+        let outbox = outbox_with(3);
+        assert_eq!(outbox.len(), 3);
+        let event = last_event();
+        assert_eq!(event["event"], "message_sent");
+        assert_eq!(event["data"][0]["nonce"], 2);
+        let path = outbox.path(2).unwrap();
+        assert!(verify_merkle_path(
+            &outbox.root(),
+            &message(2).id(),
+            2,
+            &path
+        ));
+        assert!(outbox.path(3).is_none());
+    }
+
+    #[test]
+    fn test_execute_after_root_is_registered() {
+        let outbox = outbox_with(3);
+        let root = outbox.root();
+        let path = outbox.path(1).unwrap();
+
+        // Wide stands in for a light client circuit exposing the root.
+        let mut wide = Wide::setup(2);
+        let pvk = prepare_verification_key(wide.vkey_json()).unwrap();
+        let (proof, inputs) = wide.prove_inputs(&digest_to_limbs(&root));
+
+        let mut inbox = Inbox::new(b"i".to_vec(), 0, LimbLayout::default());
+        assert_eq!(
+            inbox
+                .execute(&message(1), &root, &path)
+                .unwrap_err()
+                .to_string(),
+            "Root is not registered"
+        );
+
+        let (other_proof, _) = wide.prove_inputs(&digest_to_limbs(&[7; 32]));
+        assert!(inbox
+            .register_root(pvk.clone(), other_proof, inputs.clone())
+            .is_err());
+        assert_eq!(inbox.register_root(pvk, proof, inputs).unwrap(), root);
+
+        let mut forged = message(1);
+        forged.payload = vec![9];
+        assert_eq!(
+            inbox
+                .execute(&forged, &root, &path)
+                .unwrap_err()
+                .to_string(),
+            "Message is not included under the root"
+        );
+
+        let id = inbox.execute(&message(1), &root, &path).unwrap();
+        assert!(inbox.is_executed(&id));
+        assert!(get_logs()
+            .last()
+            .unwrap()
+            .contains("\"event\":\"message_executed\""));
+        assert_eq!(
+            inbox
+                .execute(&message(1), &root, &path)
+                .unwrap_err()
+                .to_string(),
+            "Message was already executed"
+        );
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright © 2022, Electron Labs
+
+//! Proofs submitted through NEP-366 meta transactions.
+//!
+//! With a meta transaction the user signs a `DelegateAction` off chain and
+//! a relayer submits it and pays for gas. The contract call then has the
+//! user as predecessor and the relayer as signer. Anyone who sees the proof
+//! on its way could submit it for their own account instead, so the circuit
+//! must bind the user's account in a public input, and
+//! [`ProofPayload::verify_for_sender`] checks that binding against the
+//! predecessor.
+
+use crate::components::ComponentError;
+use crate::verifier::near::public_inputs::PublicInputs;
+use crate::verifier::near::{verify_proof, PreparedVerifyingKey};
+use anyhow::Result;
+use ark_ff::PrimeField;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, AccountId};
+use sha2::{Digest, Sha256};
+
+/// Arguments of a contract method taking a relayed proof.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProofPayload {
+    pub proof: String,
+    pub public_inputs: String,
+}
+
+/// The public input binding `account`: the sha256 of the account id, read
+/// big-endian and reduced into the field.
+pub fn account_input(account: &AccountId) -> ark_bn254::Fr {
+    ark_bn254::Fr::from_be_bytes_mod_order(&Sha256::digest(account.as_str().as_bytes()))
+}
+
+impl ProofPayload {
+    /// Verifies the proof and checks that its public input at
+    /// `account_index` binds the predecessor, i.e. the user who signed the
+    /// delegate action. Returns that account.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the proof is bound to another
+    /// account, or if it fails to parse or verify.
+    pub fn verify_for_sender(
+        self,
+        pvk: PreparedVerifyingKey,
+        account_index: usize,
+    ) -> Result<AccountId> {
+        let sender = env::predecessor_account_id();
+        let inputs = PublicInputs::parse(self.public_inputs.clone())?;
+        if inputs.as_slice().get(account_index) != Some(&account_input(&sender)) {
+            return Err(ComponentError::AccountMismatch.into());
+        }
+        if !verify_proof(pvk, self.proof, self.public_inputs)? {
+            return Err(ComponentError::VerificationFailed.into());
+        }
+        Ok(sender)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::{get_prepared_verifying_key, parse_verification_key};
+    use ark_ff::One;
+    use electron_testkit::multiplier::Multiplier;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn relayed_by(sender: &str) {
+        testing_env!(VMContextBuilder::new()
+            .signer_account_id("relayer.near".parse().unwrap())
+            .predecessor_account_id(sender.parse().unwrap())
+            .build());
+    }
+
+    fn payload_for(account: &str) -> (PreparedVerifyingKey, ProofPayload) {
+        let mut multiplier = Multiplier::setup();
+        let vkey = parse_verification_key(multiplier.vkey_json()).unwrap();
+        let account = account_input(&account.parse().unwrap());
+        let (proof, public_inputs) = multiplier.prove_fr(account, ark_bn254::Fr::one());
+        let payload = ProofPayload {
+            proof,
+            public_inputs,
+        };
+        (get_prepared_verifying_key(vkey).unwrap(), payload)
+    }
+
+    #[test]
+    fn test_verify_for_sender() {
+        relayed_by("alice.near");
+        let (pvk, payload) = payload_for("alice.near");
+        let sender = payload.verify_for_sender(pvk, 0).unwrap();
+        assert_eq!(sender.as_str(), "alice.near");
+    }
+
+    #[test]
+    fn test_verify_for_other_sender() {
+        relayed_by("bob.near");
+        let (pvk, payload) = payload_for("alice.near");
+        let err = payload.verify_for_sender(pvk, 0).err().unwrap();
+        assert_eq!(err.to_string(), "Proof is not bound to the sender account");
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright © 2022, Electron Labs
+
+//! Reusable storage components for verifier contracts.
+
+use near_sdk::Balance;
+use thiserror::Error;
+
+pub mod attestation;
+pub mod challenge;
+pub mod circuit_breaker;
+pub mod committed_batch;
+pub mod fees;
+pub mod hidden_key;
+pub mod market;
+pub mod messaging;
+pub mod meta_tx;
+pub mod nullifier;
+pub mod queue;
+pub mod rate_limit;
+pub mod registry;
+pub mod remote_key;
+pub mod replay;
+pub mod session;
+
+#[derive(Error, Debug)]
+pub enum ComponentError {
+    #[error("Insufficient fee: attached {attached}, required {required}")]
+    InsufficientFee {
+        required: Balance,
+        attached: Balance,
+    },
+    #[error("Rate limit exceeded, retry after {retry_after} ns")]
+    RateLimited { retry_after: u64 },
+    #[error("Token {0} already exists")]
+    DuplicateToken(String),
+    #[error("Proof verification failed")]
+    VerificationFailed,
+    #[error("Unknown bounty {0}")]
+    UnknownBounty(u64),
+    #[error("Verifying key does not match the expected fingerprint")]
+    FingerprintMismatch,
+    #[error("Verifying key does not match the blinded key")]
+    BlindingMismatch,
+    #[error("Caller is not authorized")]
+    Unauthorized,
+    #[error("No key fingerprint pinned for circuit {0}")]
+    UnpinnedCircuit(String),
+    #[error("Failed to fetch verifying key")]
+    KeyFetchFailed,
+    #[error("No challenge issued")]
+    NoChallenge,
+    #[error("Challenge expired")]
+    ChallengeExpired,
+    #[error("Proof is not bound to the issued challenge")]
+    ChallengeMismatch,
+    #[error("Proof is not bound to the sender account")]
+    AccountMismatch,
+    #[error("No active session for circuit {0}")]
+    NoSession(String),
+    #[error("Proof is already queued")]
+    AlreadyQueued,
+    #[error("Circuit {0} is frozen")]
+    CircuitFrozen(String),
+    #[error("No compromise key set for circuit {0}")]
+    NoCompromiseKey(String),
+    #[error("Proof was submitted recently")]
+    Replayed,
+    #[error("Unknown batch {0}")]
+    UnknownBatch(u64),
+    #[error("Leaf {index} is not in batch {batch_id}")]
+    NotInBatch { batch_id: u64, index: u32 },
+    #[error("Circuit {0} is already registered")]
+    CircuitExists(String),
+    #[error("Unknown circuit {0}")]
+    UnknownCircuit(String),
+    #[error("Nullifier is already spent")]
+    NullifierSpent,
+    #[error("Public input {0} is out of range for the nullifier")]
+    NullifierIndexOutOfRange(usize),
+    #[error("Invalid registry archive: {0}")]
+    InvalidArchive(String),
+    #[error("Contract is paused")]
+    Paused,
+    #[error("No verifying key set")]
+    NoVerifyingKey,
+    #[error("Public input {0} is out of range for the root")]
+    RootIndexOutOfRange(usize),
+    #[error("Root is not registered")]
+    UnknownRoot,
+    #[error("Message is not included under the root")]
+    MessageNotInRoot,
+    #[error("Message was already executed")]
+    MessageExecuted,
+}
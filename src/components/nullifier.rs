@@ -0,0 +1,120 @@
+// Copyright © 2022, Electron Labs
+
+//! Spent nullifiers of privacy applications.
+//!
+//! A proof exposes a nullifier as one of its public inputs. Once the proof
+//! verifies, the nullifier is recorded, and any later proof with the same
+//! nullifier is rejected before verification.
+//!
+//! ```ignore
+//! pub fn withdraw(&mut self, proof: String, inputs: String) -> bool {
+//!     self.nullifiers
+//!         .verify_and_consume(self.pvk.clone(), proof, inputs, NULLIFIER_INDEX)
+//!         .unwrap()
+//! }
+//! ```
+
+use crate::components::ComponentError;
+use crate::verifier::near::multi::AnyPreparedVerifyingKey;
+use crate::verifier::near::public_inputs::PublicInputs;
+use anyhow::Result;
+use ark_ff::{BigInteger, PrimeField};
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupSet;
+use near_sdk::IntoStorageKey;
+
+/// Storage key of a nullifier: its 32-byte big-endian encoding.
+fn nullifier_key(nullifier: &ark_bn254::Fr) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&nullifier.into_repr().to_bytes_be());
+    key
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct NullifierSet {
+    spent: LookupSet<[u8; 32]>,
+}
+
+impl NullifierSet {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        NullifierSet {
+            spent: LookupSet::new(prefix),
+        }
+    }
+
+    pub fn is_spent(&self, nullifier: &ark_bn254::Fr) -> bool {
+        self.spent.contains(&nullifier_key(nullifier))
+    }
+
+    /// Verifies the proof and records public input `nullifier_index` as
+    /// spent if it verifies. A proof that doesn't verify leaves the
+    /// nullifier unspent. Takes keys of any curve, such as the keys of a
+    /// `VkeyRegistry`.
+    ///
+    /// # Errors
+    /// ComponentError::NullifierIndexOutOfRange, ComponentError::NullifierSpent
+    ///
+    /// This function will also return an error if the proof or inputs fail
+    /// to parse.
+    pub fn verify_and_consume(
+        &mut self,
+        pvk: impl Into<AnyPreparedVerifyingKey>,
+        proof_str: String,
+        pub_inputs_str: String,
+        nullifier_index: usize,
+    ) -> Result<bool> {
+        let inputs = PublicInputs::parse(pub_inputs_str.clone())?;
+        let nullifier = inputs
+            .as_slice()
+            .get(nullifier_index)
+            .ok_or(ComponentError::NullifierIndexOutOfRange(nullifier_index))?;
+        let key = nullifier_key(nullifier);
+        if self.spent.contains(&key) {
+            return Err(ComponentError::NullifierSpent.into());
+        }
+        if !pvk.into().verify_proof(proof_str, pub_inputs_str)? {
+            return Ok(false);
+        }
+        self.spent.insert(&key);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::{fixtures, prepare_verification_key};
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn test_verify_and_consume() {
+        testing_env!(VMContextBuilder::new().build());
+        let pvk = prepare_verification_key(fixtures::vkey().to_string()).unwrap();
+        let proof = fixtures::proof().to_string();
+        let inputs = fixtures::public_inputs().to_string();
+        let mut nullifiers = NullifierSet::new(b"n".to_vec());
+
+        let invalid = inputs.replacen("\"1\"", "\"2\"", 1);
+        assert!(!nullifiers
+            .verify_and_consume(pvk.clone(), proof.clone(), invalid, 1)
+            .unwrap());
+        assert!(nullifiers
+            .verify_and_consume(pvk.clone(), proof.clone(), inputs.clone(), 21)
+            .is_err());
+
+        assert!(nullifiers
+            .verify_and_consume(pvk.clone(), proof.clone(), inputs.clone(), 1)
+            .unwrap());
+        let nullifier = PublicInputs::parse(inputs.clone()).unwrap().as_slice()[1];
+        assert!(nullifiers.is_spent(&nullifier));
+        assert_eq!(
+            nullifiers
+                .verify_and_consume(pvk, proof, inputs, 1)
+                .err()
+                .unwrap()
+                .to_string(),
+            "Nullifier is already spent"
+        );
+    }
+}
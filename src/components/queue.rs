@@ -0,0 +1,266 @@
+// Copyright © 2022, Electron Labs
+
+//! A queue of proofs verified after submission.
+//!
+//! Submitters pay a bounty with [`ProofQueue::enqueue`], which only stores
+//! the proof under its hash. Anyone can later call
+//! [`ProofQueue::process_next`] to verify the oldest proofs and collect
+//! their bounties, so a burst of submissions doesn't have to be verified in
+//! the block it arrives in. Results stay readable with
+//! [`ProofQueue::result`].
+
+use crate::components::ComponentError;
+use crate::verifier::near::{verify_proof, PreparedVerifyingKey};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::{env, AccountId, Balance, IntoStorageKey, Promise};
+use std::convert::TryInto;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct QueuedProof {
+    pub submitter: AccountId,
+    pub proof: String,
+    pub pub_inputs: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ProofQueue {
+    circuit: [u8; 32],
+    bounty: Balance,
+    head: u64,
+    tail: u64,
+    order: LookupMap<u64, [u8; 32]>,
+    pending: LookupMap<[u8; 32], QueuedProof>,
+    results: LookupMap<[u8; 32], bool>,
+}
+
+/// Hash under which a proof is queued, of the submitted json with its
+/// whitespace removed. It doesn't parse anything, so a submission can be
+/// hashed and rejected before any parsing or pairing work.
+///
+/// Only whitespace is canonicalized: the same proof with its keys in
+/// another order or its numbers in hex hashes differently. A Groth16 proof
+/// can also be re-randomized into another valid proof of the same inputs.
+/// Where a statement must only be proven once, reject it by a public input
+/// the prover can't change, as `nullifier::NullifierSet` does.
+pub fn proof_hash(proof_str: &str, pub_inputs_str: &str) -> [u8; 32] {
+    let strip = |json: &str| -> Vec<u8> {
+        json.bytes()
+            .filter(|byte| !matches!(byte, b' ' | b'\t' | b'\n' | b'\r'))
+            .collect()
+    };
+    let proof = strip(proof_str);
+    let mut preimage = (proof.len() as u64).to_le_bytes().to_vec();
+    preimage.extend(proof);
+    preimage.extend(strip(pub_inputs_str));
+    env::sha256(&preimage).try_into().unwrap()
+}
+
+impl ProofQueue {
+    /// A queue for proofs of the circuit with the given key fingerprint,
+    /// paying `bounty` per processed proof.
+    pub fn new<S: IntoStorageKey>(prefix: S, circuit: [u8; 32], bounty: Balance) -> Self {
+        let prefix = prefix.into_storage_key();
+        ProofQueue {
+            circuit,
+            bounty,
+            head: 0,
+            tail: 0,
+            order: LookupMap::new([prefix.as_slice(), &b"o"[..]].concat()),
+            pending: LookupMap::new([prefix.as_slice(), &b"p"[..]].concat()),
+            results: LookupMap::new([prefix.as_slice(), &b"r"[..]].concat()),
+        }
+    }
+
+    /// Stores a proof for later verification and returns its hash. The
+    /// attached deposit must cover the bounty. Nothing is parsed until the
+    /// proof is processed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the deposit is too small, or if
+    /// the same proof and inputs are already queued or processed.
+    pub fn enqueue(&mut self, proof_str: String, pub_inputs_str: String) -> Result<[u8; 32]> {
+        let attached = env::attached_deposit();
+        if attached < self.bounty {
+            return Err(ComponentError::InsufficientFee {
+                required: self.bounty,
+                attached,
+            }
+            .into());
+        }
+        let hash = proof_hash(&proof_str, &pub_inputs_str);
+        if self.pending.contains_key(&hash) || self.results.contains_key(&hash) {
+            return Err(ComponentError::AlreadyQueued.into());
+        }
+        self.pending.insert(
+            &hash,
+            &QueuedProof {
+                submitter: env::predecessor_account_id(),
+                proof: proof_str,
+                pub_inputs: pub_inputs_str,
+            },
+        );
+        self.order.insert(&self.tail, &hash);
+        self.tail += 1;
+        Ok(hash)
+    }
+
+    /// Verifies up to `n` of the oldest queued proofs and pays their
+    /// bounties to the predecessor. Proofs that fail to parse are recorded
+    /// as invalid and the parse error is logged. Returns `None` if the queue
+    /// was empty.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `pvk` is not the key of the
+    /// queue's circuit.
+    pub fn process_next(&mut self, pvk: &PreparedVerifyingKey, n: u64) -> Result<Option<Promise>> {
+        if pvk.fingerprint() != self.circuit {
+            return Err(ComponentError::FingerprintMismatch.into());
+        }
+        let mut processed: u64 = 0;
+        while processed < n && self.head < self.tail {
+            let hash = self.order.remove(&self.head).unwrap();
+            self.head += 1;
+            let queued = self.pending.remove(&hash).unwrap();
+            let valid = match verify_proof(pvk.clone(), queued.proof, queued.pub_inputs) {
+                Ok(valid) => valid,
+                Err(err) => {
+                    env::log_str(&format!(
+                        "Queued proof {} is invalid: {}",
+                        hex::encode(hash),
+                        err
+                    ));
+                    false
+                }
+            };
+            self.results.insert(&hash, &valid);
+            processed += 1;
+        }
+        if processed == 0 {
+            return Ok(None);
+        }
+        let reward = self.bounty * processed as Balance;
+        Ok(Some(
+            Promise::new(env::predecessor_account_id()).transfer(reward),
+        ))
+    }
+
+    pub fn len(&self) -> u64 {
+        self.tail - self.head
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head == self.tail
+    }
+
+    pub fn get_pending(&self, hash: &[u8; 32]) -> Option<QueuedProof> {
+        self.pending.get(hash)
+    }
+
+    /// Whether a processed proof verified, or `None` if it is still queued
+    /// or unknown.
+    pub fn result(&self, hash: &[u8; 32]) -> Option<bool> {
+        self.results.get(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::fixtures;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn context(account: &str, deposit: Balance) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(account.parse().unwrap())
+            .attached_deposit(deposit)
+            .build());
+    }
+
+    #[test]
+    fn test_enqueue_and_process() {
+        let pvk = fixtures::pvk();
+        let mut queue = ProofQueue::new(b"q".to_vec(), pvk.fingerprint(), 100);
+
+        context("alice.near", 100);
+        let valid = queue
+            .enqueue(
+                fixtures::proof().to_string(),
+                fixtures::public_inputs().to_string(),
+            )
+            .unwrap();
+        let invalid = queue
+            .enqueue(fixtures::proof().to_string(), "[\"1\"]".to_string())
+            .unwrap();
+        assert_eq!(queue.len(), 2);
+        assert!(queue
+            .enqueue(
+                fixtures::proof().to_string(),
+                fixtures::public_inputs().to_string(),
+            )
+            .is_err());
+
+        context("bob.near", 0);
+        assert!(queue.process_next(&pvk, 1).unwrap().is_some());
+        assert_eq!(queue.result(&valid), Some(true));
+        assert_eq!(queue.result(&invalid), None);
+
+        assert!(queue.process_next(&pvk, 5).unwrap().is_some());
+        assert_eq!(queue.result(&invalid), Some(false));
+        assert!(queue.is_empty());
+        assert!(queue.process_next(&pvk, 5).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_same_submission_in_other_json() {
+        let mut queue = ProofQueue::new(b"q".to_vec(), [0u8; 32], 100);
+        context("alice.near", 100);
+        queue
+            .enqueue(
+                fixtures::proof().to_string(),
+                fixtures::public_inputs().to_string(),
+            )
+            .unwrap();
+        let reformatted = fixtures::public_inputs().replace(',', " ,\n");
+        assert!(queue
+            .enqueue(fixtures::proof().to_string(), reformatted)
+            .is_err());
+    }
+
+    #[test]
+    fn test_malformed_proof_is_processed_as_invalid() {
+        let pvk = fixtures::pvk();
+        let mut queue = ProofQueue::new(b"q".to_vec(), pvk.fingerprint(), 100);
+        context("alice.near", 100);
+        let hash = queue
+            .enqueue("{}".to_string(), fixtures::public_inputs().to_string())
+            .unwrap();
+
+        context("bob.near", 0);
+        assert!(queue.process_next(&pvk, 1).unwrap().is_some());
+        assert_eq!(queue.result(&hash), Some(false));
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].starts_with(&format!("Queued proof {} is invalid", hex::encode(hash))));
+    }
+
+    #[test]
+    fn test_enqueue_without_bounty() {
+        let mut queue = ProofQueue::new(b"q".to_vec(), [0u8; 32], 100);
+        context("alice.near", 10);
+        assert!(queue
+            .enqueue(fixtures::proof().to_string(), "[]".to_string())
+            .is_err());
+    }
+
+    #[test]
+    fn test_process_with_wrong_key() {
+        let mut queue = ProofQueue::new(b"q".to_vec(), [0u8; 32], 100);
+        context("bob.near", 0);
+        assert!(queue.process_next(&fixtures::pvk(), 1).is_err());
+    }
+}
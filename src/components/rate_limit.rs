@@ -0,0 +1,112 @@
+// Copyright © 2022, Electron Labs
+
+use crate::components::ComponentError;
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::{env, AccountId, IntoStorageKey};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+struct Bucket {
+    tokens: u32,
+    updated_at: u64,
+}
+
+/// Per-account token bucket limiting verification calls. Every account
+/// starts with `capacity` tokens and regains one every `refill_interval`
+/// nanoseconds.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct RateLimiter {
+    capacity: u32,
+    refill_interval: u64,
+    buckets: LookupMap<AccountId, Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new<S: IntoStorageKey>(prefix: S, capacity: u32, refill_interval: u64) -> Self {
+        assert!(refill_interval > 0, "Refill interval must be positive");
+        RateLimiter {
+            capacity,
+            refill_interval,
+            buckets: LookupMap::new(prefix),
+        }
+    }
+
+    /// Takes one token from the bucket of `account`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the bucket is empty.
+    pub fn consume(&mut self, account: &AccountId) -> Result<()> {
+        let now = env::block_timestamp();
+        let mut bucket = self.refilled(account, now);
+        if bucket.tokens == 0 {
+            let retry_after = bucket.updated_at + self.refill_interval - now;
+            return Err(ComponentError::RateLimited { retry_after }.into());
+        }
+        bucket.tokens -= 1;
+        self.buckets.insert(account, &bucket);
+        Ok(())
+    }
+
+    /// Tokens currently available to `account`.
+    pub fn available(&self, account: &AccountId) -> u32 {
+        self.refilled(account, env::block_timestamp()).tokens
+    }
+
+    fn refilled(&self, account: &AccountId, now: u64) -> Bucket {
+        let bucket = match self.buckets.get(account) {
+            Some(bucket) => bucket,
+            None => {
+                return Bucket {
+                    tokens: self.capacity,
+                    updated_at: now,
+                }
+            }
+        };
+        let refill = now.saturating_sub(bucket.updated_at) / self.refill_interval;
+        let tokens = (bucket.tokens as u64 + refill).min(self.capacity as u64) as u32;
+        if tokens == self.capacity {
+            Bucket {
+                tokens,
+                updated_at: now,
+            }
+        } else {
+            Bucket {
+                tokens,
+                updated_at: bucket.updated_at + refill * self.refill_interval,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn test_rate_limiter() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+        let mut limiter = RateLimiter::new(b"l".to_vec(), 2, 1_000);
+
+        testing_env!(VMContextBuilder::new().block_timestamp(10_000).build());
+        limiter.consume(&alice).unwrap();
+        limiter.consume(&alice).unwrap();
+        assert_eq!(
+            limiter.consume(&alice).err().unwrap().to_string(),
+            "Rate limit exceeded, retry after 1000 ns"
+        );
+        limiter.consume(&bob).unwrap();
+
+        testing_env!(VMContextBuilder::new().block_timestamp(11_500).build());
+        assert_eq!(limiter.available(&alice), 1);
+        limiter.consume(&alice).unwrap();
+        assert!(limiter.consume(&alice).is_err());
+
+        testing_env!(VMContextBuilder::new().block_timestamp(20_000).build());
+        assert_eq!(limiter.available(&alice), 2);
+    }
+}
@@ -0,0 +1,423 @@
+// Copyright © 2022, Electron Labs
+
+//! Prepared verification keys of many circuits, looked up by circuit id.
+//!
+//! ```ignore
+//! #[private]
+//! pub fn register(&mut self, circuit_id: String, vkey: String) {
+//!     self.registry.register_json(&circuit_id, vkey, LimbLayout::default()).unwrap();
+//! }
+//!
+//! pub fn verify(&self, circuit_id: String, proof: String, inputs: String) -> bool {
+//!     self.registry.verify_proof(&circuit_id, proof, inputs).unwrap()
+//! }
+//! ```
+//!
+//! [`VkeyRegistry::export`] writes every circuit to a single borsh archive
+//! for disaster recovery. Importing it into an empty registry, for
+//! instance in a redeployed contract, restores the same entries, and
+//! exporting again gives the same bytes, so anyone can compare the
+//! `sha256` of both archives.
+//!
+//! Keys can be over BN254 or BLS12-381, and circuits of both curves share
+//! one registry.
+//!
+//! A circuit can be frozen, by its owner or by a
+//! [`CircuitBreaker`](crate::components::circuit_breaker::CircuitBreaker)
+//! on a compromise proof, and [`VkeyRegistry::verify_proof`] fails for it
+//! until it is unfrozen. Freezes are not archived.
+//!
+//! [`VkeyRegistry::rotate`] replaces the key of a circuit, directly or when
+//! a [`HiddenKeys`](crate::components::hidden_key::HiddenKeys) key is
+//! unveiled.
+
+use crate::components::circuit_breaker::Freeze;
+use crate::components::ComponentError;
+use crate::hash::limbs::LimbLayout;
+use crate::verifier::near::multi::{prepare_any_verification_key, AnyPreparedVerifyingKey};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, UnorderedMap};
+use near_sdk::IntoStorageKey;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+/// Version of the archive format written by `VkeyRegistry::export`.
+pub const ARCHIVE_VERSION: u8 = 1;
+
+/// A registered circuit: its prepared key and the limb layout its public
+/// inputs take hashes in.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct CircuitEntry {
+    pub pvk: AnyPreparedVerifyingKey,
+    pub layout: LimbLayout,
+}
+
+/// A circuit in a registry archive, with the fingerprint of its key.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct ArchivedCircuit {
+    pub circuit_id: String,
+    pub entry: CircuitEntry,
+    pub fingerprint: [u8; 32],
+}
+
+/// Every circuit of a registry, sorted by id, with the `sha256` of their
+/// borsh encoding.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct RegistryArchive {
+    pub version: u8,
+    pub circuits: Vec<ArchivedCircuit>,
+    pub checksum: [u8; 32],
+}
+
+fn circuits_checksum(circuits: &[ArchivedCircuit]) -> [u8; 32] {
+    let bytes = circuits
+        .try_to_vec()
+        .expect("Failed to serialize archived circuits");
+    Sha256::digest(&bytes).into()
+}
+
+impl RegistryArchive {
+    /// Decodes an archive and checks its version, its checksum, that no
+    /// circuit id appears twice and the fingerprint of every key.
+    ///
+    /// # Errors
+    /// ComponentError::InvalidArchive
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        let archive = RegistryArchive::try_from_slice(bytes)
+            .map_err(|_| ComponentError::InvalidArchive("encoding".to_string()))?;
+        if archive.version != ARCHIVE_VERSION {
+            return Err(
+                ComponentError::InvalidArchive(format!("version {}", archive.version)).into(),
+            );
+        }
+        if circuits_checksum(&archive.circuits) != archive.checksum {
+            return Err(ComponentError::InvalidArchive("checksum".to_string()).into());
+        }
+        let mut ids = HashSet::new();
+        for circuit in &archive.circuits {
+            if !ids.insert(&circuit.circuit_id) {
+                return Err(ComponentError::InvalidArchive(format!(
+                    "duplicate circuit {}",
+                    circuit.circuit_id
+                ))
+                .into());
+            }
+            if circuit.entry.pvk.fingerprint() != circuit.fingerprint {
+                return Err(ComponentError::InvalidArchive(format!(
+                    "fingerprint of {}",
+                    circuit.circuit_id
+                ))
+                .into());
+            }
+        }
+        Ok(archive)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct VkeyRegistry {
+    circuits: UnorderedMap<String, CircuitEntry>,
+    frozen: LookupMap<String, Freeze>,
+}
+
+impl VkeyRegistry {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        let prefix = prefix.into_storage_key();
+        VkeyRegistry {
+            circuits: UnorderedMap::new([prefix.as_slice(), &b"c"[..]].concat()),
+            frozen: LookupMap::new([prefix.as_slice(), &b"f"[..]].concat()),
+        }
+    }
+
+    /// Registers a prepared key under `circuit_id`. Only call this from
+    /// owner methods.
+    ///
+    /// # Errors
+    /// ComponentError::CircuitExists
+    pub fn register(
+        &mut self,
+        circuit_id: &str,
+        pvk: impl Into<AnyPreparedVerifyingKey>,
+        layout: LimbLayout,
+    ) -> Result<()> {
+        let id = circuit_id.to_string();
+        if self.circuits.get(&id).is_some() {
+            return Err(ComponentError::CircuitExists(id).into());
+        }
+        let pvk = pvk.into();
+        self.circuits.insert(&id, &CircuitEntry { pvk, layout });
+        Ok(())
+    }
+
+    /// Parses, prepares and registers a circom verification key json over
+    /// any supported curve.
+    ///
+    /// # Errors
+    /// ComponentError::CircuitExists
+    ///
+    /// This function will also return an error if the key fails to parse.
+    pub fn register_json(
+        &mut self,
+        circuit_id: &str,
+        vkey_str: String,
+        layout: LimbLayout,
+    ) -> Result<()> {
+        let pvk = prepare_any_verification_key(vkey_str)?;
+        self.register(circuit_id, pvk, layout)
+    }
+
+    /// Replaces the key and layout of a circuit, registering it if it is
+    /// new, and returns the previous entry. A frozen circuit stays frozen.
+    /// Only call this from owner methods or through `HiddenKeys::unveil`.
+    pub fn rotate(
+        &mut self,
+        circuit_id: &str,
+        pvk: impl Into<AnyPreparedVerifyingKey>,
+        layout: LimbLayout,
+    ) -> Option<CircuitEntry> {
+        let pvk = pvk.into();
+        self.circuits
+            .insert(&circuit_id.to_string(), &CircuitEntry { pvk, layout })
+    }
+
+    /// Removes a circuit, returning its entry.
+    pub fn remove(&mut self, circuit_id: &str) -> Option<CircuitEntry> {
+        self.circuits.remove(&circuit_id.to_string())
+    }
+
+    pub fn get(&self, circuit_id: &str) -> Option<CircuitEntry> {
+        self.circuits.get(&circuit_id.to_string())
+    }
+
+    pub fn fingerprint(&self, circuit_id: &str) -> Option<[u8; 32]> {
+        self.get(circuit_id).map(|entry| entry.pvk.fingerprint())
+    }
+
+    /// Registered circuit ids, in no particular order.
+    pub fn circuit_ids(&self) -> Vec<String> {
+        self.circuits.keys().collect()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.circuits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.circuits.is_empty()
+    }
+
+    /// Writes every circuit to a borsh `RegistryArchive`. The bytes only
+    /// depend on the registered circuits, not on the order they were
+    /// registered in.
+    pub fn export(&self) -> Vec<u8> {
+        let mut circuits: Vec<ArchivedCircuit> = self
+            .circuits
+            .iter()
+            .map(|(circuit_id, entry)| ArchivedCircuit {
+                circuit_id,
+                fingerprint: entry.pvk.fingerprint(),
+                entry,
+            })
+            .collect();
+        circuits.sort_by(|a, b| a.circuit_id.cmp(&b.circuit_id));
+        let checksum = circuits_checksum(&circuits);
+        RegistryArchive {
+            version: ARCHIVE_VERSION,
+            circuits,
+            checksum,
+        }
+        .try_to_vec()
+        .expect("Failed to serialize registry archive")
+    }
+
+    /// Registers every circuit of an archive written by `export`, returning
+    /// how many were imported. Nothing is registered if the archive is
+    /// invalid or one of its circuits already exists. Only call this from
+    /// owner methods.
+    ///
+    /// # Errors
+    /// ComponentError::InvalidArchive, ComponentError::CircuitExists
+    pub fn import(&mut self, bytes: &[u8]) -> Result<u64> {
+        let archive = RegistryArchive::from_slice(bytes)?;
+        if let Some(circuit) = archive
+            .circuits
+            .iter()
+            .find(|circuit| self.circuits.get(&circuit.circuit_id).is_some())
+        {
+            return Err(ComponentError::CircuitExists(circuit.circuit_id.clone()).into());
+        }
+        for circuit in &archive.circuits {
+            self.circuits.insert(&circuit.circuit_id, &circuit.entry);
+        }
+        Ok(archive.circuits.len() as u64)
+    }
+
+    /// Freezes a circuit, keeping the first freeze if it is already
+    /// frozen. Returns whether it was frozen by this call. Only call this
+    /// from owner methods or through a `CircuitBreaker`.
+    pub fn freeze(&mut self, circuit_id: &str, freeze: &Freeze) -> bool {
+        let id = circuit_id.to_string();
+        if self.frozen.contains_key(&id) {
+            return false;
+        }
+        self.frozen.insert(&id, freeze);
+        true
+    }
+
+    /// Lifts a freeze, once the circuit has a new setup. Only call this
+    /// from owner methods.
+    pub fn unfreeze(&mut self, circuit_id: &str) -> Option<Freeze> {
+        self.frozen.remove(&circuit_id.to_string())
+    }
+
+    pub fn frozen(&self, circuit_id: &str) -> Option<Freeze> {
+        self.frozen.get(&circuit_id.to_string())
+    }
+
+    pub fn is_frozen(&self, circuit_id: &str) -> bool {
+        self.frozen.contains_key(&circuit_id.to_string())
+    }
+
+    /// Verifies a proof against the key registered under `circuit_id`,
+    /// with the verifier of the key's curve.
+    ///
+    /// # Errors
+    /// ComponentError::CircuitFrozen, ComponentError::UnknownCircuit
+    ///
+    /// This function will also return an error if the proof or inputs fail
+    /// to parse.
+    pub fn verify_proof(
+        &self,
+        circuit_id: &str,
+        proof_str: String,
+        pub_inputs_str: String,
+    ) -> Result<bool> {
+        if self.is_frozen(circuit_id) {
+            return Err(ComponentError::CircuitFrozen(circuit_id.to_string()).into());
+        }
+        let entry = self
+            .get(circuit_id)
+            .ok_or_else(|| ComponentError::UnknownCircuit(circuit_id.to_string()))?;
+        entry.pvk.verify_proof(proof_str, pub_inputs_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::fixtures;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn test_register_and_verify() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut registry = VkeyRegistry::new(b"v".to_vec());
+        registry
+            .register_json("age", fixtures::vkey().to_string(), LimbLayout::default())
+            .unwrap();
+        assert!(registry
+            .register_json("age", fixtures::vkey().to_string(), LimbLayout::default())
+            .is_err());
+        assert_eq!(registry.circuit_ids(), vec!["age".to_string()]);
+        assert!(registry.fingerprint("age").is_some());
+
+        let proof = fixtures::proof().to_string();
+        let inputs = fixtures::public_inputs().to_string();
+        assert!(registry
+            .verify_proof("age", proof.clone(), inputs.clone())
+            .unwrap());
+        assert_eq!(
+            registry
+                .verify_proof("residency", proof.clone(), inputs.clone())
+                .err()
+                .unwrap()
+                .to_string(),
+            "Unknown circuit residency"
+        );
+
+        assert!(registry.remove("age").is_some());
+        assert!(registry.is_empty());
+        assert!(registry.verify_proof("age", proof, inputs).is_err());
+    }
+
+    fn registry_with_circuits(prefix: &[u8], ids: &[&str]) -> VkeyRegistry {
+        let mut registry = VkeyRegistry::new(prefix.to_vec());
+        for id in ids {
+            registry
+                .register_json(id, fixtures::vkey().to_string(), LimbLayout::default())
+                .unwrap();
+        }
+        registry
+    }
+
+    #[test]
+    fn test_export_and_import() {
+        testing_env!(VMContextBuilder::new().build());
+        let registry = registry_with_circuits(b"a", &["age", "residency"]);
+        let archive = registry.export();
+        assert_eq!(
+            archive,
+            registry_with_circuits(b"b", &["residency", "age"]).export()
+        );
+
+        let mut restored = VkeyRegistry::new(b"c".to_vec());
+        assert_eq!(restored.import(&archive).unwrap(), 2);
+        assert_eq!(restored.export(), archive);
+        assert_eq!(restored.get("age"), registry.get("age"));
+        assert_eq!(
+            restored.import(&archive).err().unwrap().to_string(),
+            "Circuit age is already registered"
+        );
+    }
+
+    #[test]
+    fn test_import_rejects_tampered_archive() {
+        testing_env!(VMContextBuilder::new().build());
+        let registry = registry_with_circuits(b"a", &["age"]);
+        let mut archive = RegistryArchive::from_slice(&registry.export()).unwrap();
+        archive.circuits[0].entry.layout.order = crate::hash::limbs::LimbOrder::LowHigh;
+
+        let mut restored = VkeyRegistry::new(b"b".to_vec());
+        let err = restored
+            .import(&archive.try_to_vec().unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "Invalid registry archive: checksum");
+        assert!(restored.is_empty());
+
+        archive.circuits[0].fingerprint = [0; 32];
+        archive.checksum = circuits_checksum(&archive.circuits);
+        let err = restored
+            .import(&archive.try_to_vec().unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Invalid registry archive: fingerprint of age"
+        );
+
+        assert!(restored.import(&[0xff; 4]).is_err());
+    }
+
+    #[test]
+    fn test_import_rejects_duplicate_circuits() {
+        testing_env!(VMContextBuilder::new().build());
+        let registry = registry_with_circuits(b"a", &["age"]);
+        let mut archive = RegistryArchive::from_slice(&registry.export()).unwrap();
+        archive.circuits.push(archive.circuits[0].clone());
+        archive.checksum = circuits_checksum(&archive.circuits);
+
+        let mut restored = VkeyRegistry::new(b"b".to_vec());
+        let err = restored
+            .import(&archive.try_to_vec().unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Invalid registry archive: duplicate circuit age"
+        );
+        assert!(restored.is_empty());
+    }
+}
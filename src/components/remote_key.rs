@@ -0,0 +1,205 @@
+// Copyright © 2022, Electron Labs
+
+//! Verification against keys held by a shared registry contract.
+//!
+//! The app contract pins the fingerprint it expects for every circuit id.
+//! On the first verification the key is fetched from the registry with a
+//! cross-contract call, checked against the pinned fingerprint and cached;
+//! later verifications use the cached key synchronously.
+//!
+//! ```ignore
+//! pub fn verify(&mut self, circuit_id: String, proof: String, inputs: String) -> PromiseOrValue<bool> {
+//!     let callback = Self::ext(env::current_account_id())
+//!         .on_key_fetched(circuit_id.clone(), proof.clone(), inputs.clone());
+//!     match self.keys.verify_proof_remote_key(self.registry.clone(), circuit_id, proof, inputs, callback).unwrap() {
+//!         RemoteVerification::Verified(res) => PromiseOrValue::Value(res),
+//!         RemoteVerification::Pending(promise) => PromiseOrValue::Promise(promise),
+//!     }
+//! }
+//!
+//! #[private]
+//! pub fn on_key_fetched(&mut self, circuit_id: String, proof: String, inputs: String) -> bool {
+//!     let bytes = fetched_key_bytes().unwrap();
+//!     let pvk = self.keys.accept_fetched_key(&circuit_id, &bytes).unwrap();
+//!     verify_proof(pvk, proof, inputs).unwrap()
+//! }
+//! ```
+
+use crate::components::ComponentError;
+use crate::verifier::json::JsonLimits;
+use crate::verifier::near::{verify_proof, PreparedVerifyingKey};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, AccountId, Gas, IntoStorageKey, Promise, PromiseResult};
+
+/// View method called on the registry contract. It takes `{"circuit_id"}`
+/// and returns the borsh-serialized prepared key as base64.
+pub const GET_KEY_METHOD: &str = "get_prepared_key";
+
+/// Gas attached to the registry view call.
+pub const GET_KEY_GAS: Gas = Gas(10_000_000_000_000);
+
+pub enum RemoteVerification {
+    Verified(bool),
+    Pending(Promise),
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct RemoteKeyCache {
+    pinned: LookupMap<String, [u8; 32]>,
+    cache: LookupMap<String, PreparedVerifyingKey>,
+}
+
+impl RemoteKeyCache {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        let prefix = prefix.into_storage_key();
+        RemoteKeyCache {
+            pinned: LookupMap::new([prefix.as_slice(), &b"p"[..]].concat()),
+            cache: LookupMap::new([prefix.as_slice(), &b"c"[..]].concat()),
+        }
+    }
+
+    /// Pins the fingerprint expected for `circuit_id` and drops any cached
+    /// key that doesn't match it.
+    pub fn pin(&mut self, circuit_id: &str, fingerprint: [u8; 32]) {
+        let circuit_id = circuit_id.to_string();
+        if let Some(pvk) = self.cache.get(&circuit_id) {
+            if pvk.fingerprint() != fingerprint {
+                self.cache.remove(&circuit_id);
+            }
+        }
+        self.pinned.insert(&circuit_id, &fingerprint);
+    }
+
+    pub fn cached(&self, circuit_id: &str) -> Option<PreparedVerifyingKey> {
+        self.cache.get(&circuit_id.to_string())
+    }
+
+    /// Verifies with the cached key, or returns the registry call chained
+    /// with `callback` when the key isn't cached yet.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no fingerprint is pinned for
+    /// `circuit_id`, or if verification with the cached key fails.
+    pub fn verify_proof_remote_key(
+        &self,
+        key_contract: AccountId,
+        circuit_id: String,
+        proof_str: String,
+        pub_inputs_str: String,
+        callback: Promise,
+    ) -> Result<RemoteVerification> {
+        if !self.pinned.contains_key(&circuit_id) {
+            return Err(ComponentError::UnpinnedCircuit(circuit_id).into());
+        }
+        match self.cached(&circuit_id) {
+            Some(pvk) => Ok(RemoteVerification::Verified(verify_proof(
+                pvk,
+                proof_str,
+                pub_inputs_str,
+            )?)),
+            None => Ok(RemoteVerification::Pending(
+                fetch_key(key_contract, &circuit_id).then(callback),
+            )),
+        }
+    }
+
+    /// Checks a key fetched from the registry against the pinned
+    /// fingerprint and caches it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the registry response can't be
+    /// decoded or the key doesn't match the pinned fingerprint.
+    pub fn accept_fetched_key(
+        &mut self,
+        circuit_id: &str,
+        response: &[u8],
+    ) -> Result<PreparedVerifyingKey> {
+        let circuit_id = circuit_id.to_string();
+        let expected = self
+            .pinned
+            .get(&circuit_id)
+            .ok_or_else(|| ComponentError::UnpinnedCircuit(circuit_id.clone()))?;
+        JsonLimits::DEFAULT.check(response, "registry response")?;
+        let bytes: Base64VecU8 =
+            serde_json_wasm::from_slice(response).map_err(|_| ComponentError::KeyFetchFailed)?;
+        let pvk = PreparedVerifyingKey::try_from_slice(&bytes.0)
+            .map_err(|_| ComponentError::KeyFetchFailed)?;
+        if pvk.fingerprint() != expected {
+            return Err(ComponentError::FingerprintMismatch.into());
+        }
+        self.cache.insert(&circuit_id, &pvk);
+        Ok(pvk)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct GetKeyArgs<'a> {
+    circuit_id: &'a str,
+}
+
+/// Promise calling the registry view method for `circuit_id`.
+pub fn fetch_key(key_contract: AccountId, circuit_id: &str) -> Promise {
+    let args = serde_json_wasm::to_vec(&GetKeyArgs { circuit_id })
+        .expect("Failed to serialize registry call arguments");
+    Promise::new(key_contract).function_call(GET_KEY_METHOD.to_string(), args, 0, GET_KEY_GAS)
+}
+
+/// Result of the registry call, read from inside the callback.
+///
+/// # Errors
+///
+/// This function will return an error if the registry call failed.
+pub fn fetched_key_bytes() -> Result<Vec<u8>> {
+    match env::promise_result(0) {
+        PromiseResult::Successful(bytes) => Ok(bytes),
+        _ => Err(ComponentError::KeyFetchFailed.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::fixtures;
+
+    fn registry_response(pvk: &PreparedVerifyingKey) -> Vec<u8> {
+        serde_json_wasm::to_vec(&Base64VecU8(pvk.try_to_vec().unwrap())).unwrap()
+    }
+
+    #[test]
+    fn test_accept_fetched_key() {
+        let pvk = fixtures::pvk();
+        let mut keys = RemoteKeyCache::new(b"k".to_vec());
+        assert!(keys
+            .accept_fetched_key("circuit", &registry_response(&pvk))
+            .is_err());
+
+        keys.pin("circuit", pvk.fingerprint());
+        let fetched = keys
+            .accept_fetched_key("circuit", &registry_response(&pvk))
+            .unwrap();
+        assert_eq!(fetched, pvk);
+        assert_eq!(keys.cached("circuit"), Some(pvk));
+    }
+
+    #[test]
+    fn test_reject_unexpected_key() {
+        let pvk = fixtures::pvk();
+        let mut keys = RemoteKeyCache::new(b"k".to_vec());
+        keys.pin("circuit", [0u8; 32]);
+        assert_eq!(
+            keys.accept_fetched_key("circuit", &registry_response(&pvk))
+                .err()
+                .unwrap()
+                .to_string(),
+            "Verifying key does not match the expected fingerprint"
+        );
+        assert!(keys.cached("circuit").is_none());
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright © 2022, Electron Labs
+
+//! Rejects recently submitted proofs before any parsing or pairing work.
+//!
+//! [`ReplayFilter`] keeps two Bloom filters in contract state, covering
+//! the current and the previous window. A submission missing from both is
+//! new without any storage read. A hit can be a false positive, so it is
+//! confirmed against the exact submission times in storage, and only a
+//! confirmed replay is rejected.
+//!
+//! Submissions are keyed by `queue::proof_hash` of the raw json with its
+//! whitespace removed, so resubmitting it reindented is a replay. The same
+//! proof re-encoded, with its keys reordered or its numbers in hex, is not,
+//! and neither is a re-randomized proof: use `nullifier::NullifierSet`
+//! where a statement must only be proven once.
+//!
+//! Exact submission times are pruned as the filters rotate, so storage
+//! holds at most two windows of submissions.
+//!
+//! ```ignore
+//! pub fn verify(&mut self, proof: String, inputs: String) -> bool {
+//!     self.replays.check_and_insert(&proof, &inputs).unwrap();
+//!     verify_proof(self.pvk.clone(), proof, inputs).unwrap()
+//! }
+//! ```
+
+use crate::components::queue::proof_hash;
+use crate::components::ComponentError;
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::{env, IntoStorageKey};
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ReplayFilter {
+    num_bits: u32,
+    num_hashes: u8,
+    window: u64,
+    current: Vec<u8>,
+    previous: Vec<u8>,
+    current_started_at: u64,
+    previous_started_at: u64,
+    seen: LookupMap<[u8; 32], u64>,
+    current_hashes: Vec<[u8; 32]>,
+    previous_hashes: Vec<[u8; 32]>,
+}
+
+impl ReplayFilter {
+    /// A filter of `num_bits` bits per window, setting `num_hashes` bits per
+    /// submission. A submission is rejected for at least `window` and at
+    /// most twice `window` nanoseconds.
+    pub fn new<S: IntoStorageKey>(prefix: S, num_bits: u32, num_hashes: u8, window: u64) -> Self {
+        assert!(num_bits > 0, "Filter must have at least one bit");
+        assert!(
+            (1..=8).contains(&num_hashes),
+            "Filter takes 1 to 8 hashes per submission"
+        );
+        assert!(window > 0, "Window must be positive");
+        let bytes = (num_bits as usize + 7) / 8;
+        let now = env::block_timestamp();
+        ReplayFilter {
+            num_bits,
+            num_hashes,
+            window,
+            current: vec![0; bytes],
+            previous: vec![0; bytes],
+            current_started_at: now,
+            previous_started_at: now,
+            seen: LookupMap::new(prefix),
+            current_hashes: Vec::new(),
+            previous_hashes: Vec::new(),
+        }
+    }
+
+    /// Records a submission, rejecting it if it was seen in the current or
+    /// the previous window.
+    ///
+    /// # Errors
+    /// ComponentError::Replayed
+    ///
+    /// This function will return an error if the submission is a replay.
+    pub fn check_and_insert(&mut self, proof_str: &str, pub_inputs_str: &str) -> Result<()> {
+        let now = env::block_timestamp();
+        self.rotate(now);
+        let hash = proof_hash(proof_str, pub_inputs_str);
+        if self.is_recent(&hash) {
+            return Err(ComponentError::Replayed.into());
+        }
+        for bit in self.bits(&hash) {
+            self.current[bit / 8] |= 1 << (bit % 8);
+        }
+        self.seen.insert(&hash, &now);
+        self.current_hashes.push(hash);
+        Ok(())
+    }
+
+    /// Whether a submission would be rejected as a replay.
+    pub fn contains(&self, proof_str: &str, pub_inputs_str: &str) -> bool {
+        self.is_recent(&proof_hash(proof_str, pub_inputs_str))
+    }
+
+    fn is_recent(&self, hash: &[u8; 32]) -> bool {
+        let bits = self.bits(hash);
+        let in_filter = |filter: &[u8]| {
+            bits.iter()
+                .all(|bit| filter[bit / 8] & (1 << (bit % 8)) != 0)
+        };
+        if !in_filter(&self.current) && !in_filter(&self.previous) {
+            return false;
+        }
+        match self.seen.get(hash) {
+            Some(seen_at) => seen_at >= self.previous_started_at,
+            None => false,
+        }
+    }
+
+    /// Bit positions of a submission, from consecutive 4-byte words of its
+    /// hash.
+    fn bits(&self, hash: &[u8; 32]) -> Vec<usize> {
+        hash.chunks(4)
+            .take(self.num_hashes as usize)
+            .map(|word| {
+                let word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+                (word % self.num_bits) as usize
+            })
+            .collect()
+    }
+
+    fn rotate(&mut self, now: u64) {
+        let elapsed = now.saturating_sub(self.current_started_at);
+        if elapsed < self.window {
+            return;
+        }
+        let expired = if elapsed < 2 * self.window {
+            self.previous = std::mem::replace(&mut self.current, vec![0; self.previous.len()]);
+            self.previous_started_at = self.current_started_at;
+            std::mem::replace(
+                &mut self.previous_hashes,
+                std::mem::take(&mut self.current_hashes),
+            )
+        } else {
+            self.current.iter_mut().for_each(|byte| *byte = 0);
+            self.previous.iter_mut().for_each(|byte| *byte = 0);
+            self.previous_started_at = now;
+            let mut expired = std::mem::take(&mut self.previous_hashes);
+            expired.append(&mut self.current_hashes);
+            expired
+        };
+        self.current_started_at = now;
+        // A hash listed in an expired window may have been submitted again
+        // since, with a newer time.
+        for hash in expired {
+            if let Some(seen_at) = self.seen.get(&hash) {
+                if seen_at < self.previous_started_at {
+                    self.seen.remove(&hash);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::fixtures;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn at(timestamp: u64) {
+        testing_env!(VMContextBuilder::new().block_timestamp(timestamp).build());
+    }
+
+    #[test]
+    fn test_rejects_replays_within_window() {
+        at(0);
+        let proof = fixtures::proof();
+        let mut filter = ReplayFilter::new(b"r".to_vec(), 1024, 4, 100);
+        filter.check_and_insert(proof, "[\"1\"]").unwrap();
+        filter.check_and_insert(proof, "[\"2\"]").unwrap();
+        assert!(filter.check_and_insert(proof, "[\"1\"]").is_err());
+
+        at(150);
+        assert!(filter.contains(proof, "[\"1\"]"));
+        assert!(filter.check_and_insert(proof, "[\"1\"]").is_err());
+
+        at(250);
+        filter.check_and_insert(proof, "[\"1\"]").unwrap();
+        assert!(filter.check_and_insert(proof, "[\"1\"]").is_err());
+    }
+
+    #[test]
+    fn test_reindented_json_is_a_replay() {
+        at(0);
+        let proof = fixtures::proof();
+        let mut filter = ReplayFilter::new(b"r".to_vec(), 1024, 4, 100);
+        filter.check_and_insert(proof, "[\"255\"]").unwrap();
+        assert!(filter.contains(proof, "[ \"255\" ]"));
+        let compact = proof.replace(' ', "");
+        assert!(filter.contains(&compact, "[\"255\"]"));
+        assert!(!filter.contains(proof, "[\"0xff\"]"));
+    }
+
+    #[test]
+    fn test_rejects_unparsed_submissions() {
+        at(0);
+        let mut filter = ReplayFilter::new(b"r".to_vec(), 1024, 4, 100);
+        filter.check_and_insert("proof", "inputs").unwrap();
+        assert!(filter.check_and_insert("proof", "inputs").is_err());
+    }
+
+    #[test]
+    fn test_prunes_expired_submissions() {
+        at(0);
+        let proof = fixtures::proof();
+        let mut filter = ReplayFilter::new(b"r".to_vec(), 1024, 4, 100);
+        filter.check_and_insert(proof, "[\"1\"]").unwrap();
+        let hash = proof_hash(proof, "[\"1\"]");
+
+        at(150);
+        filter.check_and_insert(proof, "[\"2\"]").unwrap();
+        assert!(filter.seen.contains_key(&hash));
+
+        at(250);
+        filter.check_and_insert(proof, "[\"3\"]").unwrap();
+        assert!(!filter.seen.contains_key(&hash));
+        assert!(filter.seen.contains_key(&proof_hash(proof, "[\"2\"]")));
+
+        at(1000);
+        filter.check_and_insert(proof, "[\"4\"]").unwrap();
+        assert!(!filter.seen.contains_key(&proof_hash(proof, "[\"2\"]")));
+        assert!(!filter.seen.contains_key(&proof_hash(proof, "[\"3\"]")));
+    }
+
+    #[test]
+    fn test_false_positive_falls_back_to_storage() {
+        at(0);
+        let proof = fixtures::proof();
+        // One bit: every submission hits the filter.
+        let mut filter = ReplayFilter::new(b"r".to_vec(), 1, 1, 100);
+        filter.check_and_insert(proof, "[\"1\"]").unwrap();
+        assert!(!filter.contains(proof, "[\"2\"]"));
+        filter.check_and_insert(proof, "[\"2\"]").unwrap();
+        assert!(filter.check_and_insert(proof, "[\"2\"]").is_err());
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright © 2022, Electron Labs
+
+//! Time-limited sessions granted by a proof.
+//!
+//! An account proves a statement once, e.g. membership in a set, and gets a
+//! session for the circuit that other methods can check with
+//! [`SessionGrants::require_active`] instead of asking for a new proof on
+//! every call. The proof must bind the account through
+//! [`account_input`](crate::components::meta_tx::account_input), otherwise
+//! a copied proof would grant a session to whoever submits it.
+
+use crate::components::meta_tx::account_input;
+use crate::components::ComponentError;
+use crate::verifier::near::public_inputs::PublicInputs;
+use crate::verifier::near::{verify_proof, PreparedVerifyingKey};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::{env, AccountId, IntoStorageKey};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct SessionGrant {
+    pub granted_at: u64,
+    pub expires_at: u64,
+}
+
+impl SessionGrant {
+    pub fn is_active(&self) -> bool {
+        env::block_timestamp() < self.expires_at
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct SessionGrants {
+    ttl: u64,
+    grants: LookupMap<(AccountId, String), SessionGrant>,
+}
+
+impl SessionGrants {
+    /// Sessions expire `ttl` nanoseconds after they are granted.
+    pub fn new<S: IntoStorageKey>(prefix: S, ttl: u64) -> Self {
+        SessionGrants {
+            ttl,
+            grants: LookupMap::new(prefix),
+        }
+    }
+
+    /// Verifies a proof whose public input at `account_index` binds the
+    /// predecessor and grants it a session for `circuit`, replacing any
+    /// previous one.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the proof is bound to another
+    /// account, or if it fails to parse or verify.
+    pub fn grant_with_proof(
+        &mut self,
+        circuit: String,
+        pvk: PreparedVerifyingKey,
+        proof_str: String,
+        pub_inputs_str: String,
+        account_index: usize,
+    ) -> Result<SessionGrant> {
+        let account = env::predecessor_account_id();
+        let inputs = PublicInputs::parse(pub_inputs_str.clone())?;
+        if inputs.as_slice().get(account_index) != Some(&account_input(&account)) {
+            return Err(ComponentError::AccountMismatch.into());
+        }
+        if !verify_proof(pvk, proof_str, pub_inputs_str)? {
+            return Err(ComponentError::VerificationFailed.into());
+        }
+
+        let now = env::block_timestamp();
+        let grant = SessionGrant {
+            granted_at: now,
+            expires_at: now + self.ttl,
+        };
+        self.grants.insert(&(account, circuit), &grant);
+        Ok(grant)
+    }
+
+    /// Returns the session of `account` for `circuit`, expired or not.
+    pub fn get(&self, account: &AccountId, circuit: &str) -> Option<SessionGrant> {
+        self.grants.get(&(account.clone(), circuit.to_string()))
+    }
+
+    pub fn is_active(&self, account: &AccountId, circuit: &str) -> bool {
+        self.get(account, circuit)
+            .map_or(false, |grant| grant.is_active())
+    }
+
+    /// Checks that the predecessor has an active session for `circuit`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no session or it has
+    /// expired.
+    pub fn require_active(&self, circuit: &str) -> Result<()> {
+        if !self.is_active(&env::predecessor_account_id(), circuit) {
+            return Err(ComponentError::NoSession(circuit.to_string()).into());
+        }
+        Ok(())
+    }
+
+    pub fn revoke(&mut self, account: &AccountId, circuit: &str) -> Option<SessionGrant> {
+        self.grants.remove(&(account.clone(), circuit.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::{get_prepared_verifying_key, parse_verification_key};
+    use ark_ff::One;
+    use electron_testkit::multiplier::Multiplier;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn context(account: &str, timestamp: u64) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(account.parse().unwrap())
+            .block_timestamp(timestamp)
+            .build());
+    }
+
+    fn proof_for(account: &str) -> (PreparedVerifyingKey, String, String) {
+        let mut multiplier = Multiplier::setup();
+        let vkey = parse_verification_key(multiplier.vkey_json()).unwrap();
+        let account = account_input(&account.parse().unwrap());
+        let (proof, inputs) = multiplier.prove_fr(account, ark_bn254::Fr::one());
+        (get_prepared_verifying_key(vkey).unwrap(), proof, inputs)
+    }
+
+    #[test]
+    fn test_session_lifecycle() {
+        context("alice.near", 100);
+        let mut sessions = SessionGrants::new(b"s".to_vec(), 50);
+        let (pvk, proof, inputs) = proof_for("alice.near");
+        assert!(sessions.require_active("kyc").is_err());
+
+        let grant = sessions
+            .grant_with_proof("kyc".to_string(), pvk, proof, inputs, 0)
+            .unwrap();
+        assert_eq!(grant.expires_at, 150);
+        assert!(sessions.require_active("kyc").is_ok());
+        assert!(sessions.require_active("other").is_err());
+
+        context("alice.near", 150);
+        assert_eq!(
+            sessions.require_active("kyc").err().unwrap().to_string(),
+            "No active session for circuit kyc"
+        );
+    }
+
+    #[test]
+    fn test_session_other_account() {
+        context("bob.near", 100);
+        let mut sessions = SessionGrants::new(b"s".to_vec(), 50);
+        let (pvk, proof, inputs) = proof_for("alice.near");
+        let err = sessions
+            .grant_with_proof("kyc".to_string(), pvk, proof, inputs, 0)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "Proof is not bound to the sender account");
+        assert!(!sessions.is_active(&"bob.near".parse().unwrap(), "kyc"));
+    }
+
+    #[test]
+    fn test_revoke_session() {
+        context("alice.near", 100);
+        let mut sessions = SessionGrants::new(b"s".to_vec(), 50);
+        let (pvk, proof, inputs) = proof_for("alice.near");
+        sessions
+            .grant_with_proof("kyc".to_string(), pvk, proof, inputs, 0)
+            .unwrap();
+        let alice: AccountId = "alice.near".parse().unwrap();
+        assert!(sessions.revoke(&alice, "kyc").is_some());
+        assert!(!sessions.is_active(&alice, "kyc"));
+    }
+}
@@ -0,0 +1,277 @@
+// Copyright © 2022, Electron Labs
+
+//! A complete verifier contract for a single circuit.
+//!
+//! [`groth16_contract!`](crate::groth16_contract) expands to a
+//! `#[near_bindgen]` contract with an owner, who sets the verification key
+//! and can pause verification:
+//!
+//! ```ignore
+//! electron_rs::groth16_contract!(Verifier);
+//! ```
+//!
+//! The contract has the methods
+//!
+//! - `new(owner_id)`, the initializer,
+//! - `set_vkey(vkey)`, `pause()`, `unpause()` and `set_owner(owner_id)`,
+//!   callable by the owner only,
+//! - `verify(proof, inputs)`, which panics while paused or before a key is
+//!   set,
+//! - `owner()`, `is_paused()` and `vkey_fingerprint()`.
+//!
+//! Contracts that need more methods keep a [`VerifierContract`] in their own
+//! state instead, and forward to it.
+
+use crate::components::ComponentError;
+use crate::verifier::near::{prepare_verification_key, verify_proof, PreparedVerifyingKey};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LazyOption;
+use near_sdk::{env, AccountId, IntoStorageKey};
+
+/// State of the generated contract: the owner, the pause flag and the key.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct VerifierContract {
+    owner: AccountId,
+    paused: bool,
+    pvk: LazyOption<PreparedVerifyingKey>,
+}
+
+impl VerifierContract {
+    pub fn new<S: IntoStorageKey>(prefix: S, owner: AccountId) -> Self {
+        VerifierContract {
+            owner,
+            paused: false,
+            pvk: LazyOption::new(prefix, None),
+        }
+    }
+
+    pub fn owner(&self) -> &AccountId {
+        &self.owner
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Fingerprint of the key set by `set_vkey`, as hex.
+    pub fn vkey_fingerprint(&self) -> Option<String> {
+        self.pvk.get().map(|pvk| hex::encode(pvk.fingerprint()))
+    }
+
+    /// # Errors
+    /// ComponentError::Unauthorized
+    pub fn assert_owner(&self) -> Result<()> {
+        if env::predecessor_account_id() != self.owner {
+            return Err(ComponentError::Unauthorized.into());
+        }
+        Ok(())
+    }
+
+    /// Prepares and stores the verification key json, replacing the
+    /// previous key.
+    ///
+    /// # Errors
+    /// ComponentError::Unauthorized, VerifierError::ParseError,
+    /// VerifierError::UnsupportedCurve
+    ///
+    /// This function will return an error if the caller is not the owner,
+    /// or if the key fails to parse or is not a bn128 key.
+    pub fn set_vkey(&mut self, vkey_str: String) -> Result<()> {
+        self.assert_owner()?;
+        let pvk = prepare_verification_key(vkey_str)?;
+        self.pvk.set(&pvk);
+        Ok(())
+    }
+
+    /// # Errors
+    /// ComponentError::Unauthorized
+    pub fn set_owner(&mut self, owner: AccountId) -> Result<()> {
+        self.assert_owner()?;
+        self.owner = owner;
+        Ok(())
+    }
+
+    /// # Errors
+    /// ComponentError::Unauthorized
+    pub fn set_paused(&mut self, paused: bool) -> Result<()> {
+        self.assert_owner()?;
+        self.paused = paused;
+        Ok(())
+    }
+
+    /// Verifies a proof against the stored key.
+    ///
+    /// # Errors
+    /// ComponentError::Paused, ComponentError::NoVerifyingKey
+    ///
+    /// This function will return an error if the contract is paused, if no
+    /// key is set, or if the proof or the inputs fail to parse.
+    pub fn verify(&self, proof_str: String, pub_inputs_str: String) -> Result<bool> {
+        if self.paused {
+            return Err(ComponentError::Paused.into());
+        }
+        let pvk = self.pvk.get().ok_or(ComponentError::NoVerifyingKey)?;
+        verify_proof(pvk, proof_str, pub_inputs_str)
+    }
+}
+
+/// Defines a `#[near_bindgen]` contract named `$name` around a
+/// `VerifierContract`. Errors panic with their message.
+///
+/// ```ignore
+/// electron_rs::groth16_contract!(Verifier);
+/// ```
+#[macro_export]
+macro_rules! groth16_contract {
+    ($name:ident) => {
+        #[near_sdk::near_bindgen]
+        #[derive(near_sdk::PanicOnDefault)]
+        pub struct $name {
+            state: $crate::contract::VerifierContract,
+        }
+
+        impl near_sdk::borsh::BorshSerialize for $name {
+            fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                near_sdk::borsh::BorshSerialize::serialize(&self.state, writer)
+            }
+        }
+
+        impl near_sdk::borsh::BorshDeserialize for $name {
+            fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+                Ok($name {
+                    state: near_sdk::borsh::BorshDeserialize::deserialize(buf)?,
+                })
+            }
+        }
+
+        #[near_sdk::near_bindgen]
+        impl $name {
+            #[init]
+            pub fn new(owner_id: near_sdk::AccountId) -> Self {
+                $name {
+                    state: $crate::contract::VerifierContract::new(b"v".to_vec(), owner_id),
+                }
+            }
+
+            pub fn set_vkey(&mut self, vkey: String) {
+                $crate::contract::unwrap_or_panic(self.state.set_vkey(vkey))
+            }
+
+            pub fn set_owner(&mut self, owner_id: near_sdk::AccountId) {
+                $crate::contract::unwrap_or_panic(self.state.set_owner(owner_id))
+            }
+
+            pub fn pause(&mut self) {
+                $crate::contract::unwrap_or_panic(self.state.set_paused(true))
+            }
+
+            pub fn unpause(&mut self) {
+                $crate::contract::unwrap_or_panic(self.state.set_paused(false))
+            }
+
+            pub fn verify(&self, proof: String, inputs: String) -> bool {
+                $crate::contract::unwrap_or_panic(self.state.verify(proof, inputs))
+            }
+
+            pub fn owner(&self) -> near_sdk::AccountId {
+                self.state.owner().clone()
+            }
+
+            pub fn is_paused(&self) -> bool {
+                self.state.is_paused()
+            }
+
+            pub fn vkey_fingerprint(&self) -> Option<String> {
+                self.state.vkey_fingerprint()
+            }
+        }
+    };
+}
+
+/// Returns the value, or panics with the error message. Called by
+/// `groth16_contract!`.
+pub fn unwrap_or_panic<T>(res: Result<T>) -> T {
+    res.unwrap_or_else(|e| env::panic_str(&e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::fixtures;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    crate::groth16_contract!(TestVerifier);
+
+    fn context(account: &str) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(account.parse().unwrap())
+            .build());
+    }
+
+    #[test]
+    fn test_owner_checks() {
+        context("owner.near");
+        let mut state = VerifierContract::new(b"v".to_vec(), "owner.near".parse().unwrap());
+        assert!(matches!(
+            state
+                .verify(
+                    fixtures::proof().to_string(),
+                    fixtures::public_inputs().to_string()
+                )
+                .unwrap_err()
+                .downcast_ref(),
+            Some(ComponentError::NoVerifyingKey)
+        ));
+
+        context("mallory.near");
+        assert!(state.set_vkey(fixtures::vkey().to_string()).is_err());
+        assert!(state.set_paused(true).is_err());
+        assert!(state.set_owner("mallory.near".parse().unwrap()).is_err());
+
+        context("owner.near");
+        state.set_vkey(fixtures::vkey().to_string()).unwrap();
+        assert!(state.vkey_fingerprint().is_some());
+        assert!(state
+            .verify(
+                fixtures::proof().to_string(),
+                fixtures::public_inputs().to_string()
+            )
+            .unwrap());
+        state.set_owner("new.near".parse().unwrap()).unwrap();
+        assert!(state.set_paused(true).is_err());
+    }
+
+    #[test]
+    fn test_generated_contract() {
+        context("owner.near");
+        let mut contract = TestVerifier::new("owner.near".parse().unwrap());
+        contract.set_vkey(fixtures::vkey().to_string());
+        assert!(contract.verify(
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string()
+        ));
+
+        contract.pause();
+        assert!(contract.is_paused());
+        let paused = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            contract.verify(
+                fixtures::proof().to_string(),
+                fixtures::public_inputs().to_string(),
+            )
+        }));
+        assert!(paused.is_err());
+        contract.unpause();
+        assert!(contract.verify(
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string()
+        ));
+
+        let bytes = near_sdk::borsh::BorshSerialize::try_to_vec(&contract).unwrap();
+        let restored: TestVerifier =
+            near_sdk::borsh::BorshDeserialize::try_from_slice(&bytes).unwrap();
+        assert_eq!(restored.owner(), contract.owner());
+        assert_eq!(restored.vkey_fingerprint(), contract.vkey_fingerprint());
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright © 2022, Electron Labs
+
+//! Verification keys prepared at build time and compiled into the contract.
+//!
+//! Contracts with a fixed circuit can skip parsing the key json and reading
+//! the key from storage on every call. `build.rs` prepares the key once
+//! with [`emit_prepared_vkey`], and [`embed_vkey!`](crate::embed_vkey)
+//! includes the borsh encoding of the prepared key in the binary:
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     electron_rs::embed::emit_prepared_vkey("circuits/verification_key.json").unwrap();
+//! }
+//!
+//! // lib.rs
+//! let pvk = electron_rs::embed_vkey!("verification_key.json");
+//! ```
+//!
+//! The macro takes the file name of the key, without its directory.
+
+use crate::verifier::near::{
+    get_prepared_verifying_key, parse_verification_key, PreparedVerifyingKey,
+};
+use anyhow::{anyhow, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::path::{Path, PathBuf};
+
+/// Extension of the prepared keys written to `OUT_DIR`.
+pub const PREPARED_EXTENSION: &str = "pvk";
+
+/// A helper function for `build.rs` that prepares the verification key at
+/// `vkey_path` and writes its borsh encoding to `OUT_DIR` for `embed_vkey!`.
+/// Returns the path of the written file.
+///
+/// # Errors
+///
+/// This function will return an error if `OUT_DIR` is not set, if the file
+/// can't be read or written, or if the verification key json fails to
+/// parse.
+pub fn emit_prepared_vkey(vkey_path: impl AsRef<Path>) -> Result<PathBuf> {
+    let vkey_path = vkey_path.as_ref();
+    println!("cargo:rerun-if-changed={}", vkey_path.display());
+    let out_dir = std::env::var_os("OUT_DIR").ok_or_else(|| anyhow!("OUT_DIR is not set"))?;
+    let file_name = vkey_path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file", vkey_path.display()))?;
+
+    let vkey_str = std::fs::read_to_string(vkey_path)?;
+    let pvk = get_prepared_verifying_key(parse_verification_key(vkey_str)?)?;
+    let out_path = PathBuf::from(out_dir).join(format!(
+        "{}.{}",
+        file_name.to_string_lossy(),
+        PREPARED_EXTENSION
+    ));
+    std::fs::write(&out_path, pvk.try_to_vec()?)?;
+    Ok(out_path)
+}
+
+/// Decodes a key written by `emit_prepared_vkey`. Called by `embed_vkey!`.
+///
+/// # Panics
+///
+/// This function panics if the bytes are not a prepared key, which can only
+/// happen if they were not written by `emit_prepared_vkey`.
+pub fn decode_prepared_vkey(bytes: &[u8]) -> PreparedVerifyingKey {
+    PreparedVerifyingKey::try_from_slice(bytes).expect("Embedded verification key is corrupt")
+}
+
+/// The prepared key written by `emit_prepared_vkey` for the given key file
+/// name, as a `PreparedVerifyingKey`.
+///
+/// ```ignore
+/// let pvk = electron_rs::embed_vkey!("verification_key.json");
+/// ```
+#[macro_export]
+macro_rules! embed_vkey {
+    ($file_name:literal) => {
+        $crate::embed::decode_prepared_vkey(include_bytes!(concat!(
+            env!("OUT_DIR"),
+            "/",
+            $file_name,
+            ".pvk"
+        )))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emit_and_decode() {
+        let out_dir = std::env::temp_dir().join("electron-rs-embed");
+        std::fs::create_dir_all(&out_dir).unwrap();
+        std::env::set_var("OUT_DIR", &out_dir);
+        let vkey_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testkit/fixtures/bn128/verification_key.json");
+
+        let out_path = emit_prepared_vkey(&vkey_path).unwrap();
+        assert_eq!(out_path, out_dir.join("verification_key.json.pvk"));
+        let pvk = decode_prepared_vkey(&std::fs::read(out_path).unwrap());
+        let vkey_str = std::fs::read_to_string(vkey_path).unwrap();
+        let expected =
+            get_prepared_verifying_key(parse_verification_key(vkey_str).unwrap()).unwrap();
+        assert_eq!(pvk.fingerprint(), expected.fingerprint());
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright © 2022, Electron Labs
+
+//! C bindings, so relayers written in Go or C++ verify proofs with the json
+//! parsing and verification of the contracts.
+//!
+//! The declarations are in `include/electron_rs.h`, which
+//! `cargo xtask ffi-header` regenerates with cbindgen. A key is prepared
+//! once and passed around as its borsh encoding:
+//!
+//! ```c
+//! size_t pvk_len;
+//! uint8_t *pvk = electron_prepare_vkey(vkey_json, &pvk_len);
+//! if (pvk == NULL) {
+//!     char *err = electron_last_error();
+//!     /* ... */
+//!     electron_free_string(err);
+//! }
+//! int verified = electron_verify_proof(pvk, pvk_len, proof_json, inputs_json);
+//! electron_free_buffer(pvk, pvk_len);
+//! ```
+//!
+//! Functions that fail return null or -1 and keep the error message for
+//! `electron_last_error` on the calling thread. A panic is caught at the
+//! boundary and reported the same way, since unwinding into C is undefined
+//! behavior.
+
+use crate::verifier::near::{prepare_verification_key, verify_proof, PreparedVerifyingKey};
+use anyhow::{anyhow, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+fn set_last_error(err: anyhow::Error) {
+    // Messages with a nul byte are cut at it rather than lost.
+    let msg = err.to_string();
+    let msg = msg.split('\0').next().unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(msg).ok());
+}
+
+/// Runs `f`, turning a panic into an error.
+fn catch_panic<T>(f: impl FnOnce() -> Result<T>) -> Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let msg = payload
+            .downcast_ref::<&str>()
+            .map(|msg| msg.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(anyhow!("panicked: {}", msg))
+    })
+}
+
+unsafe fn read_str(ptr: *const c_char, what: &str) -> Result<String> {
+    if ptr.is_null() {
+        return Err(anyhow!("{} is null", what));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map(str::to_string)
+        .map_err(|_| anyhow!("{} is not utf-8", what))
+}
+
+/// Prepares verification key json. Returns the borsh encoding of the
+/// prepared key and writes its length to `out_len`, or returns null on
+/// error. Free the buffer with `electron_free_buffer`.
+///
+/// # Safety
+///
+/// `vkey_json` must be a nul-terminated string and `out_len` must be null
+/// or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn electron_prepare_vkey(
+    vkey_json: *const c_char,
+    out_len: *mut usize,
+) -> *mut u8 {
+    let prepared = catch_panic(|| {
+        if out_len.is_null() {
+            return Err(anyhow!("out_len is null"));
+        }
+        let pvk = prepare_verification_key(read_str(vkey_json, "vkey_json")?)?;
+        Ok(pvk.try_to_vec()?)
+    });
+    match prepared {
+        Ok(bytes) => {
+            *out_len = bytes.len();
+            Box::into_raw(bytes.into_boxed_slice()) as *mut u8
+        }
+        Err(err) => {
+            set_last_error(err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Verifies circom proof and public inputs json against a key from
+/// `electron_prepare_vkey`. Returns 1 if the proof verifies, 0 if it
+/// doesn't and -1 on error.
+///
+/// # Safety
+///
+/// `pvk` must point to `pvk_len` readable bytes, and `proof_json` and
+/// `inputs_json` must be nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn electron_verify_proof(
+    pvk: *const u8,
+    pvk_len: usize,
+    proof_json: *const c_char,
+    inputs_json: *const c_char,
+) -> c_int {
+    let verified = catch_panic(|| {
+        if pvk.is_null() {
+            return Err(anyhow!("pvk is null"));
+        }
+        let pvk = PreparedVerifyingKey::try_from_slice(slice::from_raw_parts(pvk, pvk_len))?;
+        let proof_str = read_str(proof_json, "proof_json")?;
+        let inputs_str = read_str(inputs_json, "inputs_json")?;
+        verify_proof(pvk, proof_str, inputs_str)
+    });
+    match verified {
+        Ok(verified) => verified as c_int,
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    }
+}
+
+/// Frees a buffer returned by `electron_prepare_vkey`.
+///
+/// # Safety
+///
+/// `buf` must come from `electron_prepare_vkey`, with the length it
+/// returned, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn electron_free_buffer(buf: *mut u8, len: usize) {
+    if !buf.is_null() {
+        drop(Box::from_raw(slice::from_raw_parts_mut(buf, len)));
+    }
+}
+
+/// Returns the message of the last error on this thread, or null if there
+/// was none. Free it with `electron_free_string`.
+#[no_mangle]
+pub extern "C" fn electron_last_error() -> *mut c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .clone()
+            .map_or(ptr::null_mut(), CString::into_raw)
+    })
+}
+
+/// Frees a string returned by `electron_last_error`.
+///
+/// # Safety
+///
+/// `s` must come from `electron_last_error` and must not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn electron_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::fixtures;
+
+    fn last_error() -> String {
+        unsafe {
+            let err = electron_last_error();
+            let msg = CStr::from_ptr(err).to_str().unwrap().to_string();
+            electron_free_string(err);
+            msg
+        }
+    }
+
+    #[test]
+    fn test_prepare_and_verify() {
+        let vkey = CString::new(fixtures::vkey()).unwrap();
+        let proof = CString::new(fixtures::proof()).unwrap();
+        let inputs = CString::new(fixtures::public_inputs()).unwrap();
+        let wrong_inputs =
+            CString::new(fixtures::public_inputs().replacen("\"1\"", "\"2\"", 1)).unwrap();
+        unsafe {
+            let mut len = 0;
+            let pvk = electron_prepare_vkey(vkey.as_ptr(), &mut len);
+            assert!(!pvk.is_null());
+            assert_eq!(
+                electron_verify_proof(pvk, len, proof.as_ptr(), inputs.as_ptr()),
+                1
+            );
+            assert_eq!(
+                electron_verify_proof(pvk, len, proof.as_ptr(), wrong_inputs.as_ptr()),
+                0
+            );
+            electron_free_buffer(pvk, len);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let bad = CString::new("{}").unwrap();
+        unsafe {
+            let mut len = 0;
+            assert!(electron_prepare_vkey(bad.as_ptr(), &mut len).is_null());
+            assert_eq!(last_error(), "Failed to parse circom verification key json");
+
+            assert_eq!(
+                electron_verify_proof(ptr::null(), 0, bad.as_ptr(), bad.as_ptr()),
+                -1
+            );
+            assert_eq!(last_error(), "pvk is null");
+
+            let vkey = CString::new(fixtures::vkey()).unwrap();
+            assert!(electron_prepare_vkey(vkey.as_ptr(), ptr::null_mut()).is_null());
+            assert_eq!(last_error(), "out_len is null");
+        }
+    }
+
+    #[test]
+    fn test_panics_are_caught() {
+        let err = catch_panic::<()>(|| panic!("boom")).err().unwrap();
+        assert_eq!(err.to_string(), "panicked: boom");
+        let err = catch_panic::<()>(|| panic!("{}", 42)).err().unwrap();
+        assert_eq!(err.to_string(), "panicked: 42");
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright © 2022, Electron Labs
+
+//! Packing of 32-byte digests into the two 128-bit limbs circom circuits
+//! take them as, since a digest doesn't fit in one BN254 scalar.
+//!
+//! By default the digest is read as a big-endian integer and split high
+//! limb first, the layout of the circom fixtures and of the `merge_limbs`
+//! input mapper. [`LimbLayout`] covers templates that differ.
+
+use super::HashError;
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, BigInteger256, PrimeField};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Which limb of a digest comes first.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LimbOrder {
+    HighLow,
+    LowHigh,
+}
+
+/// How the digest bytes are read as an integer.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Endianness {
+    Big,
+    Little,
+}
+
+/// The limb layout a circuit takes digests in. Circom templates differ on
+/// both counts, so the layout is configured per circuit; the default is
+/// big-endian, high limb first.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LimbLayout {
+    pub order: LimbOrder,
+    pub endianness: Endianness,
+}
+
+impl Default for LimbLayout {
+    fn default() -> Self {
+        LimbLayout {
+            order: LimbOrder::HighLow,
+            endianness: Endianness::Big,
+        }
+    }
+}
+
+impl LimbLayout {
+    pub fn new(order: LimbOrder, endianness: Endianness) -> Self {
+        LimbLayout { order, endianness }
+    }
+
+    /// Splits a digest into two limbs in this layout.
+    pub fn split(&self, digest: &[u8; 32]) -> [Fr; 2] {
+        let mut bytes = *digest;
+        if self.endianness == Endianness::Little {
+            bytes.reverse();
+        }
+        let (high, low) = bytes.split_at(16);
+        let high = Fr::from(u128::from_be_bytes(high.try_into().unwrap()));
+        let low = Fr::from(u128::from_be_bytes(low.try_into().unwrap()));
+        match self.order {
+            LimbOrder::HighLow => [high, low],
+            LimbOrder::LowHigh => [low, high],
+        }
+    }
+
+    /// Joins two limbs in this layout back into a digest.
+    ///
+    /// # Errors
+    /// HashError::LimbOverflow
+    ///
+    /// This function will return an error if a limb doesn't fit in 128
+    /// bits.
+    pub fn join(&self, limbs: &[Fr; 2]) -> Result<[u8; 32], HashError> {
+        let (high, low) = match self.order {
+            LimbOrder::HighLow => ((0, &limbs[0]), (1, &limbs[1])),
+            LimbOrder::LowHigh => ((1, &limbs[1]), (0, &limbs[0])),
+        };
+        let mut bytes = [0u8; 32];
+        for ((i, limb), out) in [high, low].iter().zip(bytes.chunks_mut(16)) {
+            let repr: BigInteger256 = limb.into_repr();
+            if repr.num_bits() > 128 {
+                return Err(HashError::LimbOverflow(*i));
+            }
+            out.copy_from_slice(&repr.to_bytes_be()[16..]);
+        }
+        if self.endianness == Endianness::Little {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+}
+
+/// Splits a digest into its high and low 128-bit limbs.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::hash::limbs::{digest_to_limbs, limbs_to_digest};
+///
+/// let mut digest = [0u8; 32];
+/// digest[15] = 1;
+/// digest[31] = 2;
+/// let limbs = digest_to_limbs(&digest);
+/// assert_eq!(limbs, [ark_bn254::Fr::from(1u64), ark_bn254::Fr::from(2u64)]);
+/// assert_eq!(limbs_to_digest(&limbs).unwrap(), digest);
+/// ```
+pub fn digest_to_limbs(digest: &[u8; 32]) -> [Fr; 2] {
+    LimbLayout::default().split(digest)
+}
+
+/// Joins the high and low limbs of a digest.
+///
+/// # Errors
+/// HashError::LimbOverflow
+///
+/// This function will return an error if a limb doesn't fit in 128 bits.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::hash::limbs::limbs_to_digest;
+///
+/// let digest = limbs_to_digest(&[0u64.into(), 7u64.into()]).unwrap();
+/// assert_eq!(digest[31], 7);
+/// ```
+pub fn limbs_to_digest(limbs: &[Fr; 2]) -> Result<[u8; 32], HashError> {
+    LimbLayout::default().join(limbs)
+}
+
+/// The sha256 of `data` as limbs.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::hash::limbs::{limbs_to_digest, sha256_limbs};
+///
+/// let digest = limbs_to_digest(&sha256_limbs(b"abc")).unwrap();
+/// assert_eq!(digest[0], 0xba);
+/// ```
+pub fn sha256_limbs(data: &[u8]) -> [Fr; 2] {
+    use sha2::{Digest, Sha256};
+    digest_to_limbs(&Sha256::digest(data).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_digest_limbs() {
+        let digest: [u8; 32] =
+            hex::decode("6899206703380ab8f586dd9f898c5b7786798f3a9d94e21f682d9fde8ef18e82")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        // Public inputs 1 and 2 of the circom fixture.
+        let limbs = [
+            Fr::from_str("139034790179591340742761703217010858871").unwrap(),
+            Fr::from_str("178747724383637324525799708680472596098").unwrap(),
+        ];
+        assert_eq!(digest_to_limbs(&digest), limbs);
+        assert_eq!(limbs_to_digest(&limbs).unwrap(), digest);
+    }
+
+    #[test]
+    fn test_limb_overflow() {
+        let limbs = [Fr::from(1u64), Fr::from(u128::MAX) + Fr::from(1u64)];
+        assert_eq!(
+            limbs_to_digest(&limbs).err().unwrap().to_string(),
+            "Limb 1 doesn't fit in 128 bits"
+        );
+    }
+
+    #[test]
+    fn test_sha256_limbs() {
+        let limbs = sha256_limbs(b"abc");
+        let digest = limbs_to_digest(&limbs).unwrap();
+        assert_eq!(
+            hex::encode(digest),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_limb_layouts() {
+        let mut digest = [0u8; 32];
+        digest[0] = 1;
+        digest[31] = 2;
+        let one = Fr::from(1u128 << 120);
+        let two = Fr::from(2u64);
+        let cases = [
+            (LimbOrder::HighLow, Endianness::Big, [one, two]),
+            (LimbOrder::LowHigh, Endianness::Big, [two, one]),
+            (
+                LimbOrder::HighLow,
+                Endianness::Little,
+                [Fr::from(2u128 << 120), Fr::from(1u64)],
+            ),
+            (
+                LimbOrder::LowHigh,
+                Endianness::Little,
+                [Fr::from(1u64), Fr::from(2u128 << 120)],
+            ),
+        ];
+        for (order, endianness, limbs) in cases {
+            let layout = LimbLayout::new(order, endianness);
+            assert_eq!(layout.split(&digest), limbs);
+            assert_eq!(layout.join(&limbs).unwrap(), digest);
+        }
+    }
+}
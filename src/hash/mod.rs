@@ -0,0 +1,18 @@
+// Copyright © 2022, Electron Labs
+
+//! Hash functions computed inside circuits, for binding public inputs to
+//! contract state.
+
+use thiserror::Error;
+
+pub mod limbs;
+pub mod poseidon;
+mod poseidon_constants;
+
+#[derive(Error, Debug)]
+pub enum HashError {
+    #[error("Poseidon takes 1 to {max} inputs, got {got}")]
+    UnsupportedArity { max: usize, got: usize },
+    #[error("Limb {0} doesn't fit in 128 bits")]
+    LimbOverflow(usize),
+}
@@ -0,0 +1,130 @@
+// Copyright © 2022, Electron Labs
+
+//! Poseidon over BN254 with the parameters of circomlib's `Poseidon(n)`
+//! template: x^5 S-box, 8 full rounds and the circomlib partial round
+//! counts, state width `n + 1` with the capacity element first.
+//!
+//! Only 1 to `MAX_INPUTS` inputs are supported, to keep the constants out
+//! of contracts that don't need them.
+
+use super::poseidon_constants::*;
+use super::HashError;
+use ark_bn254::Fr;
+use ark_ff::{Field, Zero};
+
+/// Largest number of inputs `poseidon` takes.
+pub const MAX_INPUTS: usize = 4;
+
+const FULL_ROUNDS: usize = 8;
+
+/// Hashes `inputs` like circomlib's `Poseidon(inputs.len())`.
+///
+/// # Errors
+/// HashError::UnsupportedArity
+///
+/// This function will return an error if there are no inputs or more than
+/// `MAX_INPUTS`.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::hash::poseidon::poseidon;
+///
+/// let hash = poseidon(&[1u64.into(), 2u64.into()]).unwrap();
+/// assert!(poseidon(&[]).is_err());
+/// ```
+pub fn poseidon(inputs: &[Fr]) -> Result<Fr, HashError> {
+    let (constants, mds, partial_rounds) = match inputs.len() {
+        1 => (C_2, M_2, PARTIAL_ROUNDS_2),
+        2 => (C_3, M_3, PARTIAL_ROUNDS_3),
+        3 => (C_4, M_4, PARTIAL_ROUNDS_4),
+        4 => (C_5, M_5, PARTIAL_ROUNDS_5),
+        got => {
+            return Err(HashError::UnsupportedArity {
+                max: MAX_INPUTS,
+                got,
+            })
+        }
+    };
+
+    let mut state = Vec::with_capacity(inputs.len() + 1);
+    state.push(Fr::zero());
+    state.extend_from_slice(inputs);
+    permute(&mut state, constants, mds, partial_rounds);
+    Ok(state[0])
+}
+
+/// Poseidon of two elements, as used for Merkle tree nodes.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::hash::poseidon::{poseidon, poseidon2};
+///
+/// let (left, right) = (1u64.into(), 2u64.into());
+/// assert_eq!(poseidon2(left, right), poseidon(&[left, right]).unwrap());
+/// ```
+pub fn poseidon2(left: Fr, right: Fr) -> Fr {
+    poseidon(&[left, right]).expect("two inputs are supported")
+}
+
+fn sbox(x: &mut Fr) {
+    let x2 = x.square();
+    *x *= x2.square();
+}
+
+fn permute(state: &mut [Fr], constants: &[Fr], mds: &[&[Fr]], partial_rounds: usize) {
+    let width = state.len();
+    let first_partial = FULL_ROUNDS / 2;
+    for (round, round_constants) in constants.chunks(width).enumerate() {
+        for (s, c) in state.iter_mut().zip(round_constants) {
+            *s += c;
+        }
+        if round < first_partial || round >= first_partial + partial_rounds {
+            state.iter_mut().for_each(sbox);
+        } else {
+            sbox(&mut state[0]);
+        }
+        let mixed: Vec<Fr> = mds
+            .iter()
+            .map(|row| row.iter().zip(state.iter()).map(|(m, s)| *m * s).sum())
+            .collect();
+        state.copy_from_slice(&mixed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn fr(s: &str) -> Fr {
+        Fr::from_str(s).unwrap()
+    }
+
+    // Test vectors from circomlibjs.
+    #[test]
+    fn test_matches_circomlib() {
+        assert_eq!(
+            poseidon(&[fr("1")]).unwrap(),
+            fr("18586133768512220936620570745912940619677854269274689475585506675881198879027")
+        );
+        assert_eq!(
+            poseidon2(fr("1"), fr("2")),
+            fr("7853200120776062878684798364095072458815029376092732009249414926327459813530")
+        );
+        assert_eq!(
+            poseidon(&[fr("1"), fr("2"), fr("3"), fr("4")]).unwrap(),
+            fr("18821383157269793795438455681495246036402687001665670618754263018637548127333")
+        );
+    }
+
+    #[test]
+    fn test_unsupported_arity() {
+        assert!(poseidon(&[]).is_err());
+        assert_eq!(
+            poseidon(&[Fr::zero(); 5]).err().unwrap().to_string(),
+            "Poseidon takes 1 to 4 inputs, got 5"
+        );
+    }
+}
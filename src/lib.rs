@@ -1,3 +1,58 @@
 // Copyright © 2022, Electron Labs
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+// Every other feature enables `std`.
+#[cfg(all(
+    feature = "audit-min",
+    any(
+        feature = "std",
+        feature = "ink",
+        feature = "blst",
+        feature = "solana",
+        feature = "wasm-bindgen",
+        feature = "simd",
+    )
+))]
+compile_error!(
+    "`audit-min` builds the core verifier only, use `--no-default-features --features audit-min`"
+);
+
+#[cfg(all(feature = "wasm-bindgen", not(feature = "native")))]
+compile_error!("`wasm-bindgen` needs the verifier of the `native` feature");
+
+#[cfg(all(feature = "codegen", target_arch = "wasm32"))]
+compile_error!("`codegen` generates code off chain and can't be built for wasm32");
+
+#[cfg(feature = "codegen")]
+pub mod codegen;
+#[cfg(feature = "component")]
+pub mod component;
+#[cfg(feature = "near")]
+pub mod components;
+#[cfg(feature = "near")]
+pub mod contract;
+#[cfg(any(feature = "near", feature = "native"))]
+pub mod embed;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod hash;
+#[cfg(any(feature = "near", feature = "native"))]
+pub mod mpt;
+#[cfg(feature = "near")]
+pub mod protocols;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "near")]
+pub mod schema;
+#[cfg(feature = "ssz")]
+pub mod ssz;
 pub mod verifier;
+#[cfg(all(feature = "wasm-bindgen", feature = "native"))]
+pub mod wasm;
+
+#[cfg(feature = "reproducible")]
+pub mod reproducible;
@@ -0,0 +1,271 @@
+// Copyright © 2022, Electron Labs
+
+//! Ethereum Merkle-Patricia trie inclusion proofs.
+//!
+//! A light client proves a block header, and with it the state, receipts
+//! and transactions roots. [`verify_proof`] then checks a value against one
+//! of those roots, given the trie nodes on the path to its key, as
+//! `eth_getProof` returns them. Nodes are decoded with [`rlp`] and hashed
+//! with keccak256, so it runs inside contracts.
+//!
+//! ```ignore
+//! let receipt = verify_receipt_inclusion(&header.receipts_root, tx_index, &proof)?;
+//! let logs = receipt::decode_receipt(&receipt)?.logs;
+//! ```
+
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+pub mod receipt;
+pub mod rlp;
+
+use rlp::Item;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MptError {
+    #[error("Invalid RLP: {0}")]
+    InvalidRlp(&'static str),
+    #[error("Proof node {0} doesn't match the hash its parent references")]
+    HashMismatch(usize),
+    #[error("Proof node {0} is neither a branch, an extension nor a leaf")]
+    InvalidNode(usize),
+    #[error("Proof ends before the key is resolved")]
+    ProofTooShort,
+    #[error("Proof has {0} nodes after the key is resolved")]
+    UnusedNodes(usize),
+    #[error("Key is not in the trie")]
+    NotIncluded,
+}
+
+/// How a node references a child: by hash, or embedded when its encoding is
+/// shorter than a hash.
+enum NodeRef<'a> {
+    Hash(&'a [u8]),
+    Inline(Item<'a>),
+}
+
+/// Reads a child reference, or `None` for an empty slot.
+fn child_ref<'a>(item: &Item<'a>, node: usize) -> Result<Option<NodeRef<'a>>, MptError> {
+    match item {
+        Item::Bytes(bytes) if bytes.is_empty() => Ok(None),
+        Item::Bytes(hash) if hash.len() == 32 => Ok(Some(NodeRef::Hash(*hash))),
+        Item::List(_) => Ok(Some(NodeRef::Inline(item.clone()))),
+        _ => Err(MptError::InvalidNode(node)),
+    }
+}
+
+fn nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+}
+
+/// Decodes the hex-prefix encoded path of a leaf or extension into its
+/// nibbles and whether the node is a leaf.
+fn decode_path(path: &[u8], node: usize) -> Result<(Vec<u8>, bool), MptError> {
+    let first = *path.first().ok_or(MptError::InvalidNode(node))?;
+    let flag = first >> 4;
+    if flag > 3 || (flag & 1 == 0 && first & 0x0f != 0) {
+        return Err(MptError::InvalidNode(node));
+    }
+    let mut out = Vec::with_capacity(path.len() * 2);
+    if flag & 1 == 1 {
+        out.push(first & 0x0f);
+    }
+    out.extend(nibbles(&path[1..]));
+    Ok((out, flag & 2 == 2))
+}
+
+/// Verifies a proof of `key` against the trie `root`. `proof` holds the
+/// encoded nodes from the root towards the key; nodes embedded in their
+/// parent are not listed. Returns the value stored under `key`, or `None`
+/// if the proof shows that the trie has no such key.
+///
+/// # Errors
+/// MptError::InvalidRlp, MptError::HashMismatch, MptError::InvalidNode,
+/// MptError::ProofTooShort, MptError::UnusedNodes
+pub fn verify_proof(
+    root: &[u8; 32],
+    key: &[u8],
+    proof: &[Vec<u8>],
+) -> Result<Option<Vec<u8>>, MptError> {
+    let key = nibbles(key);
+    let mut pos = 0;
+    let mut used = 0;
+    let mut next = NodeRef::Hash(root);
+    let value = loop {
+        let node = match next {
+            NodeRef::Hash(hash) => {
+                let encoded = proof.get(used).ok_or(MptError::ProofTooShort)?;
+                if Keccak256::digest(encoded).as_slice() != hash {
+                    return Err(MptError::HashMismatch(used));
+                }
+                used += 1;
+                rlp::decode(encoded)?
+            }
+            NodeRef::Inline(item) => item,
+        };
+        let index = used - 1;
+        let items = match node {
+            // The root of the empty trie.
+            Item::Bytes(bytes) if bytes.is_empty() => break None,
+            Item::List(items) => items,
+            Item::Bytes(_) => return Err(MptError::InvalidNode(index)),
+        };
+        match items.len() {
+            17 => {
+                if pos == key.len() {
+                    let value = items[16].as_bytes().ok_or(MptError::InvalidNode(index))?;
+                    break Some(value).filter(|value| !value.is_empty());
+                }
+                match child_ref(&items[key[pos] as usize], index)? {
+                    Some(child) => next = child,
+                    None => break None,
+                }
+                pos += 1;
+            }
+            2 => {
+                let path = items[0].as_bytes().ok_or(MptError::InvalidNode(index))?;
+                let (path, is_leaf) = decode_path(path, index)?;
+                if is_leaf {
+                    let value = items[1].as_bytes().ok_or(MptError::InvalidNode(index))?;
+                    break Some(value).filter(|_| key[pos..] == path[..]);
+                }
+                if !key[pos..].starts_with(&path) {
+                    break None;
+                }
+                pos += path.len();
+                match child_ref(&items[1], index)? {
+                    Some(child) => next = child,
+                    None => return Err(MptError::InvalidNode(index)),
+                }
+            }
+            _ => return Err(MptError::InvalidNode(index)),
+        }
+    };
+    if used < proof.len() {
+        return Err(MptError::UnusedNodes(proof.len() - used));
+    }
+    Ok(value.map(<[u8]>::to_vec))
+}
+
+/// Key of the receipt of transaction `tx_index` in the receipts trie.
+pub fn receipt_key(tx_index: u64) -> Vec<u8> {
+    rlp::encode_u64(tx_index)
+}
+
+/// Verifies that the receipts trie with root `receipts_root` holds a
+/// receipt for transaction `tx_index`, and returns it. Typed receipts keep
+/// their type byte in front of the RLP.
+///
+/// # Errors
+/// MptError::NotIncluded, and the errors of `verify_proof`
+pub fn verify_receipt_inclusion(
+    receipts_root: &[u8; 32],
+    tx_index: u64,
+    proof: &[Vec<u8>],
+) -> Result<Vec<u8>, MptError> {
+    verify_proof(receipts_root, &receipt_key(tx_index), proof)?.ok_or(MptError::NotIncluded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rlp::{encode_bytes, encode_list};
+    use super::*;
+
+    fn keccak(data: &[u8]) -> [u8; 32] {
+        Keccak256::digest(data).into()
+    }
+
+    fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+        let flag = (nibbles.len() % 2) as u8 + if leaf { 2 } else { 0 };
+        let mut padded = vec![flag];
+        if nibbles.len() % 2 == 0 {
+            padded.push(0);
+        }
+        padded.extend_from_slice(nibbles);
+        padded
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect()
+    }
+
+    fn leaf(nibbles: &[u8], value: &[u8]) -> Vec<u8> {
+        encode_list(&[
+            encode_bytes(&hex_prefix(nibbles, true)),
+            encode_bytes(value),
+        ])
+    }
+
+    #[test]
+    fn test_empty_trie() {
+        let root = keccak(&[0x80]);
+        assert_eq!(
+            hex::encode(root),
+            "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+        );
+        assert_eq!(verify_proof(&root, b"key", &[vec![0x80]]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_single_leaf() {
+        let value = [9u8; 64];
+        let node = leaf(&nibbles(&receipt_key(0)), &value);
+        let root = keccak(&node);
+        let proof = vec![node.clone()];
+
+        assert_eq!(verify_receipt_inclusion(&root, 0, &proof).unwrap(), value);
+        assert_eq!(
+            verify_receipt_inclusion(&root, 1, &proof).unwrap_err(),
+            MptError::NotIncluded
+        );
+
+        let mut tampered = node;
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            verify_receipt_inclusion(&root, 0, &[tampered]).unwrap_err(),
+            MptError::HashMismatch(0)
+        );
+    }
+
+    #[test]
+    fn test_extension_branch_and_inline_leaf() {
+        // Keys 0x01 and 0x02 share the nibble 0, held by an extension, and
+        // split in a branch. The short leaf of 0x02 is embedded in it.
+        let long_value = [1u8; 40];
+        let hashed_leaf = leaf(&[], &long_value);
+        let inline_leaf = leaf(&[], b"x");
+        assert!(inline_leaf.len() < 32);
+
+        let mut children = vec![encode_bytes(&[]); 17];
+        children[1] = encode_bytes(&keccak(&hashed_leaf));
+        children[2] = inline_leaf;
+        let branch = encode_list(&children);
+        let extension = encode_list(&[
+            encode_bytes(&hex_prefix(&[0], false)),
+            encode_bytes(&keccak(&branch)),
+        ]);
+        let root = keccak(&extension);
+
+        let proof = vec![extension.clone(), branch.clone(), hashed_leaf.clone()];
+        assert_eq!(
+            verify_proof(&root, &[0x01], &proof).unwrap(),
+            Some(long_value.to_vec())
+        );
+        let proof = vec![extension.clone(), branch.clone()];
+        assert_eq!(
+            verify_proof(&root, &[0x02], &proof).unwrap(),
+            Some(b"x".to_vec())
+        );
+        assert_eq!(verify_proof(&root, &[0x03], &proof).unwrap(), None);
+        assert_eq!(verify_proof(&root, &[0x10], &proof[..1]).unwrap(), None);
+
+        assert_eq!(
+            verify_proof(&root, &[0x01], &proof).unwrap_err(),
+            MptError::ProofTooShort
+        );
+        let padded = vec![extension, branch, hashed_leaf.clone(), hashed_leaf];
+        assert_eq!(
+            verify_proof(&root, &[0x01], &padded).unwrap_err(),
+            MptError::UnusedNodes(1)
+        );
+    }
+}
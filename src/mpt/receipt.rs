@@ -0,0 +1,137 @@
+// Copyright © 2022, Electron Labs
+
+//! Receipts and their logs, as returned by `verify_receipt_inclusion`.
+//!
+//! Bridges read the events of a proven receipt, e.g. the logs a bridge
+//! contract emitted on Ethereum:
+//!
+//! ```ignore
+//! let receipt = decode_receipt(&verify_receipt_inclusion(&root, tx_index, &proof)?)?;
+//! let log = receipt.logs.iter().find(|log| log.address == BRIDGE).ok_or(...)?;
+//! ```
+
+use super::rlp::{self, Item};
+use super::MptError;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Log {
+    pub address: [u8; 20],
+    pub topics: Vec<[u8; 32]>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Receipt {
+    /// The EIP-2718 transaction type, 0 for legacy transactions.
+    pub tx_type: u8,
+    pub success: bool,
+    pub cumulative_gas_used: u64,
+    pub logs: Vec<Log>,
+}
+
+fn decode_log(item: &Item) -> Result<Log, MptError> {
+    let topics = item
+        .field(1)?
+        .as_list()
+        .ok_or(MptError::InvalidRlp("expected a list"))?
+        .iter()
+        .map(|topic| topic.as_fixed::<32>())
+        .collect::<Result<_, _>>()?;
+    Ok(Log {
+        address: item.field(0)?.as_fixed()?,
+        topics,
+        data: item
+            .field(2)?
+            .as_bytes()
+            .ok_or(MptError::InvalidRlp("expected a byte string"))?
+            .to_vec(),
+    })
+}
+
+/// Decodes a legacy or typed receipt of a post-Byzantium block.
+///
+/// # Errors
+/// MptError::InvalidRlp
+///
+/// This function will also return an error for receipts with a state root
+/// instead of a status, from blocks before Byzantium.
+pub fn decode_receipt(bytes: &[u8]) -> Result<Receipt, MptError> {
+    let (tx_type, encoded) = match bytes.first() {
+        Some(tx_type) if *tx_type < 0x80 => (*tx_type, &bytes[1..]),
+        _ => (0, bytes),
+    };
+    let item = rlp::decode(encoded)?;
+    let success = match item.field(0)?.as_u64()? {
+        0 => false,
+        1 => true,
+        _ => return Err(MptError::InvalidRlp("receipt status is not 0 or 1")),
+    };
+    let logs = item
+        .field(3)?
+        .as_list()
+        .ok_or(MptError::InvalidRlp("expected a list"))?
+        .iter()
+        .map(decode_log)
+        .collect::<Result<_, _>>()?;
+    Ok(Receipt {
+        tx_type,
+        success,
+        cumulative_gas_used: item.field(1)?.as_u64()?,
+        logs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rlp::{encode_bytes, encode_list, encode_u64};
+    use super::*;
+
+    fn receipt(status: u64, topics: &[[u8; 32]]) -> Vec<u8> {
+        let log = encode_list(&[
+            encode_bytes(&[0x11; 20]),
+            encode_list(&topics.iter().map(|t| encode_bytes(t)).collect::<Vec<_>>()),
+            encode_bytes(b"payload"),
+        ]);
+        encode_list(&[
+            encode_u64(status),
+            encode_u64(21_000),
+            encode_bytes(&[0; 256]),
+            encode_list(&[log]),
+        ])
+    }
+
+    #[test]
+    fn test_decode_receipt() {
+        let legacy = decode_receipt(&receipt(1, &[[0x22; 32]])).unwrap();
+        assert_eq!(
+            legacy,
+            Receipt {
+                tx_type: 0,
+                success: true,
+                cumulative_gas_used: 21_000,
+                logs: vec![Log {
+                    address: [0x11; 20],
+                    topics: vec![[0x22; 32]],
+                    data: b"payload".to_vec(),
+                }],
+            }
+        );
+
+        let typed = [&[2u8][..], &receipt(0, &[])].concat();
+        let typed = decode_receipt(&typed).unwrap();
+        assert_eq!(typed.tx_type, 2);
+        assert!(!typed.success);
+        assert!(typed.logs[0].topics.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_state_root() {
+        let pre_byzantium = encode_list(&[
+            encode_bytes(&[0x33; 32]),
+            encode_u64(21_000),
+            encode_bytes(&[0; 256]),
+            encode_list(&[]),
+        ]);
+        assert!(decode_receipt(&pre_byzantium).is_err());
+    }
+}
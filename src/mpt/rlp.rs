@@ -0,0 +1,260 @@
+// Copyright © 2022, Electron Labs
+
+//! Recursive length prefix encoding, as used by Ethereum tries and
+//! receipts.
+//!
+//! Decoding borrows from the input and rejects non-canonical encodings, so
+//! that every item has exactly one encoding, as the trie hashes assume.
+
+use super::MptError;
+
+/// A decoded item: a byte string or a list of items.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Item<'a> {
+    Bytes(&'a [u8]),
+    List(Vec<Item<'a>>),
+}
+
+fn invalid(reason: &'static str) -> MptError {
+    MptError::InvalidRlp(reason)
+}
+
+impl<'a> Item<'a> {
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            Item::Bytes(bytes) => Some(*bytes),
+            Item::List(_) => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Item<'a>]> {
+        match self {
+            Item::Bytes(_) => None,
+            Item::List(items) => Some(items),
+        }
+    }
+
+    /// Item `index` of a list, e.g. a field of a receipt.
+    ///
+    /// # Errors
+    /// MptError::InvalidRlp
+    pub fn field(&self, index: usize) -> Result<&Item<'a>, MptError> {
+        self.as_list()
+            .ok_or_else(|| invalid("expected a list"))?
+            .get(index)
+            .ok_or_else(|| invalid("list has too few items"))
+    }
+
+    /// A byte string of exactly `N` bytes, such as an address or a hash.
+    ///
+    /// # Errors
+    /// MptError::InvalidRlp
+    pub fn as_fixed<const N: usize>(&self) -> Result<[u8; N], MptError> {
+        let bytes = self
+            .as_bytes()
+            .ok_or_else(|| invalid("expected a byte string"))?;
+        if bytes.len() != N {
+            return Err(invalid("byte string has the wrong length"));
+        }
+        let mut out = [0u8; N];
+        out.copy_from_slice(bytes);
+        Ok(out)
+    }
+
+    /// An integer encoded as big-endian bytes without leading zeros.
+    ///
+    /// # Errors
+    /// MptError::InvalidRlp
+    pub fn as_u64(&self) -> Result<u64, MptError> {
+        let bytes = self
+            .as_bytes()
+            .ok_or_else(|| invalid("expected a byte string"))?;
+        if bytes.first() == Some(&0) {
+            return Err(invalid("integer has leading zeros"));
+        }
+        if bytes.len() > 8 {
+            return Err(invalid("integer overflows"));
+        }
+        Ok(bytes.iter().fold(0, |value, b| (value << 8) | *b as u64))
+    }
+}
+
+/// Lists nested deeper than this are rejected rather than decoded
+/// recursively. Trie nodes nest two levels deep.
+const MAX_DEPTH: usize = 32;
+
+/// Decodes `data`, which must hold exactly one item.
+///
+/// # Errors
+/// MptError::InvalidRlp
+pub fn decode(data: &[u8]) -> Result<Item<'_>, MptError> {
+    let (item, rest) = decode_item(data, 0)?;
+    if !rest.is_empty() {
+        return Err(invalid("trailing bytes after the item"));
+    }
+    Ok(item)
+}
+
+/// Splits `data` into a payload of `len` bytes and the rest.
+fn take(data: &[u8], len: usize) -> Result<(&[u8], &[u8]), MptError> {
+    if data.len() < len {
+        return Err(invalid("item is truncated"));
+    }
+    Ok(data.split_at(len))
+}
+
+/// Reads the big-endian length of a long string or list.
+fn long_len(data: &[u8], len_of_len: usize) -> Result<(usize, &[u8]), MptError> {
+    let (be, rest) = take(data, len_of_len)?;
+    if be[0] == 0 {
+        return Err(invalid("length has leading zeros"));
+    }
+    if len_of_len > std::mem::size_of::<usize>() {
+        return Err(invalid("length overflows"));
+    }
+    let len = be.iter().fold(0usize, |len, b| (len << 8) | *b as usize);
+    if len < 56 {
+        return Err(invalid("long form used for a short item"));
+    }
+    Ok((len, rest))
+}
+
+fn decode_item(data: &[u8], depth: usize) -> Result<(Item<'_>, &[u8]), MptError> {
+    let prefix = *data.first().ok_or_else(|| invalid("item is empty"))?;
+    let body = &data[1..];
+    match prefix {
+        0x00..=0x7f => Ok((Item::Bytes(&data[..1]), body)),
+        0x80..=0xb7 => {
+            let (bytes, rest) = take(body, (prefix - 0x80) as usize)?;
+            if bytes.len() == 1 && bytes[0] < 0x80 {
+                return Err(invalid("single byte below 0x80 encoded as a string"));
+            }
+            Ok((Item::Bytes(bytes), rest))
+        }
+        0xb8..=0xbf => {
+            let (len, body) = long_len(body, (prefix - 0xb7) as usize)?;
+            let (bytes, rest) = take(body, len)?;
+            Ok((Item::Bytes(bytes), rest))
+        }
+        0xc0..=0xf7 => {
+            let (payload, rest) = take(body, (prefix - 0xc0) as usize)?;
+            Ok((Item::List(decode_list(payload, depth)?), rest))
+        }
+        0xf8..=0xff => {
+            let (len, body) = long_len(body, (prefix - 0xf7) as usize)?;
+            let (payload, rest) = take(body, len)?;
+            Ok((Item::List(decode_list(payload, depth)?), rest))
+        }
+    }
+}
+
+fn decode_list(mut payload: &[u8], depth: usize) -> Result<Vec<Item<'_>>, MptError> {
+    if depth == MAX_DEPTH {
+        return Err(invalid("lists are nested too deep"));
+    }
+    let mut items = Vec::new();
+    while !payload.is_empty() {
+        let (item, rest) = decode_item(payload, depth + 1)?;
+        items.push(item);
+        payload = rest;
+    }
+    Ok(items)
+}
+
+fn header(offset: u8, len: usize) -> Vec<u8> {
+    if len < 56 {
+        return vec![offset + len as u8];
+    }
+    let be = len.to_be_bytes();
+    let skip = be.iter().take_while(|b| **b == 0).count();
+    let mut out = vec![offset + 55 + (be.len() - skip) as u8];
+    out.extend_from_slice(&be[skip..]);
+    out
+}
+
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return bytes.to_vec();
+    }
+    let mut out = header(0x80, bytes.len());
+    out.extend_from_slice(bytes);
+    out
+}
+
+/// Encodes a list of already encoded items.
+pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+    let mut out = header(0xc0, payload.len());
+    out.extend_from_slice(&payload);
+    out
+}
+
+/// Encodes an integer as its big-endian bytes without leading zeros, so 0
+/// is the empty string.
+pub fn encode_u64(value: u64) -> Vec<u8> {
+    let be = value.to_be_bytes();
+    let skip = be.iter().take_while(|b| **b == 0).count();
+    encode_bytes(&be[skip..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let dog = encode_bytes(b"dog");
+        assert_eq!(dog, b"\x83dog");
+        let list = encode_list(&[encode_bytes(b"cat"), dog]);
+        assert_eq!(list, b"\xc8\x83cat\x83dog");
+        assert_eq!(
+            decode(&list).unwrap(),
+            Item::List(vec![Item::Bytes(b"cat"), Item::Bytes(b"dog")])
+        );
+
+        let long = [7u8; 56];
+        let encoded = encode_bytes(&long);
+        assert_eq!(&encoded[..2], &[0xb8, 56]);
+        assert_eq!(decode(&encoded).unwrap(), Item::Bytes(&long));
+
+        assert_eq!(encode_u64(0), vec![0x80]);
+        assert_eq!(encode_u64(15), vec![0x0f]);
+        assert_eq!(encode_u64(1024), vec![0x82, 0x04, 0x00]);
+        assert_eq!(decode(&[0xc0]).unwrap(), Item::List(vec![]));
+    }
+
+    #[test]
+    fn test_fields() {
+        let encoded = encode_list(&[encode_u64(1024), encode_bytes(&[0xaa; 20])]);
+        let item = decode(&encoded).unwrap();
+        assert_eq!(item.field(0).unwrap().as_u64().unwrap(), 1024);
+        assert_eq!(item.field(1).unwrap().as_fixed::<20>().unwrap(), [0xaa; 20]);
+        assert!(item.field(1).unwrap().as_fixed::<32>().is_err());
+        assert!(item.field(2).is_err());
+        assert_eq!(
+            decode(&[0x82, 0x00, 0x01]).unwrap().as_u64().unwrap_err(),
+            MptError::InvalidRlp("integer has leading zeros")
+        );
+    }
+
+    #[test]
+    fn test_rejects_non_canonical() {
+        let err = |data: &[u8]| decode(data).unwrap_err().to_string();
+        assert_eq!(
+            err(&[0x81, 0x05]),
+            "Invalid RLP: single byte below 0x80 encoded as a string"
+        );
+        assert_eq!(
+            err(&[0xb8, 0x05, 1, 2, 3, 4, 5]),
+            "Invalid RLP: long form used for a short item"
+        );
+        assert_eq!(err(&[0x83, b'd', b'o']), "Invalid RLP: item is truncated");
+        assert_eq!(
+            err(&[0x80, 0x80]),
+            "Invalid RLP: trailing bytes after the item"
+        );
+
+        let nested = (0..MAX_DEPTH).fold(vec![0xc0], |item, _| encode_list(&[item]));
+        assert_eq!(err(&nested), "Invalid RLP: lists are nested too deep");
+    }
+}
@@ -0,0 +1,14 @@
+// Copyright © 2022, Electron Labs
+
+use thiserror::Error;
+
+pub mod por;
+pub mod state_channel;
+
+#[derive(Error, Debug)]
+pub enum ProtocolError {
+    #[error("Stale {0}: got {1}, expected more than {2}")]
+    Stale(String, u64, u64),
+    #[error("Proof verification failed")]
+    VerificationFailed,
+}
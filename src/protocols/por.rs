@@ -0,0 +1,130 @@
+// Copyright © 2022, Electron Labs
+
+//! Proof-of-reserves attestations.
+//!
+//! The circuit exposes `[asset_root, liabilities_root, block_ref, total]` as
+//! public inputs. A successful verification records the attested totals
+//! under the submitting account so other contracts can query them.
+
+use crate::protocols::ProtocolError;
+use crate::schema::{verify_proof, InputSchema};
+use crate::verifier::near::PreparedVerifyingKey;
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, AccountId, IntoStorageKey};
+
+/// Public inputs of the proof-of-reserves circuit, in circuit order.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReserveInputs {
+    pub asset_root: String,
+    pub liabilities_root: String,
+    pub block_ref: u64,
+    pub total: U128,
+}
+
+impl InputSchema for ReserveInputs {
+    const LEN: usize = 4;
+
+    fn write_inputs(&self, out: &mut Vec<String>) {
+        out.push(self.asset_root.clone());
+        out.push(self.liabilities_root.clone());
+        out.push(self.block_ref.to_string());
+        out.push(self.total.0.to_string());
+    }
+
+    fn input_name(index: usize) -> Option<&'static str> {
+        ["asset_root", "liabilities_root", "block_ref", "total"]
+            .get(index)
+            .copied()
+    }
+}
+
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReserveAttestation {
+    pub inputs: ReserveInputs,
+    pub timestamp: u64,
+}
+
+/// Latest verified reserve attestation per account.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ReserveAttestations {
+    attestations: LookupMap<AccountId, ReserveAttestation>,
+}
+
+impl ReserveAttestations {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        ReserveAttestations {
+            attestations: LookupMap::new(prefix),
+        }
+    }
+
+    /// Verifies a proof-of-reserves proof and records it for the
+    /// predecessor account.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `block_ref` is not newer than
+    /// the stored attestation, if the proof fails to parse, or if it does not
+    /// verify.
+    pub fn verify_and_record(
+        &mut self,
+        pvk: PreparedVerifyingKey,
+        proof_str: String,
+        inputs: ReserveInputs,
+    ) -> Result<ReserveAttestation> {
+        let account = env::predecessor_account_id();
+        if let Some(prev) = self.attestations.get(&account) {
+            if inputs.block_ref <= prev.inputs.block_ref {
+                return Err(ProtocolError::Stale(
+                    "block ref".to_string(),
+                    inputs.block_ref,
+                    prev.inputs.block_ref,
+                )
+                .into());
+            }
+        }
+
+        if !verify_proof(&pvk, proof_str, &inputs)? {
+            return Err(ProtocolError::VerificationFailed.into());
+        }
+
+        let attestation = ReserveAttestation {
+            inputs,
+            timestamp: env::block_timestamp(),
+        };
+        self.attestations.insert(&account, &attestation);
+        Ok(attestation)
+    }
+
+    pub fn get(&self, account: &AccountId) -> Option<ReserveAttestation> {
+        self.attestations.get(account)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_inputs_layout() {
+        let inputs = ReserveInputs {
+            asset_root: "11".to_string(),
+            liabilities_root: "22".to_string(),
+            block_ref: 33,
+            total: U128(44),
+        };
+        assert_eq!(inputs.to_public_inputs(), vec!["11", "22", "33", "44"]);
+    }
+
+    #[test]
+    fn test_missing_attestation() {
+        let attestations = ReserveAttestations::new(b"r".to_vec());
+        let account: AccountId = "alice.near".parse().unwrap();
+        assert!(attestations.get(&account).is_none());
+    }
+}
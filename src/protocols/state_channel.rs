@@ -0,0 +1,122 @@
+// Copyright © 2022, Electron Labs
+
+//! zk state-channel checkpoints.
+//!
+//! The circuit exposes `[channel_id, nonce, state_root]` as public inputs.
+//! A checkpoint is only accepted when its proof verifies and its nonce is
+//! strictly greater than the stored one; the stored checkpoint is written
+//! last, after every check has passed.
+
+use crate::protocols::ProtocolError;
+use crate::schema::{verify_proof, InputSchema};
+use crate::verifier::near::PreparedVerifyingKey;
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, IntoStorageKey};
+
+/// Public inputs of the channel state circuit, in circuit order.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ChannelStateInputs {
+    pub channel_id: String,
+    pub nonce: u64,
+    pub state_root: String,
+}
+
+impl InputSchema for ChannelStateInputs {
+    const LEN: usize = 3;
+
+    fn write_inputs(&self, out: &mut Vec<String>) {
+        out.push(self.channel_id.clone());
+        out.push(self.nonce.to_string());
+        out.push(self.state_root.clone());
+    }
+
+    fn input_name(index: usize) -> Option<&'static str> {
+        ["channel_id", "nonce", "state_root"].get(index).copied()
+    }
+}
+
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Checkpoint {
+    pub nonce: u64,
+    pub state_root: String,
+    pub timestamp: u64,
+}
+
+/// Latest verified checkpoint per channel id.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ChannelCheckpoints {
+    checkpoints: LookupMap<String, Checkpoint>,
+}
+
+impl ChannelCheckpoints {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        ChannelCheckpoints {
+            checkpoints: LookupMap::new(prefix),
+        }
+    }
+
+    /// Verifies a proof of the latest channel state and stores it as the
+    /// channel checkpoint.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the nonce does not increase, if
+    /// the proof fails to parse, or if it does not verify. The stored
+    /// checkpoint is left untouched in every error case.
+    pub fn submit_checkpoint(
+        &mut self,
+        pvk: PreparedVerifyingKey,
+        proof_str: String,
+        inputs: ChannelStateInputs,
+    ) -> Result<Checkpoint> {
+        if let Some(prev) = self.checkpoints.get(&inputs.channel_id) {
+            if inputs.nonce <= prev.nonce {
+                return Err(
+                    ProtocolError::Stale("nonce".to_string(), inputs.nonce, prev.nonce).into(),
+                );
+            }
+        }
+
+        if !verify_proof(&pvk, proof_str, &inputs)? {
+            return Err(ProtocolError::VerificationFailed.into());
+        }
+
+        let checkpoint = Checkpoint {
+            nonce: inputs.nonce,
+            state_root: inputs.state_root,
+            timestamp: env::block_timestamp(),
+        };
+        self.checkpoints.insert(&inputs.channel_id, &checkpoint);
+        Ok(checkpoint)
+    }
+
+    pub fn get(&self, channel_id: &str) -> Option<Checkpoint> {
+        self.checkpoints.get(&channel_id.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_state_inputs_layout() {
+        let inputs = ChannelStateInputs {
+            channel_id: "7".to_string(),
+            nonce: 3,
+            state_root: "99".to_string(),
+        };
+        assert_eq!(inputs.to_public_inputs(), vec!["7", "3", "99"]);
+    }
+
+    #[test]
+    fn test_missing_checkpoint() {
+        let checkpoints = ChannelCheckpoints::new(b"c".to_vec());
+        assert!(checkpoints.get("7").is_none());
+    }
+}
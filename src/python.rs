@@ -0,0 +1,89 @@
+// Copyright © 2022, Electron Labs
+
+//! Python bindings, for pipelines that double-check proofs without
+//! shelling out to snarkjs.
+//!
+//! ```python
+//! import electron_rs
+//!
+//! pvk = electron_rs.prepare_verifying_key(vkey_json)
+//! assert electron_rs.verify_proof(pvk, proof_json, public_json)
+//! ```
+//!
+//! Build the module with `maturin build`, which reads the features from
+//! `pyproject.toml`. Errors are raised as `ValueError` with the message of
+//! the Rust error.
+
+use crate::verifier::near;
+use borsh::BorshSerialize;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// A prepared verification key, reusable across proofs.
+#[pyclass(name = "PreparedVerifyingKey")]
+pub struct PreparedKey(near::PreparedVerifyingKey);
+
+#[pymethods]
+impl PreparedKey {
+    /// Hex fingerprint of the key, as reported by contracts.
+    fn fingerprint(&self) -> String {
+        hex::encode(self.0.fingerprint())
+    }
+
+    /// The borsh encoding of the key, as contracts store it.
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.try_to_vec().unwrap()
+    }
+}
+
+fn to_py<T>(res: anyhow::Result<T>) -> PyResult<T> {
+    res.map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Parses and prepares snarkjs verification key json.
+#[pyfunction]
+fn prepare_verifying_key(vkey_json: String) -> PyResult<PreparedKey> {
+    to_py(near::prepare_verification_key(vkey_json)).map(PreparedKey)
+}
+
+/// Verifies snarkjs proof and public inputs json, with the code the
+/// contracts run.
+#[pyfunction]
+fn verify_proof(pvk: &PreparedKey, proof_json: String, public_json: String) -> PyResult<bool> {
+    to_py(near::verify_proof(pvk.0.clone(), proof_json, public_json))
+}
+
+#[pymodule]
+fn electron_rs(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PreparedKey>()?;
+    m.add_function(wrap_pyfunction!(prepare_verifying_key, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_proof, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::fixtures;
+
+    #[test]
+    fn test_same_path_as_contract() {
+        let pvk = prepare_verifying_key(fixtures::vkey().to_string()).unwrap();
+        let expected = near::prepare_verification_key(fixtures::vkey().to_string()).unwrap();
+        assert_eq!(pvk.to_bytes(), expected.try_to_vec().unwrap());
+
+        assert!(verify_proof(
+            &pvk,
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string()
+        )
+        .unwrap());
+        assert!(!verify_proof(
+            &pvk,
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().replacen("\"1\"", "\"2\"", 1)
+        )
+        .unwrap());
+        assert!(prepare_verifying_key("{}".to_string()).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright © 2022, Electron Labs
+
+//! Helpers for reproducible verifier contract builds.
+//!
+//! A contract calls [`emit_vkey_fingerprint`] from its `build.rs` and then
+//! [`embed_build_info!`](crate::embed_build_info) at the crate root. The
+//! resulting wasm carries a `electron_build_info` custom section holding the
+//! crate version and the fingerprint of the embedded verification key, so a
+//! binary rebuilt with `reproducible/build.sh` can be compared against the
+//! deployed one.
+
+use crate::verifier::near::{get_prepared_verifying_key, parse_verification_key};
+use anyhow::Result;
+use std::path::Path;
+
+/// Name of the wasm custom section written by `embed_build_info!`.
+pub const BUILD_INFO_SECTION: &str = "electron_build_info";
+
+/// Environment variable set by [`emit_vkey_fingerprint`] for the contract
+/// crate being built.
+pub const FINGERPRINT_ENV: &str = "ELECTRON_VKEY_FINGERPRINT";
+
+const PREFIX: &str = concat!("electron-rs/", env!("CARGO_PKG_VERSION"), ";vkey=");
+
+/// Length of the build info record for the given fingerprint.
+pub const fn build_info_len(fingerprint: &str) -> usize {
+    PREFIX.len() + fingerprint.len()
+}
+
+/// Build info record embedded into the custom section. `N` must equal
+/// `build_info_len(fingerprint)`.
+pub const fn build_info<const N: usize>(fingerprint: &str) -> [u8; N] {
+    let mut out = [0u8; N];
+    let prefix = PREFIX.as_bytes();
+    let fingerprint = fingerprint.as_bytes();
+    let mut i = 0;
+    while i < prefix.len() {
+        out[i] = prefix[i];
+        i += 1;
+    }
+    let mut j = 0;
+    while j < fingerprint.len() {
+        out[i + j] = fingerprint[j];
+        j += 1;
+    }
+    out
+}
+
+/// A helper function for `build.rs` that prepares the verification key at
+/// `vkey_path` and exports its hex fingerprint as `ELECTRON_VKEY_FINGERPRINT`.
+///
+/// # Errors
+///
+/// This function will return an error if the file can't be read or the
+/// verification key json fails to parse.
+pub fn emit_vkey_fingerprint(vkey_path: impl AsRef<Path>) -> Result<String> {
+    let vkey_path = vkey_path.as_ref();
+    println!("cargo:rerun-if-changed={}", vkey_path.display());
+    let vkey_str = std::fs::read_to_string(vkey_path)?;
+    let pvk = get_prepared_verifying_key(parse_verification_key(vkey_str)?)?;
+    let fingerprint = hex::encode(pvk.fingerprint());
+    println!("cargo:rustc-env={}={}", FINGERPRINT_ENV, fingerprint);
+    Ok(fingerprint)
+}
+
+/// Embeds the crate version and the given vkey fingerprint into the
+/// `electron_build_info` wasm custom section.
+///
+/// ```ignore
+/// electron_rs::embed_build_info!(env!("ELECTRON_VKEY_FINGERPRINT"));
+/// ```
+#[macro_export]
+macro_rules! embed_build_info {
+    ($fingerprint:expr) => {
+        #[cfg_attr(target_arch = "wasm32", link_section = "electron_build_info")]
+        #[used]
+        static ELECTRON_BUILD_INFO: [u8; $crate::reproducible::build_info_len($fingerprint)] =
+            $crate::reproducible::build_info($fingerprint);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info() {
+        const FINGERPRINT: &str = "00ff";
+        const INFO: [u8; build_info_len(FINGERPRINT)] = build_info(FINGERPRINT);
+        let expected = format!("electron-rs/{};vkey=00ff", env!("CARGO_PKG_VERSION"));
+        assert_eq!(&INFO[..], expected.as_bytes());
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright © 2022, Electron Labs
+
+//! Typed public input layouts.
+//!
+//! Circuits composed from several templates expose the inputs of each
+//! template back to back. [`Concat`] joins two schemas into one, and the
+//! position of every part is known at compile time:
+//!
+//! ```ignore
+//! type Inputs = Concat<HeaderInputs, PayloadInputs>;
+//! const PAYLOAD_START: usize = Inputs::OFFSET;
+//! ```
+//!
+//! Schemas can name their inputs, so that [`verify_proof`] reports a bad
+//! input as `Input 'merkle_root' (index 3) exceeds the field modulus`
+//! rather than by position alone.
+
+use crate::verifier::circom::field_from_str;
+use crate::verifier::core::{verify_prepared, VerifierError};
+use crate::verifier::near::{fr_to_string, parse_proof, PreparedVerifyingKey};
+use anyhow::Result;
+
+pub trait InputSchema {
+    /// Number of public inputs contributed by this schema.
+    const LEN: usize;
+
+    /// Appends the public inputs, as decimal strings, in circuit order.
+    fn write_inputs(&self, out: &mut Vec<String>);
+
+    /// Name of the input at `index`, used in error messages.
+    fn input_name(_index: usize) -> Option<&'static str> {
+        None
+    }
+
+    fn to_public_inputs(&self) -> Vec<String> {
+        let mut out = Vec::with_capacity(Self::LEN);
+        self.write_inputs(&mut out);
+        debug_assert_eq!(out.len(), Self::LEN);
+        out
+    }
+
+    /// Public inputs as the json string accepted by `verify_proof`.
+    fn to_public_inputs_json(&self) -> Result<String> {
+        Ok(serde_json_wasm::to_string(&self.to_public_inputs())?)
+    }
+
+    /// Public inputs as field elements.
+    ///
+    /// # Errors
+    /// VerifierError::InvalidInput
+    ///
+    /// This function will return an error naming the input if an input is
+    /// not a decimal or hex number, or if it is not below the field modulus.
+    fn to_field_elements(&self) -> Result<Vec<ark_bn254::Fr>, VerifierError> {
+        self.to_public_inputs()
+            .iter()
+            .enumerate()
+            .map(|(index, input)| parse_input::<Self>(index, input))
+            .collect()
+    }
+}
+
+fn parse_input<S: InputSchema + ?Sized>(
+    index: usize,
+    input: &str,
+) -> Result<ark_bn254::Fr, VerifierError> {
+    let invalid = |reason| VerifierError::InvalidInput {
+        index,
+        name: S::input_name(index),
+        reason,
+    };
+    let value: ark_bn254::Fr =
+        field_from_str(input, String::new).map_err(|_| invalid("is not a field element"))?;
+    // Decimal strings are reduced when parsed, so a value at or above the
+    // modulus only shows up as a mismatch when printed back.
+    let decimal = !input.starts_with("0x") && !input.starts_with("0X");
+    if decimal && input.trim_start_matches('0') != fr_to_string(value).trim_start_matches('0') {
+        return Err(invalid("exceeds the field modulus"));
+    }
+    Ok(value)
+}
+
+/// Verifies a proof against inputs given by a schema.
+///
+/// # Errors
+/// VerifierError::ParseError, VerifierError::InvalidProof,
+/// VerifierError::InvalidInput, VerifierError::InputCountMismatch
+///
+/// This function will return an error if the proof fails to parse, if an
+/// input is not a field element, or if the number of inputs doesn't match
+/// the key.
+pub fn verify_proof<S: InputSchema + ?Sized>(
+    pvk: &PreparedVerifyingKey,
+    proof_str: String,
+    inputs: &S,
+) -> Result<bool> {
+    let proof = parse_proof(proof_str)?;
+    let pub_inputs = inputs.to_field_elements()?;
+    Ok(verify_prepared(pvk, &proof, &pub_inputs)?)
+}
+
+/// Two schemas laid out back to back, `A` first.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Concat<A, B>(pub A, pub B);
+
+impl<A: InputSchema, B: InputSchema> Concat<A, B> {
+    /// Index of the first input of `B` in the combined vector.
+    pub const OFFSET: usize = A::LEN;
+}
+
+impl<A: InputSchema, B: InputSchema> InputSchema for Concat<A, B> {
+    const LEN: usize = A::LEN + B::LEN;
+
+    fn write_inputs(&self, out: &mut Vec<String>) {
+        self.0.write_inputs(out);
+        self.1.write_inputs(out);
+    }
+
+    fn input_name(index: usize) -> Option<&'static str> {
+        if index < A::LEN {
+            A::input_name(index)
+        } else {
+            B::input_name(index - A::LEN)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Header {
+        root: u64,
+    }
+
+    impl InputSchema for Header {
+        const LEN: usize = 1;
+
+        fn write_inputs(&self, out: &mut Vec<String>) {
+            out.push(self.root.to_string());
+        }
+
+        fn input_name(index: usize) -> Option<&'static str> {
+            ["root"].get(index).copied()
+        }
+    }
+
+    struct Payload {
+        sender: u64,
+        amount: u64,
+    }
+
+    impl InputSchema for Payload {
+        const LEN: usize = 2;
+
+        fn write_inputs(&self, out: &mut Vec<String>) {
+            out.push(self.sender.to_string());
+            out.push(self.amount.to_string());
+        }
+    }
+
+    #[test]
+    fn test_concat_schemas() {
+        type Inputs = Concat<Header, Concat<Payload, Header>>;
+        const LEN: usize = Inputs::LEN;
+        const OFFSET: usize = Inputs::OFFSET;
+        assert_eq!(LEN, 4);
+        assert_eq!(OFFSET, 1);
+        assert_eq!(Concat::<Payload, Header>::OFFSET, 2);
+
+        let inputs = Concat(
+            Header { root: 1 },
+            Concat(
+                Payload {
+                    sender: 2,
+                    amount: 3,
+                },
+                Header { root: 4 },
+            ),
+        );
+        assert_eq!(inputs.to_public_inputs(), vec!["1", "2", "3", "4"]);
+        assert_eq!(
+            inputs.to_public_inputs_json().unwrap(),
+            r#"["1","2","3","4"]"#
+        );
+    }
+
+    struct Raw(Vec<String>);
+
+    impl InputSchema for Raw {
+        const LEN: usize = 2;
+
+        fn write_inputs(&self, out: &mut Vec<String>) {
+            out.extend(self.0.iter().cloned());
+        }
+
+        fn input_name(index: usize) -> Option<&'static str> {
+            ["merkle_root", "nullifier"].get(index).copied()
+        }
+    }
+
+    const MODULUS: &str =
+        "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+    #[test]
+    fn test_input_names() {
+        type Inputs = Concat<Header, Concat<Payload, Raw>>;
+        assert_eq!(Inputs::input_name(0), Some("root"));
+        assert_eq!(Inputs::input_name(1), None);
+        assert_eq!(Inputs::input_name(3), Some("merkle_root"));
+        assert_eq!(Inputs::input_name(5), None);
+    }
+
+    #[test]
+    fn test_errors_name_inputs() {
+        let ok = Raw(vec!["0".to_string(), "0x2a".to_string()]);
+        assert_eq!(
+            ok.to_field_elements().unwrap(),
+            vec![ark_bn254::Fr::from(0u64), ark_bn254::Fr::from(42u64)]
+        );
+
+        let inputs = Concat(
+            Header { root: 1 },
+            Raw(vec![MODULUS.to_string(), "7".to_string()]),
+        );
+        let err = inputs.to_field_elements().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Input 'merkle_root' (index 1) exceeds the field modulus"
+        );
+
+        let inputs = Concat(
+            Payload {
+                sender: 2,
+                amount: 3,
+            },
+            Raw(vec!["1".to_string(), "one".to_string()]),
+        );
+        let err = inputs.to_field_elements().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Input 'nullifier' (index 3) is not a field element"
+        );
+
+        let err = parse_input::<Payload>(1, MODULUS).unwrap_err();
+        assert_eq!(err.to_string(), "Input (index 1) exceeds the field modulus");
+    }
+}
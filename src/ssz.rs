@@ -0,0 +1,199 @@
+// Copyright © 2022, Electron Labs
+
+//! Minimal SSZ decoding, for reading fields of beacon chain payloads, such
+//! as headers and execution payloads, that proofs commit to.
+//!
+//! A container is a fixed part, holding fixed-size fields and a 4-byte
+//! little-endian offset for every variable-size field, followed by the
+//! variable-size fields in order. Callers read fields at the positions of
+//! the container's schema:
+//!
+//! ```ignore
+//! // BeaconBlockHeader: slot, proposer_index, parent_root, state_root, body_root
+//! let slot = read_u64(header, 0)?;
+//! let state_root = read_bytes32(header, 48)?;
+//! ```
+
+use std::convert::TryInto;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SszError {
+    #[error("SSZ input has {got} bytes, the field ends at byte {end}")]
+    TooShort { end: usize, got: usize },
+    #[error("SSZ offset {0} is out of order or out of bounds")]
+    InvalidOffset(usize),
+    #[error("SSZ list of {len} bytes doesn't split into items of {item_size} bytes")]
+    InvalidListLength { len: usize, item_size: usize },
+    #[error("SSZ boolean is neither 0 nor 1")]
+    InvalidBool,
+}
+
+/// The `N` bytes at `pos`.
+///
+/// # Errors
+/// SszError::TooShort
+pub fn read_fixed<const N: usize>(bytes: &[u8], pos: usize) -> Result<[u8; N], SszError> {
+    let end = pos + N;
+    bytes
+        .get(pos..end)
+        .map(|field| field.try_into().unwrap())
+        .ok_or(SszError::TooShort {
+            end,
+            got: bytes.len(),
+        })
+}
+
+/// # Errors
+/// SszError::TooShort
+pub fn read_u64(bytes: &[u8], pos: usize) -> Result<u64, SszError> {
+    read_fixed(bytes, pos).map(u64::from_le_bytes)
+}
+
+/// # Errors
+/// SszError::TooShort
+pub fn read_u32(bytes: &[u8], pos: usize) -> Result<u32, SszError> {
+    read_fixed(bytes, pos).map(u32::from_le_bytes)
+}
+
+/// A root or hash.
+///
+/// # Errors
+/// SszError::TooShort
+pub fn read_bytes32(bytes: &[u8], pos: usize) -> Result<[u8; 32], SszError> {
+    read_fixed(bytes, pos)
+}
+
+/// # Errors
+/// SszError::TooShort, SszError::InvalidBool
+pub fn read_bool(bytes: &[u8], pos: usize) -> Result<bool, SszError> {
+    match read_fixed::<1>(bytes, pos)? {
+        [0] => Ok(false),
+        [1] => Ok(true),
+        _ => Err(SszError::InvalidBool),
+    }
+}
+
+/// The variable-size field whose offset is at `offset_pos` in the fixed
+/// part. It ends where the field with the offset at `next_offset_pos`
+/// starts, or at the end of `bytes` for the last variable-size field.
+///
+/// # Errors
+/// SszError::TooShort, SszError::InvalidOffset
+pub fn read_variable(
+    bytes: &[u8],
+    offset_pos: usize,
+    next_offset_pos: Option<usize>,
+) -> Result<&[u8], SszError> {
+    let start = read_u32(bytes, offset_pos)? as usize;
+    let end = match next_offset_pos {
+        Some(pos) => read_u32(bytes, pos)? as usize,
+        None => bytes.len(),
+    };
+    if start > end || end > bytes.len() {
+        return Err(SszError::InvalidOffset(offset_pos));
+    }
+    Ok(&bytes[start..end])
+}
+
+/// Splits a list of fixed-size items, such as a list of roots.
+///
+/// # Errors
+/// SszError::InvalidListLength
+pub fn split_fixed_list(bytes: &[u8], item_size: usize) -> Result<Vec<&[u8]>, SszError> {
+    if item_size == 0 || bytes.len() % item_size != 0 {
+        return Err(SszError::InvalidListLength {
+            len: bytes.len(),
+            item_size,
+        });
+    }
+    Ok(bytes.chunks(item_size).collect())
+}
+
+/// Splits a list of variable-size items, such as the transactions of an
+/// execution payload, by the offsets in front of them.
+///
+/// # Errors
+/// SszError::TooShort, SszError::InvalidOffset
+pub fn split_variable_list(bytes: &[u8]) -> Result<Vec<&[u8]>, SszError> {
+    if bytes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let first = read_u32(bytes, 0)? as usize;
+    if first % 4 != 0 || first == 0 {
+        return Err(SszError::InvalidOffset(0));
+    }
+    let count = first / 4;
+    (0..count)
+        .map(|i| read_variable(bytes, i * 4, Some(i * 4 + 4).filter(|_| i + 1 < count)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_fields() {
+        // BeaconBlockHeader
+        let mut header = Vec::new();
+        header.extend_from_slice(&7u64.to_le_bytes());
+        header.extend_from_slice(&42u64.to_le_bytes());
+        header.extend_from_slice(&[1; 32]);
+        header.extend_from_slice(&[2; 32]);
+        header.extend_from_slice(&[3; 32]);
+
+        assert_eq!(read_u64(&header, 0).unwrap(), 7);
+        assert_eq!(read_u64(&header, 8).unwrap(), 42);
+        assert_eq!(read_bytes32(&header, 48).unwrap(), [2; 32]);
+        assert_eq!(
+            read_bytes32(&header, 96).unwrap_err(),
+            SszError::TooShort { end: 128, got: 112 }
+        );
+        assert!(read_bool(&[1], 0).unwrap());
+        assert_eq!(read_bool(&[2], 0).unwrap_err(), SszError::InvalidBool);
+    }
+
+    #[test]
+    fn test_variable_fields() {
+        // { a: uint64, b: List[uint8], c: List[uint8] }
+        let mut container = Vec::new();
+        container.extend_from_slice(&5u64.to_le_bytes());
+        container.extend_from_slice(&16u32.to_le_bytes());
+        container.extend_from_slice(&19u32.to_le_bytes());
+        container.extend_from_slice(b"abcde");
+
+        assert_eq!(read_u64(&container, 0).unwrap(), 5);
+        assert_eq!(read_variable(&container, 8, Some(12)).unwrap(), b"abc");
+        assert_eq!(read_variable(&container, 12, None).unwrap(), b"de");
+
+        container[12] = 30;
+        assert_eq!(
+            read_variable(&container, 8, Some(12)).unwrap_err(),
+            SszError::InvalidOffset(8)
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        assert_eq!(
+            split_fixed_list(&[1, 2, 3, 4], 2).unwrap(),
+            vec![&[1, 2][..], &[3, 4][..]]
+        );
+        assert!(split_fixed_list(&[1, 2, 3], 2).is_err());
+
+        let mut list = Vec::new();
+        list.extend_from_slice(&8u32.to_le_bytes());
+        list.extend_from_slice(&10u32.to_le_bytes());
+        list.extend_from_slice(b"txtx2");
+        assert_eq!(
+            split_variable_list(&list).unwrap(),
+            vec![&b"tx"[..], &b"tx2"[..]]
+        );
+        assert!(split_variable_list(&[]).unwrap().is_empty());
+        assert_eq!(
+            split_variable_list(&[3, 0, 0, 0]).unwrap_err(),
+            SszError::InvalidOffset(0)
+        );
+    }
+}
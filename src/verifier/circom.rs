@@ -0,0 +1,132 @@
+// Copyright © 2022, Electron Labs
+
+//! Parsing of the decimal or hex strings that circom json uses for field
+//! elements and points, shared by the json adapters of every chain.
+
+use crate::verifier::core::VerifierError;
+use ark_ff::PrimeField;
+
+/// Parses a field element written in decimal or as `0x`-prefixed
+/// big-endian hex, naming it with `at` if it is malformed. In either
+/// encoding the value must be below the modulus, so that `x` and `x + r`
+/// are not two encodings of the same element.
+pub(crate) fn field_from_str<F: PrimeField>(
+    s: &str,
+    at: impl FnOnce() -> String,
+) -> Result<F, VerifierError> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(digits) => field_from_hex(digits),
+        None => field_from_decimal(s),
+    };
+    parsed.ok_or_else(|| VerifierError::InvalidFieldElement(at()))
+}
+
+/// Parses a decimal field element, without reducing it.
+pub(crate) fn field_from_decimal<F: PrimeField>(digits: &str) -> Option<F> {
+    if digits.is_empty() {
+        return None;
+    }
+    let max_len = F::BigInt::default().as_ref().len() * 8;
+    // Big-endian bytes, multiplied by ten for every digit.
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in digits.chars() {
+        let mut carry = c.to_digit(10)?;
+        for byte in bytes.iter_mut().rev() {
+            let value = u32::from(*byte) * 10 + carry;
+            *byte = value as u8;
+            carry = value >> 8;
+        }
+        if carry > 0 {
+            bytes.insert(0, carry as u8);
+        }
+        if bytes.len() > max_len {
+            return None;
+        }
+    }
+    field_from_be_bytes(&bytes)
+}
+
+/// The field element of big-endian bytes, if they are below the modulus.
+fn field_from_be_bytes<F: PrimeField>(bytes: &[u8]) -> Option<F> {
+    let mut repr = F::BigInt::default();
+    let limbs = repr.as_mut();
+    let significant: Vec<u8> = bytes
+        .iter()
+        .copied()
+        .skip_while(|byte| *byte == 0)
+        .collect();
+    if significant.len() > limbs.len() * 8 {
+        return None;
+    }
+    for (i, byte) in significant.iter().rev().enumerate() {
+        limbs[i / 8] |= u64::from(*byte) << (8 * (i % 8));
+    }
+    F::from_repr(repr)
+}
+
+fn field_from_hex<F: PrimeField>(digits: &str) -> Option<F> {
+    if digits.is_empty() {
+        return None;
+    }
+    let bytes = if digits.len() % 2 == 1 {
+        hex::decode(format!("0{}", digits))
+    } else {
+        hex::decode(digits)
+    }
+    .ok()?;
+    field_from_be_bytes(&bytes)
+}
+
+/// Parses public input strings, naming the first malformed one.
+pub(crate) fn inputs_from_str<F: PrimeField>(
+    pub_inputs: &[String],
+) -> Result<Vec<F>, VerifierError> {
+    pub_inputs
+        .iter()
+        .enumerate()
+        .map(|(i, s)| field_from_str(s, || format!("public input {}", i)))
+        .collect()
+}
+
+/// Coordinate `index` of the point `name` in circom json, as an empty
+/// string if it is missing so that it fails to parse.
+pub(crate) fn coordinate<'a>(point: &'a [String], index: usize) -> &'a str {
+    point.get(index).map(String::as_str).unwrap_or("")
+}
+
+fn fq_from_str(g: &[String], name: &str, index: usize) -> Result<ark_bn254::Fq, VerifierError> {
+    field_from_str(coordinate(g, index), || format!("{}[{}]", name, index))
+}
+
+pub(crate) fn g1_from_str(g1: &[String], name: &str) -> Result<ark_bn254::G1Affine, VerifierError> {
+    let x = fq_from_str(g1, name, 0)?;
+    let y = fq_from_str(g1, name, 1)?;
+    let z = fq_from_str(g1, name, 2)?;
+    Ok(ark_bn254::G1Affine::from(ark_bn254::G1Projective::new(
+        x, y, z,
+    )))
+}
+
+fn fq2_from_str(
+    g2: &[Vec<String>],
+    name: &str,
+    index: usize,
+) -> Result<ark_bn254::Fq2, VerifierError> {
+    let coords = g2.get(index).map(Vec::as_slice).unwrap_or(&[]);
+    let name = format!("{}[{}]", name, index);
+    let c0 = fq_from_str(coords, &name, 0)?;
+    let c1 = fq_from_str(coords, &name, 1)?;
+    Ok(ark_bn254::Fq2::new(c0, c1))
+}
+
+pub(crate) fn g2_from_str(
+    g2: &[Vec<String>],
+    name: &str,
+) -> Result<ark_bn254::G2Affine, VerifierError> {
+    let x = fq2_from_str(g2, name, 0)?;
+    let y = fq2_from_str(g2, name, 1)?;
+    let z = fq2_from_str(g2, name, 2)?;
+    Ok(ark_bn254::G2Affine::from(ark_bn254::G2Projective::new(
+        x, y, z,
+    )))
+}
@@ -0,0 +1,131 @@
+// Copyright © 2022, Electron Labs
+
+//! Pluggable pairing implementations.
+//!
+//! Groth16 verification reduces to checking that a product of four
+//! pairings is one. [`verify_with_backend`] computes the pairing inputs and
+//! hands the check to a [`PairingBackend`], so a faster pairing, e.g. a
+//! host function of the chain, can be dropped in without touching the
+//! verification logic. [`ArkworksBackend`] is the portable implementation
+//! used everywhere else in the crate.
+
+use super::{check_input_count, VerifierError, WrappedEngine};
+use alloc::vec::Vec;
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{One, PrimeField};
+
+/// Checks products of pairings on the curve `E`.
+pub trait PairingBackend<E: PairingEngine> {
+    /// Returns whether `prod e(a_i, b_i)` is the identity of the target
+    /// group.
+    fn pairing_check(&self, pairs: &[(E::G1Affine, E::G2Affine)]) -> bool;
+}
+
+/// Pairings computed by arkworks, in wasm or natively.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ArkworksBackend;
+
+impl<E: PairingEngine> PairingBackend<E> for ArkworksBackend {
+    fn pairing_check(&self, pairs: &[(E::G1Affine, E::G2Affine)]) -> bool {
+        let prepared: Vec<(E::G1Prepared, E::G2Prepared)> = pairs
+            .iter()
+            .map(|(a, b)| ((*a).into(), (*b).into()))
+            .collect();
+        E::final_exponentiation(&E::miller_loop(prepared.iter())) == Some(E::Fqk::one())
+    }
+}
+
+/// Verifies a proof with the pairing check done by `backend`.
+///
+/// # Errors
+/// VerifierError::InputCountMismatch
+///
+/// This function will return an error if the number of inputs doesn't
+/// match the key.
+pub fn verify_with_backend<E: WrappedEngine, B: PairingBackend<E> + ?Sized>(
+    backend: &B,
+    pvk: &E::PreparedVerifyingKey,
+    proof: &ark_groth16::Proof<E>,
+    pub_inputs: &[E::Fr],
+) -> Result<bool, VerifierError> {
+    let pvk: ark_groth16::PreparedVerifyingKey<E> = pvk.clone().into();
+    verify_key_with_backend(backend, &pvk.vk, proof, pub_inputs)
+}
+
+/// Like `verify_with_backend`, for a key that was never prepared. The
+/// pairing check recomputes e(alpha, beta) on every call, which is cheaper
+/// than storing the prepared key where storage is charged per byte.
+///
+/// # Errors
+/// VerifierError::InputCountMismatch
+pub fn verify_key_with_backend<E: PairingEngine, B: PairingBackend<E> + ?Sized>(
+    backend: &B,
+    vk: &ark_groth16::VerifyingKey<E>,
+    proof: &ark_groth16::Proof<E>,
+    pub_inputs: &[E::Fr],
+) -> Result<bool, VerifierError> {
+    check_input_count(vk.gamma_abc_g1.len(), pub_inputs.len())?;
+
+    let mut prepared_inputs = vk.gamma_abc_g1[0].into_projective();
+    for (input, base) in pub_inputs.iter().zip(&vk.gamma_abc_g1[1..]) {
+        prepared_inputs += &base.mul(input.into_repr());
+    }
+
+    // e(A, B) * e(-PI, gamma) * e(-C, delta) * e(-alpha, beta) == 1
+    let pairs = [
+        (proof.a, proof.b),
+        (-prepared_inputs.into_affine(), vk.gamma_g2),
+        (-proof.c, vk.delta_g2),
+        (-vk.alpha_g1, vk.beta_g2),
+    ];
+    Ok(backend.pairing_check(&pairs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::test_circuit;
+    use std::cell::Cell;
+
+    /// Counts the pairing checks it forwards to arkworks.
+    #[derive(Default)]
+    struct CountingBackend(Cell<usize>);
+
+    impl<E: PairingEngine> PairingBackend<E> for CountingBackend {
+        fn pairing_check(&self, pairs: &[(E::G1Affine, E::G2Affine)]) -> bool {
+            self.0.set(self.0.get() + 1);
+            <ArkworksBackend as PairingBackend<E>>::pairing_check(&ArkworksBackend, pairs)
+        }
+    }
+
+    fn check_curve<E: WrappedEngine>() {
+        let (vk, proof, c) = test_circuit::prove::<E>(3, 5);
+        let pvk: E::PreparedVerifyingKey = ark_groth16::prepare_verifying_key(&vk).into();
+
+        assert!(verify_with_backend(&ArkworksBackend, &pvk, &proof, &[c]).unwrap());
+        assert!(
+            !verify_with_backend(&ArkworksBackend, &pvk, &proof, &[E::Fr::from(16u64)]).unwrap()
+        );
+        assert!(verify_with_backend(&ArkworksBackend, &pvk, &proof, &[]).is_err());
+    }
+
+    #[test]
+    fn test_arkworks_backend() {
+        check_curve::<ark_bn254::Bn254>();
+        check_curve::<ark_bls12_381::Bls12_381>();
+        check_curve::<ark_bls12_377::Bls12_377>();
+    }
+
+    #[test]
+    fn test_backend_chosen_at_runtime() {
+        let (vk, proof, c) = test_circuit::prove::<ark_bn254::Bn254>(3, 5);
+        let pvk: crate::verifier::core::PreparedVerifyingKey =
+            ark_groth16::prepare_verifying_key(&vk).into();
+        let counting = CountingBackend::default();
+        let backends: [&dyn PairingBackend<ark_bn254::Bn254>; 2] = [&ArkworksBackend, &counting];
+        for backend in backends.iter() {
+            assert!(verify_with_backend(*backend, &pvk, &proof, &[c]).unwrap());
+        }
+        assert_eq!(counting.0.get(), 1);
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright © 2022, Electron Labs
+
+//! Batch verification of Groth16 proofs against one key.
+//!
+//! Each proof's verification equation is scaled by a random 128-bit scalar
+//! `r_i` and the equations are summed, which needs one miller loop term
+//! per proof plus two shared terms and a single final exponentiation:
+//!
+//! `prod e(r_i A_i, B_i) * e(sum r_i PI_i, -gamma) * e(sum r_i C_i, -delta)
+//!  == e(alpha, beta)^(sum r_i)`
+//!
+//! If any proof is invalid the batch fails except with probability about
+//! 2^-128 over the choice of scalars, so the scalars must not be known to
+//! the prover before the proofs are fixed.
+//!
+//! [`verify_batch_items`] reports the outcome of every proof instead, so
+//! callers can settle the valid proofs of a batch and reject only the
+//! invalid ones.
+
+use super::{check_input_count, VerifierError, WrappedEngine};
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, PrimeField, Zero};
+use sha2::{Digest, Sha256};
+
+/// Derives the scalar of the proof at `index` from the batch seed.
+fn batch_scalar<F: PrimeField>(seed: &[u8; 32], index: usize) -> F {
+    let mut hasher = Sha256::new();
+    hasher.update(seed);
+    hasher.update((index as u64).to_le_bytes());
+    let digest = hasher.finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    F::from(u128::from_le_bytes(bytes))
+}
+
+/// Verifies all proofs at once with a random linear combination seeded by
+/// `seed`. Returns `Ok(true)` for an empty batch.
+///
+/// # Errors
+/// VerifierError::InputCountMismatch, VerifierError::SynthesisError
+///
+/// This function will return an error if the number of inputs of any proof
+/// doesn't match the key, or if they can't be checked against it.
+pub fn verify_batch<E: WrappedEngine>(
+    pvk: &E::PreparedVerifyingKey,
+    proofs: &[(ark_groth16::Proof<E>, Vec<E::Fr>)],
+    seed: &[u8; 32],
+) -> Result<bool, VerifierError> {
+    if proofs.is_empty() {
+        return Ok(true);
+    }
+    let ark_pvk: ark_groth16::PreparedVerifyingKey<E> = pvk.clone().into();
+
+    let mut pairs: Vec<(E::G1Prepared, E::G2Prepared)> = Vec::with_capacity(proofs.len() + 2);
+    let mut inputs_acc = E::G1Projective::zero();
+    let mut c_acc = E::G1Projective::zero();
+    let mut r_sum = E::Fr::zero();
+    for (i, (proof, pub_inputs)) in proofs.iter().enumerate() {
+        check_input_count(ark_pvk.vk.gamma_abc_g1.len(), pub_inputs.len())?;
+        let r: E::Fr = batch_scalar(seed, i);
+        let prepared_inputs = ark_groth16::prepare_inputs(&ark_pvk, pub_inputs)
+            .map_err(|e| VerifierError::SynthesisError(format!("{:?}", e)))?;
+
+        inputs_acc += &prepared_inputs.mul(r.into_repr());
+        c_acc += &proof.c.mul(r.into_repr());
+        let a: E::G1Affine = proof.a.mul(r.into_repr()).into_affine();
+        pairs.push((a.into(), proof.b.into()));
+        r_sum += &r;
+    }
+    pairs.push((
+        inputs_acc.into_affine().into(),
+        ark_pvk.gamma_g2_neg_pc.clone(),
+    ));
+    pairs.push((c_acc.into_affine().into(), ark_pvk.delta_g2_neg_pc.clone()));
+
+    let test = E::final_exponentiation(&E::miller_loop(pairs.iter()))
+        .ok_or_else(|| VerifierError::SynthesisError("UnexpectedIdentity".to_string()))?;
+    Ok(test == ark_pvk.alpha_g1_beta_g2.pow(r_sum.into_repr()))
+}
+
+/// Outcome of every proof of a batch.
+#[derive(Debug, Default)]
+pub struct BatchResult {
+    /// Indices of the proofs that verify, in increasing order.
+    pub passed: Vec<usize>,
+    /// Indices of the proofs that don't, with the reason, in increasing
+    /// order.
+    pub failed: Vec<(usize, VerifierError)>,
+}
+
+impl BatchResult {
+    /// Whether every proof of the batch verifies.
+    pub fn all_passed(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+fn verify_item<E: WrappedEngine>(
+    pvk: &ark_groth16::PreparedVerifyingKey<E>,
+    proof: &ark_groth16::Proof<E>,
+    pub_inputs: &[E::Fr],
+) -> Result<(), VerifierError> {
+    check_input_count(pvk.vk.gamma_abc_g1.len(), pub_inputs.len())?;
+    match ark_groth16::verify_proof(pvk, proof, pub_inputs) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(VerifierError::VerificationFailed),
+        Err(e) => Err(VerifierError::SynthesisError(format!("{:?}", e))),
+    }
+}
+
+/// Verifies every proof and reports each outcome. Proofs with the wrong
+/// number of inputs fail right away, the others are checked with
+/// `verify_batch` and, only if the batch fails, one by one.
+pub fn verify_batch_items<E: WrappedEngine>(
+    pvk: &E::PreparedVerifyingKey,
+    proofs: &[(ark_groth16::Proof<E>, Vec<E::Fr>)],
+    seed: &[u8; 32],
+) -> BatchResult {
+    let ark_pvk: ark_groth16::PreparedVerifyingKey<E> = pvk.clone().into();
+    let mut result = BatchResult::default();
+    let mut candidates = Vec::with_capacity(proofs.len());
+    for (i, (_, pub_inputs)) in proofs.iter().enumerate() {
+        match check_input_count(ark_pvk.vk.gamma_abc_g1.len(), pub_inputs.len()) {
+            Ok(()) => candidates.push(i),
+            Err(e) => result.failed.push((i, e)),
+        }
+    }
+
+    let batch: Vec<_> = candidates.iter().map(|i| proofs[*i].clone()).collect();
+    if let Ok(true) = verify_batch::<E>(pvk, &batch, seed) {
+        result.passed = candidates;
+    } else {
+        for i in candidates {
+            let (proof, pub_inputs) = &proofs[i];
+            match verify_item(&ark_pvk, proof, pub_inputs) {
+                Ok(()) => result.passed.push(i),
+                Err(e) => result.failed.push((i, e)),
+            }
+        }
+    }
+    result.failed.sort_by_key(|(i, _)| *i);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::{bls12_381, test_circuit};
+
+    type E = ark_bls12_381::Bls12_381;
+
+    fn batch(
+        values: &[(u64, u64)],
+    ) -> (
+        ark_groth16::VerifyingKey<E>,
+        Vec<(ark_groth16::Proof<E>, Vec<ark_bls12_381::Fr>)>,
+    ) {
+        let (vk, proofs) = test_circuit::prove_many::<E>(values);
+        let proofs = proofs
+            .into_iter()
+            .map(|(proof, c)| (proof, vec![c]))
+            .collect();
+        (vk, proofs)
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let (vk, proofs) = batch(&[(3, 5), (7, 11), (2, 9)]);
+        let pvk: bls12_381::PreparedVerifyingKey = ark_groth16::prepare_verifying_key(&vk).into();
+        assert!(verify_batch::<E>(&pvk, &proofs, &[1u8; 32]).unwrap());
+        assert!(verify_batch::<E>(&pvk, &[], &[1u8; 32]).unwrap());
+    }
+
+    #[test]
+    fn test_verify_batch_invalid_proof() {
+        let (vk, mut proofs) = batch(&[(3, 5), (7, 11), (2, 9)]);
+        proofs[1].1 = vec![ark_bls12_381::Fr::from(78u64)];
+        let pvk: bls12_381::PreparedVerifyingKey = ark_groth16::prepare_verifying_key(&vk).into();
+        assert!(!verify_batch::<E>(&pvk, &proofs, &[1u8; 32]).unwrap());
+    }
+
+    #[test]
+    fn test_verify_batch_items() {
+        let (vk, mut proofs) = batch(&[(3, 5), (7, 11), (2, 9), (4, 4)]);
+        let pvk: bls12_381::PreparedVerifyingKey = ark_groth16::prepare_verifying_key(&vk).into();
+        let result = verify_batch_items::<E>(&pvk, &proofs, &[1u8; 32]);
+        assert!(result.all_passed());
+        assert_eq!(result.passed, vec![0, 1, 2, 3]);
+
+        proofs[1].1 = vec![ark_bls12_381::Fr::from(78u64)];
+        proofs[3].1 = vec![];
+        let result = verify_batch_items::<E>(&pvk, &proofs, &[1u8; 32]);
+        assert_eq!(result.passed, vec![0, 2]);
+        let failed: Vec<(usize, String)> = result
+            .failed
+            .iter()
+            .map(|(i, e)| (*i, e.to_string()))
+            .collect();
+        assert_eq!(
+            failed,
+            vec![
+                (1, "Proof does not verify".to_string()),
+                (3, "Verifying key takes 1 public inputs, got 0".to_string()),
+            ]
+        );
+    }
+}
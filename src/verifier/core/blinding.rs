@@ -0,0 +1,133 @@
+// Copyright © 2022, Electron Labs
+
+//! Re-randomized verifying keys, which verify re-randomized proofs of the
+//! original circuit without showing which key they were derived from.
+//!
+//! With nonzero scalars `k, u, s, t` the key
+//!
+//! `(k u alpha, beta / u, s gamma, delta / t, (k / s) IC)`
+//!
+//! accepts `(k A, B, k t C)` for every proof `(A, B, C)` of the original
+//! key with the same public inputs, since each side of the pairing
+//! equation is raised to the power `k`. Forging a proof for the blinded key
+//! is as hard as for the original one, as anyone who knows the factors can
+//! map proofs between them.
+//!
+//! Linking a blinded key to a candidate key without the factors means
+//! deciding Diffie-Hellman tuples in G1 and GT, which is assumed hard for
+//! BN254 and BLS12-381. The number of public inputs is not hidden. Provers
+//! need `k` and `t`, so [`Blinding`] derives all four factors from one
+//! 32-byte seed that is shared with them and revealed at unveil.
+
+use alloc::vec::Vec;
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, PrimeField, Zero};
+use sha2::{Digest, Sha256};
+
+/// Derives factor `index` from the seed, hashing again in the negligible
+/// case of zero.
+fn blinding_factor<F: PrimeField>(seed: &[u8; 32], index: u8) -> F {
+    (0u64..)
+        .map(|counter| {
+            let mut hasher = Sha256::new();
+            hasher.update(seed);
+            hasher.update([index]);
+            hasher.update(counter.to_le_bytes());
+            F::from_le_bytes_mod_order(&hasher.finalize())
+        })
+        .find(|factor| !factor.is_zero())
+        .expect("Failed to derive a nonzero blinding factor")
+}
+
+/// The factors `k, u, s, t` of a blinded key, derived from a seed.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Blinding<E: PairingEngine> {
+    k: E::Fr,
+    u: E::Fr,
+    s: E::Fr,
+    t: E::Fr,
+}
+
+impl<E: PairingEngine> Blinding<E> {
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        Blinding {
+            k: blinding_factor(seed, 0),
+            u: blinding_factor(seed, 1),
+            s: blinding_factor(seed, 2),
+            t: blinding_factor(seed, 3),
+        }
+    }
+
+    /// Blinds a verifying key. The result is an ordinary key, which can be
+    /// prepared and stored like any other.
+    pub fn blind_verifying_key(
+        &self,
+        vk: &ark_groth16::VerifyingKey<E>,
+    ) -> ark_groth16::VerifyingKey<E> {
+        let u_inv = self.u.inverse().expect("Blinding factors are nonzero");
+        let s_inv = self.s.inverse().expect("Blinding factors are nonzero");
+        let t_inv = self.t.inverse().expect("Blinding factors are nonzero");
+        let ic_factor = (self.k * s_inv).into_repr();
+        ark_groth16::VerifyingKey {
+            alpha_g1: vk.alpha_g1.mul((self.k * self.u).into_repr()).into_affine(),
+            beta_g2: vk.beta_g2.mul(u_inv.into_repr()).into_affine(),
+            gamma_g2: vk.gamma_g2.mul(self.s.into_repr()).into_affine(),
+            delta_g2: vk.delta_g2.mul(t_inv.into_repr()).into_affine(),
+            gamma_abc_g1: vk
+                .gamma_abc_g1
+                .iter()
+                .map(|point| point.mul(ic_factor).into_affine())
+                .collect::<Vec<_>>(),
+        }
+    }
+
+    /// Blinds a proof of the original key, so that it verifies against the
+    /// blinded key with the same public inputs.
+    pub fn blind_proof(&self, proof: &ark_groth16::Proof<E>) -> ark_groth16::Proof<E> {
+        ark_groth16::Proof {
+            a: proof.a.mul(self.k.into_repr()).into_affine(),
+            b: proof.b,
+            c: proof.c.mul((self.k * self.t).into_repr()).into_affine(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::{test_circuit, GrothVerifier, WrappedEngine};
+    use ark_ff::One;
+
+    fn check_blinding<E: WrappedEngine>() {
+        let (vk, proof, c) = test_circuit::prove::<E>(3, 5);
+        let blinding = Blinding::<E>::from_seed(&[7; 32]);
+        let blinded_vk = blinding.blind_verifying_key(&vk);
+        let blinded_proof = blinding.blind_proof(&proof);
+        assert_ne!(blinded_vk.alpha_g1, vk.alpha_g1);
+        assert_ne!(blinded_vk.delta_g2, vk.delta_g2);
+
+        let verifier = GrothVerifier::<E>::new(&blinded_vk);
+        assert!(verifier.verify(&blinded_proof, &[c]).unwrap());
+        assert!(!verifier
+            .verify(&blinded_proof, &[c + E::Fr::one()])
+            .unwrap());
+        assert!(!verifier.verify(&proof, &[c]).unwrap());
+        assert!(!GrothVerifier::<E>::new(&vk)
+            .verify(&blinded_proof, &[c])
+            .unwrap());
+
+        let other = Blinding::<E>::from_seed(&[8; 32]);
+        assert_ne!(other, blinding);
+        assert!(!verifier.verify(&other.blind_proof(&proof), &[c]).unwrap());
+    }
+
+    #[test]
+    fn test_blinding_bn254() {
+        check_blinding::<ark_bn254::Bn254>();
+    }
+
+    #[test]
+    fn test_blinding_bls12_381() {
+        check_blinding::<ark_bls12_381::Bls12_381>();
+    }
+}
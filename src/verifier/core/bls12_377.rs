@@ -0,0 +1,5 @@
+// Copyright © 2022, Electron Labs
+
+//! Borsh-serializable mirrors of the arkworks BLS12-377 types.
+
+curve_wrappers!(ark_bls12_377, Bls12_377, BigInteger384, 6, bls12);
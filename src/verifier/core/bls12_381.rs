@@ -0,0 +1,32 @@
+// Copyright © 2022, Electron Labs
+
+//! Borsh-serializable mirrors of the arkworks BLS12-381 types.
+
+curve_wrappers!(ark_bls12_381, Bls12_381, BigInteger384, 6, bls12);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::test_circuit;
+    use borsh::{BorshDeserialize, BorshSerialize};
+
+    #[test]
+    fn test_prepared_verifying_key_round_trip() {
+        let (vk, _, _) = test_circuit::prove::<ark_bls12_381::Bls12_381>(3, 5);
+        let pvk: PreparedVerifyingKey = ark_groth16::prepare_verifying_key(&vk).into();
+        let bytes = pvk.try_to_vec().unwrap();
+        let decoded = PreparedVerifyingKey::try_from_slice(&bytes).unwrap();
+        assert_eq!(decoded, pvk);
+        assert_eq!(decoded.fingerprint(), pvk.fingerprint());
+    }
+
+    #[test]
+    fn test_verify_prepared() {
+        let (vk, proof, c) = test_circuit::prove::<ark_bls12_381::Bls12_381>(3, 5);
+        let pvk: PreparedVerifyingKey = ark_groth16::prepare_verifying_key(&vk).into();
+        assert!(verify_prepared(&pvk, &proof, &[c]).unwrap());
+
+        let wrong = ark_bls12_381::Fr::from(16u64);
+        assert!(!verify_prepared(&pvk, &proof, &[wrong]).unwrap());
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright © 2022, Electron Labs
+
+//! BLS12-381 pairings computed by blst, for relayers and other native
+//! verifiers. Enabled by the `blst` feature; blst has no BN254 support.
+//!
+//! Points are handed to blst in the uncompressed zcash encoding, which
+//! blst checks to be on the curve when deserializing.
+
+use super::backend::PairingBackend;
+use alloc::vec::Vec;
+use ark_ff::{BigInteger, PrimeField, Zero};
+use blst::{
+    blst_final_exp, blst_fp12, blst_fp12_is_one, blst_fp12_mul, blst_miller_loop, blst_p1_affine,
+    blst_p1_deserialize, blst_p2_affine, blst_p2_deserialize, BLST_ERROR,
+};
+
+const FP_LEN: usize = 48;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BlstBackend;
+
+fn push_fp(fp: ark_bls12_381::Fq, out: &mut Vec<u8>) {
+    out.extend_from_slice(&fp.into_repr().to_bytes_be());
+}
+
+fn to_blst_g1(point: &ark_bls12_381::G1Affine) -> Option<blst_p1_affine> {
+    let mut bytes = Vec::with_capacity(2 * FP_LEN);
+    push_fp(point.x, &mut bytes);
+    push_fp(point.y, &mut bytes);
+    let mut out = blst_p1_affine::default();
+    let res = unsafe { blst_p1_deserialize(&mut out, bytes.as_ptr()) };
+    if res == BLST_ERROR::BLST_SUCCESS {
+        Some(out)
+    } else {
+        None
+    }
+}
+
+fn to_blst_g2(point: &ark_bls12_381::G2Affine) -> Option<blst_p2_affine> {
+    // The zcash encoding puts the imaginary part of each coordinate first.
+    let mut bytes = Vec::with_capacity(4 * FP_LEN);
+    push_fp(point.x.c1, &mut bytes);
+    push_fp(point.x.c0, &mut bytes);
+    push_fp(point.y.c1, &mut bytes);
+    push_fp(point.y.c0, &mut bytes);
+    let mut out = blst_p2_affine::default();
+    let res = unsafe { blst_p2_deserialize(&mut out, bytes.as_ptr()) };
+    if res == BLST_ERROR::BLST_SUCCESS {
+        Some(out)
+    } else {
+        None
+    }
+}
+
+impl PairingBackend<ark_bls12_381::Bls12_381> for BlstBackend {
+    fn pairing_check(&self, pairs: &[(ark_bls12_381::G1Affine, ark_bls12_381::G2Affine)]) -> bool {
+        let mut acc: Option<blst_fp12> = None;
+        for (a, b) in pairs {
+            // e(O, Q) = e(P, O) = 1, so these pairs don't affect the product.
+            if a.is_zero() || b.is_zero() {
+                continue;
+            }
+            let (p, q) = match (to_blst_g1(a), to_blst_g2(b)) {
+                (Some(p), Some(q)) => (p, q),
+                _ => return false,
+            };
+            let mut ml = blst_fp12::default();
+            unsafe { blst_miller_loop(&mut ml, &q, &p) };
+            acc = Some(match acc {
+                None => ml,
+                Some(prev) => {
+                    let mut product = blst_fp12::default();
+                    unsafe { blst_fp12_mul(&mut product, &prev, &ml) };
+                    product
+                }
+            });
+        }
+        let acc = match acc {
+            None => return true,
+            Some(acc) => acc,
+        };
+        let mut result = blst_fp12::default();
+        unsafe {
+            blst_final_exp(&mut result, &acc);
+            blst_fp12_is_one(&result)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::backend::{verify_with_backend, ArkworksBackend};
+    use crate::verifier::core::{bls12_381, test_circuit};
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+
+    #[test]
+    fn test_blst_matches_arkworks() {
+        let (vk, proofs) =
+            test_circuit::prove_many::<ark_bls12_381::Bls12_381>(&[(3, 5), (2, 7), (11, 13)]);
+        let pvk: bls12_381::PreparedVerifyingKey = ark_groth16::prepare_verifying_key(&vk).into();
+        for (proof, c) in &proofs {
+            for input in [*c, *c + ark_bls12_381::Fr::from(1u64)] {
+                let blst = verify_with_backend(&BlstBackend, &pvk, proof, &[input]).unwrap();
+                let ark = verify_with_backend(&ArkworksBackend, &pvk, proof, &[input]).unwrap();
+                assert_eq!(blst, ark);
+            }
+        }
+    }
+
+    #[test]
+    fn test_blst_pairing_check() {
+        let g1 = ark_bls12_381::G1Affine::prime_subgroup_generator();
+        let g2 = ark_bls12_381::G2Affine::prime_subgroup_generator();
+        let two_g1 = g1.mul(2u64).into_affine();
+        let two_g2 = g2.mul(2u64).into_affine();
+        // e(2 g1, g2) * e(-g1, 2 g2) = 1
+        assert!(BlstBackend.pairing_check(&[(two_g1, g2), (-g1, two_g2)]));
+        assert!(!BlstBackend.pairing_check(&[(two_g1, g2), (g1, two_g2)]));
+        assert!(BlstBackend.pairing_check(&[(ark_bls12_381::G1Affine::zero(), g2)]));
+    }
+}
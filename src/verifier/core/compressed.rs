@@ -0,0 +1,157 @@
+// Copyright © 2022, Electron Labs
+
+//! Compressed BN254 verifying keys, for contracts that store many keys.
+//!
+//! Each point keeps only its x-coordinate and whether y is the larger of
+//! the two roots, which halves the size of the IC vector. A compressed key
+//! is also far smaller than a `PreparedVerifyingKey`, whose precomputed
+//! pairing lines dominate its size, so storing compressed keys and
+//! preparing them when a proof arrives trades gas for storage.
+
+use super::{BigInteger256, Fq2, VerifierError};
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use ark_ff::Zero;
+
+#[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+pub(crate) struct CompressedG1 {
+    pub(crate) x: BigInteger256,
+    pub(crate) greatest: bool,
+    pub(crate) infinity: bool,
+}
+
+impl From<ark_bn254::G1Affine> for CompressedG1 {
+    fn from(src: ark_bn254::G1Affine) -> Self {
+        CompressedG1 {
+            x: src.x.0.into(),
+            greatest: src.y > -src.y,
+            infinity: src.infinity,
+        }
+    }
+}
+
+impl CompressedG1 {
+    fn decompress(&self) -> Result<ark_bn254::G1Affine, VerifierError> {
+        if self.infinity {
+            return Ok(ark_bn254::G1Affine::zero());
+        }
+        let x = ark_bn254::Fq::new(self.x.clone().into());
+        ark_bn254::G1Affine::get_point_from_x(x, self.greatest).ok_or_else(|| {
+            VerifierError::InvalidEncoding("G1 x-coordinate is not on the curve".to_string())
+        })
+    }
+}
+
+#[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+pub(crate) struct CompressedG2 {
+    pub(crate) x: Fq2,
+    pub(crate) greatest: bool,
+    pub(crate) infinity: bool,
+}
+
+impl From<ark_bn254::G2Affine> for CompressedG2 {
+    fn from(src: ark_bn254::G2Affine) -> Self {
+        CompressedG2 {
+            x: src.x.into(),
+            greatest: src.y > -src.y,
+            infinity: src.infinity,
+        }
+    }
+}
+
+impl CompressedG2 {
+    fn decompress(&self) -> Result<ark_bn254::G2Affine, VerifierError> {
+        if self.infinity {
+            return Ok(ark_bn254::G2Affine::zero());
+        }
+        let point = ark_bn254::G2Affine::get_point_from_x(self.x.clone().into(), self.greatest)
+            .ok_or_else(|| {
+                VerifierError::InvalidEncoding("G2 x-coordinate is not on the curve".to_string())
+            })?;
+        if !point.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(VerifierError::InvalidEncoding(
+                "G2 point is not in the prime order subgroup".to_string(),
+            ));
+        }
+        Ok(point)
+    }
+}
+
+/// A BN254 verifying key with compressed points.
+#[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct CompressedVerifyingKey {
+    pub(crate) alpha_g1: CompressedG1,
+    pub(crate) beta_g2: CompressedG2,
+    pub(crate) gamma_g2: CompressedG2,
+    pub(crate) delta_g2: CompressedG2,
+    pub(crate) gamma_abc_g1: Vec<CompressedG1>,
+}
+
+impl From<ark_groth16::VerifyingKey<ark_bn254::Bn254>> for CompressedVerifyingKey {
+    fn from(src: ark_groth16::VerifyingKey<ark_bn254::Bn254>) -> Self {
+        CompressedVerifyingKey {
+            alpha_g1: src.alpha_g1.into(),
+            beta_g2: src.beta_g2.into(),
+            gamma_g2: src.gamma_g2.into(),
+            delta_g2: src.delta_g2.into(),
+            gamma_abc_g1: src.gamma_abc_g1.into_iter().map(|p| p.into()).collect(),
+        }
+    }
+}
+
+impl CompressedVerifyingKey {
+    /// Recovers the full points of the key.
+    ///
+    /// # Errors
+    /// VerifierError::InvalidEncoding
+    ///
+    /// This function will return an error if an x-coordinate has no point
+    /// on the curve, or a G2 point is outside the prime order subgroup.
+    pub fn decompress(&self) -> Result<ark_groth16::VerifyingKey<ark_bn254::Bn254>, VerifierError> {
+        Ok(ark_groth16::VerifyingKey {
+            alpha_g1: self.alpha_g1.decompress()?,
+            beta_g2: self.beta_g2.decompress()?,
+            gamma_g2: self.gamma_g2.decompress()?,
+            delta_g2: self.delta_g2.decompress()?,
+            gamma_abc_g1: self
+                .gamma_abc_g1
+                .iter()
+                .map(|p| p.decompress())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::{test_circuit, VerifyingKey};
+    use borsh::BorshSerialize;
+
+    #[test]
+    fn test_compressed_round_trip() {
+        let (vk, _, _) = test_circuit::prove::<ark_bn254::Bn254>(3, 11);
+        let compressed = CompressedVerifyingKey::from(vk.clone());
+        assert_eq!(compressed.decompress().unwrap(), vk);
+
+        let full_len = VerifyingKey::from(vk).try_to_vec().unwrap().len();
+        let compressed_len = compressed.try_to_vec().unwrap().len();
+        assert!(compressed_len < full_len * 3 / 5);
+    }
+
+    #[test]
+    fn test_compressed_invalid_x() {
+        let (vk, _, _) = test_circuit::prove::<ark_bn254::Bn254>(3, 11);
+        let mut compressed = CompressedVerifyingKey::from(vk);
+        let mut x = ark_bn254::Fq::from(1u64);
+        // y^2 = x^3 + 3 has no root for some small x; find one.
+        while ark_bn254::G1Affine::get_point_from_x(x, true).is_some() {
+            x += ark_bn254::Fq::from(1u64);
+        }
+        compressed.gamma_abc_g1[0].x = x.0.into();
+        assert!(matches!(
+            compressed.decompress(),
+            Err(VerifierError::InvalidEncoding(_))
+        ));
+    }
+}
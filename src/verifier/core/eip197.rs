@@ -0,0 +1,222 @@
+// Copyright © 2022, Electron Labs
+
+//! Encoding of BN254 points in the format of the EIP-196 and EIP-197
+//! precompiles, which chains without the EVM reuse for their `alt_bn128`
+//! host functions.
+//!
+//! A base field element takes 32 bytes, big-endian. G1 points are `x || y`
+//! (64 bytes), G2 points are `x.c1 || x.c0 || y.c1 || y.c0` (128 bytes),
+//! with the imaginary part first unlike EIP-2537, and the point at infinity
+//! is encoded as all zeros. Proofs are `a || b || c` and scalars are 32
+//! bytes, big-endian.
+
+use super::VerifierError;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use ark_ec::AffineCurve;
+use ark_ff::{BigInteger, BigInteger256, PrimeField, Zero};
+use core::convert::TryInto;
+
+pub const FP_LEN: usize = 32;
+pub const G1_LEN: usize = 2 * FP_LEN;
+pub const G2_LEN: usize = 4 * FP_LEN;
+pub const PROOF_LEN: usize = 2 * G1_LEN + G2_LEN;
+
+fn encode_fp(fq: ark_bn254::Fq, out: &mut Vec<u8>) {
+    out.extend_from_slice(&fq.into_repr().to_bytes_be());
+}
+
+fn decode_repr(bytes: &[u8]) -> BigInteger256 {
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(8).rev()) {
+        *limb = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    BigInteger256::new(limbs)
+}
+
+fn decode_fp(bytes: &[u8]) -> Result<ark_bn254::Fq, VerifierError> {
+    ark_bn254::Fq::from_repr(decode_repr(bytes))
+        .ok_or_else(|| VerifierError::InvalidEncoding("field element is not reduced".to_string()))
+}
+
+fn check_len(bytes: &[u8], len: usize, what: &str) -> Result<(), VerifierError> {
+    if bytes.len() != len {
+        return Err(VerifierError::InvalidEncoding(format!(
+            "{} must be {} bytes, got {}",
+            what,
+            len,
+            bytes.len()
+        )));
+    }
+    Ok(())
+}
+
+fn check_point<P: ark_ec::SWModelParameters>(
+    point: &ark_ec::short_weierstrass_jacobian::GroupAffine<P>,
+) -> Result<(), VerifierError> {
+    if !point.is_on_curve() {
+        return Err(VerifierError::InvalidEncoding(
+            "point is not on the curve".to_string(),
+        ));
+    }
+    if !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(VerifierError::InvalidEncoding(
+            "point is not in the prime order subgroup".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn encode_fr(fr: &ark_bn254::Fr) -> [u8; FP_LEN] {
+    fr.into_repr().to_bytes_be().try_into().unwrap()
+}
+
+/// Decodes a scalar, which must be reduced.
+///
+/// # Errors
+/// VerifierError::InvalidEncoding
+pub fn decode_fr(bytes: &[u8]) -> Result<ark_bn254::Fr, VerifierError> {
+    check_len(bytes, FP_LEN, "scalar")?;
+    ark_bn254::Fr::from_repr(decode_repr(bytes))
+        .ok_or_else(|| VerifierError::InvalidEncoding("scalar is not reduced".to_string()))
+}
+
+pub fn encode_g1(point: &ark_bn254::G1Affine) -> Vec<u8> {
+    if point.is_zero() {
+        return vec![0u8; G1_LEN];
+    }
+    let mut out = Vec::with_capacity(G1_LEN);
+    encode_fp(point.x, &mut out);
+    encode_fp(point.y, &mut out);
+    out
+}
+
+/// Decodes a G1 point, checking that it is on the curve as the precompiles
+/// do.
+///
+/// # Errors
+/// VerifierError::InvalidEncoding
+pub fn decode_g1(bytes: &[u8]) -> Result<ark_bn254::G1Affine, VerifierError> {
+    check_len(bytes, G1_LEN, "G1 point")?;
+    if bytes.iter().all(|b| *b == 0) {
+        return Ok(ark_bn254::G1Affine::zero());
+    }
+    let x = decode_fp(&bytes[..FP_LEN])?;
+    let y = decode_fp(&bytes[FP_LEN..])?;
+    let point = ark_bn254::G1Affine::new(x, y, false);
+    check_point(&point)?;
+    Ok(point)
+}
+
+pub fn encode_g2(point: &ark_bn254::G2Affine) -> Vec<u8> {
+    if point.is_zero() {
+        return vec![0u8; G2_LEN];
+    }
+    let mut out = Vec::with_capacity(G2_LEN);
+    encode_fp(point.x.c1, &mut out);
+    encode_fp(point.x.c0, &mut out);
+    encode_fp(point.y.c1, &mut out);
+    encode_fp(point.y.c0, &mut out);
+    out
+}
+
+/// Decodes a G2 point, checking that it is on the curve and in the prime
+/// order subgroup as the pairing precompile does.
+///
+/// # Errors
+/// VerifierError::InvalidEncoding
+pub fn decode_g2(bytes: &[u8]) -> Result<ark_bn254::G2Affine, VerifierError> {
+    check_len(bytes, G2_LEN, "G2 point")?;
+    if bytes.iter().all(|b| *b == 0) {
+        return Ok(ark_bn254::G2Affine::zero());
+    }
+    let mut fps = bytes.chunks(FP_LEN).map(decode_fp);
+    let mut next = || fps.next().unwrap();
+    let (x_c1, x_c0) = (next()?, next()?);
+    let (y_c1, y_c0) = (next()?, next()?);
+    let x = ark_bn254::Fq2::new(x_c0, x_c1);
+    let y = ark_bn254::Fq2::new(y_c0, y_c1);
+    let point = ark_bn254::G2Affine::new(x, y, false);
+    check_point(&point)?;
+    Ok(point)
+}
+
+/// Encodes pairs of points as the input of the pairing precompile.
+pub fn encode_pairing_input(pairs: &[(ark_bn254::G1Affine, ark_bn254::G2Affine)]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(pairs.len() * (G1_LEN + G2_LEN));
+    for (a, b) in pairs {
+        out.extend(encode_g1(a));
+        out.extend(encode_g2(b));
+    }
+    out
+}
+
+pub fn encode_proof(proof: &ark_groth16::Proof<ark_bn254::Bn254>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(PROOF_LEN);
+    out.extend(encode_g1(&proof.a));
+    out.extend(encode_g2(&proof.b));
+    out.extend(encode_g1(&proof.c));
+    out
+}
+
+/// Decodes a proof written by `encode_proof`.
+///
+/// # Errors
+/// VerifierError::InvalidEncoding
+pub fn decode_proof(bytes: &[u8]) -> Result<ark_groth16::Proof<ark_bn254::Bn254>, VerifierError> {
+    check_len(bytes, PROOF_LEN, "proof")?;
+    Ok(ark_groth16::Proof {
+        a: decode_g1(&bytes[..G1_LEN])?,
+        b: decode_g2(&bytes[G1_LEN..G1_LEN + G2_LEN])?,
+        c: decode_g1(&bytes[G1_LEN + G2_LEN..])?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::test_circuit;
+
+    #[test]
+    fn test_encode_g1_generator() {
+        let encoded = encode_g1(&ark_bn254::G1Affine::prime_subgroup_generator());
+        let mut expected = vec![0u8; G1_LEN];
+        expected[FP_LEN - 1] = 1;
+        expected[G1_LEN - 1] = 2;
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let g1 = ark_bn254::G1Affine::prime_subgroup_generator();
+        let g2 = ark_bn254::G2Affine::prime_subgroup_generator();
+        assert_eq!(decode_g1(&encode_g1(&g1)).unwrap(), g1);
+        assert_eq!(decode_g2(&encode_g2(&g2)).unwrap(), g2);
+        assert!(decode_g2(&encode_g2(&ark_bn254::G2Affine::zero()))
+            .unwrap()
+            .is_zero());
+
+        let (_, proof, c) = test_circuit::prove::<ark_bn254::Bn254>(3, 5);
+        assert_eq!(decode_proof(&encode_proof(&proof)).unwrap(), proof);
+        assert_eq!(decode_fr(&encode_fr(&c)).unwrap(), c);
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_encodings() {
+        let mut encoded = encode_g1(&ark_bn254::G1Affine::prime_subgroup_generator());
+        encoded[G1_LEN - 1] ^= 1;
+        assert_eq!(
+            decode_g1(&encoded).unwrap_err().to_string(),
+            "Invalid point encoding: point is not on the curve"
+        );
+        assert_eq!(
+            decode_fr(&[0xff; FP_LEN]).unwrap_err().to_string(),
+            "Invalid point encoding: scalar is not reduced"
+        );
+        assert_eq!(
+            decode_g2(&[0u8; G1_LEN]).unwrap_err().to_string(),
+            "Invalid point encoding: G2 point must be 128 bytes, got 64"
+        );
+    }
+}
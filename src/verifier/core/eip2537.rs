@@ -0,0 +1,212 @@
+// Copyright © 2022, Electron Labs
+
+//! Encoding of BLS12-381 points in the format of the EIP-2537 precompiles.
+//!
+//! A base field element takes 64 bytes: 16 zero bytes followed by the
+//! 48-byte big-endian value. G1 points are `x || y` (128 bytes), G2 points
+//! are `x.c0 || x.c1 || y.c0 || y.c1` (256 bytes), and the point at
+//! infinity is encoded as all zeros. Proofs are `a || b || c`.
+
+use super::VerifierError;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use ark_ec::AffineCurve;
+use ark_ff::{BigInteger384, PrimeField, Zero};
+use core::convert::TryInto;
+
+pub const FP_LEN: usize = 64;
+pub const G1_LEN: usize = 2 * FP_LEN;
+pub const G2_LEN: usize = 4 * FP_LEN;
+pub const PROOF_LEN: usize = 2 * G1_LEN + G2_LEN;
+
+const PADDING: usize = 16;
+
+fn encode_fp(fq: ark_bls12_381::Fq, out: &mut Vec<u8>) {
+    out.extend_from_slice(&[0u8; PADDING]);
+    for limb in fq.into_repr().0.iter().rev() {
+        out.extend_from_slice(&limb.to_be_bytes());
+    }
+}
+
+fn decode_fp(bytes: &[u8]) -> Result<ark_bls12_381::Fq, VerifierError> {
+    if bytes[..PADDING].iter().any(|b| *b != 0) {
+        return Err(VerifierError::InvalidEncoding(
+            "non-zero field element padding".to_string(),
+        ));
+    }
+    let mut limbs = [0u64; 6];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes[PADDING..].chunks(8).rev()) {
+        *limb = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    ark_bls12_381::Fq::from_repr(BigInteger384::new(limbs))
+        .ok_or_else(|| VerifierError::InvalidEncoding("field element is not reduced".to_string()))
+}
+
+fn check_len(bytes: &[u8], len: usize, what: &str) -> Result<(), VerifierError> {
+    if bytes.len() != len {
+        return Err(VerifierError::InvalidEncoding(format!(
+            "{} must be {} bytes, got {}",
+            what,
+            len,
+            bytes.len()
+        )));
+    }
+    Ok(())
+}
+
+fn check_point<P: ark_ec::SWModelParameters>(
+    point: &ark_ec::short_weierstrass_jacobian::GroupAffine<P>,
+) -> Result<(), VerifierError> {
+    if !point.is_on_curve() {
+        return Err(VerifierError::InvalidEncoding(
+            "point is not on the curve".to_string(),
+        ));
+    }
+    if !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(VerifierError::InvalidEncoding(
+            "point is not in the prime order subgroup".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn encode_g1(point: &ark_bls12_381::G1Affine) -> Vec<u8> {
+    if point.is_zero() {
+        return vec![0u8; G1_LEN];
+    }
+    let mut out = Vec::with_capacity(G1_LEN);
+    encode_fp(point.x, &mut out);
+    encode_fp(point.y, &mut out);
+    out
+}
+
+/// Decodes a G1 point, checking that it is on the curve and in the prime
+/// order subgroup as the precompiles do.
+///
+/// # Errors
+/// VerifierError::InvalidEncoding
+pub fn decode_g1(bytes: &[u8]) -> Result<ark_bls12_381::G1Affine, VerifierError> {
+    check_len(bytes, G1_LEN, "G1 point")?;
+    if bytes.iter().all(|b| *b == 0) {
+        return Ok(ark_bls12_381::G1Affine::zero());
+    }
+    let x = decode_fp(&bytes[..FP_LEN])?;
+    let y = decode_fp(&bytes[FP_LEN..])?;
+    let point = ark_bls12_381::G1Affine::new(x, y, false);
+    check_point(&point)?;
+    Ok(point)
+}
+
+pub fn encode_g2(point: &ark_bls12_381::G2Affine) -> Vec<u8> {
+    if point.is_zero() {
+        return vec![0u8; G2_LEN];
+    }
+    let mut out = Vec::with_capacity(G2_LEN);
+    encode_fp(point.x.c0, &mut out);
+    encode_fp(point.x.c1, &mut out);
+    encode_fp(point.y.c0, &mut out);
+    encode_fp(point.y.c1, &mut out);
+    out
+}
+
+/// Decodes a G2 point, checking that it is on the curve and in the prime
+/// order subgroup as the precompiles do.
+///
+/// # Errors
+/// VerifierError::InvalidEncoding
+pub fn decode_g2(bytes: &[u8]) -> Result<ark_bls12_381::G2Affine, VerifierError> {
+    check_len(bytes, G2_LEN, "G2 point")?;
+    if bytes.iter().all(|b| *b == 0) {
+        return Ok(ark_bls12_381::G2Affine::zero());
+    }
+    let mut fps = bytes.chunks(FP_LEN).map(decode_fp);
+    let mut next = || fps.next().unwrap();
+    let x = ark_bls12_381::Fq2::new(next()?, next()?);
+    let y = ark_bls12_381::Fq2::new(next()?, next()?);
+    let point = ark_bls12_381::G2Affine::new(x, y, false);
+    check_point(&point)?;
+    Ok(point)
+}
+
+pub fn encode_proof(proof: &ark_groth16::Proof<ark_bls12_381::Bls12_381>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(PROOF_LEN);
+    out.extend(encode_g1(&proof.a));
+    out.extend(encode_g2(&proof.b));
+    out.extend(encode_g1(&proof.c));
+    out
+}
+
+/// Decodes a proof written by `encode_proof`.
+///
+/// # Errors
+/// VerifierError::InvalidEncoding
+pub fn decode_proof(
+    bytes: &[u8],
+) -> Result<ark_groth16::Proof<ark_bls12_381::Bls12_381>, VerifierError> {
+    check_len(bytes, PROOF_LEN, "proof")?;
+    Ok(ark_groth16::Proof {
+        a: decode_g1(&bytes[..G1_LEN])?,
+        b: decode_g2(&bytes[G1_LEN..G1_LEN + G2_LEN])?,
+        c: decode_g1(&bytes[G1_LEN + G2_LEN..])?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::test_circuit;
+
+    #[test]
+    fn test_encode_g1_generator() {
+        let encoded = encode_g1(&ark_bls12_381::G1Affine::prime_subgroup_generator());
+        assert_eq!(encoded.len(), G1_LEN);
+        assert_eq!(&encoded[..PADDING], &[0u8; PADDING]);
+        assert_eq!(
+            hex::encode(&encoded[PADDING..FP_LEN]),
+            "17f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb"
+        );
+    }
+
+    #[test]
+    fn test_point_round_trip() {
+        let g1 = ark_bls12_381::G1Affine::prime_subgroup_generator();
+        let g2 = ark_bls12_381::G2Affine::prime_subgroup_generator();
+        assert_eq!(decode_g1(&encode_g1(&g1)).unwrap(), g1);
+        assert_eq!(decode_g2(&encode_g2(&g2)).unwrap(), g2);
+
+        let zero = ark_bls12_381::G1Affine::zero();
+        assert_eq!(encode_g1(&zero), vec![0u8; G1_LEN]);
+        assert!(decode_g1(&encode_g1(&zero)).unwrap().is_zero());
+    }
+
+    #[test]
+    fn test_proof_round_trip() {
+        let (_, proof, _) = test_circuit::prove::<ark_bls12_381::Bls12_381>(3, 5);
+        let encoded = encode_proof(&proof);
+        assert_eq!(encoded.len(), PROOF_LEN);
+        assert_eq!(decode_proof(&encoded).unwrap(), proof);
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_points() {
+        let mut encoded = encode_g1(&ark_bls12_381::G1Affine::prime_subgroup_generator());
+        encoded[0] = 1;
+        assert_eq!(
+            decode_g1(&encoded).unwrap_err().to_string(),
+            "Invalid point encoding: non-zero field element padding"
+        );
+
+        let mut encoded = encode_g1(&ark_bls12_381::G1Affine::prime_subgroup_generator());
+        encoded[G1_LEN - 1] ^= 1;
+        assert_eq!(
+            decode_g1(&encoded).unwrap_err().to_string(),
+            "Invalid point encoding: point is not on the curve"
+        );
+
+        assert_eq!(
+            decode_g1(&[0u8; 64]).unwrap_err().to_string(),
+            "Invalid point encoding: G1 point must be 128 bytes, got 64"
+        );
+    }
+}
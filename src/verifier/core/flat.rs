@@ -0,0 +1,291 @@
+// Copyright © 2022, Electron Labs
+
+//! A flat binary encoding of BN254 prepared keys for relayers that load
+//! many keys, for instance from memory-mapped files shared between
+//! processes.
+//!
+//! Every value is a little-endian `u64` word, so every offset is a
+//! multiple of 8 and is fixed by the header. Field elements are stored in
+//! Montgomery form, as in memory, so loading a key copies limbs without any
+//! parsing or field arithmetic. The layout is:
+//!
+//! | words | content |
+//! |---|---|
+//! | 1 | magic `EPVKFLAT` |
+//! | 1 | format version (low 32 bits), IC length (high 32 bits) |
+//! | 1 | line coefficients of gamma (low), of delta (high) |
+//! | 1 | flags: bit 0 gamma prepared at infinity, bit 1 delta |
+//! | 9 | alpha (x, y, infinity) |
+//! | 17 x 3 | beta, gamma, delta (x, y, infinity) |
+//! | 48 | e(alpha, beta) |
+//! | 9 x IC length | IC |
+//! | 24 x n | gamma then delta line coefficients |
+
+use super::{PreparedVerifyingKey, VerifierError};
+use alloc::format;
+use alloc::vec::Vec;
+use ark_bn254::{Fq, Fq12, Fq2, Fq6, G1Affine, G2Affine};
+use ark_ff::BigInteger256;
+
+/// First word of every flat key.
+pub const MAGIC: [u8; 8] = *b"EPVKFLAT";
+
+/// Version of the layout written by `to_flat`.
+pub const FLAT_VERSION: u32 = 1;
+
+const HEADER_WORDS: usize = 4;
+const G1_WORDS: usize = 9;
+const G2_WORDS: usize = 17;
+const FQ12_WORDS: usize = 48;
+const COEFF_WORDS: usize = 24;
+
+type Coeff = (Fq2, Fq2, Fq2);
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn word(&mut self, word: u64) {
+        self.0.extend_from_slice(&word.to_le_bytes());
+    }
+
+    fn fq(&mut self, fq: &Fq) {
+        (fq.0).0.iter().for_each(|limb| self.word(*limb));
+    }
+
+    fn fq2(&mut self, fq2: &Fq2) {
+        self.fq(&fq2.c0);
+        self.fq(&fq2.c1);
+    }
+
+    fn g1(&mut self, point: &G1Affine) {
+        self.fq(&point.x);
+        self.fq(&point.y);
+        self.word(point.infinity as u64);
+    }
+
+    fn g2(&mut self, point: &G2Affine) {
+        self.fq2(&point.x);
+        self.fq2(&point.y);
+        self.word(point.infinity as u64);
+    }
+
+    fn fq12(&mut self, fq12: &Fq12) {
+        for fq6 in [&fq12.c0, &fq12.c1] {
+            for fq2 in [&fq6.c0, &fq6.c1, &fq6.c2] {
+                self.fq2(fq2);
+            }
+        }
+    }
+
+    fn coeffs(&mut self, coeffs: &[Coeff]) {
+        for (a, b, c) in coeffs {
+            self.fq2(a);
+            self.fq2(b);
+            self.fq2(c);
+        }
+    }
+}
+
+/// Encodes a prepared key in the flat layout.
+pub fn to_flat(pvk: &PreparedVerifyingKey) -> Vec<u8> {
+    let pvk: ark_groth16::PreparedVerifyingKey<ark_bn254::Bn254> = pvk.clone().into();
+    let gamma = &pvk.gamma_g2_neg_pc;
+    let delta = &pvk.delta_g2_neg_pc;
+    let mut out = Writer(Vec::new());
+    out.0.extend_from_slice(&MAGIC);
+    out.word(FLAT_VERSION as u64 | (pvk.vk.gamma_abc_g1.len() as u64) << 32);
+    out.word(gamma.ell_coeffs.len() as u64 | (delta.ell_coeffs.len() as u64) << 32);
+    out.word(gamma.infinity as u64 | (delta.infinity as u64) << 1);
+    out.g1(&pvk.vk.alpha_g1);
+    out.g2(&pvk.vk.beta_g2);
+    out.g2(&pvk.vk.gamma_g2);
+    out.g2(&pvk.vk.delta_g2);
+    out.fq12(&pvk.alpha_g1_beta_g2);
+    pvk.vk.gamma_abc_g1.iter().for_each(|point| out.g1(point));
+    out.coeffs(&gamma.ell_coeffs);
+    out.coeffs(&delta.ell_coeffs);
+    out.0
+}
+
+/// A flat key borrowed from a byte buffer, such as a memory-mapped file.
+/// Only the header is read when it is created.
+#[derive(Debug, Clone, Copy)]
+pub struct FlatPreparedKey<'a> {
+    bytes: &'a [u8],
+    ic_len: usize,
+    gamma_coeffs: usize,
+    delta_coeffs: usize,
+    flags: u64,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn word(&mut self) -> u64 {
+        let word = u64::from_le_bytes(self.bytes[self.offset..self.offset + 8].try_into().unwrap());
+        self.offset += 8;
+        word
+    }
+
+    fn fq(&mut self) -> Fq {
+        let limbs = [self.word(), self.word(), self.word(), self.word()];
+        Fq::new(BigInteger256::new(limbs))
+    }
+
+    fn fq2(&mut self) -> Fq2 {
+        let c0 = self.fq();
+        Fq2::new(c0, self.fq())
+    }
+
+    fn g1(&mut self) -> G1Affine {
+        let x = self.fq();
+        let y = self.fq();
+        G1Affine::new(x, y, self.word() != 0)
+    }
+
+    fn g2(&mut self) -> G2Affine {
+        let x = self.fq2();
+        let y = self.fq2();
+        G2Affine::new(x, y, self.word() != 0)
+    }
+
+    fn fq6(&mut self) -> Fq6 {
+        let c0 = self.fq2();
+        let c1 = self.fq2();
+        Fq6::new(c0, c1, self.fq2())
+    }
+
+    fn fq12(&mut self) -> Fq12 {
+        let c0 = self.fq6();
+        Fq12::new(c0, self.fq6())
+    }
+
+    fn coeffs(&mut self, n: usize) -> Vec<Coeff> {
+        (0..n)
+            .map(|_| {
+                let a = self.fq2();
+                let b = self.fq2();
+                (a, b, self.fq2())
+            })
+            .collect()
+    }
+}
+
+impl<'a> FlatPreparedKey<'a> {
+    /// Checks the header and the length of a flat key.
+    ///
+    /// # Errors
+    /// VerifierError::InvalidEncoding
+    ///
+    /// This function will return an error if the buffer doesn't start with
+    /// a flat key header of a supported version, or its length doesn't
+    /// match the header.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, VerifierError> {
+        let invalid = |what: &str| VerifierError::InvalidEncoding(format!("flat key {}", what));
+        if bytes.len() < HEADER_WORDS * 8 || bytes[..8] != MAGIC {
+            return Err(invalid("header"));
+        }
+        let mut header = Reader { bytes, offset: 8 };
+        let sizes = header.word();
+        if sizes as u32 != FLAT_VERSION {
+            return Err(invalid("version"));
+        }
+        let coeffs = header.word();
+        let key = FlatPreparedKey {
+            bytes,
+            ic_len: (sizes >> 32) as usize,
+            gamma_coeffs: coeffs as u32 as usize,
+            delta_coeffs: (coeffs >> 32) as usize,
+            flags: header.word(),
+        };
+        let words = HEADER_WORDS
+            + G1_WORDS * (1 + key.ic_len)
+            + G2_WORDS * 3
+            + FQ12_WORDS
+            + COEFF_WORDS * (key.gamma_coeffs + key.delta_coeffs);
+        if bytes.len() != words * 8 {
+            return Err(invalid("length"));
+        }
+        Ok(key)
+    }
+
+    /// Number of IC points, one more than the number of public inputs.
+    pub fn ic_len(&self) -> usize {
+        self.ic_len
+    }
+
+    /// Copies the key out of the buffer.
+    pub fn to_prepared(&self) -> PreparedVerifyingKey {
+        let mut r = Reader {
+            bytes: self.bytes,
+            offset: HEADER_WORDS * 8,
+        };
+        let alpha_g1 = r.g1();
+        let beta_g2 = r.g2();
+        let gamma_g2 = r.g2();
+        let delta_g2 = r.g2();
+        let alpha_g1_beta_g2 = r.fq12();
+        let gamma_abc_g1 = (0..self.ic_len).map(|_| r.g1()).collect();
+        let gamma_g2_neg_pc = ark_ec::bn::G2Prepared {
+            ell_coeffs: r.coeffs(self.gamma_coeffs),
+            infinity: self.flags & 1 != 0,
+        };
+        let delta_g2_neg_pc = ark_ec::bn::G2Prepared {
+            ell_coeffs: r.coeffs(self.delta_coeffs),
+            infinity: self.flags & 2 != 0,
+        };
+        ark_groth16::PreparedVerifyingKey {
+            vk: ark_groth16::VerifyingKey {
+                alpha_g1,
+                beta_g2,
+                gamma_g2,
+                delta_g2,
+                gamma_abc_g1,
+            },
+            alpha_g1_beta_g2,
+            gamma_g2_neg_pc,
+            delta_g2_neg_pc,
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_circuit;
+    use super::super::verify_prepared;
+    use super::*;
+
+    #[test]
+    fn test_flat_round_trip() {
+        let (vk, proof, c) = test_circuit::prove::<ark_bn254::Bn254>(3, 5);
+        let pvk: PreparedVerifyingKey = ark_groth16::prepare_verifying_key(&vk).into();
+        let bytes = to_flat(&pvk);
+        assert_eq!(bytes.len() % 8, 0);
+
+        let flat = FlatPreparedKey::new(&bytes).unwrap();
+        assert_eq!(flat.ic_len(), 2);
+        let loaded = flat.to_prepared();
+        assert_eq!(loaded, pvk);
+        assert!(verify_prepared(&loaded, &proof, &[c]).unwrap());
+    }
+
+    #[test]
+    fn test_flat_rejects_bad_buffers() {
+        let (vk, _, _) = test_circuit::prove::<ark_bn254::Bn254>(3, 5);
+        let pvk: PreparedVerifyingKey = ark_groth16::prepare_verifying_key(&vk).into();
+        let bytes = to_flat(&pvk);
+
+        assert!(FlatPreparedKey::new(&bytes[..bytes.len() - 8]).is_err());
+        assert!(FlatPreparedKey::new(&bytes[..16]).is_err());
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] ^= 1;
+        assert!(FlatPreparedKey::new(&wrong_magic).is_err());
+        let mut wrong_version = bytes;
+        wrong_version[8] = 2;
+        assert!(FlatPreparedKey::new(&wrong_version).is_err());
+    }
+}
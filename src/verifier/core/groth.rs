@@ -0,0 +1,156 @@
+// Copyright © 2022, Electron Labs
+
+use super::{check_input_count, VerifierError};
+use alloc::format;
+use alloc::vec::Vec;
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_ff::PrimeField;
+use borsh::maybestd::io::{Result as IoResult, Write};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// A pairing engine with borsh-serializable wrapper types in this crate.
+///
+/// Implemented for `ark_bn254::Bn254`, `ark_bls12_381::Bls12_381` and
+/// `ark_bls12_377::Bls12_377`.
+pub trait WrappedEngine: PairingEngine {
+    type PreparedVerifyingKey: BorshSerialize
+        + BorshDeserialize
+        + Clone
+        + From<ark_groth16::PreparedVerifyingKey<Self>>
+        + Into<ark_groth16::PreparedVerifyingKey<Self>>;
+
+    /// Returns `gamma_abc_g1[0] + sum pub_inputs[i] * gamma_abc_g1[i + 1]`,
+    /// the point the inputs contribute to the pairing check. The number of
+    /// inputs must already be checked against the key.
+    fn prepare_inputs(
+        gamma_abc_g1: &[Self::G1Affine],
+        pub_inputs: &[Self::Fr],
+    ) -> Self::G1Projective {
+        let mut prepared_inputs = gamma_abc_g1[0].into_projective();
+        for (input, base) in pub_inputs.iter().zip(&gamma_abc_g1[1..]) {
+            prepared_inputs += &base.mul(input.into_repr());
+        }
+        prepared_inputs
+    }
+}
+
+/// A Groth16 verifier for a single key, generic over the curve.
+///
+/// Borsh-encodes as the prepared key of the curve, so a contract can keep
+/// it in state and pick the curve with a type parameter.
+pub struct GrothVerifier<E: WrappedEngine> {
+    pvk: E::PreparedVerifyingKey,
+}
+
+impl<E: WrappedEngine> GrothVerifier<E> {
+    pub fn new(vk: &ark_groth16::VerifyingKey<E>) -> Self {
+        GrothVerifier {
+            pvk: ark_groth16::prepare_verifying_key(vk).into(),
+        }
+    }
+
+    pub fn from_prepared(pvk: E::PreparedVerifyingKey) -> Self {
+        GrothVerifier { pvk }
+    }
+
+    pub fn prepared_key(&self) -> &E::PreparedVerifyingKey {
+        &self.pvk
+    }
+
+    /// Verifies a proof against the key, with the inputs already parsed
+    /// into field elements.
+    ///
+    /// # Errors
+    /// VerifierError::SynthesisError
+    ///
+    /// This function will return an error if the inputs can't be checked
+    /// against the key, e.g. when their number doesn't match.
+    pub fn verify(
+        &self,
+        proof: &ark_groth16::Proof<E>,
+        pub_inputs: &[E::Fr],
+    ) -> Result<bool, VerifierError> {
+        let pvk: ark_groth16::PreparedVerifyingKey<E> = self.pvk.clone().into();
+        check_input_count(pvk.vk.gamma_abc_g1.len(), pub_inputs.len())?;
+        let prepared_inputs = E::prepare_inputs(&pvk.vk.gamma_abc_g1, pub_inputs);
+        let res = ark_groth16::verify_proof_with_prepared_inputs(&pvk, proof, &prepared_inputs)
+            .map_err(|e| VerifierError::SynthesisError(format!("{:?}", e)))?;
+        Ok(res)
+    }
+
+    /// Verifies several proofs at once, see `batch::verify_batch`.
+    ///
+    /// # Errors
+    /// VerifierError::SynthesisError
+    pub fn verify_batch(
+        &self,
+        proofs: &[(ark_groth16::Proof<E>, Vec<E::Fr>)],
+        seed: &[u8; 32],
+    ) -> Result<bool, VerifierError> {
+        super::batch::verify_batch::<E>(&self.pvk, proofs, seed)
+    }
+
+    /// Verifies several proofs and reports the outcome of each, see
+    /// `batch::verify_batch_items`.
+    pub fn verify_batch_items(
+        &self,
+        proofs: &[(ark_groth16::Proof<E>, Vec<E::Fr>)],
+        seed: &[u8; 32],
+    ) -> super::batch::BatchResult {
+        super::batch::verify_batch_items::<E>(&self.pvk, proofs, seed)
+    }
+}
+
+impl<E: WrappedEngine> Clone for GrothVerifier<E> {
+    fn clone(&self) -> Self {
+        GrothVerifier {
+            pvk: self.pvk.clone(),
+        }
+    }
+}
+
+impl<E: WrappedEngine> BorshSerialize for GrothVerifier<E> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        self.pvk.serialize(writer)
+    }
+}
+
+impl<E: WrappedEngine> BorshDeserialize for GrothVerifier<E> {
+    fn deserialize(buf: &mut &[u8]) -> IoResult<Self> {
+        Ok(GrothVerifier {
+            pvk: E::PreparedVerifyingKey::deserialize(buf)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::test_circuit;
+
+    fn check_curve<E: WrappedEngine>() {
+        let (vk, proof, c) = test_circuit::prove::<E>(3, 5);
+        let verifier = GrothVerifier::<E>::new(&vk);
+        let bytes = verifier.try_to_vec().unwrap();
+        let verifier = GrothVerifier::<E>::try_from_slice(&bytes).unwrap();
+
+        assert!(verifier.verify(&proof, &[c]).unwrap());
+        assert!(!verifier.verify(&proof, &[E::Fr::from(16u64)]).unwrap());
+        assert!(verifier.verify(&proof, &[]).is_err());
+    }
+
+    #[test]
+    fn test_bn254() {
+        check_curve::<ark_bn254::Bn254>();
+    }
+
+    #[test]
+    fn test_bls12_381() {
+        check_curve::<ark_bls12_381::Bls12_381>();
+    }
+
+    #[test]
+    fn test_bls12_377() {
+        check_curve::<ark_bls12_377::Bls12_377>();
+    }
+}
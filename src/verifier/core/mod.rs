@@ -0,0 +1,310 @@
+// Copyright © 2022, Electron Labs
+
+//! Curve wrapper types shared by every target.
+//!
+//! These are borsh-serializable mirrors of the arkworks types, with
+//! conversions in both directions. Target adapters such as `verifier::near`
+//! parse their own input formats into arkworks types and store keys as
+//! these wrappers. The BN254 wrappers live at the root of this module, the
+//! other curves in `bls12_381` and `bls12_377`; `GrothVerifier` works over
+//! any of them.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use borsh::{BorshDeserialize, BorshSerialize};
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+
+#[macro_use]
+mod wrappers;
+
+pub mod backend;
+pub mod batch;
+pub mod blinding;
+pub mod bls12_377;
+pub mod bls12_381;
+#[cfg(feature = "blst")]
+pub mod blst_backend;
+pub mod compressed;
+pub mod eip197;
+pub mod eip2537;
+pub mod flat;
+mod groth;
+pub mod randomness;
+#[cfg(feature = "simd")]
+pub mod simd;
+pub mod zk;
+
+pub use groth::{GrothVerifier, WrappedEngine};
+pub use randomness::{FixedSeed, RandomnessSource};
+pub use zk::ZkVerifier;
+
+#[cfg(test)]
+pub(crate) mod test_circuit;
+
+/// Errors of parsing and verification, shared by the verifiers of every
+/// target. Adapters report their own failures, such as json limits or
+/// incomplete uploads, with their own error types. `Display` is implemented
+/// by hand rather than derived, so that the core builds without std.
+#[derive(Debug)]
+pub enum VerifierError {
+    ParseError(String),
+    SynthesisError(String),
+    InvalidProof(String),
+    IncompleteMillerLoop,
+    IncompleteInputPreparation,
+    UnsupportedProtocol(String),
+    UnsupportedCurve(String),
+    BaseKeyMismatch,
+    InputMapping(usize, String),
+    InvalidEncoding(String),
+    BatchLengthMismatch(usize, usize),
+    InvalidPoint(String),
+    InvalidSubgroupPoint(String),
+    InputCountMismatch {
+        expected: usize,
+        got: usize,
+    },
+    InvalidFieldElement(String),
+    MissingField(String),
+    VerificationFailed,
+    InvalidInput {
+        index: usize,
+        name: Option<&'static str>,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for VerifierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifierError::ParseError(what) => write!(f, "Failed to parse circom {} json", what),
+            VerifierError::SynthesisError(e) => write!(f, "Synthesis error: {}", e),
+            VerifierError::InvalidProof(what) => write!(f, "Invalid proof: {}", what),
+            VerifierError::IncompleteMillerLoop => write!(f, "Miller loop is not complete"),
+            VerifierError::IncompleteInputPreparation => {
+                write!(f, "Public inputs are not fully prepared")
+            }
+            VerifierError::UnsupportedProtocol(protocol) => {
+                write!(f, "Unsupported protocol {}", protocol)
+            }
+            VerifierError::UnsupportedCurve(curve) => write!(f, "Unsupported curve {}", curve),
+            VerifierError::BaseKeyMismatch => {
+                write!(f, "Verifying key does not belong to the base key family")
+            }
+            VerifierError::InputMapping(index, e) => {
+                write!(f, "Failed to map public input {}: {}", index, e)
+            }
+            VerifierError::InvalidEncoding(what) => write!(f, "Invalid point encoding: {}", what),
+            VerifierError::BatchLengthMismatch(proofs, inputs) => write!(
+                f,
+                "Batch has {} proofs and {} sets of public inputs",
+                proofs, inputs
+            ),
+            VerifierError::InvalidPoint(name) => write!(f, "Point {} is not on the curve", name),
+            VerifierError::InvalidSubgroupPoint(name) => {
+                write!(f, "Point {} is not in the prime order subgroup", name)
+            }
+            VerifierError::InputCountMismatch { expected, got } => write!(
+                f,
+                "Verifying key takes {} public inputs, got {}",
+                expected, got
+            ),
+            VerifierError::InvalidFieldElement(at) => write!(f, "Invalid field element {}", at),
+            VerifierError::MissingField(field) => {
+                write!(f, "Verification key is missing field {}", field)
+            }
+            VerifierError::VerificationFailed => write!(f, "Proof does not verify"),
+            VerifierError::InvalidInput {
+                index,
+                name: Some(name),
+                reason,
+            } => write!(f, "Input '{}' (index {}) {}", name, index, reason),
+            VerifierError::InvalidInput {
+                index,
+                name: None,
+                reason,
+            } => write!(f, "Input (index {}) {}", index, reason),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerifierError {}
+
+/// Checks the number of public inputs against the IC length of a key, which
+/// ark_groth16 would otherwise reject with an opaque synthesis error.
+///
+/// # Errors
+/// VerifierError::InputCountMismatch
+pub(crate) fn check_input_count(ic_len: usize, got: usize) -> Result<(), VerifierError> {
+    let expected = ic_len.saturating_sub(1);
+    if got != expected {
+        return Err(VerifierError::InputCountMismatch { expected, got });
+    }
+    Ok(())
+}
+
+/// Checks that a deserialized point is on the curve and in the prime order
+/// subgroup. Pairing a point that is neither breaks the soundness of the
+/// verifier, so every point read from untrusted input goes through this.
+///
+/// # Errors
+/// VerifierError::InvalidPoint, VerifierError::InvalidSubgroupPoint
+pub fn check_point<P: ark_ec::SWModelParameters>(
+    point: &ark_ec::short_weierstrass_jacobian::GroupAffine<P>,
+    name: &str,
+) -> Result<(), VerifierError> {
+    if !point.is_on_curve() {
+        return Err(VerifierError::InvalidPoint(name.to_string()));
+    }
+    if !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(VerifierError::InvalidSubgroupPoint(name.to_string()));
+    }
+    Ok(())
+}
+
+/// A BN254 scalar, for submitting public inputs in borsh instead of json.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct Fr {
+    pub(crate) c0: BigInteger256,
+}
+
+impl Fr {
+    pub(crate) fn new(src: BigInteger256) -> Self {
+        Fr { c0: src }
+    }
+}
+
+/// Rejects limbs that are not below the scalar modulus.
+///
+/// # Errors
+/// VerifierError::InvalidFieldElement
+impl TryFrom<Fr> for ark_bn254::Fr {
+    type Error = VerifierError;
+
+    fn try_from(src: Fr) -> Result<ark_bn254::Fr, VerifierError> {
+        let modulus = <ark_bn254::Fr as ark_ff::PrimeField>::Params::MODULUS;
+        Ok(ark_bn254::Fr::new(src.c0.below(modulus, "public input")?))
+    }
+}
+
+impl From<ark_bn254::Fr> for Fr {
+    fn from(src: ark_bn254::Fr) -> Fr {
+        Fr::new(src.0.into())
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+curve_wrappers!(ark_bn254, Bn254, BigInteger256, 4, bn);
+#[cfg(feature = "simd")]
+curve_wrappers!(ark_bn254, Bn254, BigInteger256, 4, bn, simd::prepare_inputs);
+
+/// Reads `bytes` written by arkworks' `CanonicalSerialize`, trying the
+/// compressed encoding first. Every byte must be read.
+///
+/// # Errors
+/// VerifierError::InvalidEncoding
+pub(crate) fn from_ark_bytes<T: ark_serialize::CanonicalDeserialize>(
+    bytes: &[u8],
+    what: &str,
+) -> Result<T, VerifierError> {
+    let mut reader = bytes;
+    if let Ok(value) = T::deserialize(&mut reader) {
+        if reader.is_empty() {
+            return Ok(value);
+        }
+    }
+    let mut reader = bytes;
+    match T::deserialize_uncompressed(&mut reader) {
+        Ok(value) if reader.is_empty() => Ok(value),
+        _ => Err(VerifierError::InvalidEncoding(alloc::format!(
+            "{} is not in the arkworks encoding",
+            what
+        ))),
+    }
+}
+
+/// Verifies a proof submitted in borsh, which skips the json parsing of
+/// `verifier::near::verify_proof`.
+///
+/// # Errors
+/// VerifierError::InvalidFieldElement, VerifierError::InvalidPoint,
+/// VerifierError::InvalidSubgroupPoint, VerifierError::InputCountMismatch,
+/// VerifierError::SynthesisError
+///
+/// This function will return an error if a coordinate or an input is not
+/// below its modulus, if a proof point is not on the curve or not in the
+/// prime order subgroup, if the number of inputs doesn't match the key, or
+/// if the inputs can't be checked against it.
+pub fn verify_proof_borsh(
+    pvk: PreparedVerifyingKey,
+    proof: Proof,
+    pub_inputs: Vec<Fr>,
+) -> Result<bool, VerifierError> {
+    let ark_pub_inputs = pub_inputs
+        .into_iter()
+        .map(ark_bn254::Fr::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    let proof = proof.try_into()?;
+    check_proof(&proof)?;
+    verify_prepared(&pvk, &proof, &ark_pub_inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_serialize::CanonicalSerialize;
+
+    #[test]
+    fn test_ark_bytes() {
+        let (vk, proof, c) = test_circuit::prove::<ark_bn254::Bn254>(3, 5);
+        let mut compressed = Vec::new();
+        proof.serialize(&mut compressed).unwrap();
+        let mut uncompressed = Vec::new();
+        proof.serialize_uncompressed(&mut uncompressed).unwrap();
+        let mut vk_bytes = Vec::new();
+        vk.serialize(&mut vk_bytes).unwrap();
+
+        let pvk = VerifyingKey::from_ark_bytes(&vk_bytes).unwrap().prepare();
+        for bytes in [&compressed, &uncompressed] {
+            let proof = Proof::from_ark_bytes(bytes).unwrap();
+            assert!(verify_proof_borsh(pvk.clone(), proof, vec![c.into()]).unwrap());
+        }
+
+        let mut vk_uncompressed = Vec::new();
+        vk.serialize_uncompressed(&mut vk_uncompressed).unwrap();
+        assert_eq!(
+            VerifyingKey::from_ark_bytes(&vk_uncompressed).unwrap(),
+            VerifyingKey::from(vk)
+        );
+
+        assert!(matches!(
+            Proof::from_ark_bytes(&compressed[1..]),
+            Err(VerifierError::InvalidEncoding(_))
+        ));
+        compressed.push(0);
+        assert!(Proof::from_ark_bytes(&compressed).is_err());
+    }
+
+    #[test]
+    fn test_borsh_limbs_above_modulus() {
+        let (vk, proof, c) = test_circuit::prove::<ark_bn254::Bn254>(3, 5);
+        let pvk: PreparedVerifyingKey = ark_groth16::prepare_verifying_key(&vk).into();
+        let q = <ark_bn254::Fq as ark_ff::PrimeField>::Params::MODULUS;
+        let r = <ark_bn254::Fr as ark_ff::PrimeField>::Params::MODULUS;
+
+        let mut unreduced = Proof::from(proof.clone());
+        unreduced.a.x = BigInteger256::new(q.0);
+        assert!(matches!(
+            verify_proof_borsh(pvk.clone(), unreduced, vec![c.into()]),
+            Err(VerifierError::InvalidFieldElement(at)) if at == "pi_a"
+        ));
+
+        let input = Fr::new(BigInteger256::new(r.0));
+        assert!(matches!(
+            verify_proof_borsh(pvk, proof.into(), vec![input]),
+            Err(VerifierError::InvalidFieldElement(_))
+        ));
+    }
+}
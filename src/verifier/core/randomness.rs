@@ -0,0 +1,66 @@
+// Copyright © 2022, Electron Labs
+
+//! Sources of the seeds that batch verification and challenges are derived
+//! from.
+//!
+//! Callers hash the seed together with the data it must not be chosen
+//! after, such as the proofs of a batch, so a source only has to be
+//! unpredictable to the prover. `verifier::near::batch::EnvSeed` reads the
+//! block random seed, [`OsRandomness`] the operating system's generator on
+//! native builds, and [`FixedSeed`] returns a fixed seed for tests.
+
+/// A source of 32-byte seeds.
+pub trait RandomnessSource {
+    fn random_seed(&self) -> [u8; 32];
+}
+
+/// Returns the same seed every time, for tests and reproducible runs. Not
+/// for production: a prover who knows the seed can craft proofs that
+/// cancel out in a batch.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FixedSeed(pub [u8; 32]);
+
+impl RandomnessSource for FixedSeed {
+    fn random_seed(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+/// Seeds from the operating system's generator, enabled by the `native`
+/// feature. Panics if the generator is unavailable.
+#[cfg(feature = "getrandom")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsRandomness;
+
+#[cfg(feature = "getrandom")]
+impl RandomnessSource for OsRandomness {
+    fn random_seed(&self) -> [u8; 32] {
+        let mut seed = [0u8; 32];
+        getrandom::getrandom(&mut seed).expect("OS randomness is unavailable");
+        seed
+    }
+}
+
+impl<R: RandomnessSource + ?Sized> RandomnessSource for &R {
+    fn random_seed(&self) -> [u8; 32] {
+        (**self).random_seed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_seed() {
+        let source = FixedSeed([7; 32]);
+        assert_eq!(source.random_seed(), [7; 32]);
+        assert_eq!((&source).random_seed(), source.random_seed());
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn test_os_randomness() {
+        assert_ne!(OsRandomness.random_seed(), OsRandomness.random_seed());
+    }
+}
@@ -0,0 +1,376 @@
+// Copyright © 2022, Electron Labs
+
+//! Two-lane field multiplication and the public input MSM of BN254.
+//!
+//! [`mul2`] computes two independent Montgomery products of 256-bit field
+//! elements at once, with 32-bit limbs in the two 64-bit lanes of a wasm
+//! `v128`. wasm has no 64x64 to 128-bit multiply, which arkworks' 64-bit
+//! limbs lean on, while `u64x2_extmul_low_u32x4` gives two 32x32 to 64-bit
+//! products per instruction. Both Fq and Fr of BN254 are 256-bit, so the
+//! same code multiplies either.
+//!
+//! [`prepare_inputs`] accumulates `gamma_abc_g1[0] + sum input_i *
+//! gamma_abc_g1[i + 1]` with one shared doubling per scalar bit instead of
+//! one full scalar multiplication per input, pairing the independent
+//! multiplications of each point doubling and addition in `mul2`. With the
+//! `simd` feature `verify_prepared` of BN254 uses it.
+//!
+//! The vector instructions are only used in wasm builds with the `simd128`
+//! target feature:
+//!
+//! ```sh
+//! RUSTFLAGS="-C target-feature=+simd128" cargo build --target wasm32-unknown-unknown --release --features simd
+//! ```
+//!
+//! Every other build runs the same algorithm on a pair of `u64`, which is
+//! what the native tests check against arkworks. A runtime that rejects
+//! SIMD opcodes, as NEAR does at the time of writing, also rejects a
+//! contract built with `simd128`.
+
+use alloc::vec::Vec;
+use ark_bn254::{Fq, Fr, G1Affine, G1Projective};
+use ark_ff::{
+    BigInteger, BigInteger256, Field, Fp256, Fp256Parameters, FpParameters, One, PrimeField, Zero,
+};
+
+/// 32-bit limbs of a 256-bit element.
+const LIMBS: usize = 8;
+const MASK: u64 = 0xffff_ffff;
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod lanes {
+    use super::MASK;
+    use core::arch::wasm32::*;
+
+    /// Two `u64` in a `v128`.
+    #[derive(Clone, Copy)]
+    pub struct Lanes(v128);
+
+    impl Lanes {
+        #[inline(always)]
+        pub fn new(a: u64, b: u64) -> Self {
+            Lanes(u64x2(a, b))
+        }
+
+        #[inline(always)]
+        pub fn splat(a: u64) -> Self {
+            Lanes(u64x2_splat(a))
+        }
+
+        #[inline(always)]
+        pub fn add(self, other: Self) -> Self {
+            Lanes(i64x2_add(self.0, other.0))
+        }
+
+        /// The products of the low 32 bits of each lane.
+        #[inline(always)]
+        pub fn mul_lo(self, other: Self) -> Self {
+            let a = i32x4_shuffle::<0, 2, 0, 2>(self.0, self.0);
+            let b = i32x4_shuffle::<0, 2, 0, 2>(other.0, other.0);
+            Lanes(u64x2_extmul_low_u32x4(a, b))
+        }
+
+        #[inline(always)]
+        pub fn lo(self) -> Self {
+            Lanes(v128_and(self.0, u64x2_splat(MASK)))
+        }
+
+        #[inline(always)]
+        pub fn hi(self) -> Self {
+            Lanes(u64x2_shr(self.0, 32))
+        }
+
+        #[inline(always)]
+        pub fn to_array(self) -> [u64; 2] {
+            [
+                u64x2_extract_lane::<0>(self.0),
+                u64x2_extract_lane::<1>(self.0),
+            ]
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+mod lanes {
+    use super::MASK;
+
+    /// Two `u64`, for targets without `simd128`.
+    #[derive(Clone, Copy)]
+    pub struct Lanes([u64; 2]);
+
+    impl Lanes {
+        #[inline(always)]
+        pub fn new(a: u64, b: u64) -> Self {
+            Lanes([a, b])
+        }
+
+        #[inline(always)]
+        pub fn splat(a: u64) -> Self {
+            Lanes([a, a])
+        }
+
+        #[inline(always)]
+        pub fn add(self, other: Self) -> Self {
+            Lanes([
+                self.0[0].wrapping_add(other.0[0]),
+                self.0[1].wrapping_add(other.0[1]),
+            ])
+        }
+
+        /// The products of the low 32 bits of each lane.
+        #[inline(always)]
+        pub fn mul_lo(self, other: Self) -> Self {
+            Lanes([
+                (self.0[0] & MASK) * (other.0[0] & MASK),
+                (self.0[1] & MASK) * (other.0[1] & MASK),
+            ])
+        }
+
+        #[inline(always)]
+        pub fn lo(self) -> Self {
+            Lanes([self.0[0] & MASK, self.0[1] & MASK])
+        }
+
+        #[inline(always)]
+        pub fn hi(self) -> Self {
+            Lanes([self.0[0] >> 32, self.0[1] >> 32])
+        }
+
+        #[inline(always)]
+        pub fn to_array(self) -> [u64; 2] {
+            self.0
+        }
+    }
+}
+
+use lanes::Lanes;
+
+/// The 32-bit limbs of two 256-bit integers, least significant first, one
+/// integer per lane.
+fn split(a: &BigInteger256, b: &BigInteger256) -> [Lanes; LIMBS] {
+    let mut limbs = [Lanes::splat(0); LIMBS];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        let shift = 32 * (i % 2);
+        *limb = Lanes::new((a.0[i / 2] >> shift) & MASK, (b.0[i / 2] >> shift) & MASK);
+    }
+    limbs
+}
+
+/// Multiplies `a[0]` by `b[0]` and `a[1]` by `b[1]`, with CIOS Montgomery
+/// multiplication on 32-bit limbs. R is 2^256 for both limb sizes, so the
+/// products are the Montgomery forms arkworks computes.
+pub fn mul2<P: Fp256Parameters>(a: [Fp256<P>; 2], b: [Fp256<P>; 2]) -> [Fp256<P>; 2] {
+    let x = split(&a[0].0, &a[1].0);
+    let y = split(&b[0].0, &b[1].0);
+    let modulus = split(&P::MODULUS, &P::MODULUS);
+    // -p^-1 mod 2^32 is the low half of -p^-1 mod 2^64.
+    let inv = Lanes::splat(P::INV & MASK);
+
+    // Every limb of `t` stays below 2^32 between steps, so each
+    // `t + x * y + carry` fits in 64 bits.
+    let mut t = [Lanes::splat(0); LIMBS + 2];
+    for y_i in y.iter() {
+        let mut carry = Lanes::splat(0);
+        for (t_j, x_j) in t.iter_mut().zip(x.iter()) {
+            let s = t_j.add(x_j.mul_lo(*y_i)).add(carry);
+            *t_j = s.lo();
+            carry = s.hi();
+        }
+        let s = t[LIMBS].add(carry);
+        t[LIMBS] = s.lo();
+        t[LIMBS + 1] = s.hi();
+
+        let m = t[0].mul_lo(inv).lo();
+        let mut carry = t[0].add(m.mul_lo(modulus[0])).hi();
+        for j in 1..LIMBS {
+            let s = t[j].add(m.mul_lo(modulus[j])).add(carry);
+            t[j - 1] = s.lo();
+            carry = s.hi();
+        }
+        let s = t[LIMBS].add(carry);
+        t[LIMBS - 1] = s.lo();
+        t[LIMBS] = t[LIMBS + 1].add(s.hi());
+    }
+
+    // The result is below 2p; subtract p once if it isn't below p.
+    let mut out = [BigInteger256::default(); 2];
+    for (j, pair) in t.iter().take(LIMBS).enumerate() {
+        let [l0, l1] = pair.to_array();
+        out[0].0[j / 2] |= l0 << (32 * (j % 2));
+        out[1].0[j / 2] |= l1 << (32 * (j % 2));
+    }
+    let overflow = t[LIMBS].to_array();
+    for (repr, overflow) in out.iter_mut().zip(overflow) {
+        if overflow != 0 || *repr >= P::MODULUS {
+            repr.sub_noborrow(&P::MODULUS);
+        }
+    }
+    [Fp256::new(out[0]), Fp256::new(out[1])]
+}
+
+/// A BN254 G1 point in Jacobian coordinates, the point at infinity having
+/// `z = 0`.
+#[derive(Clone, Copy, Debug)]
+struct Jacobian {
+    x: Fq,
+    y: Fq,
+    z: Fq,
+}
+
+impl Jacobian {
+    fn zero() -> Self {
+        Jacobian {
+            x: Fq::one(),
+            y: Fq::one(),
+            z: Fq::zero(),
+        }
+    }
+
+    /// dbl-2009-l, for curves with a = 0.
+    fn double(&self) -> Self {
+        if self.z.is_zero() {
+            return *self;
+        }
+        let [a, b] = mul2([self.x, self.y], [self.x, self.y]);
+        let xb = self.x + b;
+        let [c, xb2] = mul2([b, xb], [b, xb]);
+        let d = (xb2 - a - c).double();
+        let e = a.double() + a;
+        let [f, yz] = mul2([e, self.y], [e, self.z]);
+        let x = f - d.double();
+        let y = e * (d - x) - c.double().double().double();
+        Jacobian {
+            x,
+            y,
+            z: yz.double(),
+        }
+    }
+
+    /// madd-2007-bl, adding a point with z = 1.
+    fn add_affine(&self, q: &G1Affine) -> Self {
+        if q.infinity {
+            return *self;
+        }
+        if self.z.is_zero() {
+            return Jacobian {
+                x: q.x,
+                y: q.y,
+                z: Fq::one(),
+            };
+        }
+        let [z1z1, yz] = mul2([self.z, q.y], [self.z, self.z]);
+        let [u2, s2] = mul2([q.x, yz], [z1z1, z1z1]);
+        let h = u2 - self.x;
+        let r = (s2 - self.y).double();
+        if h.is_zero() {
+            return if r.is_zero() {
+                self.double()
+            } else {
+                Jacobian::zero()
+            };
+        }
+        let z1h = self.z + h;
+        let [hh, z1h2] = mul2([h, z1h], [h, z1h]);
+        let i = hh.double().double();
+        let [j, v] = mul2([h, self.x], [i, i]);
+        let x = r.square() - j - v.double();
+        let [rv, yj] = mul2([r, self.y], [v - x, j]);
+        Jacobian {
+            x,
+            y: rv - yj.double(),
+            z: z1h2 - z1z1 - hh,
+        }
+    }
+}
+
+/// Returns `gamma_abc_g1[0] + sum pub_inputs[i] * gamma_abc_g1[i + 1]`, as
+/// `ark_groth16::prepare_inputs` does. `gamma_abc_g1` must have one more
+/// point than there are inputs.
+pub fn prepare_inputs(gamma_abc_g1: &[G1Affine], pub_inputs: &[Fr]) -> G1Projective {
+    let scalars: Vec<BigInteger256> = pub_inputs.iter().map(|input| input.into_repr()).collect();
+    let bits = <Fr as PrimeField>::Params::MODULUS_BITS as usize;
+    let mut acc = Jacobian::zero();
+    for bit in (0..bits).rev() {
+        acc = acc.double();
+        for (scalar, base) in scalars.iter().zip(&gamma_abc_g1[1..]) {
+            if scalar.get_bit(bit) {
+                acc = acc.add_affine(base);
+            }
+        }
+    }
+    let acc = acc.add_affine(&gamma_abc_g1[0]);
+    G1Projective::new(acc.x, acc.y, acc.z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_std::UniformRand;
+
+    fn check_mul2<P: Fp256Parameters>() {
+        let rng = &mut ark_std::test_rng();
+        let mut values: Vec<Fp256<P>> = (0..32).map(|_| Fp256::rand(rng)).collect();
+        values.extend([Fp256::zero(), Fp256::one(), -Fp256::one()]);
+        for (i, a) in values.iter().enumerate() {
+            for b in values.iter() {
+                let c = values[(i + 1) % values.len()];
+                assert_eq!(mul2([*a, *b], [*b, c]), [*a * b, *b * c]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mul2() {
+        check_mul2::<ark_bn254::FqParameters>();
+        check_mul2::<ark_bn254::FrParameters>();
+    }
+
+    fn projective(p: &Jacobian) -> G1Projective {
+        G1Projective::new(p.x, p.y, p.z)
+    }
+
+    #[test]
+    fn test_point_arithmetic() {
+        let rng = &mut ark_std::test_rng();
+        for _ in 0..16 {
+            let p = G1Projective::rand(rng);
+            let q = G1Projective::rand(rng).into_affine();
+            let jacobian = Jacobian {
+                x: p.x,
+                y: p.y,
+                z: p.z,
+            };
+            assert_eq!(projective(&jacobian.double()), p.double());
+            assert_eq!(projective(&jacobian.add_affine(&q)), p.add_mixed(&q));
+
+            let p_affine = p.into_affine();
+            assert_eq!(projective(&jacobian.add_affine(&p_affine)), p.double());
+            assert!(projective(&jacobian.add_affine(&-p_affine)).is_zero());
+            assert_eq!(projective(&jacobian.add_affine(&G1Affine::zero())), p);
+            assert_eq!(
+                projective(&Jacobian::zero().add_affine(&q)),
+                q.into_projective()
+            );
+        }
+        assert!(projective(&Jacobian::zero().double()).is_zero());
+    }
+
+    #[test]
+    fn test_prepare_inputs() {
+        let rng = &mut ark_std::test_rng();
+        let bases: Vec<G1Affine> = (0..6)
+            .map(|_| G1Projective::rand(rng).into_affine())
+            .collect();
+        let mut inputs: Vec<Fr> = (0..5).map(|_| Fr::rand(rng)).collect();
+        inputs[1] = Fr::zero();
+        inputs[2] = -Fr::one();
+        for n in 0..=inputs.len() {
+            let mut expected = bases[0].into_projective();
+            for (input, base) in inputs[..n].iter().zip(&bases[1..]) {
+                expected += &base.mul(input.into_repr());
+            }
+            assert_eq!(prepare_inputs(&bases[..=n], &inputs[..n]), expected);
+        }
+    }
+}
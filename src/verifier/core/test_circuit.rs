@@ -0,0 +1,45 @@
+// Copyright © 2022, Electron Labs
+
+//! Proofs of the testkit multiplier on any curve, for tests on curves we
+//! don't have circom fixtures for.
+
+use ark_ec::PairingEngine;
+use electron_testkit::multiplier::MulCircuit;
+
+/// Runs a setup for `MulCircuit` and proves `a * b`, returning the
+/// verifying key, the proof and the single public input.
+pub(crate) fn prove<E: PairingEngine>(
+    a: u64,
+    b: u64,
+) -> (ark_groth16::VerifyingKey<E>, ark_groth16::Proof<E>, E::Fr) {
+    let (vk, mut proofs) = prove_many::<E>(&[(a, b)]);
+    let (proof, c) = proofs.remove(0);
+    (vk, proof, c)
+}
+
+/// Like `prove`, for several `(a, b)` pairs under one setup.
+pub(crate) fn prove_many<E: PairingEngine>(
+    values: &[(u64, u64)],
+) -> (
+    ark_groth16::VerifyingKey<E>,
+    Vec<(ark_groth16::Proof<E>, E::Fr)>,
+) {
+    let rng = &mut ark_std::test_rng();
+    let empty = MulCircuit::<E::Fr> { a: None, b: None };
+    let params = ark_groth16::generate_random_parameters::<E, _, _>(empty, rng).unwrap();
+
+    let proofs = values
+        .iter()
+        .map(|(a, b)| {
+            let a = E::Fr::from(*a);
+            let b = E::Fr::from(*b);
+            let circuit = MulCircuit {
+                a: Some(a),
+                b: Some(b),
+            };
+            let proof = ark_groth16::create_random_proof(circuit, &params, rng).unwrap();
+            (proof, a * b)
+        })
+        .collect();
+    (params.vk, proofs)
+}
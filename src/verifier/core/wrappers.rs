@@ -0,0 +1,551 @@
+// Copyright © 2022, Electron Labs
+
+//! The borsh-serializable wrapper types, generated per curve.
+//!
+//! Each wrapper mirrors an arkworks type limb for limb, so the borsh
+//! encoding is the in-memory Montgomery representation and loading a key
+//! doesn't need any field arithmetic. Proofs come from untrusted callers,
+//! so their limbs are checked to be below the modulus on conversion.
+
+/// Declares the wrapper types for one curve in the calling module.
+///
+/// `$curve` is the arkworks curve crate, `$engine` its pairing engine,
+/// `$bigint` the `ark_ff` big integer of the base field with `$limbs`
+/// limbs, and `$model` the `ark_ec` module of the curve family (`bn` or
+/// `bls12`). The engine gets a `WrappedEngine` impl pointing at the
+/// generated `PreparedVerifyingKey`, with `$prepare_inputs` overriding
+/// `WrappedEngine::prepare_inputs` if given.
+macro_rules! curve_wrappers {
+    ($curve:ident, $engine:ident, $bigint:ident, $limbs:literal, $model:ident $(, $prepare_inputs:path)?) => {
+        #[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+        pub(crate) struct $bigint {
+            pub(crate) val: [u64; $limbs],
+        }
+
+        impl $bigint {
+            pub fn new(src: [u64; $limbs]) -> Self {
+                $bigint { val: src }
+            }
+
+            /// The limbs, if they are below `modulus` as the Montgomery
+            /// form of every field element is.
+            ///
+            /// # Errors
+            /// $crate::verifier::core::VerifierError::InvalidFieldElement
+            pub(crate) fn below(
+                &self,
+                modulus: ark_ff::$bigint,
+                at: &str,
+            ) -> Result<ark_ff::$bigint, $crate::verifier::core::VerifierError> {
+                let repr = ark_ff::$bigint::new(self.val);
+                if repr >= modulus {
+                    return Err($crate::verifier::core::VerifierError::InvalidFieldElement(
+                        alloc::string::ToString::to_string(at),
+                    ));
+                }
+                Ok(repr)
+            }
+
+            /// The base field element of the limbs.
+            ///
+            /// # Errors
+            /// $crate::verifier::core::VerifierError::InvalidFieldElement
+            fn fq(&self, at: &str) -> Result<$curve::Fq, $crate::verifier::core::VerifierError> {
+                let modulus = <$curve::Fq as ark_ff::PrimeField>::Params::MODULUS;
+                Ok($curve::Fq::new(self.below(modulus, at)?))
+            }
+        }
+
+        impl From<$bigint> for ark_ff::$bigint {
+            fn from(src: $bigint) -> ark_ff::$bigint {
+                ark_ff::$bigint::new(src.val)
+            }
+        }
+
+        impl From<ark_ff::$bigint> for $bigint {
+            fn from(src: ark_ff::$bigint) -> $bigint {
+                $bigint::new(src.0)
+            }
+        }
+
+        #[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+        pub(crate) struct Fq {
+            pub(crate) c0: $bigint,
+        }
+
+        impl Fq {
+            pub fn new(src: $bigint) -> Self {
+                Fq { c0: src }
+            }
+        }
+
+        impl From<Fq> for $curve::Fq {
+            fn from(src: Fq) -> $curve::Fq {
+                $curve::Fq::new(src.c0.into())
+            }
+        }
+
+        impl From<$curve::Fq> for Fq {
+            fn from(src: $curve::Fq) -> Fq {
+                Fq::new(src.0.into())
+            }
+        }
+
+        #[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+        pub(crate) struct Fq2 {
+            pub(crate) c0: $bigint,
+            pub(crate) c1: $bigint,
+        }
+
+        impl Fq2 {
+            pub fn new(c0_: $bigint, c1_: $bigint) -> Self {
+                Fq2 { c0: c0_, c1: c1_ }
+            }
+        }
+
+        impl From<Fq2> for $curve::Fq2 {
+            fn from(src: Fq2) -> $curve::Fq2 {
+                let c0: ark_ff::$bigint = src.c0.into();
+                let c1: ark_ff::$bigint = src.c1.into();
+                $curve::Fq2::new($curve::Fq::new(c0), $curve::Fq::new(c1))
+            }
+        }
+
+        impl From<$curve::Fq2> for Fq2 {
+            fn from(src: $curve::Fq2) -> Fq2 {
+                Fq2::new(src.c0.0.into(), src.c1.0.into())
+            }
+        }
+
+        #[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+        pub(crate) struct Fq6 {
+            pub(crate) c0: Fq2,
+            pub(crate) c1: Fq2,
+            pub(crate) c2: Fq2,
+        }
+
+        impl Fq6 {
+            pub fn new(c0_: Fq2, c1_: Fq2, c2_: Fq2) -> Self {
+                Fq6 {
+                    c0: c0_,
+                    c1: c1_,
+                    c2: c2_,
+                }
+            }
+        }
+
+        impl From<Fq6> for $curve::Fq6 {
+            fn from(src: Fq6) -> $curve::Fq6 {
+                let c0: $curve::Fq2 = src.c0.into();
+                let c1: $curve::Fq2 = src.c1.into();
+                let c2: $curve::Fq2 = src.c2.into();
+                $curve::Fq6::new(c0, c1, c2)
+            }
+        }
+
+        impl From<$curve::Fq6> for Fq6 {
+            fn from(src: $curve::Fq6) -> Fq6 {
+                let c0: $curve::Fq2 = src.c0;
+                let c1: $curve::Fq2 = src.c1;
+                let c2: $curve::Fq2 = src.c2;
+                Fq6::new(c0.into(), c1.into(), c2.into())
+            }
+        }
+
+        #[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+        pub(crate) struct Fq12 {
+            pub(crate) c0: Fq6,
+            pub(crate) c1: Fq6,
+        }
+
+        impl Fq12 {
+            pub fn new(c0_: Fq6, c1_: Fq6) -> Self {
+                Fq12 { c0: c0_, c1: c1_ }
+            }
+        }
+
+        impl From<Fq12> for $curve::Fq12 {
+            fn from(src: Fq12) -> $curve::Fq12 {
+                let c0: $curve::Fq6 = src.c0.into();
+                let c1: $curve::Fq6 = src.c1.into();
+                $curve::Fq12::new(c0, c1)
+            }
+        }
+
+        impl From<$curve::Fq12> for Fq12 {
+            fn from(src: $curve::Fq12) -> Fq12 {
+                let c0: $curve::Fq6 = src.c0;
+                let c1: $curve::Fq6 = src.c1;
+                Fq12::new(c0.into(), c1.into())
+            }
+        }
+
+        #[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+        pub(crate) struct G1Affine {
+            pub(crate) x: $bigint,
+            pub(crate) y: $bigint,
+            pub(crate) infinity: bool,
+        }
+
+        impl G1Affine {
+            pub fn new(x_: $bigint, y_: $bigint, infinity_: bool) -> Self {
+                G1Affine {
+                    x: x_,
+                    y: y_,
+                    infinity: infinity_,
+                }
+            }
+        }
+
+        impl From<G1Affine> for $curve::G1Affine {
+            fn from(src: G1Affine) -> $curve::G1Affine {
+                let x: ark_ff::$bigint = src.x.into();
+                let y: ark_ff::$bigint = src.y.into();
+                $curve::G1Affine::new($curve::Fq::new(x), $curve::Fq::new(y), src.infinity)
+            }
+        }
+
+        impl From<$curve::G1Affine> for G1Affine {
+            fn from(src: $curve::G1Affine) -> G1Affine {
+                let x: ark_ff::$bigint = src.x.0;
+                let y: ark_ff::$bigint = src.y.0;
+                G1Affine::new(x.into(), y.into(), src.infinity)
+            }
+        }
+
+        #[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+        pub(crate) struct G2Affine {
+            pub(crate) x: Fq2,
+            pub(crate) y: Fq2,
+            pub(crate) infinity: bool,
+        }
+
+        impl G2Affine {
+            pub fn new(x_: Fq2, y_: Fq2, infinity_: bool) -> Self {
+                G2Affine {
+                    x: x_,
+                    y: y_,
+                    infinity: infinity_,
+                }
+            }
+        }
+
+        impl From<$curve::G2Affine> for G2Affine {
+            fn from(src: $curve::G2Affine) -> G2Affine {
+                let x: $curve::Fq2 = src.x;
+                let y: $curve::Fq2 = src.y;
+                G2Affine::new(x.into(), y.into(), src.infinity)
+            }
+        }
+
+        impl From<G2Affine> for $curve::G2Affine {
+            fn from(src: G2Affine) -> $curve::G2Affine {
+                let x: $curve::Fq2 = src.x.into();
+                let y: $curve::Fq2 = src.y.into();
+                $curve::G2Affine::new(x, y, src.infinity)
+            }
+        }
+
+        #[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+        pub(crate) struct G2Prepared {
+            pub(crate) ell_coeffs: alloc::vec::Vec<(Fq2, Fq2, Fq2)>,
+            pub(crate) infinity: bool,
+        }
+
+        impl G2Prepared {
+            pub fn new(ell_coeffs_: alloc::vec::Vec<(Fq2, Fq2, Fq2)>, inf: bool) -> Self {
+                G2Prepared {
+                    ell_coeffs: ell_coeffs_,
+                    infinity: inf,
+                }
+            }
+        }
+
+        impl From<ark_ec::$model::G2Prepared<$curve::Parameters>> for G2Prepared {
+            fn from(src: ark_ec::$model::G2Prepared<$curve::Parameters>) -> G2Prepared {
+                let ark_ell_coeffs = src
+                    .ell_coeffs
+                    .into_iter()
+                    .map(|elem| (elem.0, elem.1, elem.2));
+                let ell_coeffs: alloc::vec::Vec<(Fq2, Fq2, Fq2)> = ark_ell_coeffs
+                    .map(|elem| (elem.0.into(), elem.1.into(), elem.2.into()))
+                    .collect();
+                G2Prepared::new(ell_coeffs, src.infinity)
+            }
+        }
+
+        impl From<G2Prepared> for ark_ec::$model::G2Prepared<$curve::Parameters> {
+            fn from(src: G2Prepared) -> ark_ec::$model::G2Prepared<$curve::Parameters> {
+                let ark_ell_coeffs = src
+                    .ell_coeffs
+                    .into_iter()
+                    .map(|elem| (elem.0.into(), elem.1.into(), elem.2.into()));
+                ark_ec::$model::G2Prepared {
+                    ell_coeffs: ark_ell_coeffs
+                        .map(|elem| (elem.0, elem.1, elem.2))
+                        .collect(),
+                    infinity: src.infinity,
+                }
+            }
+        }
+
+        /// A Groth16 verifying key, before preparation.
+        #[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+        pub struct VerifyingKey {
+            pub(crate) alpha_g1: G1Affine,
+            pub(crate) beta_g2: G2Affine,
+            pub(crate) gamma_g2: G2Affine,
+            pub(crate) delta_g2: G2Affine,
+            pub(crate) gamma_abc_g1: alloc::vec::Vec<G1Affine>,
+        }
+
+        impl From<VerifyingKey> for ark_groth16::VerifyingKey<$curve::$engine> {
+            fn from(src: VerifyingKey) -> ark_groth16::VerifyingKey<$curve::$engine> {
+                ark_groth16::VerifyingKey {
+                    alpha_g1: src.alpha_g1.into(),
+                    beta_g2: src.beta_g2.into(),
+                    gamma_g2: src.gamma_g2.into(),
+                    delta_g2: src.delta_g2.into(),
+                    gamma_abc_g1: src
+                        .gamma_abc_g1
+                        .into_iter()
+                        .map(|elem| elem.into())
+                        .collect(),
+                }
+            }
+        }
+
+        impl From<ark_groth16::VerifyingKey<$curve::$engine>> for VerifyingKey {
+            fn from(src: ark_groth16::VerifyingKey<$curve::$engine>) -> VerifyingKey {
+                VerifyingKey {
+                    alpha_g1: src.alpha_g1.into(),
+                    beta_g2: src.beta_g2.into(),
+                    gamma_g2: src.gamma_g2.into(),
+                    delta_g2: src.delta_g2.into(),
+                    gamma_abc_g1: src
+                        .gamma_abc_g1
+                        .into_iter()
+                        .map(|elem| elem.into())
+                        .collect(),
+                }
+            }
+        }
+
+        impl VerifyingKey {
+            /// Reads a key written by arkworks' `CanonicalSerialize`,
+            /// compressed or uncompressed, and checks its points.
+            ///
+            /// # Errors
+            /// $crate::verifier::core::VerifierError::InvalidEncoding,
+            /// $crate::verifier::core::VerifierError::InvalidPoint,
+            /// $crate::verifier::core::VerifierError::InvalidSubgroupPoint
+            pub fn from_ark_bytes(
+                bytes: &[u8],
+            ) -> Result<VerifyingKey, $crate::verifier::core::VerifierError> {
+                let vk: ark_groth16::VerifyingKey<$curve::$engine> =
+                    $crate::verifier::core::from_ark_bytes(bytes, "verifying key")?;
+                check_verifying_key(&vk)?;
+                Ok(vk.into())
+            }
+
+            /// The prepared key, as contracts store it.
+            pub fn prepare(&self) -> PreparedVerifyingKey {
+                ark_groth16::prepare_verifying_key(&self.clone().into()).into()
+            }
+        }
+
+        /// A Groth16 proof, for submitting proofs in borsh instead of json.
+        #[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+        pub struct Proof {
+            pub(crate) a: G1Affine,
+            pub(crate) b: G2Affine,
+            pub(crate) c: G1Affine,
+        }
+
+        impl G1Affine {
+            /// The point, if both coordinates are field elements.
+            fn checked(
+                &self,
+                at: &str,
+            ) -> Result<$curve::G1Affine, $crate::verifier::core::VerifierError> {
+                let (x, y) = (self.x.fq(at)?, self.y.fq(at)?);
+                Ok($curve::G1Affine::new(x, y, self.infinity))
+            }
+        }
+
+        impl G2Affine {
+            /// The point, if all four coordinates are field elements.
+            fn checked(
+                &self,
+                at: &str,
+            ) -> Result<$curve::G2Affine, $crate::verifier::core::VerifierError> {
+                let x = $curve::Fq2::new(self.x.c0.fq(at)?, self.x.c1.fq(at)?);
+                let y = $curve::Fq2::new(self.y.c0.fq(at)?, self.y.c1.fq(at)?);
+                Ok($curve::G2Affine::new(x, y, self.infinity))
+            }
+        }
+
+        /// Rejects coordinates whose limbs are not below the modulus, which
+        /// arkworks would otherwise take as unreduced elements. The points
+        /// still have to go through `check_proof`.
+        ///
+        /// # Errors
+        /// $crate::verifier::core::VerifierError::InvalidFieldElement
+        impl core::convert::TryFrom<Proof> for ark_groth16::Proof<$curve::$engine> {
+            type Error = $crate::verifier::core::VerifierError;
+
+            fn try_from(src: Proof) -> Result<ark_groth16::Proof<$curve::$engine>, Self::Error> {
+                Ok(ark_groth16::Proof {
+                    a: src.a.checked("pi_a")?,
+                    b: src.b.checked("pi_b")?,
+                    c: src.c.checked("pi_c")?,
+                })
+            }
+        }
+
+        impl From<ark_groth16::Proof<$curve::$engine>> for Proof {
+            fn from(src: ark_groth16::Proof<$curve::$engine>) -> Proof {
+                Proof {
+                    a: src.a.into(),
+                    b: src.b.into(),
+                    c: src.c.into(),
+                }
+            }
+        }
+
+        impl Proof {
+            /// Reads a proof written by arkworks' `CanonicalSerialize`,
+            /// compressed or uncompressed, as provers built on ark-groth16
+            /// emit it, and checks its points.
+            ///
+            /// # Errors
+            /// $crate::verifier::core::VerifierError::InvalidEncoding,
+            /// $crate::verifier::core::VerifierError::InvalidPoint,
+            /// $crate::verifier::core::VerifierError::InvalidSubgroupPoint
+            pub fn from_ark_bytes(
+                bytes: &[u8],
+            ) -> Result<Proof, $crate::verifier::core::VerifierError> {
+                let proof: ark_groth16::Proof<$curve::$engine> =
+                    $crate::verifier::core::from_ark_bytes(bytes, "proof")?;
+                check_proof(&proof)?;
+                Ok(proof.into())
+            }
+        }
+
+        #[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq, Clone)]
+        pub struct PreparedVerifyingKey {
+            pub(crate) vk: VerifyingKey,
+            pub(crate) alpha_g1_beta_g2: Fq12,
+            pub(crate) gamma_g2_neg_pc: G2Prepared,
+            pub(crate) delta_g2_neg_pc: G2Prepared,
+        }
+
+        impl PreparedVerifyingKey {
+            /// Returns the sha256 digest of the borsh encoding of the key, which
+            /// identifies a key independently of where it is stored.
+            pub fn fingerprint(&self) -> [u8; 32] {
+                let bytes = borsh::BorshSerialize::try_to_vec(self)
+                    .expect("Failed to serialize prepared verifying key");
+                <sha2::Sha256 as sha2::Digest>::digest(&bytes).into()
+            }
+        }
+
+        impl From<PreparedVerifyingKey> for ark_groth16::PreparedVerifyingKey<$curve::$engine> {
+            fn from(
+                src: PreparedVerifyingKey,
+            ) -> ark_groth16::PreparedVerifyingKey<$curve::$engine> {
+                ark_groth16::PreparedVerifyingKey {
+                    vk: src.vk.into(),
+                    alpha_g1_beta_g2: src.alpha_g1_beta_g2.into(),
+                    gamma_g2_neg_pc: src.gamma_g2_neg_pc.into(),
+                    delta_g2_neg_pc: src.delta_g2_neg_pc.into(),
+                }
+            }
+        }
+
+        impl From<ark_groth16::PreparedVerifyingKey<$curve::$engine>> for PreparedVerifyingKey {
+            fn from(
+                src: ark_groth16::PreparedVerifyingKey<$curve::$engine>,
+            ) -> PreparedVerifyingKey {
+                PreparedVerifyingKey {
+                    vk: src.vk.into(),
+                    alpha_g1_beta_g2: src.alpha_g1_beta_g2.into(),
+                    gamma_g2_neg_pc: src.gamma_g2_neg_pc.into(),
+                    delta_g2_neg_pc: src.delta_g2_neg_pc.into(),
+                }
+            }
+        }
+
+        /// Verifies a proof against a prepared key, with the inputs already
+        /// parsed into field elements.
+        ///
+        /// # Errors
+        /// $crate::verifier::core::VerifierError::InputCountMismatch,
+        /// $crate::verifier::core::VerifierError::SynthesisError
+        ///
+        /// This function will return an error if the number of inputs doesn't
+        /// match the key, or if the inputs can't be checked against it.
+        pub fn verify_prepared(
+            pvk: &PreparedVerifyingKey,
+            proof: &ark_groth16::Proof<$curve::$engine>,
+            pub_inputs: &[$curve::Fr],
+        ) -> Result<bool, $crate::verifier::core::VerifierError> {
+            $crate::verifier::core::check_input_count(pvk.vk.gamma_abc_g1.len(), pub_inputs.len())?;
+            let pvk: ark_groth16::PreparedVerifyingKey<$curve::$engine> = pvk.clone().into();
+            let prepared_inputs =
+                <$curve::$engine as $crate::verifier::core::WrappedEngine>::prepare_inputs(
+                    &pvk.vk.gamma_abc_g1,
+                    pub_inputs,
+                );
+            let res = ark_groth16::verify_proof_with_prepared_inputs(&pvk, proof, &prepared_inputs)
+                .map_err(|e| {
+                    $crate::verifier::core::VerifierError::SynthesisError(alloc::format!("{:?}", e))
+                })?;
+            Ok(res)
+        }
+
+        /// Checks the points of a proof read from untrusted input.
+        ///
+        /// # Errors
+        /// $crate::verifier::core::VerifierError::InvalidPoint,
+        /// $crate::verifier::core::VerifierError::InvalidSubgroupPoint
+        pub fn check_proof(
+            proof: &ark_groth16::Proof<$curve::$engine>,
+        ) -> Result<(), $crate::verifier::core::VerifierError> {
+            $crate::verifier::core::check_point(&proof.a, "pi_a")?;
+            $crate::verifier::core::check_point(&proof.b, "pi_b")?;
+            $crate::verifier::core::check_point(&proof.c, "pi_c")
+        }
+
+        /// Checks the points of a verifying key read from untrusted input.
+        ///
+        /// # Errors
+        /// $crate::verifier::core::VerifierError::InvalidPoint,
+        /// $crate::verifier::core::VerifierError::InvalidSubgroupPoint
+        pub fn check_verifying_key(
+            vk: &ark_groth16::VerifyingKey<$curve::$engine>,
+        ) -> Result<(), $crate::verifier::core::VerifierError> {
+            $crate::verifier::core::check_point(&vk.alpha_g1, "vk_alpha_1")?;
+            $crate::verifier::core::check_point(&vk.beta_g2, "vk_beta_2")?;
+            $crate::verifier::core::check_point(&vk.gamma_g2, "vk_gamma_2")?;
+            $crate::verifier::core::check_point(&vk.delta_g2, "vk_delta_2")?;
+            for (i, point) in vk.gamma_abc_g1.iter().enumerate() {
+                $crate::verifier::core::check_point(point, &alloc::format!("IC[{}]", i))?;
+            }
+            Ok(())
+        }
+
+        impl $crate::verifier::core::WrappedEngine for $curve::$engine {
+            type PreparedVerifyingKey = PreparedVerifyingKey;
+
+            $(
+                fn prepare_inputs(
+                    gamma_abc_g1: &[$curve::G1Affine],
+                    pub_inputs: &[$curve::Fr],
+                ) -> $curve::G1Projective {
+                    $prepare_inputs(gamma_abc_g1, pub_inputs)
+                }
+            )?
+        }
+    };
+}
@@ -0,0 +1,75 @@
+// Copyright © 2022, Electron Labs
+
+//! A proof-system-agnostic verifier interface.
+//!
+//! Code written against [`ZkVerifier`] rather than [`GrothVerifier`]
+//! doesn't depend on the proof system, so another system can be swapped
+//! in, e.g. behind a feature flag selecting the type a contract stores.
+
+use super::{GrothVerifier, VerifierError, WrappedEngine};
+
+pub trait ZkVerifier: Sized {
+    /// The verifying key the verifier is built from.
+    type Vk;
+    type Proof;
+    /// A public input.
+    type Input;
+
+    fn from_vk(vk: &Self::Vk) -> Result<Self, VerifierError>;
+
+    /// Verifies a proof for the given public inputs. A well-formed proof
+    /// that doesn't verify returns `Ok(false)`.
+    fn verify(
+        &self,
+        proof: &Self::Proof,
+        pub_inputs: &[Self::Input],
+    ) -> Result<bool, VerifierError>;
+}
+
+impl<E: WrappedEngine> ZkVerifier for GrothVerifier<E> {
+    type Vk = ark_groth16::VerifyingKey<E>;
+    type Proof = ark_groth16::Proof<E>;
+    type Input = E::Fr;
+
+    fn from_vk(vk: &Self::Vk) -> Result<Self, VerifierError> {
+        Ok(GrothVerifier::new(vk))
+    }
+
+    fn verify(
+        &self,
+        proof: &Self::Proof,
+        pub_inputs: &[Self::Input],
+    ) -> Result<bool, VerifierError> {
+        GrothVerifier::verify(self, proof, pub_inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::test_circuit;
+
+    /// Knows nothing about Groth16.
+    fn verify_all<V: ZkVerifier>(
+        vk: &V::Vk,
+        proofs: &[(V::Proof, Vec<V::Input>)],
+    ) -> Result<bool, VerifierError> {
+        let verifier = V::from_vk(vk)?;
+        for (proof, inputs) in proofs {
+            if !verifier.verify(proof, inputs)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    #[test]
+    fn test_groth_verifier_as_zk_verifier() {
+        let (vk, proofs) = test_circuit::prove_many::<ark_bn254::Bn254>(&[(3, 5), (2, 7)]);
+        let mut proofs: Vec<_> = proofs.into_iter().map(|(p, c)| (p, vec![c])).collect();
+        assert!(verify_all::<GrothVerifier<ark_bn254::Bn254>>(&vk, &proofs).unwrap());
+
+        proofs[1].1 = vec![ark_bn254::Fr::from(15u64)];
+        assert!(!verify_all::<GrothVerifier<ark_bn254::Bn254>>(&vk, &proofs).unwrap());
+    }
+}
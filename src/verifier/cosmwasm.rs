@@ -0,0 +1,247 @@
+// Copyright © 2022, Electron Labs
+
+//! Verification of circom Groth16 proofs in CosmWasm contracts.
+//!
+//! The json types derive plain serde traits, so contracts can embed them in
+//! their messages and let `cosmwasm_std::from_slice` parse them, and they
+//! read the same verification key and proof json as `verifier::near`. Only
+//! BN254 keys are supported.
+//!
+//! CosmWasm charges gas per byte read from storage as well as per wasm
+//! instruction, and the line coefficients of a prepared BN254 key take
+//! some 33 KB. Contracts therefore store a [`CompressedVerifyingKey`],
+//! made once by [`compress_verification_key`] when the contract is
+//! instantiated, and [`verify_proof`] checks the proof against the
+//! unprepared key, which costs one more pairing term than a prepared key
+//! but saves reading it.
+//!
+//! ```ignore
+//! use electron_rs::verifier::cosmwasm::{
+//!     compress_verification_key, verify_proof, ProofJson, VerificationKeyJson,
+//! };
+//!
+//! pub fn instantiate(deps: DepsMut, msg: InstantiateMsg) -> StdResult<Response> {
+//!     let vk = compress_verification_key(&msg.vkey).map_err(to_std_err)?;
+//!     VKEY.save(deps.storage, &vk.try_to_vec()?)?;
+//!     Ok(Response::new())
+//! }
+//!
+//! pub fn verify(deps: Deps, proof: ProofJson, inputs: Vec<String>) -> StdResult<bool> {
+//!     let vk = CompressedVerifyingKey::try_from_slice(&VKEY.load(deps.storage)?)?;
+//!     verify_proof(&vk, &proof, &inputs).map_err(to_std_err)
+//! }
+//! ```
+
+use crate::verifier::circom::{g1_from_str, g2_from_str, inputs_from_str};
+use crate::verifier::core::backend::{verify_key_with_backend, ArkworksBackend};
+use crate::verifier::core::{check_proof, check_verifying_key, VerifierError};
+use crate::verifier::json::{self, JsonError};
+use serde::{Deserialize, Serialize};
+
+pub use crate::verifier::core::compressed::CompressedVerifyingKey;
+
+/// Circom verification key json.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VerificationKeyJson {
+    pub protocol: String,
+    pub curve: String,
+    #[serde(rename = "nPublic")]
+    pub num_public: u64,
+    pub vk_alpha_1: Vec<String>,
+    pub vk_beta_2: Vec<Vec<String>>,
+    pub vk_gamma_2: Vec<Vec<String>>,
+    pub vk_delta_2: Vec<Vec<String>>,
+    /// e(alpha, beta), which newer snarkjs releases omit. It is never read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vk_alphabeta_12: Option<Vec<Vec<Vec<String>>>>,
+    #[serde(rename = "IC")]
+    pub ic: Vec<Vec<String>>,
+}
+
+/// Circom proof json.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProofJson {
+    pub pi_a: Vec<String>,
+    pub pi_b: Vec<Vec<String>>,
+    pub pi_c: Vec<String>,
+    pub protocol: String,
+    #[serde(default)]
+    pub curve: String,
+}
+
+impl TryFrom<&VerificationKeyJson> for ark_groth16::VerifyingKey<ark_bn254::Bn254> {
+    type Error = VerifierError;
+
+    fn try_from(src: &VerificationKeyJson) -> Result<Self, VerifierError> {
+        if src.protocol != "groth16" {
+            return Err(VerifierError::UnsupportedProtocol(src.protocol.clone()));
+        }
+        if src.curve != "bn128" {
+            return Err(VerifierError::UnsupportedCurve(src.curve.clone()));
+        }
+        Ok(ark_groth16::VerifyingKey {
+            alpha_g1: g1_from_str(&src.vk_alpha_1, "vk_alpha_1")?,
+            beta_g2: g2_from_str(&src.vk_beta_2, "vk_beta_2")?,
+            gamma_g2: g2_from_str(&src.vk_gamma_2, "vk_gamma_2")?,
+            delta_g2: g2_from_str(&src.vk_delta_2, "vk_delta_2")?,
+            gamma_abc_g1: src
+                .ic
+                .iter()
+                .enumerate()
+                .map(|(i, x)| g1_from_str(x, &format!("IC[{}]", i)))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl TryFrom<&ProofJson> for ark_groth16::Proof<ark_bn254::Bn254> {
+    type Error = VerifierError;
+
+    fn try_from(src: &ProofJson) -> Result<Self, VerifierError> {
+        if src.pi_a.len() != 3 {
+            return Err(VerifierError::InvalidProof("pi_a".to_string()));
+        }
+        if src.pi_b.len() != 3 || src.pi_b.iter().any(|c| c.len() != 2) {
+            return Err(VerifierError::InvalidProof("pi_b".to_string()));
+        }
+        if src.pi_c.len() != 3 {
+            return Err(VerifierError::InvalidProof("pi_c".to_string()));
+        }
+        Ok(ark_groth16::Proof {
+            a: g1_from_str(&src.pi_a, "pi_a")?,
+            b: g2_from_str(&src.pi_b, "pi_b")?,
+            c: g1_from_str(&src.pi_c, "pi_c")?,
+        })
+    }
+}
+
+/// Parses verification key json, for keys passed as raw bytes rather than
+/// as part of a message.
+///
+/// # Errors
+/// VerifierError::ParseError, JsonError::TooDeep, JsonError::TooLarge
+pub fn parse_verification_key(vkey: &[u8]) -> Result<VerificationKeyJson, JsonError> {
+    json::from_slice(vkey, "verification key")
+}
+
+/// Parses proof json.
+///
+/// # Errors
+/// VerifierError::ParseError, JsonError::TooDeep, JsonError::TooLarge
+pub fn parse_proof(proof: &[u8]) -> Result<ProofJson, JsonError> {
+    json::from_slice(proof, "proof")
+}
+
+/// Parses public inputs json.
+///
+/// # Errors
+/// VerifierError::ParseError, JsonError::TooDeep, JsonError::TooLarge
+pub fn parse_public_inputs(inputs: &[u8]) -> Result<Vec<String>, JsonError> {
+    json::from_slice(inputs, "public inputs")
+}
+
+/// Parses a key, checks its points and compresses it for storage.
+///
+/// # Errors
+/// VerifierError::UnsupportedProtocol, VerifierError::UnsupportedCurve,
+/// VerifierError::InvalidFieldElement, VerifierError::InvalidPoint,
+/// VerifierError::InvalidSubgroupPoint
+///
+/// This function will return an error if the key is not a BN254 Groth16
+/// key, if one of its field elements fails to parse, or if a point is not
+/// on the curve or not in the prime order subgroup.
+pub fn compress_verification_key(
+    vkey: &VerificationKeyJson,
+) -> Result<CompressedVerifyingKey, VerifierError> {
+    let vk: ark_groth16::VerifyingKey<ark_bn254::Bn254> = vkey.try_into()?;
+    check_verifying_key(&vk)?;
+    Ok(vk.into())
+}
+
+/// Verifies a proof against a stored key. A well-formed proof that doesn't
+/// verify returns `Ok(false)`.
+///
+/// # Errors
+/// VerifierError::InvalidEncoding, VerifierError::InvalidProof,
+/// VerifierError::InvalidFieldElement, VerifierError::InvalidPoint,
+/// VerifierError::InvalidSubgroupPoint, VerifierError::InputCountMismatch
+///
+/// This function will return an error if the key fails to decompress, if
+/// the proof or the inputs fail to parse, if a proof point is invalid, or
+/// if the number of inputs doesn't match the key.
+pub fn verify_proof(
+    vk: &CompressedVerifyingKey,
+    proof: &ProofJson,
+    pub_inputs: &[String],
+) -> Result<bool, VerifierError> {
+    let vk = vk.decompress()?;
+    let proof: ark_groth16::Proof<ark_bn254::Bn254> = proof.try_into()?;
+    check_proof(&proof)?;
+    let pub_inputs: Vec<ark_bn254::Fr> = inputs_from_str(pub_inputs)?;
+    verify_key_with_backend(&ArkworksBackend, &vk, &proof, &pub_inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use electron_testkit::circom;
+
+    fn fixtures() -> (CompressedVerifyingKey, ProofJson, Vec<String>) {
+        let vkey = parse_verification_key(circom::vkey().as_bytes()).unwrap();
+        let proof = parse_proof(circom::proof().as_bytes()).unwrap();
+        let inputs = parse_public_inputs(circom::public_inputs().as_bytes()).unwrap();
+        (compress_verification_key(&vkey).unwrap(), proof, inputs)
+    }
+
+    #[test]
+    fn test_verify_proof() {
+        let (vk, proof, mut inputs) = fixtures();
+        assert!(verify_proof(&vk, &proof, &inputs).unwrap());
+
+        inputs[0] = "2".to_string();
+        assert!(!verify_proof(&vk, &proof, &inputs).unwrap());
+        inputs.pop();
+        assert!(matches!(
+            verify_proof(&vk, &proof, &inputs),
+            Err(VerifierError::InputCountMismatch { .. })
+        ));
+    }
+
+    #[cfg(feature = "near")]
+    #[test]
+    fn test_matches_near_verifier() {
+        let (vk, proof, inputs) = fixtures();
+        let expected = crate::verifier::near::prepare_verification_key(circom::vkey().to_string())
+            .unwrap()
+            .fingerprint();
+        let pvk: crate::verifier::core::PreparedVerifyingKey =
+            ark_groth16::prepare_verifying_key(&vk.decompress().unwrap()).into();
+        assert_eq!(pvk.fingerprint(), expected);
+        assert!(verify_proof(&vk, &proof, &inputs).unwrap());
+    }
+
+    #[test]
+    fn test_rejects_other_curves() {
+        let mut vkey = parse_verification_key(circom::vkey().as_bytes()).unwrap();
+        vkey.curve = "bls12381".to_string();
+        assert!(matches!(
+            compress_verification_key(&vkey),
+            Err(VerifierError::UnsupportedCurve(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_malformed_proof() {
+        let (vk, mut proof, inputs) = fixtures();
+        proof.pi_b.pop();
+        assert!(matches!(
+            verify_proof(&vk, &proof, &inputs),
+            Err(VerifierError::InvalidProof(_))
+        ));
+        let nested = format!("{}{}", "[".repeat(1000), "]".repeat(1000));
+        assert!(matches!(
+            parse_proof(nested.as_bytes()),
+            Err(JsonError::TooDeep(_, _))
+        ));
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright © 2022, Electron Labs
+
+//! Groth16 verification in ink! contracts on pallet-contracts.
+//!
+//! Keys, proofs and public inputs are SCALE encoded byte arrays in the
+//! EIP-197 layout of `verifier::core::eip197`, so they can be passed as
+//! message arguments and kept in contract storage as they are. Keys are
+//! stored unprepared, because pallet-contracts charges for storage by the
+//! byte.
+//!
+//! [`verify_proof`] computes the pairings in wasm. Chains that expose the
+//! `alt_bn128` pairing as a host function, usually through a chain
+//! extension, can hand it to [`verify_proof_with`] instead:
+//!
+//! ```ignore
+//! let host = HostPairing(|input: &[u8]| self.env().extension().bn128_pairing(input));
+//! verify_proof_with(&host, &self.vk, &proof, &inputs)
+//! ```
+
+use crate::verifier::core::backend::{verify_key_with_backend, ArkworksBackend, PairingBackend};
+use crate::verifier::core::eip197::{
+    decode_fr, decode_g1, decode_g2, encode_fr, encode_g1, encode_g2, encode_pairing_input, FP_LEN,
+    G1_LEN, G2_LEN,
+};
+use crate::verifier::core::VerifierError;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+
+/// A BN254 verifying key.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct InkVerifyingKey {
+    pub alpha_g1: [u8; G1_LEN],
+    pub beta_g2: [u8; G2_LEN],
+    pub gamma_g2: [u8; G2_LEN],
+    pub delta_g2: [u8; G2_LEN],
+    pub gamma_abc_g1: Vec<[u8; G1_LEN]>,
+}
+
+/// A BN254 Groth16 proof.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct InkProof {
+    pub a: [u8; G1_LEN],
+    pub b: [u8; G2_LEN],
+    pub c: [u8; G1_LEN],
+}
+
+/// A public input, as a big-endian scalar.
+pub type InkPublicInput = [u8; FP_LEN];
+
+impl From<&ark_groth16::VerifyingKey<ark_bn254::Bn254>> for InkVerifyingKey {
+    fn from(src: &ark_groth16::VerifyingKey<ark_bn254::Bn254>) -> Self {
+        InkVerifyingKey {
+            alpha_g1: encode_g1(&src.alpha_g1).try_into().unwrap(),
+            beta_g2: encode_g2(&src.beta_g2).try_into().unwrap(),
+            gamma_g2: encode_g2(&src.gamma_g2).try_into().unwrap(),
+            delta_g2: encode_g2(&src.delta_g2).try_into().unwrap(),
+            gamma_abc_g1: src
+                .gamma_abc_g1
+                .iter()
+                .map(|p| encode_g1(p).try_into().unwrap())
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<&InkVerifyingKey> for ark_groth16::VerifyingKey<ark_bn254::Bn254> {
+    type Error = VerifierError;
+
+    fn try_from(src: &InkVerifyingKey) -> Result<Self, VerifierError> {
+        Ok(ark_groth16::VerifyingKey {
+            alpha_g1: decode_g1(&src.alpha_g1)?,
+            beta_g2: decode_g2(&src.beta_g2)?,
+            gamma_g2: decode_g2(&src.gamma_g2)?,
+            delta_g2: decode_g2(&src.delta_g2)?,
+            gamma_abc_g1: src
+                .gamma_abc_g1
+                .iter()
+                .map(|p| decode_g1(p))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<&ark_groth16::Proof<ark_bn254::Bn254>> for InkProof {
+    fn from(src: &ark_groth16::Proof<ark_bn254::Bn254>) -> Self {
+        InkProof {
+            a: encode_g1(&src.a).try_into().unwrap(),
+            b: encode_g2(&src.b).try_into().unwrap(),
+            c: encode_g1(&src.c).try_into().unwrap(),
+        }
+    }
+}
+
+impl TryFrom<&InkProof> for ark_groth16::Proof<ark_bn254::Bn254> {
+    type Error = VerifierError;
+
+    fn try_from(src: &InkProof) -> Result<Self, VerifierError> {
+        Ok(ark_groth16::Proof {
+            a: decode_g1(&src.a)?,
+            b: decode_g2(&src.b)?,
+            c: decode_g1(&src.c)?,
+        })
+    }
+}
+
+/// Encodes public inputs for `verify_proof`.
+pub fn encode_public_inputs(pub_inputs: &[ark_bn254::Fr]) -> Vec<InkPublicInput> {
+    pub_inputs.iter().map(encode_fr).collect()
+}
+
+/// Pairing checks through a host function, which takes the EIP-197
+/// pairing input and returns whether the product of pairings is one.
+pub struct HostPairing<F: Fn(&[u8]) -> bool>(pub F);
+
+impl<F: Fn(&[u8]) -> bool> PairingBackend<ark_bn254::Bn254> for HostPairing<F> {
+    fn pairing_check(&self, pairs: &[(ark_bn254::G1Affine, ark_bn254::G2Affine)]) -> bool {
+        (self.0)(&encode_pairing_input(pairs))
+    }
+}
+
+/// Verifies a proof with the pairings computed in wasm.
+///
+/// # Errors
+/// VerifierError::InvalidEncoding, VerifierError::InputCountMismatch
+///
+/// This function will return an error if a point of the key or the proof
+/// is invalid, if a public input is not reduced, or if the number of
+/// inputs doesn't match the key.
+pub fn verify_proof(
+    vk: &InkVerifyingKey,
+    proof: &InkProof,
+    pub_inputs: &[InkPublicInput],
+) -> Result<bool, VerifierError> {
+    verify_proof_with(&ArkworksBackend, vk, proof, pub_inputs)
+}
+
+/// Like `verify_proof`, with the pairing check done by `backend`, for
+/// instance a `HostPairing`.
+///
+/// # Errors
+/// VerifierError::InvalidEncoding, VerifierError::InputCountMismatch
+pub fn verify_proof_with<B: PairingBackend<ark_bn254::Bn254> + ?Sized>(
+    backend: &B,
+    vk: &InkVerifyingKey,
+    proof: &InkProof,
+    pub_inputs: &[InkPublicInput],
+) -> Result<bool, VerifierError> {
+    let vk: ark_groth16::VerifyingKey<ark_bn254::Bn254> = vk.try_into()?;
+    let proof: ark_groth16::Proof<ark_bn254::Bn254> = proof.try_into()?;
+    let pub_inputs: Vec<ark_bn254::Fr> = pub_inputs
+        .iter()
+        .map(|input| decode_fr(input))
+        .collect::<Result<_, _>>()?;
+    verify_key_with_backend(backend, &vk, &proof, &pub_inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::test_circuit;
+    use std::cell::Cell;
+
+    fn setup() -> (InkVerifyingKey, InkProof, Vec<InkPublicInput>) {
+        let (vk, proof, c) = test_circuit::prove::<ark_bn254::Bn254>(3, 5);
+        let vk = InkVerifyingKey::from(&vk);
+        let vk = InkVerifyingKey::decode(&mut vk.encode().as_slice()).unwrap();
+        (vk, InkProof::from(&proof), encode_public_inputs(&[c]))
+    }
+
+    #[test]
+    fn test_verify_proof() {
+        let (vk, proof, inputs) = setup();
+        assert!(verify_proof(&vk, &proof, &inputs).unwrap());
+        let wrong = encode_public_inputs(&[ark_bn254::Fr::from(16u64)]);
+        assert!(!verify_proof(&vk, &proof, &wrong).unwrap());
+        assert!(verify_proof(&vk, &proof, &[]).is_err());
+        assert!(verify_proof(&vk, &proof, &[[0xff; FP_LEN]]).is_err());
+    }
+
+    #[test]
+    fn test_host_pairing() {
+        let (vk, proof, inputs) = setup();
+        let calls = Cell::new(0);
+        // Stands in for a host function by decoding the input again.
+        let host = HostPairing(|input: &[u8]| {
+            calls.set(calls.get() + 1);
+            let pairs: Vec<_> = input
+                .chunks(G1_LEN + G2_LEN)
+                .map(|pair| {
+                    (
+                        decode_g1(&pair[..G1_LEN]).unwrap(),
+                        decode_g2(&pair[G1_LEN..]).unwrap(),
+                    )
+                })
+                .collect();
+            ArkworksBackend.pairing_check(&pairs)
+        });
+        assert!(verify_proof_with(&host, &vk, &proof, &inputs).unwrap());
+        assert_eq!(calls.get(), 1);
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright © 2022, Electron Labs
+
+//! Nesting and size limits for json arguments.
+//!
+//! serde_json_wasm parses nested arrays and objects recursively, so a
+//! payload of a few thousand `[` exhausts the wasm stack and aborts the
+//! contract instead of returning an error. Every json entry point of the
+//! crate first scans its argument with [`JsonLimits::DEFAULT`], which
+//! rejects such payloads before the parser sees them. Contracts taking
+//! other json arguments can check them with their own limits:
+//!
+//! ```
+//! use electron_rs::verifier::json::JsonLimits;
+//!
+//! let limits = JsonLimits { max_depth: 2, max_len: 64 };
+//! assert!(limits.check(br#"{"a": ["1"]}"#, "argument").is_ok());
+//! assert!(limits.check(b"[[[1]]]", "argument").is_err());
+//! ```
+
+use crate::verifier::core::VerifierError;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+#[cfg(not(feature = "serde-json-wasm"))]
+use serde_json as backend;
+#[cfg(feature = "serde-json-wasm")]
+use serde_json_wasm as backend;
+use thiserror::Error;
+
+/// Errors of the json entry points: a payload over the limits, or the
+/// parse error of the core.
+#[derive(Error, Debug)]
+pub enum JsonError {
+    #[error("{0} json nests deeper than {1} levels")]
+    TooDeep(String, usize),
+    #[error("{0} json is longer than {1} bytes")]
+    TooLarge(String, usize),
+    #[error(transparent)]
+    Verifier(#[from] VerifierError),
+}
+
+/// Bounds on the json a parser accepts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JsonLimits {
+    /// Maximum number of nested arrays and objects.
+    pub max_depth: usize,
+    /// Maximum length in bytes.
+    pub max_len: usize,
+}
+
+impl JsonLimits {
+    /// The limits of every parser in the crate. Circom keys nest four
+    /// levels deep, and a key with 512 public inputs takes about 130 KB.
+    pub const DEFAULT: JsonLimits = JsonLimits {
+        max_depth: 16,
+        max_len: 1 << 20,
+    };
+
+    /// Checks the length and nesting depth of `json` without parsing it.
+    /// Brackets inside strings are ignored; malformed json is left for the
+    /// parser to reject.
+    ///
+    /// # Errors
+    /// JsonError::TooLarge, JsonError::TooDeep
+    pub fn check(&self, json: &[u8], what: &str) -> Result<(), JsonError> {
+        if json.len() > self.max_len {
+            return Err(JsonError::TooLarge(what.to_string(), self.max_len));
+        }
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+        for byte in json {
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match byte {
+                b'"' => in_string = true,
+                b'[' | b'{' => {
+                    depth += 1;
+                    if depth > self.max_depth {
+                        return Err(JsonError::TooDeep(what.to_string(), self.max_depth));
+                    }
+                }
+                b']' | b'}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for JsonLimits {
+    fn default() -> Self {
+        JsonLimits::DEFAULT
+    }
+}
+
+/// Checks `json` against the default limits and parses it, reporting parse
+/// failures as `VerifierError::ParseError(what)`.
+pub(crate) fn from_slice<T: DeserializeOwned>(json: &[u8], what: &str) -> Result<T, JsonError> {
+    JsonLimits::DEFAULT.check(json, what)?;
+    backend::from_slice(json).map_err(|_| VerifierError::ParseError(what.to_string()).into())
+}
+
+/// Serializes `value`, reporting failures as `VerifierError::ParseError(what)`.
+pub(crate) fn to_string<T: Serialize + ?Sized>(
+    value: &T,
+    what: &str,
+) -> Result<String, VerifierError> {
+    backend::to_string(value).map_err(|_| VerifierError::ParseError(what.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nested(depth: usize) -> String {
+        format!("{}{}", "[".repeat(depth), "]".repeat(depth))
+    }
+
+    #[test]
+    fn test_check_depth() {
+        let limits = JsonLimits::DEFAULT;
+        assert!(limits.check(nested(16).as_bytes(), "test").is_ok());
+        assert!(matches!(
+            limits.check(nested(17).as_bytes(), "test"),
+            Err(JsonError::TooDeep(_, 16))
+        ));
+        // Brackets in strings, including after escaped quotes, don't nest.
+        let quoted = format!(r#"["\"{}"]"#, "[".repeat(100));
+        assert!(limits.check(quoted.as_bytes(), "test").is_ok());
+    }
+
+    #[test]
+    fn test_check_len() {
+        let limits = JsonLimits {
+            max_depth: 16,
+            max_len: 8,
+        };
+        assert!(limits.check(b"[\"1234\"]", "test").is_ok());
+        assert!(matches!(
+            limits.check(b"[\"12345\"]", "test"),
+            Err(JsonError::TooLarge(_, 8))
+        ));
+    }
+}
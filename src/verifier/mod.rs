@@ -1,3 +1,15 @@
 // Copyright © 2022, Electron Labs
 
+#[cfg(any(feature = "near", feature = "native", feature = "cosmwasm"))]
+pub(crate) mod circom;
+pub mod core;
+#[cfg(feature = "cosmwasm")]
+pub mod cosmwasm;
+#[cfg(feature = "ink")]
+pub mod ink;
+#[cfg(any(feature = "near", feature = "native", feature = "cosmwasm"))]
+pub mod json;
+#[cfg(any(feature = "near", feature = "native"))]
 pub mod near;
+#[cfg(feature = "solana")]
+pub mod solana;
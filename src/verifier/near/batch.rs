@@ -0,0 +1,204 @@
+// Copyright © 2022, Electron Labs
+
+//! Batch verification of circom proofs against one key.
+
+use super::{inputs_from_str, parse_proof, parse_public_inputs};
+use super::{PreparedVerifyingKey, VerifierError};
+use crate::verifier::core::batch::verify_batch_items;
+use crate::verifier::core::RandomnessSource;
+use crate::verifier::json::JsonError;
+use anyhow::Result;
+use near_sdk::env;
+use sha2::{Digest, Sha256};
+
+pub use crate::verifier::core::batch::BatchResult;
+
+/// The block random seed, the randomness source of contracts.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EnvSeed;
+
+impl RandomnessSource for EnvSeed {
+    fn random_seed(&self) -> [u8; 32] {
+        env::random_seed_array()
+    }
+}
+
+type ParsedItem = (ark_groth16::Proof<ark_bn254::Bn254>, Vec<ark_bn254::Fr>);
+
+fn parse_item(proof_str: String, inputs_str: String) -> Result<ParsedItem, VerifierError> {
+    let parsed = parse_proof(proof_str).and_then(|proof| {
+        let pub_inputs: Vec<ark_bn254::Fr> = inputs_from_str(&parse_public_inputs(inputs_str)?)?;
+        Ok((proof, pub_inputs))
+    });
+    parsed.map_err(|e| match e.downcast::<JsonError>() {
+        Ok(JsonError::Verifier(e)) => e,
+        Ok(JsonError::TooDeep(what, _) | JsonError::TooLarge(what, _)) => {
+            VerifierError::ParseError(what)
+        }
+        Err(e) => e
+            .downcast::<VerifierError>()
+            .unwrap_or_else(|e| VerifierError::ParseError(e.to_string())),
+    })
+}
+
+/// Verifies `proofs[i]` against `inputs[i]` for every `i` and reports the
+/// outcome of each, so that the valid proofs of a batch can be settled
+/// while the invalid ones are rejected. Proofs that fail to parse fail
+/// with the parse error. The others are checked with a single pairing
+/// check and, if the batch fails, one by one.
+///
+/// The batch scalars are derived from the block random seed and the
+/// submitted proofs and inputs, so a prover can't choose proofs that
+/// cancel each other out. `verify_proofs_batch_with` takes the seed from
+/// another source.
+///
+/// # Errors
+/// VerifierError::BatchLengthMismatch
+///
+/// This function will return an error if the numbers of proofs and inputs
+/// differ.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::batch::verify_proofs_batch;
+/// use electron_rs::verifier::near::prepare_verification_key;
+/// use electron_testkit::circom;
+///
+/// let pvk = prepare_verification_key(circom::vkey().to_string()).unwrap();
+/// let proofs = vec![circom::proof().to_string(); 2];
+/// let inputs = vec![
+///     circom::public_inputs().to_string(),
+///     circom::public_inputs().replacen("\"1\"", "\"2\"", 1),
+/// ];
+/// let res = verify_proofs_batch(&pvk, proofs, inputs).unwrap();
+/// assert_eq!(res.passed, vec![0]);
+/// assert_eq!(res.failed[0].0, 1);
+/// ```
+pub fn verify_proofs_batch(
+    pvk: &PreparedVerifyingKey,
+    proofs: Vec<String>,
+    inputs: Vec<String>,
+) -> Result<BatchResult> {
+    verify_proofs_batch_with(&EnvSeed, pvk, proofs, inputs)
+}
+
+/// `verify_proofs_batch` with the seed taken from `source`, for relayers
+/// and other off-chain callers, which pass `OsRandomness`.
+///
+/// # Errors
+/// VerifierError::BatchLengthMismatch
+pub fn verify_proofs_batch_with<R: RandomnessSource + ?Sized>(
+    source: &R,
+    pvk: &PreparedVerifyingKey,
+    proofs: Vec<String>,
+    inputs: Vec<String>,
+) -> Result<BatchResult> {
+    if proofs.len() != inputs.len() {
+        return Err(VerifierError::BatchLengthMismatch(proofs.len(), inputs.len()).into());
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(source.random_seed());
+    let mut parse_failures = Vec::new();
+    let mut indices = Vec::with_capacity(proofs.len());
+    let mut batch = Vec::with_capacity(proofs.len());
+    for (i, (proof_str, inputs_str)) in proofs.into_iter().zip(inputs).enumerate() {
+        hasher.update(proof_str.as_bytes());
+        hasher.update(inputs_str.as_bytes());
+        match parse_item(proof_str, inputs_str) {
+            Ok(item) => {
+                indices.push(i);
+                batch.push(item);
+            }
+            Err(e) => parse_failures.push((i, e)),
+        }
+    }
+    let seed: [u8; 32] = hasher.finalize().into();
+
+    let verified = verify_batch_items::<ark_bn254::Bn254>(pvk, &batch, &seed);
+    let mut result = BatchResult {
+        passed: verified.passed.into_iter().map(|i| indices[i]).collect(),
+        failed: parse_failures,
+    };
+    result
+        .failed
+        .extend(verified.failed.into_iter().map(|(i, e)| (indices[i], e)));
+    result.failed.sort_by_key(|(i, _)| *i);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures;
+    use super::*;
+    use crate::verifier::core::FixedSeed;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn test_verify_proofs_batch() {
+        testing_env!(VMContextBuilder::new().random_seed([7; 32]).build());
+        let pvk = fixtures::pvk();
+        let proofs = vec![fixtures::proof().to_string(); 3];
+        let inputs = vec![fixtures::public_inputs().to_string(); 3];
+        let result = verify_proofs_batch(&pvk, proofs, inputs).unwrap();
+        assert!(result.all_passed());
+        assert_eq!(result.passed, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_verify_proofs_batch_reports_each_proof() {
+        testing_env!(VMContextBuilder::new().random_seed([7; 32]).build());
+        let pvk = fixtures::pvk();
+        let proofs = vec![fixtures::proof().to_string(); 4];
+        let mut inputs = vec![fixtures::public_inputs().to_string(); 4];
+        inputs[0] = "[\"1\"".to_string();
+        inputs[2] = inputs[2].replacen("\"1\"", "\"2\"", 1);
+        let result = verify_proofs_batch(&pvk, proofs, inputs).unwrap();
+        assert_eq!(result.passed, vec![1, 3]);
+        let failed: Vec<(usize, String)> = result
+            .failed
+            .iter()
+            .map(|(i, e)| (*i, e.to_string()))
+            .collect();
+        assert_eq!(
+            failed,
+            vec![
+                (0, "Failed to parse circom public inputs json".to_string()),
+                (2, "Proof does not verify".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_proofs_batch_with_fixed_seed() {
+        let pvk = fixtures::pvk();
+        let proofs = vec![fixtures::proof().to_string(); 2];
+        let mut inputs = vec![fixtures::public_inputs().to_string(); 2];
+        inputs[1] = inputs[1].replacen("\"1\"", "\"2\"", 1);
+        let result = verify_proofs_batch_with(&FixedSeed([7; 32]), &pvk, proofs, inputs).unwrap();
+        assert_eq!(result.passed, vec![0]);
+        assert_eq!(result.failed[0].0, 1);
+    }
+
+    #[test]
+    fn test_env_seed() {
+        testing_env!(VMContextBuilder::new().random_seed([9; 32]).build());
+        assert_eq!(EnvSeed.random_seed(), [9; 32]);
+    }
+
+    #[test]
+    fn test_verify_proofs_batch_length_mismatch() {
+        let pvk = fixtures::pvk();
+        let proofs = vec![fixtures::proof().to_string(); 2];
+        let inputs = vec![fixtures::public_inputs().to_string()];
+        assert_eq!(
+            verify_proofs_batch(&pvk, proofs, inputs)
+                .err()
+                .unwrap()
+                .to_string(),
+            "Batch has 2 proofs and 1 sets of public inputs"
+        );
+    }
+}
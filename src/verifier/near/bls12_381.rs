@@ -0,0 +1,253 @@
+// Copyright © 2022, Electron Labs
+
+//! Verification of circom proofs over BLS12-381 (curve `bls12381` in
+//! snarkjs). The vkey and proof json have the same layout as for bn128,
+//! only the field elements are wider.
+
+use super::{coordinate, field_from_str, inputs_from_str, parse_circom_proof, parse_public_inputs};
+use super::{CircomProofJson, PointChecks, VerificationKeyJson};
+use crate::verifier::core::bls12_381::{check_proof, check_verifying_key, verify_prepared};
+use crate::verifier::core::VerifierError;
+use anyhow::Result;
+use ark_ff::PrimeField;
+
+pub use crate::verifier::core::bls12_381::PreparedVerifyingKey;
+
+/// Curve name snarkjs writes into BLS12-381 vkey and proof json.
+pub const CURVE: &str = "bls12381";
+
+/// A helper function to parse verification key json into a prepared
+/// BLS12-381 verifying key.
+///
+/// # Errors
+/// VerifierError::UnsupportedCurve, VerifierError::InvalidFieldElement
+///
+/// This function will return an error if the key was not generated for
+/// BLS12-381, or one of its field elements fails to parse.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::{bls12_381, parse_verification_key};
+///
+/// // A bn128 key is rejected.
+/// let vkey = parse_verification_key(electron_testkit::circom::vkey().to_string()).unwrap();
+/// assert!(bls12_381::get_prepared_verifying_key(vkey).is_err());
+/// ```
+pub fn get_prepared_verifying_key(vkey: VerificationKeyJson) -> Result<PreparedVerifyingKey> {
+    if vkey.curve != CURVE {
+        return Err(VerifierError::UnsupportedCurve(vkey.curve).into());
+    }
+    let parse_vkey: ark_groth16::VerifyingKey<ark_bls12_381::Bls12_381> = vkey.try_into()?;
+    Ok(ark_groth16::prepare_verifying_key(&parse_vkey).into())
+}
+
+/// A helper function to verify a BLS12-381 proof
+///
+/// # Errors
+/// VerifierError::ParseError, VerifierError::InvalidProof,
+/// VerifierError::InvalidFieldElement, VerifierError::InvalidPoint,
+/// VerifierError::InvalidSubgroupPoint, VerifierError::SynthesisError
+///
+/// This function will return an error if the proof or public inputs fail to
+/// parse, or if the proof can't be checked against the verifying key. A
+/// well-formed proof that doesn't verify returns `Ok(false)`.
+pub fn verify_proof(
+    pvk: PreparedVerifyingKey,
+    proof_str: String,
+    pub_inputs_str: String,
+) -> Result<bool> {
+    let proof: ark_groth16::Proof<ark_bls12_381::Bls12_381> =
+        parse_circom_proof(proof_str)?.try_into()?;
+    check_proof(&proof)?;
+    let pub_inputs = parse_public_inputs(pub_inputs_str)?;
+    let ark_pub_inputs: Vec<ark_bls12_381::Fr> = inputs_from_str(&pub_inputs)?;
+
+    Ok(verify_prepared(&pvk, &proof, &ark_pub_inputs)?)
+}
+
+/// Parses the field elements of a BLS12-381 key and checks its points if
+/// asked to, for `parse_verification_key`.
+pub(crate) fn check_vkey(
+    vkey: &VerificationKeyJson,
+    checks: PointChecks,
+) -> Result<(), VerifierError> {
+    let parsed: ark_groth16::VerifyingKey<ark_bls12_381::Bls12_381> = vkey.clone().try_into()?;
+    if checks == PointChecks::Full {
+        check_verifying_key(&parsed)?;
+    }
+    Ok(())
+}
+
+/// Parses a scalar written in decimal or `0x` hex.
+///
+/// # Errors
+/// VerifierError::InvalidFieldElement
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::bls12_381::{fr_from_str, fr_to_string};
+///
+/// let fr = fr_from_str("0x10".to_string()).unwrap();
+/// assert_eq!(fr_to_string(fr), "16");
+/// ```
+pub fn fr_from_str(s: String) -> Result<ark_bls12_381::Fr, VerifierError> {
+    field_from_str(&s, || format!("{:?}", s))
+}
+
+/// Decimal string of a field element, as used in circom json.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::bls12_381::{fr_from_str, fr_to_string};
+///
+/// let fr = fr_from_str("42".to_string()).unwrap();
+/// assert_eq!(fr_to_string(fr), "42");
+/// ```
+pub fn fr_to_string(fr: ark_bls12_381::Fr) -> String {
+    super::repr_to_string(&fr.into_repr().0)
+}
+
+fn fq_from_str(g: &[String], name: &str, index: usize) -> Result<ark_bls12_381::Fq, VerifierError> {
+    field_from_str(coordinate(g, index), || format!("{}[{}]", name, index))
+}
+
+fn g1_from_str(g1: &[String], name: &str) -> Result<ark_bls12_381::G1Affine, VerifierError> {
+    let x = fq_from_str(g1, name, 0)?;
+    let y = fq_from_str(g1, name, 1)?;
+    let z = fq_from_str(g1, name, 2)?;
+    Ok(ark_bls12_381::G1Affine::from(
+        ark_bls12_381::G1Projective::new(x, y, z),
+    ))
+}
+
+fn fq2_from_str(
+    g2: &[Vec<String>],
+    name: &str,
+    index: usize,
+) -> Result<ark_bls12_381::Fq2, VerifierError> {
+    let coords = g2.get(index).map(Vec::as_slice).unwrap_or(&[]);
+    let name = format!("{}[{}]", name, index);
+    let c0 = fq_from_str(coords, &name, 0)?;
+    let c1 = fq_from_str(coords, &name, 1)?;
+    Ok(ark_bls12_381::Fq2::new(c0, c1))
+}
+
+fn g2_from_str(g2: &[Vec<String>], name: &str) -> Result<ark_bls12_381::G2Affine, VerifierError> {
+    let x = fq2_from_str(g2, name, 0)?;
+    let y = fq2_from_str(g2, name, 1)?;
+    let z = fq2_from_str(g2, name, 2)?;
+    Ok(ark_bls12_381::G2Affine::from(
+        ark_bls12_381::G2Projective::new(x, y, z),
+    ))
+}
+
+impl TryFrom<CircomProofJson> for ark_groth16::Proof<ark_bls12_381::Bls12_381> {
+    type Error = VerifierError;
+
+    fn try_from(src: CircomProofJson) -> Result<Self, VerifierError> {
+        Ok(ark_groth16::Proof {
+            a: g1_from_str(&src.pi_a, "pi_a")?,
+            b: g2_from_str(&src.pi_b, "pi_b")?,
+            c: g1_from_str(&src.pi_c, "pi_c")?,
+        })
+    }
+}
+
+impl TryFrom<VerificationKeyJson> for ark_groth16::VerifyingKey<ark_bls12_381::Bls12_381> {
+    type Error = VerifierError;
+
+    fn try_from(src: VerificationKeyJson) -> Result<Self, VerifierError> {
+        Ok(ark_groth16::VerifyingKey {
+            alpha_g1: g1_from_str(&src.vk_alpha_1, "vk_alpha_1")?,
+            beta_g2: g2_from_str(&src.vk_beta_2, "vk_beta_2")?,
+            gamma_g2: g2_from_str(&src.vk_gamma_2, "vk_gamma_2")?,
+            delta_g2: g2_from_str(&src.vk_delta_2, "vk_delta_2")?,
+            gamma_abc_g1: src
+                .ic
+                .iter()
+                .enumerate()
+                .map(|(i, x)| g1_from_str(x, &format!("IC[{}]", i)))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Renders BLS12-381 keys and proofs in the snarkjs json layout.
+#[cfg(test)]
+pub(crate) mod test_json {
+    use super::fr_to_string;
+    use crate::verifier::core::test_circuit;
+    use crate::verifier::near::repr_to_string;
+    use ark_ff::PrimeField;
+
+    fn fq_to_string(fq: ark_bls12_381::Fq) -> String {
+        repr_to_string(&fq.into_repr().0)
+    }
+
+    fn g1_json(p: &ark_bls12_381::G1Affine) -> String {
+        format!(r#"["{}", "{}", "1"]"#, fq_to_string(p.x), fq_to_string(p.y))
+    }
+
+    fn g2_json(p: &ark_bls12_381::G2Affine) -> String {
+        format!(
+            r#"[["{}", "{}"], ["{}", "{}"], ["1", "0"]]"#,
+            fq_to_string(p.x.c0),
+            fq_to_string(p.x.c1),
+            fq_to_string(p.y.c0),
+            fq_to_string(p.y.c1)
+        )
+    }
+
+    // Renders a key and proof in the snarkjs json layout.
+    pub(crate) fn circom_json(curve: &str) -> (String, String, String) {
+        let (vk, proof, c) = test_circuit::prove::<ark_bls12_381::Bls12_381>(3, 5);
+        let ic: Vec<String> = vk.gamma_abc_g1.iter().map(g1_json).collect();
+        let vkey = format!(
+            r#"{{"protocol": "groth16", "curve": "{}", "nPublic": 1, "vk_alpha_1": {},
+            "vk_beta_2": {}, "vk_gamma_2": {}, "vk_delta_2": {}, "vk_alphabeta_12": [],
+            "IC": [{}]}}"#,
+            curve,
+            g1_json(&vk.alpha_g1),
+            g2_json(&vk.beta_g2),
+            g2_json(&vk.gamma_g2),
+            g2_json(&vk.delta_g2),
+            ic.join(", ")
+        );
+        let proof = format!(
+            r#"{{"pi_a": {}, "pi_b": {}, "pi_c": {}, "protocol": "groth16", "curve": "{}"}}"#,
+            g1_json(&proof.a),
+            g2_json(&proof.b),
+            g1_json(&proof.c),
+            curve
+        );
+        let inputs = format!(r#"["{}"]"#, fr_to_string(c));
+        (vkey, proof, inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_json::circom_json;
+    use super::*;
+    use crate::verifier::near::parse_verification_key;
+
+    #[test]
+    fn test_valid_proof() {
+        let (vkey, proof, inputs) = circom_json(CURVE);
+        let vkey = parse_verification_key(vkey).unwrap();
+        let pvk = get_prepared_verifying_key(vkey).unwrap();
+        assert!(verify_proof(pvk.clone(), proof.clone(), inputs).unwrap());
+        assert!(!verify_proof(pvk, proof, r#"["16"]"#.to_string()).unwrap());
+    }
+
+    #[test]
+    fn test_wrong_curve() {
+        let (vkey, _, _) = circom_json("bn128");
+        let vkey = parse_verification_key(vkey).unwrap();
+        let res = get_prepared_verifying_key(vkey);
+        assert_eq!(res.err().unwrap().to_string(), "Unsupported curve bn128");
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright © 2022, Electron Labs
+
+use serde::Serialize;
+
+/// Version of the borsh layout used for `PreparedVerifyingKey` and the
+/// other wrapper types stored on chain.
+pub const SERIALIZATION_VERSION: u32 = 1;
+
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+pub struct VerifierCapabilities {
+    pub crate_version: String,
+    pub serialization_version: u32,
+    pub curves: Vec<String>,
+    pub protocols: Vec<String>,
+    pub precompile: bool,
+}
+
+/// Describes what this build of the verifier supports. Contracts can
+/// return this from a view method so tooling can adapt to a deployed
+/// verifier.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::capabilities::verifier_capabilities;
+///
+/// let caps = verifier_capabilities();
+/// assert!(caps.protocols.contains(&"groth16".to_string()));
+/// ```
+pub fn verifier_capabilities() -> VerifierCapabilities {
+    VerifierCapabilities {
+        crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        serialization_version: SERIALIZATION_VERSION,
+        curves: vec!["bn128".to_string(), "bls12381".to_string()],
+        protocols: vec!["groth16".to_string()],
+        precompile: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verifier_capabilities() {
+        let caps = verifier_capabilities();
+        assert_eq!(caps.crate_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(caps.curves, vec!["bn128", "bls12381"]);
+        assert_eq!(caps.protocols, vec!["groth16"]);
+        assert!(!caps.precompile);
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright © 2022, Electron Labs
+
+//! Resumable Groth16 verification.
+//!
+//! The miller loop is split into one step per bit of the ate loop count plus
+//! a final step for the two frobenius lines. The Fq12 accumulator and the
+//! position in the line coefficients are kept in a borsh-serializable
+//! [`ChunkedVerification`], so a verification that doesn't fit in one
+//! transaction can be resumed in the next one.
+
+use super::{inputs_from_str, parse_proof, parse_public_inputs};
+use crate::verifier::core::{
+    check_input_count, Fq12, G1Affine, G2Affine, PreparedVerifyingKey, VerifierError,
+};
+use anyhow::Result;
+use ark_ec::bn::BnParameters;
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, One};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+type EllCoeff = (ark_bn254::Fq2, ark_bn254::Fq2, ark_bn254::Fq2);
+type G2Prepared = ark_ec::bn::G2Prepared<ark_bn254::Parameters>;
+
+const ATE_LOOP_COUNT: &[i8] = <ark_bn254::Parameters as BnParameters>::ATE_LOOP_COUNT;
+
+/// Total number of steps in the miller loop.
+pub const MILLER_LOOP_STEPS: u32 = ATE_LOOP_COUNT.len() as u32;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct ChunkedVerification {
+    a: G1Affine,
+    b: G2Affine,
+    c: G1Affine,
+    prepared_inputs: G1Affine,
+    f: Fq12,
+    step: u32,
+    coeff_index: u32,
+}
+
+impl ChunkedVerification {
+    /// Parses the proof and public inputs and prepares the inputs for a
+    /// resumable verification.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the proof or public inputs fail
+    /// to parse, or if the inputs don't match the verification key.
+    pub fn new(
+        pvk: &PreparedVerifyingKey,
+        proof_str: String,
+        pub_inputs_str: String,
+    ) -> Result<Self> {
+        let proof = parse_proof(proof_str)?;
+        let pub_inputs = parse_public_inputs(pub_inputs_str)?;
+        check_input_count(pvk.vk.gamma_abc_g1.len(), pub_inputs.len())?;
+        let ark_pub_inputs: Vec<ark_bn254::Fr> = inputs_from_str(&pub_inputs)?;
+
+        let ark_pvk: ark_groth16::PreparedVerifyingKey<ark_bn254::Bn254> = pvk.clone().into();
+        let prepared_inputs = ark_groth16::prepare_inputs(&ark_pvk, &ark_pub_inputs)
+            .map_err(|e| VerifierError::SynthesisError(format!("{:?}", e)))?;
+
+        Ok(Self::from_prepared_inputs(
+            &proof,
+            prepared_inputs.into_affine(),
+        ))
+    }
+
+    /// Starts the miller loop for a proof whose inputs are already folded
+    /// into one point.
+    pub(crate) fn from_prepared_inputs(
+        proof: &ark_groth16::Proof<ark_bn254::Bn254>,
+        prepared_inputs: ark_bn254::G1Affine,
+    ) -> Self {
+        ChunkedVerification {
+            a: proof.a.into(),
+            b: proof.b.into(),
+            c: proof.c.into(),
+            prepared_inputs: prepared_inputs.into(),
+            f: ark_bn254::Fq12::one().into(),
+            step: 0,
+            coeff_index: 0,
+        }
+    }
+
+    /// Number of miller loop steps already processed.
+    pub fn step(&self) -> u32 {
+        self.step
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.step == MILLER_LOOP_STEPS
+    }
+
+    /// Runs at most `max_steps` further steps of the miller loop and returns
+    /// whether the loop is complete.
+    pub fn resume(&mut self, pvk: &PreparedVerifyingKey, max_steps: u32) -> bool {
+        let pairs = self.pairs(pvk);
+        let mut f: ark_bn254::Fq12 = self.f.clone().into();
+        let mut coeff_index = self.coeff_index as usize;
+
+        let mut remaining = max_steps;
+        while !self.is_done() && remaining > 0 {
+            let step = self.step as usize;
+            if step < ATE_LOOP_COUNT.len() - 1 {
+                let i = ATE_LOOP_COUNT.len() - 1 - step;
+                if step != 0 {
+                    f.square_in_place();
+                }
+                ell_pairs(&mut f, &pairs, coeff_index);
+                coeff_index += 1;
+                if ATE_LOOP_COUNT[i - 1] != 0 {
+                    ell_pairs(&mut f, &pairs, coeff_index);
+                    coeff_index += 1;
+                }
+            } else {
+                if <ark_bn254::Parameters as BnParameters>::X_IS_NEGATIVE {
+                    f.conjugate();
+                }
+                ell_pairs(&mut f, &pairs, coeff_index);
+                ell_pairs(&mut f, &pairs, coeff_index + 1);
+                coeff_index += 2;
+            }
+            self.step += 1;
+            remaining -= 1;
+        }
+
+        self.f = f.into();
+        self.coeff_index = coeff_index as u32;
+        self.is_done()
+    }
+
+    /// Runs the final exponentiation and checks the result against the key.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the miller loop hasn't been run
+    /// to completion.
+    pub fn finish(&self, pvk: &PreparedVerifyingKey) -> Result<bool> {
+        if !self.is_done() {
+            return Err(VerifierError::IncompleteMillerLoop.into());
+        }
+        let f: ark_bn254::Fq12 = self.f.clone().into();
+        let test = ark_bn254::Bn254::final_exponentiation(&f)
+            .ok_or_else(|| VerifierError::SynthesisError("UnexpectedIdentity".to_string()))?;
+        let expected: ark_bn254::Fq12 = pvk.alpha_g1_beta_g2.clone().into();
+        Ok(test == expected)
+    }
+
+    fn pairs(&self, pvk: &PreparedVerifyingKey) -> Vec<(ark_bn254::G1Affine, G2Prepared)> {
+        let b: ark_bn254::G2Affine = self.b.clone().into();
+        vec![
+            (self.a.clone().into(), b.into()),
+            (
+                self.prepared_inputs.clone().into(),
+                pvk.gamma_g2_neg_pc.clone().into(),
+            ),
+            (self.c.clone().into(), pvk.delta_g2_neg_pc.clone().into()),
+        ]
+        .into_iter()
+        .filter(|(p, q): &(ark_bn254::G1Affine, G2Prepared)| !p.infinity && !q.infinity)
+        .collect()
+    }
+}
+
+fn ell_pairs(f: &mut ark_bn254::Fq12, pairs: &[(ark_bn254::G1Affine, G2Prepared)], index: usize) {
+    for (p, q) in pairs {
+        ell(f, &q.ell_coeffs[index], p);
+    }
+}
+
+// BN254 uses a D-type twist, so lines are sparse in the 0, 3 and 4 slots.
+fn ell(f: &mut ark_bn254::Fq12, coeffs: &EllCoeff, p: &ark_bn254::G1Affine) {
+    let mut c0 = coeffs.0;
+    let mut c1 = coeffs.1;
+    c0.mul_assign_by_fp(&p.y);
+    c1.mul_assign_by_fp(&p.x);
+    f.mul_by_034(&c0, &c1, &coeffs.2);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures;
+    use super::*;
+
+    #[test]
+    fn test_chunked_verification() {
+        let pvk = fixtures::pvk();
+        let mut chunked = ChunkedVerification::new(
+            &pvk,
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string(),
+        )
+        .unwrap();
+
+        while !chunked.resume(&pvk, 10) {
+            let bytes = chunked.try_to_vec().unwrap();
+            chunked = ChunkedVerification::try_from_slice(&bytes).unwrap();
+        }
+        assert_eq!(chunked.step(), MILLER_LOOP_STEPS);
+        assert!(chunked.finish(&pvk).unwrap());
+    }
+
+    #[test]
+    fn test_chunked_verification_wrong_input() {
+        let pvk = fixtures::pvk();
+        let inputs = fixtures::public_inputs().replacen("\"1\"", "\"2\"", 1);
+        let mut chunked =
+            ChunkedVerification::new(&pvk, fixtures::proof().to_string(), inputs).unwrap();
+        assert!(chunked.resume(&pvk, MILLER_LOOP_STEPS));
+        assert!(!chunked.finish(&pvk).unwrap());
+    }
+
+    #[test]
+    fn test_finish_incomplete() {
+        let pvk = fixtures::pvk();
+        let mut chunked = ChunkedVerification::new(
+            &pvk,
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string(),
+        )
+        .unwrap();
+        assert!(!chunked.resume(&pvk, 1));
+        assert!(chunked.finish(&pvk).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright © 2022, Electron Labs
+
+//! Gas estimates for verification, so relayers can attach enough gas.
+//!
+//! The estimate is a linear model in the stored key size, the proof json
+//! size and the number of public inputs. The default coefficients are
+//! conservative; contracts that know their costs better can calibrate a
+//! [`CostModel`] from their own gas profiles and the scaling shown by
+//! `cargo bench --bench verifier`.
+
+use super::PreparedVerifyingKey;
+use borsh::BorshSerialize;
+use near_sdk::serde::Serialize;
+use near_sdk::Gas;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CostModel {
+    /// Gas for the pairing check, independent of the inputs.
+    pub base: u64,
+    /// Gas per public input, for parsing it and multiplying it into the
+    /// prepared inputs.
+    pub per_input: u64,
+    /// Gas per byte of proof json.
+    pub per_proof_byte: u64,
+    /// Gas per byte of a prepared key read from storage.
+    pub per_key_byte: u64,
+}
+
+pub const DEFAULT_COST_MODEL: CostModel = CostModel {
+    base: 150_000_000_000_000,
+    per_input: 3_000_000_000_000,
+    per_proof_byte: 1_000_000,
+    per_key_byte: 6_000_000,
+};
+
+impl Default for CostModel {
+    fn default() -> Self {
+        DEFAULT_COST_MODEL
+    }
+}
+
+/// What the estimate needs to know about a stored key.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct KeySummary {
+    /// Size of the borsh encoding of the prepared key.
+    pub key_bytes: u64,
+}
+
+impl From<&PreparedVerifyingKey> for KeySummary {
+    fn from(pvk: &PreparedVerifyingKey) -> Self {
+        let bytes = pvk
+            .try_to_vec()
+            .expect("Failed to serialize prepared verifying key");
+        KeySummary {
+            key_bytes: bytes.len() as u64,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GasEstimate {
+    pub key_loading: Gas,
+    pub parsing: Gas,
+    pub input_preparation: Gas,
+    pub pairing: Gas,
+}
+
+impl GasEstimate {
+    pub fn total(&self) -> Gas {
+        self.key_loading + self.parsing + self.input_preparation + self.pairing
+    }
+}
+
+impl CostModel {
+    pub fn estimate(
+        &self,
+        summary: &KeySummary,
+        proof_size: usize,
+        n_inputs: usize,
+    ) -> GasEstimate {
+        GasEstimate {
+            key_loading: Gas(self.per_key_byte * summary.key_bytes),
+            parsing: Gas(self.per_proof_byte * proof_size as u64),
+            input_preparation: Gas(self.per_input * n_inputs as u64),
+            pairing: Gas(self.base),
+        }
+    }
+}
+
+/// Estimates the gas `verify_proof` takes with a stored key, using the
+/// default cost model.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::cost::{estimate_verification_cost, KeySummary};
+/// use electron_rs::verifier::near::prepare_verification_key;
+/// use electron_testkit::circom;
+///
+/// let pvk = prepare_verification_key(circom::vkey().to_string()).unwrap();
+/// let estimate = estimate_verification_cost(&KeySummary::from(&pvk), circom::proof().len(), 21);
+/// assert!(estimate.total().0 > estimate.pairing.0);
+/// ```
+pub fn estimate_verification_cost(
+    pvk_summary: &KeySummary,
+    proof_size: usize,
+    n_inputs: usize,
+) -> GasEstimate {
+    DEFAULT_COST_MODEL.estimate(pvk_summary, proof_size, n_inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::fixtures;
+    use crate::verifier::near::{get_prepared_verifying_key, parse_verification_key};
+
+    #[test]
+    fn test_estimate_verification_cost() {
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        let pvk = get_prepared_verifying_key(vkey).unwrap();
+        let summary = KeySummary::from(&pvk);
+        assert_eq!(summary.key_bytes, pvk.try_to_vec().unwrap().len() as u64);
+
+        let proof_size = fixtures::proof().len();
+        let estimate = estimate_verification_cost(&summary, proof_size, 21);
+        assert_eq!(estimate.pairing, Gas(DEFAULT_COST_MODEL.base));
+        assert_eq!(
+            estimate.total(),
+            estimate.key_loading + estimate.parsing + estimate.input_preparation + estimate.pairing
+        );
+        assert!(estimate_verification_cost(&summary, proof_size, 22).total() > estimate.total());
+    }
+
+    #[test]
+    fn test_custom_cost_model() {
+        let model = CostModel {
+            base: 1,
+            per_input: 10,
+            per_proof_byte: 100,
+            per_key_byte: 1000,
+        };
+        let estimate = model.estimate(&KeySummary { key_bytes: 2 }, 3, 4);
+        assert_eq!(estimate.total(), Gas(1 + 40 + 300 + 2000));
+    }
+}
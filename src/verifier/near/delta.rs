@@ -0,0 +1,114 @@
+// Copyright © 2022, Electron Labs
+
+//! Delta encoding for families of circuits that share alpha, beta and
+//! gamma (e.g. the same circuit at different tree depths).
+//!
+//! The shared [`VerifyingKeyBase`] is stored once, and each circuit only
+//! stores a [`VerifyingKeyDelta`] with its delta point and IC vector. The
+//! prepared delta is recomputed when the key is reconstructed, so the large
+//! line coefficient vector is never stored per circuit.
+
+use crate::verifier::core::{
+    Fq12, G1Affine, G2Affine, G2Prepared, PreparedVerifyingKey, VerifierError, VerifyingKey,
+};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct VerifyingKeyBase {
+    alpha_g1: G1Affine,
+    beta_g2: G2Affine,
+    gamma_g2: G2Affine,
+    alpha_g1_beta_g2: Fq12,
+    gamma_g2_neg_pc: G2Prepared,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct VerifyingKeyDelta {
+    delta_g2: G2Affine,
+    gamma_abc_g1: Vec<G1Affine>,
+}
+
+impl VerifyingKeyBase {
+    /// Takes the parts of `pvk` shared by the circuit family.
+    pub fn from_prepared(pvk: &PreparedVerifyingKey) -> Self {
+        VerifyingKeyBase {
+            alpha_g1: pvk.vk.alpha_g1.clone(),
+            beta_g2: pvk.vk.beta_g2.clone(),
+            gamma_g2: pvk.vk.gamma_g2.clone(),
+            alpha_g1_beta_g2: pvk.alpha_g1_beta_g2.clone(),
+            gamma_g2_neg_pc: pvk.gamma_g2_neg_pc.clone(),
+        }
+    }
+
+    /// Returns true if `pvk` belongs to the family of this base key.
+    pub fn matches(&self, pvk: &PreparedVerifyingKey) -> bool {
+        self.alpha_g1 == pvk.vk.alpha_g1
+            && self.beta_g2 == pvk.vk.beta_g2
+            && self.gamma_g2 == pvk.vk.gamma_g2
+    }
+
+    /// Computes the per-circuit delta of `pvk` against this base key.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `pvk` doesn't share alpha, beta
+    /// and gamma with the base key.
+    pub fn delta(&self, pvk: &PreparedVerifyingKey) -> Result<VerifyingKeyDelta> {
+        if !self.matches(pvk) {
+            return Err(VerifierError::BaseKeyMismatch.into());
+        }
+        Ok(VerifyingKeyDelta {
+            delta_g2: pvk.vk.delta_g2.clone(),
+            gamma_abc_g1: pvk.vk.gamma_abc_g1.clone(),
+        })
+    }
+
+    /// Rebuilds the prepared verifying key of a circuit from its delta.
+    pub fn reconstruct(&self, delta: &VerifyingKeyDelta) -> PreparedVerifyingKey {
+        let delta_g2: ark_bn254::G2Affine = delta.delta_g2.clone().into();
+        let delta_g2_neg_pc: ark_ec::bn::G2Prepared<ark_bn254::Parameters> = (-delta_g2).into();
+        PreparedVerifyingKey {
+            vk: VerifyingKey {
+                alpha_g1: self.alpha_g1.clone(),
+                beta_g2: self.beta_g2.clone(),
+                gamma_g2: self.gamma_g2.clone(),
+                delta_g2: delta.delta_g2.clone(),
+                gamma_abc_g1: delta.gamma_abc_g1.clone(),
+            },
+            alpha_g1_beta_g2: self.alpha_g1_beta_g2.clone(),
+            gamma_g2_neg_pc: self.gamma_g2_neg_pc.clone(),
+            delta_g2_neg_pc: delta_g2_neg_pc.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures;
+    use super::*;
+
+    #[test]
+    fn test_reconstruct_from_delta() {
+        let pvk = fixtures::pvk();
+        let base = VerifyingKeyBase::from_prepared(&pvk);
+        let delta = base.delta(&pvk).unwrap();
+        assert_eq!(base.reconstruct(&delta), pvk);
+    }
+
+    #[test]
+    fn test_delta_is_smaller_than_key() {
+        let pvk = fixtures::pvk();
+        let base = VerifyingKeyBase::from_prepared(&pvk);
+        let delta = base.delta(&pvk).unwrap();
+        assert!(delta.try_to_vec().unwrap().len() * 2 < pvk.try_to_vec().unwrap().len());
+    }
+
+    #[test]
+    fn test_delta_base_mismatch() {
+        let pvk = fixtures::pvk();
+        let mut base = VerifyingKeyBase::from_prepared(&pvk);
+        base.gamma_g2 = pvk.vk.delta_g2.clone();
+        assert!(base.delta(&pvk).is_err());
+    }
+}
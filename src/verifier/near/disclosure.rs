@@ -0,0 +1,203 @@
+// Copyright © 2022, Electron Labs
+
+//! Selective disclosure for credential circuits.
+//!
+//! A [`CredentialSchema`] names the attributes of a credential. The circuit
+//! is expected to expose two public inputs per attribute, in schema order:
+//! a reveal flag constrained to 0 or 1, and the attribute value times the
+//! flag. The verifier never takes public inputs from the prover; it builds
+//! them from the attributes it asked for, so a proof that reveals more or
+//! fewer attributes, or other values, doesn't verify.
+
+use super::{field_from_str, json, parse_proof, AdapterError, PreparedVerifyingKey};
+use crate::verifier::core::verify_prepared;
+use anyhow::Result;
+use ark_ff::{One, Zero};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct CredentialSchema {
+    attributes: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct ProverInputJson {
+    values: Vec<String>,
+    reveal: Vec<String>,
+}
+
+impl CredentialSchema {
+    pub fn new(attributes: Vec<String>) -> Self {
+        CredentialSchema { attributes }
+    }
+
+    pub fn attributes(&self) -> &[String] {
+        &self.attributes
+    }
+
+    /// Number of public inputs of the circuit.
+    pub fn num_inputs(&self) -> usize {
+        2 * self.attributes.len()
+    }
+
+    /// Positions of the reveal flag and the disclosed value of `attribute`
+    /// in the public inputs.
+    pub fn positions(&self, attribute: &str) -> Option<(usize, usize)> {
+        let i = self.attributes.iter().position(|a| a == attribute)?;
+        Some((2 * i, 2 * i + 1))
+    }
+
+    fn check_known<'a, I: IntoIterator<Item = &'a String>>(
+        &self,
+        names: I,
+    ) -> Result<(), AdapterError> {
+        for name in names {
+            if self.positions(name).is_none() {
+                return Err(AdapterError::UnknownAttribute(name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The circom input json for proving a credential with attribute
+    /// `values` that reveals the attributes in `revealed`.
+    ///
+    /// # Errors
+    /// AdapterError::UnknownAttribute, AdapterError::MissingAttribute
+    pub fn prover_input_json(
+        &self,
+        values: &BTreeMap<String, String>,
+        revealed: &[String],
+    ) -> Result<String> {
+        self.check_known(values.keys().chain(revealed))?;
+        let mut input = ProverInputJson {
+            values: Vec::with_capacity(self.attributes.len()),
+            reveal: Vec::with_capacity(self.attributes.len()),
+        };
+        for attribute in &self.attributes {
+            let value = values
+                .get(attribute)
+                .ok_or_else(|| AdapterError::MissingAttribute(attribute.clone()))?;
+            input.values.push(value.clone());
+            let flag = if revealed.contains(attribute) {
+                "1"
+            } else {
+                "0"
+            };
+            input.reveal.push(flag.to_string());
+        }
+        Ok(json::to_string(&input, "prover input")?)
+    }
+
+    /// The public inputs a proof revealing exactly `revealed`, attribute
+    /// name to decimal value, must have.
+    ///
+    /// # Errors
+    /// AdapterError::UnknownAttribute, VerifierError::InvalidFieldElement
+    pub fn expected_inputs(
+        &self,
+        revealed: &BTreeMap<String, String>,
+    ) -> Result<Vec<ark_bn254::Fr>> {
+        self.check_known(revealed.keys())?;
+        let mut inputs = Vec::with_capacity(self.num_inputs());
+        for attribute in &self.attributes {
+            match revealed.get(attribute) {
+                Some(value) => {
+                    inputs.push(ark_bn254::Fr::one());
+                    inputs.push(field_from_str(value, || attribute.clone())?);
+                }
+                None => {
+                    inputs.push(ark_bn254::Fr::zero());
+                    inputs.push(ark_bn254::Fr::zero());
+                }
+            }
+        }
+        Ok(inputs)
+    }
+
+    /// Verifies that a proof discloses exactly the attributes and values in
+    /// `revealed` and hides all others.
+    ///
+    /// # Errors
+    /// AdapterError::UnknownAttribute, VerifierError::InvalidFieldElement,
+    /// and the errors of `verify_proof` for the proof
+    pub fn verify_disclosure(
+        &self,
+        pvk: &PreparedVerifyingKey,
+        proof_str: String,
+        revealed: &BTreeMap<String, String>,
+    ) -> Result<bool> {
+        let inputs = self.expected_inputs(revealed)?;
+        let proof = parse_proof(proof_str)?;
+        Ok(verify_prepared(pvk, &proof, &inputs)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::{get_prepared_verifying_key, parse_verification_key};
+    use electron_testkit::credential::Credential;
+
+    fn schema() -> CredentialSchema {
+        CredentialSchema::new(vec![
+            "age".to_string(),
+            "country".to_string(),
+            "score".to_string(),
+        ])
+    }
+
+    fn revealed(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_disclosure() {
+        let mut credential = Credential::setup(3);
+        let vkey = parse_verification_key(credential.vkey_json()).unwrap();
+        let pvk = get_prepared_verifying_key(vkey).unwrap();
+        let (proof, _) = credential.prove(&[30, 49, 7], &[false, true, false]);
+
+        let schema = schema();
+        assert!(schema
+            .verify_disclosure(&pvk, proof.clone(), &revealed(&[("country", "49")]))
+            .unwrap());
+        // Wrong value, an extra attribute, or nothing revealed.
+        assert!(!schema
+            .verify_disclosure(&pvk, proof.clone(), &revealed(&[("country", "50")]))
+            .unwrap());
+        assert!(!schema
+            .verify_disclosure(
+                &pvk,
+                proof.clone(),
+                &revealed(&[("country", "49"), ("age", "30")])
+            )
+            .unwrap());
+        assert!(!schema
+            .verify_disclosure(&pvk, proof.clone(), &revealed(&[]))
+            .unwrap());
+        assert!(schema
+            .verify_disclosure(&pvk, proof, &revealed(&[("name", "1")]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_prover_input_json() {
+        let schema = schema();
+        assert_eq!(schema.positions("country"), Some((2, 3)));
+        let values = revealed(&[("age", "30"), ("country", "49"), ("score", "7")]);
+        let json = schema
+            .prover_input_json(&values, &["country".to_string()])
+            .unwrap();
+        assert_eq!(json, r#"{"values":["30","49","7"],"reveal":["0","1","0"]}"#);
+        assert!(schema
+            .prover_input_json(&revealed(&[("age", "30")]), &[])
+            .is_err());
+    }
+}
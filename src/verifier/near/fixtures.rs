@@ -0,0 +1,12 @@
+// Copyright © 2022, Electron Labs
+
+//! Circom fixtures shared by the verifier tests, from `electron-testkit`.
+
+pub use electron_testkit::circom::{proof, public_inputs, vkey};
+
+use super::{prepare_verification_key, PreparedVerifyingKey};
+
+/// The prepared key of `vkey`.
+pub fn pvk() -> PreparedVerifyingKey {
+    prepare_verification_key(vkey().to_string()).unwrap()
+}
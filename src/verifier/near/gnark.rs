@@ -0,0 +1,668 @@
+// Copyright © 2022, Electron Labs
+
+//! Proofs and verification keys of gnark Groth16 circuits over BN254.
+//!
+//! gnark writes them either as json or in the binary format of `WriteTo`
+//! (compressed points) and `WriteRawTo` (uncompressed points).
+//! [`from_gnark_proof`] and [`from_gnark_vkey`] read all three and tell them
+//! apart by their first byte:
+//!
+//! ```ignore
+//! let pvk = from_gnark_vkey(&std::fs::read("circuit.vk")?)?;
+//! let proof = from_gnark_proof(&proof_bytes)?;
+//! let verified = verify_proof_borsh(pvk, proof, inputs)?;
+//! ```
+//!
+//! Binary points are big-endian with G2 coordinates imaginary part first,
+//! and the two top bits of the first byte flag compression, the sign of y
+//! and the point at infinity.
+//!
+//! Newer gnark releases add Pedersen commitments to proofs and keys.
+//! Circuits without commitments are read whether or not the commitment
+//! section is there, while a proof or key with a commitment is rejected
+//! with `VerifierError::UnsupportedProtocol`, since its verification needs
+//! a check this verifier doesn't do. Any other bytes after the fields read
+//! here are an error.
+
+use super::import::VkeyImporter;
+use super::{field_from_str, json, PreparedVerifyingKey, Proof, VerifierError};
+use crate::verifier::core::{check_proof, check_verifying_key};
+use anyhow::Result;
+use ark_ec::AffineCurve;
+use ark_ff::{BigInteger256, PrimeField, Zero};
+use serde::Deserialize;
+use std::convert::TryInto;
+
+const FQ_LEN: usize = 32;
+const FLAG_MASK: u8 = 0b11 << 6;
+const UNCOMPRESSED: u8 = 0;
+const INFINITY: u8 = 0b01 << 6;
+const COMPRESSED_SMALLEST: u8 = 0b10 << 6;
+const COMPRESSED_LARGEST: u8 = 0b11 << 6;
+
+/// A field element, which gnark writes as a json number when it has at
+/// most 15 digits and as a decimal string otherwise.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+#[serde(untagged)]
+enum ElementJson {
+    Number(u64),
+    Text(String),
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct G1Json {
+    #[serde(rename = "X")]
+    x: ElementJson,
+    #[serde(rename = "Y")]
+    y: ElementJson,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct E2Json {
+    #[serde(rename = "A0")]
+    a0: ElementJson,
+    #[serde(rename = "A1")]
+    a1: ElementJson,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct G2Json {
+    #[serde(rename = "X")]
+    x: E2Json,
+    #[serde(rename = "Y")]
+    y: E2Json,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct ProofJson {
+    #[serde(rename = "Ar")]
+    ar: G1Json,
+    #[serde(rename = "Bs")]
+    bs: G2Json,
+    #[serde(rename = "Krs")]
+    krs: G1Json,
+    #[serde(rename = "Commitments", default)]
+    commitments: Vec<G1Json>,
+}
+
+/// A Pedersen commitment key. Its points are private in gnark and left
+/// out of the json, so only the number of keys is read.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct CommitmentKeyJson {}
+
+/// The commitment of gnark 0.8 keys.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct CommitmentInfoJson {
+    #[serde(rename = "Committed", default)]
+    committed: Vec<u64>,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct VkeyG1Json {
+    #[serde(rename = "Alpha")]
+    alpha: G1Json,
+    #[serde(rename = "K")]
+    k: Vec<G1Json>,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct VkeyG2Json {
+    #[serde(rename = "Beta")]
+    beta: G2Json,
+    #[serde(rename = "Gamma")]
+    gamma: G2Json,
+    #[serde(rename = "Delta")]
+    delta: G2Json,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct VkeyJson {
+    #[serde(rename = "G1")]
+    g1: VkeyG1Json,
+    #[serde(rename = "G2")]
+    g2: VkeyG2Json,
+    #[serde(rename = "CommitmentInfo")]
+    commitment_info: Option<CommitmentInfoJson>,
+    #[serde(rename = "PublicAndCommitmentCommitted", default)]
+    public_and_commitment_committed: Vec<Vec<u64>>,
+    #[serde(rename = "CommitmentKeys", default)]
+    commitment_keys: Vec<CommitmentKeyJson>,
+}
+
+impl VkeyJson {
+    /// Whether the key has a commitment. `CommitmentKey` holds no data in
+    /// json, so the committed wires tell.
+    fn has_commitments(&self) -> bool {
+        self.commitment_info
+            .as_ref()
+            .map_or(false, |info| !info.committed.is_empty())
+            || !self.public_and_commitment_committed.is_empty()
+            || !self.commitment_keys.is_empty()
+    }
+}
+
+impl ElementJson {
+    fn fq(&self, name: &str) -> Result<ark_bn254::Fq, VerifierError> {
+        match self {
+            ElementJson::Number(n) => Ok(ark_bn254::Fq::from(*n)),
+            ElementJson::Text(s) => field_from_str(s, || name.to_string()),
+        }
+    }
+}
+
+impl G1Json {
+    fn point(&self, name: &str) -> Result<ark_bn254::G1Affine, VerifierError> {
+        Ok(g1(self.x.fq(name)?, self.y.fq(name)?))
+    }
+}
+
+impl E2Json {
+    fn fq2(&self, name: &str) -> Result<ark_bn254::Fq2, VerifierError> {
+        Ok(ark_bn254::Fq2::new(self.a0.fq(name)?, self.a1.fq(name)?))
+    }
+}
+
+impl G2Json {
+    fn point(&self, name: &str) -> Result<ark_bn254::G2Affine, VerifierError> {
+        Ok(g2(self.x.fq2(name)?, self.y.fq2(name)?))
+    }
+}
+
+/// gnark keeps the point at infinity as (0, 0).
+fn g1(x: ark_bn254::Fq, y: ark_bn254::Fq) -> ark_bn254::G1Affine {
+    if x.is_zero() && y.is_zero() {
+        return ark_bn254::G1Affine::zero();
+    }
+    ark_bn254::G1Affine::new(x, y, false)
+}
+
+fn g2(x: ark_bn254::Fq2, y: ark_bn254::Fq2) -> ark_bn254::G2Affine {
+    if x.is_zero() && y.is_zero() {
+        return ark_bn254::G2Affine::zero();
+    }
+    ark_bn254::G2Affine::new(x, y, false)
+}
+
+fn encoding_error(what: &str) -> VerifierError {
+    VerifierError::InvalidEncoding(what.to_string())
+}
+
+fn commitments_error() -> VerifierError {
+    VerifierError::UnsupportedProtocol("groth16 with gnark Pedersen commitments".to_string())
+}
+
+/// Reads binary points, all compressed or all uncompressed.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    compressed: bool,
+    what: &'static str,
+}
+
+impl<'a> Reader<'a> {
+    /// A reader of `bytes`, compressed if the flags of its first point
+    /// say so.
+    fn new(bytes: &'a [u8], what: &'static str) -> Self {
+        let flags = bytes.first().map_or(UNCOMPRESSED, |b| b & FLAG_MASK);
+        Reader {
+            bytes,
+            compressed: flags == COMPRESSED_SMALLEST || flags == COMPRESSED_LARGEST,
+            what,
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], VerifierError> {
+        if self.bytes.len() < len {
+            return Err(VerifierError::InvalidEncoding(format!(
+                "{} is truncated",
+                self.what
+            )));
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, VerifierError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Checks that every byte was read.
+    fn finish(&self) -> Result<(), VerifierError> {
+        if !self.is_empty() {
+            return Err(VerifierError::InvalidEncoding(format!(
+                "{} has {} trailing bytes",
+                self.what,
+                self.bytes.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Takes `count` coordinates and the flags of the first one.
+    fn coordinates(&mut self, count: usize) -> Result<(u8, Vec<ark_bn254::Fq>), VerifierError> {
+        let bytes = self.take(count * FQ_LEN)?;
+        let flags = bytes[0] & FLAG_MASK;
+        let mut first = bytes[..FQ_LEN].to_vec();
+        first[0] &= !FLAG_MASK;
+        let coordinates = std::iter::once(first.as_slice())
+            .chain(bytes[FQ_LEN..].chunks(FQ_LEN))
+            .map(fq_from_be)
+            .collect::<Result<_, _>>()?;
+        Ok((flags, coordinates))
+    }
+
+    fn g1(&mut self) -> Result<ark_bn254::G1Affine, VerifierError> {
+        let count = if self.compressed { 1 } else { 2 };
+        let (flags, c) = self.coordinates(count)?;
+        match flags {
+            INFINITY => Ok(ark_bn254::G1Affine::zero()),
+            UNCOMPRESSED if !self.compressed => Ok(ark_bn254::G1Affine::new(c[0], c[1], false)),
+            COMPRESSED_SMALLEST | COMPRESSED_LARGEST if self.compressed => {
+                ark_bn254::G1Affine::get_point_from_x(c[0], flags == COMPRESSED_LARGEST)
+                    .ok_or_else(|| encoding_error("G1 x-coordinate is not on the curve"))
+            }
+            _ => Err(encoding_error("mixed compressed and uncompressed points")),
+        }
+    }
+
+    fn g2(&mut self) -> Result<ark_bn254::G2Affine, VerifierError> {
+        let count = if self.compressed { 2 } else { 4 };
+        let (flags, c) = self.coordinates(count)?;
+        let x = ark_bn254::Fq2::new(c[1], c[0]);
+        match flags {
+            INFINITY => Ok(ark_bn254::G2Affine::zero()),
+            UNCOMPRESSED if !self.compressed => {
+                let y = ark_bn254::Fq2::new(c[3], c[2]);
+                Ok(ark_bn254::G2Affine::new(x, y, false))
+            }
+            // arkworks orders Fq2 by c1, then c0, as gnark picks the
+            // lexicographically largest root.
+            COMPRESSED_SMALLEST | COMPRESSED_LARGEST if self.compressed => {
+                ark_bn254::G2Affine::get_point_from_x(x, flags == COMPRESSED_LARGEST)
+                    .ok_or_else(|| encoding_error("G2 x-coordinate is not on the curve"))
+            }
+            _ => Err(encoding_error("mixed compressed and uncompressed points")),
+        }
+    }
+}
+
+fn fq_from_be(bytes: &[u8]) -> Result<ark_bn254::Fq, VerifierError> {
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(8).rev()) {
+        *limb = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    ark_bn254::Fq::from_repr(BigInteger256::new(limbs))
+        .ok_or_else(|| encoding_error("field element is not reduced"))
+}
+
+/// Whether `bytes` hold json rather than binary points.
+fn is_json(bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .map_or(false, |b| *b == b'{')
+}
+
+fn gnark_proof(bytes: &[u8]) -> Result<ark_groth16::Proof<ark_bn254::Bn254>> {
+    let proof = if is_json(bytes) {
+        let proof: ProofJson = json::from_slice(bytes, "gnark proof")?;
+        if !proof.commitments.is_empty() {
+            return Err(commitments_error().into());
+        }
+        ark_groth16::Proof {
+            a: proof.ar.point("Ar")?,
+            b: proof.bs.point("Bs")?,
+            c: proof.krs.point("Krs")?,
+        }
+    } else {
+        let mut reader = Reader::new(bytes, "gnark proof");
+        let proof = ark_groth16::Proof {
+            a: reader.g1()?,
+            b: reader.g2()?,
+            c: reader.g1()?,
+        };
+        // The commitments and their proof of knowledge, which is the point
+        // at infinity when there are none.
+        if !reader.is_empty() {
+            if reader.u32()? != 0 {
+                return Err(commitments_error().into());
+            }
+            if !reader.g1()?.is_zero() {
+                return Err(commitments_error().into());
+            }
+        }
+        reader.finish()?;
+        proof
+    };
+    check_proof(&proof)?;
+    Ok(proof)
+}
+
+fn gnark_vkey(bytes: &[u8]) -> Result<ark_groth16::VerifyingKey<ark_bn254::Bn254>> {
+    let vk = if is_json(bytes) {
+        let vkey: VkeyJson = json::from_slice(bytes, "gnark verification key")?;
+        if vkey.has_commitments() {
+            return Err(commitments_error().into());
+        }
+        ark_groth16::VerifyingKey {
+            alpha_g1: vkey.g1.alpha.point("G1.Alpha")?,
+            beta_g2: vkey.g2.beta.point("G2.Beta")?,
+            gamma_g2: vkey.g2.gamma.point("G2.Gamma")?,
+            delta_g2: vkey.g2.delta.point("G2.Delta")?,
+            gamma_abc_g1: vkey
+                .g1
+                .k
+                .iter()
+                .map(|p| p.point("G1.K"))
+                .collect::<Result<_, _>>()?,
+        }
+    } else {
+        let mut reader = Reader::new(bytes, "gnark verification key");
+        let alpha_g1 = reader.g1()?;
+        let _beta_g1 = reader.g1()?;
+        let beta_g2 = reader.g2()?;
+        let gamma_g2 = reader.g2()?;
+        let _delta_g1 = reader.g1()?;
+        let delta_g2 = reader.g2()?;
+        let k_len = reader.u32()?;
+        let gamma_abc_g1 = (0..k_len).map(|_| reader.g1()).collect::<Result<_, _>>()?;
+        // The committed wires of each commitment, then the number of
+        // commitment keys.
+        if !reader.is_empty() && reader.u32()? != 0 {
+            return Err(commitments_error().into());
+        }
+        if !reader.is_empty() && reader.u32()? != 0 {
+            return Err(commitments_error().into());
+        }
+        reader.finish()?;
+        ark_groth16::VerifyingKey {
+            alpha_g1,
+            beta_g2,
+            gamma_g2,
+            delta_g2,
+            gamma_abc_g1,
+        }
+    };
+    check_verifying_key(&vk)?;
+    Ok(vk)
+}
+
+/// A helper function to read a gnark proof, json or binary.
+///
+/// # Errors
+/// VerifierError::ParseError, VerifierError::InvalidEncoding,
+/// VerifierError::InvalidFieldElement, VerifierError::InvalidPoint,
+/// VerifierError::InvalidSubgroupPoint
+///
+/// This function will return an error if the proof fails to parse, or if
+/// one of its points is not on the curve or not in the prime order subgroup.
+pub fn from_gnark_proof(bytes: &[u8]) -> Result<Proof> {
+    Ok(gnark_proof(bytes)?.into())
+}
+
+/// A helper function to read and prepare a gnark verification key, json or
+/// binary.
+///
+/// # Errors
+/// VerifierError::ParseError, VerifierError::InvalidEncoding,
+/// VerifierError::InvalidFieldElement, VerifierError::InvalidPoint,
+/// VerifierError::InvalidSubgroupPoint
+///
+/// This function will return an error if the key fails to parse, or if one
+/// of its points is not on the curve or not in the prime order subgroup.
+pub fn from_gnark_vkey(bytes: &[u8]) -> Result<PreparedVerifyingKey> {
+    Ok(ark_groth16::prepare_verifying_key(&gnark_vkey(bytes)?).into())
+}
+
+/// The gnark verification key json layout.
+pub struct Gnark;
+
+impl VkeyImporter for Gnark {
+    fn name(&self) -> &'static str {
+        "gnark"
+    }
+
+    fn import(&self, vkey_str: &str) -> Result<ark_groth16::VerifyingKey<ark_bn254::Bn254>> {
+        gnark_vkey(vkey_str.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::{parse_proof, prepare_verification_key, repr_to_string};
+    use ark_ff::BigInteger;
+    use electron_testkit::multiplier::Multiplier;
+
+    fn push_fq(out: &mut Vec<u8>, fq: ark_bn254::Fq) {
+        out.extend(fq.into_repr().to_bytes_be());
+    }
+
+    /// Flags the first byte of the point starting at `start`.
+    fn flag(out: &mut [u8], start: usize, greatest: bool) {
+        out[start] |= if greatest {
+            COMPRESSED_LARGEST
+        } else {
+            COMPRESSED_SMALLEST
+        };
+    }
+
+    fn push_g1(out: &mut Vec<u8>, p: ark_bn254::G1Affine, compressed: bool) {
+        let start = out.len();
+        push_fq(out, p.x);
+        if compressed {
+            flag(out, start, p.y > -p.y);
+        } else {
+            push_fq(out, p.y);
+        }
+    }
+
+    fn push_g2(out: &mut Vec<u8>, p: ark_bn254::G2Affine, compressed: bool) {
+        let start = out.len();
+        push_fq(out, p.x.c1);
+        push_fq(out, p.x.c0);
+        if compressed {
+            flag(out, start, p.y > -p.y);
+        } else {
+            push_fq(out, p.y.c1);
+            push_fq(out, p.y.c0);
+        }
+    }
+
+    fn proof_bytes(proof: &ark_groth16::Proof<ark_bn254::Bn254>, compressed: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        push_g1(&mut out, proof.a, compressed);
+        push_g2(&mut out, proof.b, compressed);
+        push_g1(&mut out, proof.c, compressed);
+        out
+    }
+
+    fn vkey_bytes(vk: &ark_groth16::VerifyingKey<ark_bn254::Bn254>, compressed: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        push_g1(&mut out, vk.alpha_g1, compressed);
+        push_g1(&mut out, vk.alpha_g1, compressed);
+        push_g2(&mut out, vk.beta_g2, compressed);
+        push_g2(&mut out, vk.gamma_g2, compressed);
+        push_g1(&mut out, vk.alpha_g1, compressed);
+        push_g2(&mut out, vk.delta_g2, compressed);
+        out.extend((vk.gamma_abc_g1.len() as u32).to_be_bytes());
+        for p in &vk.gamma_abc_g1 {
+            push_g1(&mut out, *p, compressed);
+        }
+        // No committed wires and no commitment keys.
+        out.extend([0u8; 8]);
+        out
+    }
+
+    fn fq_json(fq: ark_bn254::Fq) -> String {
+        format!("\"{}\"", repr_to_string(&fq.into_repr().0))
+    }
+
+    fn g1_json(p: ark_bn254::G1Affine) -> String {
+        format!(r#"{{"X": {}, "Y": {}}}"#, fq_json(p.x), fq_json(p.y))
+    }
+
+    fn g2_json(p: ark_bn254::G2Affine) -> String {
+        format!(
+            r#"{{"X": {{"A0": {}, "A1": {}}}, "Y": {{"A0": {}, "A1": {}}}}}"#,
+            fq_json(p.x.c0),
+            fq_json(p.x.c1),
+            fq_json(p.y.c0),
+            fq_json(p.y.c1)
+        )
+    }
+
+    #[test]
+    fn test_binary_proofs() {
+        let mut multiplier = Multiplier::setup();
+        let (proof_str, _) = multiplier.prove(3, 11);
+        let proof = parse_proof(proof_str).unwrap();
+
+        for compressed in [true, false] {
+            let bytes = proof_bytes(&proof, compressed);
+            assert_eq!(bytes.len(), if compressed { 128 } else { 256 });
+            assert_eq!(
+                from_gnark_proof(&bytes).unwrap(),
+                Proof::from(proof.clone())
+            );
+        }
+        let bytes = proof_bytes(&proof, true);
+        assert!(from_gnark_proof(&bytes[..127]).is_err());
+    }
+
+    #[test]
+    fn test_binary_vkeys() {
+        let multiplier = Multiplier::setup();
+        let expected = prepare_verification_key(multiplier.vkey_json()).unwrap();
+        for compressed in [true, false] {
+            let bytes = vkey_bytes(multiplier.verifying_key(), compressed);
+            assert_eq!(from_gnark_vkey(&bytes).unwrap(), expected);
+            // Releases without commitments end after K.
+            assert_eq!(
+                from_gnark_vkey(&bytes[..bytes.len() - 8]).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_binary_commitments_are_rejected() {
+        let multiplier = Multiplier::setup();
+        let bytes = vkey_bytes(multiplier.verifying_key(), true);
+        let k_end = bytes.len() - 8;
+
+        let mut committed = bytes[..k_end].to_vec();
+        committed.extend(1u32.to_be_bytes());
+        committed.extend(1u32.to_be_bytes());
+        committed.extend(2u64.to_be_bytes());
+        committed.extend(1u32.to_be_bytes());
+        assert_eq!(
+            from_gnark_vkey(&committed).err().unwrap().to_string(),
+            "Unsupported protocol groth16 with gnark Pedersen commitments"
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            from_gnark_vkey(&trailing).err().unwrap().to_string(),
+            "Invalid point encoding: gnark verification key has 1 trailing bytes"
+        );
+
+        let mut multiplier = Multiplier::setup();
+        let (proof_str, _) = multiplier.prove(3, 11);
+        let proof = parse_proof(proof_str).unwrap();
+        let mut bytes = proof_bytes(&proof, true);
+        bytes.extend(0u32.to_be_bytes());
+        let mut pok = [0u8; FQ_LEN];
+        pok[0] = INFINITY;
+        bytes.extend(pok);
+        assert_eq!(
+            from_gnark_proof(&bytes).unwrap(),
+            Proof::from(proof.clone())
+        );
+
+        let mut committed = proof_bytes(&proof, true);
+        committed.extend(1u32.to_be_bytes());
+        push_g1(&mut committed, proof.a, true);
+        push_g1(&mut committed, proof.a, true);
+        assert!(from_gnark_proof(&committed).is_err());
+
+        let mut trailing = proof_bytes(&proof, true);
+        trailing.extend([0u8; 3]);
+        assert!(from_gnark_proof(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let mut multiplier = Multiplier::setup();
+        let vk = multiplier.verifying_key().clone();
+        let (proof_str, _) = multiplier.prove(3, 11);
+        let proof = parse_proof(proof_str).unwrap();
+
+        let proof_json = format!(
+            r#"{{"Ar": {}, "Bs": {}, "Krs": {}, "Commitments": [], "CommitmentPok": {{"X": 0, "Y": 0}}}}"#,
+            g1_json(proof.a),
+            g2_json(proof.b),
+            g1_json(proof.c)
+        );
+        assert_eq!(
+            from_gnark_proof(proof_json.as_bytes()).unwrap(),
+            Proof::from(proof.clone())
+        );
+        let committed_json = proof_json.replace(
+            r#""Commitments": []"#,
+            &format!(r#""Commitments": [{}]"#, g1_json(proof.a)),
+        );
+        assert!(from_gnark_proof(committed_json.as_bytes()).is_err());
+
+        let k: Vec<String> = vk.gamma_abc_g1.iter().map(|p| g1_json(*p)).collect();
+        let vkey_json = format!(
+            r#"{{"G1": {{"Alpha": {}, "Beta": {}, "Delta": {}, "K": [{}]}}, "G2": {{"Beta": {}, "Gamma": {}, "Delta": {}}}}}"#,
+            g1_json(vk.alpha_g1),
+            g1_json(vk.alpha_g1),
+            g1_json(vk.alpha_g1),
+            k.join(", "),
+            g2_json(vk.beta_g2),
+            g2_json(vk.gamma_g2),
+            g2_json(vk.delta_g2)
+        );
+        let expected = prepare_verification_key(multiplier.vkey_json()).unwrap();
+        assert_eq!(from_gnark_vkey(vkey_json.as_bytes()).unwrap(), expected);
+        let with_fields = |fields: &str| {
+            let vkey = format!("{}, {}}}", &vkey_json[..vkey_json.len() - 1], fields);
+            from_gnark_vkey(vkey.as_bytes())
+        };
+        let uncommitted =
+            r#""CommitmentKey": {}, "CommitmentKeys": [], "PublicAndCommitmentCommitted": []"#;
+        assert_eq!(with_fields(uncommitted).unwrap(), expected);
+        assert!(with_fields(r#""PublicAndCommitmentCommitted": [[1]]"#).is_err());
+        assert!(with_fields(r#""CommitmentKeys": [{}]"#).is_err());
+        assert!(with_fields(r#""CommitmentInfo": {"Committed": [1]}"#).is_err());
+        assert_eq!(
+            crate::verifier::near::import::detect_dialect(&vkey_json)
+                .unwrap()
+                .name(),
+            "gnark"
+        );
+    }
+
+    #[test]
+    fn test_small_elements_are_numbers() {
+        let element: ElementJson = json::from_slice(b"12", "element").unwrap();
+        assert_eq!(element.fq("X").unwrap(), ark_bn254::Fq::from(12u64));
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright © 2022, Electron Labs
+
+//! Verification keys in json dialects other than snarkjs.
+//!
+//! Each dialect implements [`VkeyImporter`], and [`detect_dialect`] picks
+//! the importer that can parse a given key. Supported are snarkjs, ZoKrates
+//! (`zokrates export-verifier` json, `g16` scheme) and gnark json; binary
+//! gnark keys are read by [`gnark`](super::gnark). Only Groth16 keys over
+//! bn128 can be imported; proof systems without a Groth16 verifying key,
+//! such as longfellow-zk, are out of scope.
+
+use super::gnark::Gnark;
+use super::{json, parse_verification_key, PreparedVerifyingKey, VerifierError};
+use anyhow::Result;
+use ark_ff::{BigInteger256, PrimeField};
+use serde::Deserialize;
+use std::convert::TryInto;
+
+/// A verification key json dialect.
+pub trait VkeyImporter {
+    /// Name of the dialect, for logs and errors.
+    fn name(&self) -> &'static str;
+
+    /// Parses a key in this dialect.
+    ///
+    /// # Errors
+    /// VerifierError::ParseError, VerifierError::UnsupportedProtocol,
+    /// VerifierError::UnsupportedCurve
+    fn import(&self, vkey_str: &str) -> Result<ark_groth16::VerifyingKey<ark_bn254::Bn254>>;
+}
+
+/// The circom and snarkjs `verification_key.json` layout.
+pub struct Snarkjs;
+
+impl VkeyImporter for Snarkjs {
+    fn name(&self) -> &'static str {
+        "snarkjs"
+    }
+
+    fn import(&self, vkey_str: &str) -> Result<ark_groth16::VerifyingKey<ark_bn254::Bn254>> {
+        let vkey = parse_verification_key(vkey_str.to_string())?;
+        Ok(vkey.try_into()?)
+    }
+}
+
+/// The ZoKrates `verification.key` json layout, with hex coordinates.
+pub struct Zokrates;
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct ZokratesKeyJson {
+    scheme: String,
+    curve: String,
+    alpha: Vec<String>,
+    beta: Vec<Vec<String>>,
+    gamma: Vec<Vec<String>>,
+    delta: Vec<Vec<String>>,
+    gamma_abc: Vec<Vec<String>>,
+}
+
+impl VkeyImporter for Zokrates {
+    fn name(&self) -> &'static str {
+        "zokrates"
+    }
+
+    fn import(&self, vkey_str: &str) -> Result<ark_groth16::VerifyingKey<ark_bn254::Bn254>> {
+        let vkey: ZokratesKeyJson =
+            json::from_slice(vkey_str.as_bytes(), "zokrates verification key")?;
+        if vkey.scheme != "g16" {
+            return Err(VerifierError::UnsupportedProtocol(vkey.scheme).into());
+        }
+        if vkey.curve != "bn128" {
+            return Err(VerifierError::UnsupportedCurve(vkey.curve).into());
+        }
+        Ok(ark_groth16::VerifyingKey {
+            alpha_g1: hex_g1(&vkey.alpha)?,
+            beta_g2: hex_g2(&vkey.beta)?,
+            gamma_g2: hex_g2(&vkey.gamma)?,
+            delta_g2: hex_g2(&vkey.delta)?,
+            gamma_abc_g1: vkey
+                .gamma_abc
+                .iter()
+                .map(|p| hex_g1(p))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+fn hex_error() -> VerifierError {
+    VerifierError::ParseError("zokrates verification key".to_string())
+}
+
+fn fq_from_hex(s: &str) -> Result<ark_bn254::Fq, VerifierError> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    let bytes = hex::decode(digits).map_err(|_| hex_error())?;
+    if bytes.len() > 32 {
+        return Err(hex_error());
+    }
+    let mut padded = [0u8; 32];
+    padded[32 - bytes.len()..].copy_from_slice(&bytes);
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(padded.chunks(8).rev()) {
+        *limb = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    ark_bn254::Fq::from_repr(BigInteger256::new(limbs)).ok_or_else(hex_error)
+}
+
+fn hex_g1(point: &[String]) -> Result<ark_bn254::G1Affine, VerifierError> {
+    if point.len() != 2 {
+        return Err(hex_error());
+    }
+    let point = ark_bn254::G1Affine::new(fq_from_hex(&point[0])?, fq_from_hex(&point[1])?, false);
+    if !point.is_on_curve() {
+        return Err(hex_error());
+    }
+    Ok(point)
+}
+
+fn hex_g2(point: &[Vec<String>]) -> Result<ark_bn254::G2Affine, VerifierError> {
+    if point.len() != 2 || point.iter().any(|c| c.len() != 2) {
+        return Err(hex_error());
+    }
+    let x = ark_bn254::Fq2::new(fq_from_hex(&point[0][0])?, fq_from_hex(&point[0][1])?);
+    let y = ark_bn254::Fq2::new(fq_from_hex(&point[1][0])?, fq_from_hex(&point[1][1])?);
+    let point = ark_bn254::G2Affine::new(x, y, false);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(hex_error());
+    }
+    Ok(point)
+}
+
+/// Returns the importers tried by [`detect_dialect`], in order.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::import::importers;
+///
+/// let names: Vec<&str> = importers().iter().map(|importer| importer.name()).collect();
+/// assert_eq!(names, ["snarkjs", "zokrates", "gnark"]);
+/// ```
+pub fn importers() -> Vec<Box<dyn VkeyImporter>> {
+    vec![Box::new(Snarkjs), Box::new(Zokrates), Box::new(Gnark)]
+}
+
+/// Returns the first importer that parses the key.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::import::detect_dialect;
+///
+/// let importer = detect_dialect(electron_testkit::circom::vkey()).unwrap();
+/// assert_eq!(importer.name(), "snarkjs");
+/// assert!(detect_dialect("{}").is_none());
+/// ```
+pub fn detect_dialect(vkey_str: &str) -> Option<Box<dyn VkeyImporter>> {
+    importers()
+        .into_iter()
+        .find(|importer| importer.import(vkey_str).is_ok())
+}
+
+/// Parses and prepares a key in any supported dialect.
+///
+/// # Errors
+/// VerifierError::ParseError
+///
+/// This function will return an error if no importer parses the key.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::import::import_verification_key;
+/// use electron_rs::verifier::near::prepare_verification_key;
+/// use electron_testkit::circom;
+///
+/// let pvk = import_verification_key(circom::vkey()).unwrap();
+/// let expected = prepare_verification_key(circom::vkey().to_string()).unwrap();
+/// assert_eq!(pvk.fingerprint(), expected.fingerprint());
+/// ```
+pub fn import_verification_key(vkey_str: &str) -> Result<PreparedVerifyingKey> {
+    for importer in importers() {
+        if let Ok(vk) = importer.import(vkey_str) {
+            return Ok(ark_groth16::prepare_verifying_key(&vk).into());
+        }
+    }
+    Err(VerifierError::ParseError("verification key".to_string()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::{fixtures, get_prepared_verifying_key, verify_proof};
+    use ark_ff::BigInteger;
+    use electron_testkit::multiplier::Multiplier;
+
+    fn fq_hex(fq: ark_bn254::Fq) -> String {
+        format!("0x{}", hex::encode(fq.into_repr().to_bytes_be()))
+    }
+
+    fn g2_hex(p: ark_bn254::G2Affine) -> String {
+        format!(
+            r#"[["{}", "{}"], ["{}", "{}"]]"#,
+            fq_hex(p.x.c0),
+            fq_hex(p.x.c1),
+            fq_hex(p.y.c0),
+            fq_hex(p.y.c1)
+        )
+    }
+
+    fn zokrates_json(vk: &ark_groth16::VerifyingKey<ark_bn254::Bn254>) -> String {
+        let gamma_abc: Vec<String> = vk
+            .gamma_abc_g1
+            .iter()
+            .map(|p| format!(r#"["{}", "{}"]"#, fq_hex(p.x), fq_hex(p.y)))
+            .collect();
+        format!(
+            r#"{{"scheme": "g16", "curve": "bn128", "alpha": ["{}", "{}"], "beta": {}, "gamma": {}, "delta": {}, "gamma_abc": [{}]}}"#,
+            fq_hex(vk.alpha_g1.x),
+            fq_hex(vk.alpha_g1.y),
+            g2_hex(vk.beta_g2),
+            g2_hex(vk.gamma_g2),
+            g2_hex(vk.delta_g2),
+            gamma_abc.join(", ")
+        )
+    }
+
+    #[test]
+    fn test_detect_snarkjs() {
+        let importer = detect_dialect(fixtures::vkey()).unwrap();
+        assert_eq!(importer.name(), "snarkjs");
+
+        let pvk = import_verification_key(fixtures::vkey()).unwrap();
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        assert_eq!(pvk, get_prepared_verifying_key(vkey).unwrap());
+    }
+
+    #[test]
+    fn test_import_zokrates() {
+        let mut multiplier = Multiplier::setup();
+        let vkey_str = zokrates_json(multiplier.verifying_key());
+        assert_eq!(detect_dialect(&vkey_str).unwrap().name(), "zokrates");
+
+        let pvk = import_verification_key(&vkey_str).unwrap();
+        let (proof, inputs) = multiplier.prove(3, 11);
+        assert!(verify_proof(pvk, proof, inputs).unwrap());
+    }
+
+    #[test]
+    fn test_import_unknown_dialect() {
+        assert!(detect_dialect(r#"{"format": "other"}"#).is_none());
+        let vkey_str =
+            zokrates_json(Multiplier::setup().verifying_key()).replace(r#""g16""#, r#""gm17""#);
+        assert!(Zokrates.import(&vkey_str).is_err());
+        assert!(import_verification_key(&vkey_str).is_err());
+    }
+}
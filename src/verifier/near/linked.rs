@@ -0,0 +1,129 @@
+// Copyright © 2022, Electron Labs
+
+//! Verification of two proofs whose circuits share values, such as an
+//! output of one circuit that must be an input of the other.
+
+use super::{inputs_from_str, parse_proof, parse_public_inputs};
+use super::{AdapterError, PreparedVerifyingKey};
+use crate::verifier::core::verify_prepared;
+use anyhow::Result;
+
+/// Public input `a` of the first proof must equal public input `b` of the
+/// second.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Link {
+    pub a: usize,
+    pub b: usize,
+}
+
+impl Link {
+    pub fn new(a: usize, b: usize) -> Self {
+        Link { a, b }
+    }
+}
+
+/// Verifies both proofs and checks that the linked public inputs are
+/// equal. The links are checked first, so a mismatch costs no pairings.
+///
+/// # Errors
+/// AdapterError::LinkOutOfRange, AdapterError::LinkMismatch,
+/// VerifierError::ParseError, VerifierError::InvalidFieldElement,
+/// VerifierError::InputCountMismatch
+///
+/// This function will return an error if a link refers to a missing input
+/// or joins unequal inputs, or if a proof or its inputs fail to parse or
+/// can't be checked against its key.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::linked::{verify_linked, Link};
+/// use electron_rs::verifier::near::prepare_verification_key;
+/// use electron_testkit::circom;
+///
+/// let pvk = prepare_verification_key(circom::vkey().to_string()).unwrap();
+/// let proof = circom::proof().to_string();
+/// let inputs = circom::public_inputs().to_string();
+/// let res = verify_linked(
+///     &pvk,
+///     proof.clone(),
+///     inputs.clone(),
+///     &pvk,
+///     proof,
+///     inputs,
+///     &[Link::new(5, 7)],
+/// );
+/// assert!(res.unwrap());
+/// ```
+pub fn verify_linked(
+    pvk_a: &PreparedVerifyingKey,
+    proof_a: String,
+    inputs_a: String,
+    pvk_b: &PreparedVerifyingKey,
+    proof_b: String,
+    inputs_b: String,
+    links: &[Link],
+) -> Result<bool> {
+    let inputs_a: Vec<ark_bn254::Fr> = inputs_from_str(&parse_public_inputs(inputs_a)?)?;
+    let inputs_b: Vec<ark_bn254::Fr> = inputs_from_str(&parse_public_inputs(inputs_b)?)?;
+    for link in links {
+        match (inputs_a.get(link.a), inputs_b.get(link.b)) {
+            (Some(a), Some(b)) if a == b => {}
+            (Some(_), Some(_)) => {
+                return Err(AdapterError::LinkMismatch(link.a, link.b).into());
+            }
+            _ => return Err(AdapterError::LinkOutOfRange(link.a, link.b).into()),
+        }
+    }
+
+    let proof_a = parse_proof(proof_a)?;
+    let proof_b = parse_proof(proof_b)?;
+    Ok(
+        verify_prepared(pvk_a, &proof_a, &inputs_a)?
+            && verify_prepared(pvk_b, &proof_b, &inputs_b)?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures;
+    use super::*;
+
+    fn verify(inputs_b: &str, links: &[Link]) -> Result<bool> {
+        let pvk = fixtures::pvk();
+        verify_linked(
+            &pvk,
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string(),
+            &pvk,
+            fixtures::proof().to_string(),
+            inputs_b.to_string(),
+            links,
+        )
+    }
+
+    #[test]
+    fn test_verify_linked() {
+        let inputs = fixtures::public_inputs();
+        assert!(verify(inputs, &[Link::new(0, 0), Link::new(5, 7)]).unwrap());
+
+        let err = verify(inputs, &[Link::new(1, 2)]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Public input 1 of the first proof doesn't equal input 2 of the second"
+        );
+        let err = verify(inputs, &[Link::new(0, 21)]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Link from input 0 to input 21 is out of range"
+        );
+
+        // Linked inputs are equal but the second proof is invalid.
+        let invalid = inputs.replacen(
+            "139034790179591340742761703217010858871",
+            "139034790179591340742761703217010858872",
+            1,
+        );
+        assert!(!verify(&invalid, &[Link::new(0, 0)]).unwrap());
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright © 2022, Electron Labs
+
+//! Display wrappers for logging keys, proofs and public inputs by
+//! fingerprint instead of by value.
+//!
+//! ```ignore
+//! log!("verifying {} with {}", LoggableProof(&proof_str), LoggableKey(&pvk));
+//! ```
+
+use super::PreparedVerifyingKey;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Number of fingerprint bytes printed by the wrappers.
+const PREFIX_LEN: usize = 4;
+
+pub struct LoggableKey<'a>(pub &'a PreparedVerifyingKey);
+
+pub struct LoggableProof<'a>(pub &'a str);
+
+pub struct LoggableInputs<'a>(pub &'a str);
+
+impl fmt::Display for LoggableKey<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pvk:{} ic={}",
+            hex::encode(&self.0.fingerprint()[..PREFIX_LEN]),
+            self.0.vk.gamma_abc_g1.len()
+        )
+    }
+}
+
+impl fmt::Display for LoggableProof<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "proof:{} len={}", digest_prefix(self.0), self.0.len())
+    }
+}
+
+impl fmt::Display for LoggableInputs<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "inputs:{} len={}", digest_prefix(self.0), self.0.len())
+    }
+}
+
+fn digest_prefix(s: &str) -> String {
+    hex::encode(&Sha256::digest(s.as_bytes())[..PREFIX_LEN])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures;
+    use super::super::{get_prepared_verifying_key, parse_verification_key};
+    use super::*;
+
+    #[test]
+    fn test_loggable_key() {
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        let pvk = get_prepared_verifying_key(vkey).unwrap();
+        let expected = format!("pvk:{} ic=22", hex::encode(&pvk.fingerprint()[..4]));
+        assert_eq!(LoggableKey(&pvk).to_string(), expected);
+    }
+
+    #[test]
+    fn test_loggable_proof_and_inputs() {
+        let proof = LoggableProof(fixtures::proof()).to_string();
+        assert!(proof.starts_with("proof:"));
+        assert!(proof.len() < 32);
+
+        let inputs = LoggableInputs("[\"1\"]").to_string();
+        assert_eq!(inputs, format!("inputs:{} len=5", digest_prefix("[\"1\"]")));
+    }
+}
@@ -1,359 +1,74 @@
 // Copyright © 2022, Electron Labs
 
+use crate::verifier::circom::{g1_from_str, g2_from_str};
+use crate::verifier::core::{check_proof, check_verifying_key, verify_prepared};
+use crate::verifier::json::{self, JsonLimits};
 use anyhow::Result;
-use borsh::{BorshDeserialize, BorshSerialize};
-use near_sdk::serde::Deserialize;
-use serde_json_wasm;
-use std::str::FromStr;
+use ark_ff::PrimeField;
+use serde::Deserialize;
 use thiserror::Error;
 
-#[derive(Error, Debug)]
-pub enum VerifierError {
-    #[error("Failed to parse circom {0} json")]
-    ParseError(String),
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
-struct BigInteger256 {
-    val: [u64; 4],
-}
-
-impl BigInteger256 {
-    pub fn new(src: [u64; 4]) -> Self {
-        BigInteger256 { val: src }
-    }
-}
-
-impl From<BigInteger256> for ark_ff::BigInteger256 {
-    fn from(src: BigInteger256) -> ark_ff::BigInteger256 {
-        ark_ff::BigInteger256::new(src.val)
-    }
-}
-
-impl From<ark_ff::BigInteger256> for BigInteger256 {
-    fn from(src: ark_ff::BigInteger256) -> BigInteger256 {
-        BigInteger256::new(src.0)
-    }
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
-struct Fr {
-    c0: BigInteger256,
-}
-
-impl Fr {
-    pub fn new(src: BigInteger256) -> Self {
-        Fr { c0: src }
-    }
-}
-
-impl From<Fr> for ark_bn254::Fr {
-    fn from(src: Fr) -> ark_bn254::Fr {
-        ark_bn254::Fr::new(src.c0.into())
-    }
-}
-
-impl From<ark_bn254::Fr> for Fr {
-    fn from(src: ark_bn254::Fr) -> Fr {
-        Fr::new(src.0.into())
-    }
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
-struct Fq {
-    c0: BigInteger256,
-}
-
-impl Fq {
-    pub fn new(src: BigInteger256) -> Self {
-        Fq { c0: src }
-    }
-}
-
-impl From<Fq> for ark_bn254::Fq {
-    fn from(src: Fq) -> ark_bn254::Fq {
-        ark_bn254::Fq::new(src.c0.into())
-    }
-}
-
-impl From<ark_bn254::Fq> for Fq {
-    fn from(src: ark_bn254::Fq) -> Fq {
-        Fq::new(src.0.into())
-    }
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
-struct Fq2 {
-    c0: BigInteger256,
-    c1: BigInteger256,
-}
-
-impl Fq2 {
-    pub fn new(c0_: BigInteger256, c1_: BigInteger256) -> Self {
-        Fq2 { c0: c0_, c1: c1_ }
-    }
-}
-
-impl From<Fq2> for ark_bn254::Fq2 {
-    fn from(src: Fq2) -> ark_bn254::Fq2 {
-        let c0: ark_ff::BigInteger256 = src.c0.into();
-        let c1: ark_ff::BigInteger256 = src.c1.into();
-        ark_bn254::Fq2::new(ark_ff::Fp256::new(c0), ark_ff::Fp256::new(c1))
-    }
-}
+pub(crate) use crate::verifier::circom::{coordinate, field_from_str, inputs_from_str};
+pub use crate::verifier::core::compressed::CompressedVerifyingKey;
+pub use crate::verifier::core::{
+    verify_proof_borsh, Fr, PreparedVerifyingKey, Proof, VerifierError, VerifyingKey,
+};
+pub use bls12_381::verify_proof as verify_proof_bls12_381;
+
+#[cfg(feature = "near")]
+pub mod batch;
+pub mod bls12_381;
+pub mod capabilities;
+pub mod chunked;
+#[cfg(feature = "near")]
+pub mod cost;
+pub mod delta;
+pub mod disclosure;
+pub mod gnark;
+pub mod import;
+pub mod linked;
+pub mod logging;
+#[cfg(feature = "near")]
+pub mod multi;
+pub mod normalize;
+pub mod pipeline;
+pub mod public_inputs;
+pub mod staged;
+pub mod trace;
+pub mod trust;
+pub mod upload;
+#[cfg(feature = "native")]
+pub mod zkey;
 
-impl From<ark_bn254::Fq2> for Fq2 {
-    fn from(src: ark_bn254::Fq2) -> Fq2 {
-        Fq2::new(src.c0.0.into(), src.c1.0.into())
-    }
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
-struct Fq6 {
-    c0: Fq2,
-    c1: Fq2,
-    c2: Fq2,
-}
-
-impl Fq6 {
-    pub fn new(c0_: Fq2, c1_: Fq2, c2_: Fq2) -> Self {
-        Fq6 {
-            c0: c0_,
-            c1: c1_,
-            c2: c2_,
-        }
-    }
-}
-
-impl From<Fq6> for ark_bn254::Fq6 {
-    fn from(src: Fq6) -> ark_bn254::Fq6 {
-        let c0: ark_bn254::Fq2 = src.c0.into();
-        let c1: ark_bn254::Fq2 = src.c1.into();
-        let c2: ark_bn254::Fq2 = src.c2.into();
-        ark_bn254::Fq6::new(c0, c1, c2)
-    }
-}
-
-impl From<ark_bn254::Fq6> for Fq6 {
-    fn from(src: ark_bn254::Fq6) -> Fq6 {
-        let c0: ark_bn254::Fq2 = src.c0;
-        let c1: ark_bn254::Fq2 = src.c1;
-        let c2: ark_bn254::Fq2 = src.c2;
-        Fq6::new(c0.into(), c1.into(), c2.into())
-    }
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
-struct Fq12 {
-    c0: Fq6,
-    c1: Fq6,
-}
-
-impl Fq12 {
-    pub fn new(c0_: Fq6, c1_: Fq6) -> Self {
-        Fq12 { c0: c0_, c1: c1_ }
-    }
-}
-
-impl From<Fq12> for ark_bn254::Fq12 {
-    fn from(src: Fq12) -> ark_bn254::Fq12 {
-        let c0: ark_bn254::Fq6 = src.c0.into();
-        let c1: ark_bn254::Fq6 = src.c1.into();
-        ark_bn254::Fq12::new(c0, c1)
-    }
-}
-
-impl From<ark_bn254::Fq12> for Fq12 {
-    fn from(src: ark_bn254::Fq12) -> Fq12 {
-        let c0: ark_bn254::Fq6 = src.c0;
-        let c1: ark_bn254::Fq6 = src.c1;
-        Fq12::new(c0.into(), c1.into())
-    }
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
-struct G1Affine {
-    x: BigInteger256,
-    y: BigInteger256,
-    infinity: bool,
-}
-
-impl G1Affine {
-    pub fn new(x_: BigInteger256, y_: BigInteger256, infinity_: bool) -> Self {
-        G1Affine {
-            x: x_,
-            y: y_,
-            infinity: infinity_,
-        }
-    }
-}
-
-impl From<G1Affine> for ark_bn254::G1Affine {
-    fn from(src: G1Affine) -> ark_bn254::G1Affine {
-        let x: ark_ff::BigInteger256 = src.x.into();
-        let y: ark_ff::BigInteger256 = src.y.into();
-        ark_bn254::G1Affine::new(ark_ff::Fp256::new(x), ark_ff::Fp256::new(y), src.infinity)
-    }
-}
-
-impl From<ark_bn254::G1Affine> for G1Affine {
-    fn from(src: ark_bn254::G1Affine) -> G1Affine {
-        let x: ark_ff::BigInteger256 = src.x.0;
-        let y: ark_ff::BigInteger256 = src.y.0;
-        G1Affine::new(x.into(), y.into(), src.infinity)
-    }
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
-struct G2Affine {
-    x: Fq2,
-    y: Fq2,
-    infinity: bool,
-}
-
-impl G2Affine {
-    pub fn new(x_: Fq2, y_: Fq2, infinity_: bool) -> Self {
-        G2Affine {
-            x: x_,
-            y: y_,
-            infinity: infinity_,
-        }
-    }
-}
-
-impl From<ark_bn254::G2Affine> for G2Affine {
-    fn from(src: ark_bn254::G2Affine) -> G2Affine {
-        let x: ark_bn254::Fq2 = src.x;
-        let y: ark_bn254::Fq2 = src.y;
-        G2Affine::new(x.into(), y.into(), src.infinity)
-    }
-}
-
-impl From<G2Affine> for ark_bn254::G2Affine {
-    fn from(src: G2Affine) -> ark_bn254::G2Affine {
-        let x: ark_bn254::Fq2 = src.x.into();
-        let y: ark_bn254::Fq2 = src.y.into();
-        ark_bn254::G2Affine::new(x, y, src.infinity)
-    }
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
-struct G2Prepared {
-    ell_coeffs: Vec<(Fq2, Fq2, Fq2)>,
-    infinity: bool,
-}
-
-impl G2Prepared {
-    pub fn new(ell_coeffs_: Vec<(Fq2, Fq2, Fq2)>, inf: bool) -> Self {
-        G2Prepared {
-            ell_coeffs: ell_coeffs_,
-            infinity: inf,
-        }
-    }
-}
-
-impl From<ark_ec::bn::G2Prepared<ark_bn254::Parameters>> for G2Prepared {
-    fn from(src: ark_ec::bn::G2Prepared<ark_bn254::Parameters>) -> G2Prepared {
-        let ark_ell_coeffs = src
-            .ell_coeffs
-            .into_iter()
-            .map(|elem| (elem.0, elem.1, elem.2));
-        let ell_coeffs: Vec<(Fq2, Fq2, Fq2)> = ark_ell_coeffs
-            .map(|elem| (elem.0.into(), elem.1.into(), elem.2.into()))
-            .collect();
-        G2Prepared::new(ell_coeffs, src.infinity)
-    }
-}
-
-impl From<G2Prepared> for ark_ec::bn::G2Prepared<ark_bn254::Parameters> {
-    fn from(src: G2Prepared) -> ark_ec::bn::G2Prepared<ark_bn254::Parameters> {
-        let ark_ell_coeffs = src
-            .ell_coeffs
-            .into_iter()
-            .map(|elem| (elem.0.into(), elem.1.into(), elem.2.into()));
-        ark_ec::bn::G2Prepared {
-            ell_coeffs: ark_ell_coeffs
-                .map(|elem| (elem.0, elem.1, elem.2))
-                .collect(),
-            infinity: src.infinity,
-        }
-    }
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
-struct VerifyingKey {
-    alpha_g1: G1Affine,
-    beta_g2: G2Affine,
-    gamma_g2: G2Affine,
-    delta_g2: G2Affine,
-    gamma_abc_g1: Vec<G1Affine>,
-}
-
-impl From<VerifyingKey> for ark_groth16::VerifyingKey<ark_bn254::Bn254> {
-    fn from(src: VerifyingKey) -> ark_groth16::VerifyingKey<ark_bn254::Bn254> {
-        ark_groth16::VerifyingKey {
-            alpha_g1: src.alpha_g1.into(),
-            beta_g2: src.beta_g2.into(),
-            gamma_g2: src.gamma_g2.into(),
-            delta_g2: src.delta_g2.into(),
-            gamma_abc_g1: src
-                .gamma_abc_g1
-                .into_iter()
-                .map(|elem| elem.into())
-                .collect(),
-        }
-    }
-}
-
-impl From<ark_groth16::VerifyingKey<ark_bn254::Bn254>> for VerifyingKey {
-    fn from(src: ark_groth16::VerifyingKey<ark_bn254::Bn254>) -> VerifyingKey {
-        VerifyingKey {
-            alpha_g1: src.alpha_g1.into(),
-            beta_g2: src.beta_g2.into(),
-            gamma_g2: src.gamma_g2.into(),
-            delta_g2: src.delta_g2.into(),
-            gamma_abc_g1: src
-                .gamma_abc_g1
-                .into_iter()
-                .map(|elem| elem.into())
-                .collect(),
-        }
-    }
-}
+#[cfg(test)]
+pub(crate) mod fixtures;
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
-pub struct PreparedVerifyingKey {
-    vk: VerifyingKey,
-    alpha_g1_beta_g2: Fq12,
-    gamma_g2_neg_pc: G2Prepared,
-    delta_g2_neg_pc: G2Prepared,
-}
+/// Curve name snarkjs writes into bn128 vkey and proof json.
+pub const CURVE: &str = "bn128";
 
-impl From<PreparedVerifyingKey> for ark_groth16::PreparedVerifyingKey<ark_bn254::Bn254> {
-    fn from(src: PreparedVerifyingKey) -> ark_groth16::PreparedVerifyingKey<ark_bn254::Bn254> {
-        ark_groth16::PreparedVerifyingKey {
-            vk: src.vk.into(),
-            alpha_g1_beta_g2: src.alpha_g1_beta_g2.into(),
-            gamma_g2_neg_pc: src.gamma_g2_neg_pc.into(),
-            delta_g2_neg_pc: src.delta_g2_neg_pc.into(),
-        }
-    }
-}
-
-impl From<ark_groth16::PreparedVerifyingKey<ark_bn254::Bn254>> for PreparedVerifyingKey {
-    fn from(src: ark_groth16::PreparedVerifyingKey<ark_bn254::Bn254>) -> PreparedVerifyingKey {
-        PreparedVerifyingKey {
-            vk: src.vk.into(),
-            alpha_g1_beta_g2: src.alpha_g1_beta_g2.into(),
-            gamma_g2_neg_pc: src.gamma_g2_neg_pc.into(),
-            delta_g2_neg_pc: src.delta_g2_neg_pc.into(),
-        }
-    }
+/// Failures of the adapter helpers around parsing and verification, such
+/// as key uploads, zkeys, credentials and linked proofs.
+#[derive(Error, Debug)]
+pub enum AdapterError {
+    #[error("Unexpected IC chunk starting at {0}, expected {1}")]
+    UnexpectedIcChunk(u32, u32),
+    #[error("Received {0} of {1} IC points")]
+    IncompleteUpload(u32, u32),
+    #[error("Attribute {0} is not in the credential schema")]
+    UnknownAttribute(String),
+    #[error("No value for attribute {0}")]
+    MissingAttribute(String),
+    #[error("Public input {0} of the first proof doesn't equal input {1} of the second")]
+    LinkMismatch(usize, usize),
+    #[error("Link from input {0} to input {1} is out of range")]
+    LinkOutOfRange(usize, usize),
+    #[error("Invalid zkey: {0}")]
+    InvalidZkey(String),
 }
 
 #[allow(dead_code)]
 #[derive(Deserialize, Clone)]
-#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
 pub struct VerificationKeyJson {
     protocol: String,
     curve: String,
@@ -363,14 +78,18 @@ pub struct VerificationKeyJson {
     vk_beta_2: Vec<Vec<String>>,
     vk_gamma_2: Vec<Vec<String>>,
     vk_delta_2: Vec<Vec<String>>,
-    vk_alphabeta_12: Vec<Vec<Vec<String>>>,
+    /// e(alpha, beta), which newer snarkjs releases omit. It is never read:
+    /// preparing the key always recomputes it from `vk_alpha_1` and
+    /// `vk_beta_2`.
+    #[serde(default)]
+    vk_alphabeta_12: Option<Vec<Vec<Vec<String>>>>,
     #[serde(rename = "IC")]
     ic: Vec<Vec<String>>,
 }
 
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
-#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
 pub struct CircomProofJson {
     pi_a: Vec<String>,
     pi_b: Vec<Vec<String>>,
@@ -380,321 +99,450 @@ pub struct CircomProofJson {
     curve: String,
 }
 
-impl From<CircomProofJson> for ark_groth16::Proof<ark_bn254::Bn254> {
-    fn from(src: CircomProofJson) -> Self {
-        ark_groth16::Proof {
-            a: g1_from_str(&src.pi_a),
-            b: g2_from_str(&src.pi_b),
-            c: g1_from_str(&src.pi_c),
+impl TryFrom<CircomProofJson> for ark_groth16::Proof<ark_bn254::Bn254> {
+    type Error = VerifierError;
+
+    fn try_from(src: CircomProofJson) -> Result<Self, VerifierError> {
+        Ok(ark_groth16::Proof {
+            a: g1_from_str(&src.pi_a, "pi_a")?,
+            b: g2_from_str(&src.pi_b, "pi_b")?,
+            c: g1_from_str(&src.pi_c, "pi_c")?,
+        })
+    }
+}
+
+impl CircomProofJson {
+    fn validate(&self) -> Result<(), VerifierError> {
+        if self.pi_a.len() != 3 {
+            return Err(VerifierError::InvalidProof("pi_a".to_string()));
+        }
+        if self.pi_b.len() != 3 || self.pi_b.iter().any(|c| c.len() != 2) {
+            return Err(VerifierError::InvalidProof("pi_b".to_string()));
+        }
+        if self.pi_c.len() != 3 {
+            return Err(VerifierError::InvalidProof("pi_c".to_string()));
         }
+        Ok(())
     }
 }
 
 fn parse_circom_proof(proof: String) -> Result<CircomProofJson> {
-    let proof = serde_json_wasm::from_str(&proof)
-        .map_err(|_| VerifierError::ParseError("proof".to_string()))?;
+    let proof: CircomProofJson = json::from_slice(proof.as_bytes(), "proof")?;
+    proof.validate()?;
+    Ok(proof)
+}
+
+/// Parses circom proof json and checks its points.
+pub(crate) fn parse_proof(proof: String) -> Result<ark_groth16::Proof<ark_bn254::Bn254>> {
+    let proof: ark_groth16::Proof<ark_bn254::Bn254> = parse_circom_proof(proof)?.try_into()?;
+    check_proof(&proof)?;
     Ok(proof)
 }
 
 fn parse_public_inputs(inputs: String) -> Result<Vec<String>> {
-    let pub_inputs: Vec<String> = serde_json_wasm::from_str(&inputs)
-        .map_err(|_| VerifierError::ParseError("public inputs".to_string()))?;
+    let pub_inputs: Vec<String> = json::from_slice(inputs.as_bytes(), "public inputs")?;
     Ok(pub_inputs)
 }
 
+/// Whether parsing a verification key checks its points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PointChecks {
+    /// Check that every point is on the curve and in the prime order
+    /// subgroup.
+    Full,
+    /// Skip the checks, for keys compiled into the contract or validated
+    /// off chain. Never use this for keys from call arguments.
+    Skip,
+}
+
 /// A helper function to parse raw verification key json returned by circom.
 ///
 /// # Errors
-/// VerifierError::VkeyParseError
+/// VerifierError::ParseError, VerifierError::InvalidFieldElement,
+/// VerifierError::InvalidPoint, VerifierError::InvalidSubgroupPoint
 ///
 /// This function will return an error if it fails to parse the verification
-/// key json file returned by circom.
+/// key json file returned by circom or one of its field elements, or if a
+/// point of the key is not on the curve or not in the prime order subgroup.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::parse_verification_key;
+///
+/// let vkey = parse_verification_key(electron_testkit::circom::vkey().to_string()).unwrap();
+/// assert!(parse_verification_key("{}".to_string()).is_err());
+/// ```
 pub fn parse_verification_key(vkey_str: String) -> Result<VerificationKeyJson> {
-    let vkey = serde_json_wasm::from_str(&vkey_str)
-        .map_err(|_| VerifierError::ParseError("verification key".to_string()))?;
+    parse_verification_key_with(vkey_str, PointChecks::Full)
+}
+
+/// Like `parse_verification_key`, with the point checks chosen by
+/// `checks`.
+///
+/// # Errors
+/// VerifierError::ParseError, VerifierError::InvalidFieldElement,
+/// VerifierError::InvalidPoint, VerifierError::InvalidSubgroupPoint
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::{parse_verification_key_with, PointChecks};
+///
+/// let vkey_str = electron_testkit::circom::vkey().to_string();
+/// let vkey = parse_verification_key_with(vkey_str, PointChecks::Skip).unwrap();
+/// ```
+pub fn parse_verification_key_with(
+    vkey_str: String,
+    checks: PointChecks,
+) -> Result<VerificationKeyJson> {
+    let vkey: VerificationKeyJson = json::from_slice(vkey_str.as_bytes(), "verification key")?;
+    check_vkey_json(&vkey, checks)?;
+    Ok(vkey)
+}
+
+/// Fields of `VerificationKeyJson` that every key must have.
+const REQUIRED_VKEY_FIELDS: [&str; 8] = [
+    "protocol",
+    "curve",
+    "nPublic",
+    "vk_alpha_1",
+    "vk_beta_2",
+    "vk_gamma_2",
+    "vk_delta_2",
+    "IC",
+];
+
+/// Like `parse_verification_key_with`, ignoring fields the key json
+/// doesn't define, such as `nVars` or `power` from some snarkjs releases.
+///
+/// # Errors
+/// VerifierError::ParseError, VerifierError::MissingField,
+/// VerifierError::InvalidFieldElement, VerifierError::InvalidPoint,
+/// VerifierError::InvalidSubgroupPoint
+///
+/// This function will return an error if the json fails to parse, if a
+/// required field is missing, or in the cases of `parse_verification_key`.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::{parse_verification_key_lenient, PointChecks};
+///
+/// let vkey_str = electron_testkit::circom::vkey().replacen('{', r#"{"nVars": 42,"#, 1);
+/// let vkey = parse_verification_key_lenient(vkey_str, PointChecks::Full).unwrap();
+/// ```
+pub fn parse_verification_key_lenient(
+    vkey_str: String,
+    checks: PointChecks,
+) -> Result<VerificationKeyJson> {
+    let parse_error = || VerifierError::ParseError("verification key".to_string());
+    JsonLimits::DEFAULT.check(vkey_str.as_bytes(), "verification key")?;
+    let value: serde_json::Value = serde_json::from_str(&vkey_str).map_err(|_| parse_error())?;
+    let fields = match value {
+        serde_json::Value::Object(fields) => fields,
+        _ => return Err(parse_error().into()),
+    };
+    if let Some(missing) = REQUIRED_VKEY_FIELDS
+        .iter()
+        .find(|field| !fields.contains_key(**field))
+    {
+        return Err(VerifierError::MissingField(missing.to_string()).into());
+    }
+    let known: serde_json::Map<String, serde_json::Value> = fields
+        .into_iter()
+        .filter(|(field, _)| {
+            REQUIRED_VKEY_FIELDS.contains(&field.as_str()) || field == "vk_alphabeta_12"
+        })
+        .collect();
+
+    let vkey: VerificationKeyJson =
+        serde_json::from_value(serde_json::Value::Object(known)).map_err(|_| parse_error())?;
+    check_vkey_json(&vkey, checks)?;
     Ok(vkey)
 }
 
+/// Parses the field elements of a key and checks its points if asked to.
+fn check_vkey_json(vkey: &VerificationKeyJson, checks: PointChecks) -> Result<()> {
+    if vkey.curve == bls12_381::CURVE {
+        bls12_381::check_vkey(vkey, checks)?;
+    } else {
+        let parsed: ark_groth16::VerifyingKey<ark_bn254::Bn254> = vkey.clone().try_into()?;
+        if checks == PointChecks::Full {
+            check_verifying_key(&parsed)?;
+        }
+    }
+    Ok(())
+}
+
 /// A helper function to parse verification key json into a prepared
 /// verifying key.
-pub fn get_prepared_verifying_key(vkey: VerificationKeyJson) -> PreparedVerifyingKey {
-    let parse_vkey: ark_groth16::VerifyingKey<ark_bn254::Bn254> = vkey.into();
-    ark_groth16::prepare_verifying_key(&parse_vkey).into()
+///
+/// # Errors
+/// VerifierError::UnsupportedCurve, VerifierError::InvalidFieldElement
+///
+/// This function will return an error if the key was not generated for
+/// bn128, as `parse_verification_key` also accepts BLS12-381 keys, or if
+/// one of its field elements fails to parse.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::{get_prepared_verifying_key, parse_verification_key};
+///
+/// let vkey = parse_verification_key(electron_testkit::circom::vkey().to_string()).unwrap();
+/// let pvk = get_prepared_verifying_key(vkey).unwrap();
+/// ```
+pub fn get_prepared_verifying_key(vkey: VerificationKeyJson) -> Result<PreparedVerifyingKey> {
+    if vkey.curve != CURVE {
+        return Err(VerifierError::UnsupportedCurve(vkey.curve).into());
+    }
+    let parse_vkey: ark_groth16::VerifyingKey<ark_bn254::Bn254> = vkey.try_into()?;
+    Ok(ark_groth16::prepare_verifying_key(&parse_vkey).into())
+}
+
+/// Parses and prepares circom verification key json in one step.
+///
+/// # Errors
+/// VerifierError::ParseError, VerifierError::UnsupportedCurve,
+/// VerifierError::InvalidFieldElement, VerifierError::InvalidPoint,
+/// VerifierError::InvalidSubgroupPoint
+///
+/// This function will return an error in the same cases as
+/// `parse_verification_key`, or if the key is not a bn128 key.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::prepare_verification_key;
+///
+/// let pvk = prepare_verification_key(electron_testkit::circom::vkey().to_string()).unwrap();
+/// ```
+pub fn prepare_verification_key(vkey_str: String) -> Result<PreparedVerifyingKey> {
+    get_prepared_verifying_key(parse_verification_key(vkey_str)?)
+}
+
+/// Compresses verification key json for storage. Store the result instead
+/// of a `PreparedVerifyingKey` when keeping many keys, and prepare it with
+/// `get_prepared_verifying_key_compressed` when a proof arrives.
+///
+/// # Errors
+/// VerifierError::UnsupportedCurve, VerifierError::InvalidFieldElement
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::{
+///     compress_verification_key, get_prepared_verifying_key_compressed, parse_verification_key,
+/// };
+///
+/// let vkey = parse_verification_key(electron_testkit::circom::vkey().to_string()).unwrap();
+/// let compressed = compress_verification_key(vkey).unwrap();
+/// let pvk = get_prepared_verifying_key_compressed(&compressed).unwrap();
+/// ```
+pub fn compress_verification_key(vkey: VerificationKeyJson) -> Result<CompressedVerifyingKey> {
+    if vkey.curve != CURVE {
+        return Err(VerifierError::UnsupportedCurve(vkey.curve).into());
+    }
+    let parse_vkey: ark_groth16::VerifyingKey<ark_bn254::Bn254> = vkey.try_into()?;
+    Ok(parse_vkey.into())
+}
+
+/// Decompresses a stored key and prepares it for verification.
+///
+/// # Errors
+/// VerifierError::InvalidEncoding
+///
+/// This function will return an error if a point of the key fails to
+/// decompress.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::{
+///     compress_verification_key, get_prepared_verifying_key_compressed, parse_verification_key,
+///     verify_proof,
+/// };
+/// use electron_testkit::circom;
+///
+/// let vkey = parse_verification_key(circom::vkey().to_string()).unwrap();
+/// let compressed = compress_verification_key(vkey).unwrap();
+/// let pvk = get_prepared_verifying_key_compressed(&compressed).unwrap();
+/// let inputs = circom::public_inputs().to_string();
+/// assert!(verify_proof(pvk, circom::proof().to_string(), inputs).unwrap());
+/// ```
+pub fn get_prepared_verifying_key_compressed(
+    vkey: &CompressedVerifyingKey,
+) -> Result<PreparedVerifyingKey> {
+    let parse_vkey = vkey.decompress()?;
+    Ok(ark_groth16::prepare_verifying_key(&parse_vkey).into())
 }
 
 /// A helper function to verify proof
+///
+/// # Errors
+/// VerifierError::ParseError, VerifierError::InvalidProof,
+/// VerifierError::InvalidFieldElement, VerifierError::InvalidPoint,
+/// VerifierError::InvalidSubgroupPoint, VerifierError::SynthesisError
+///
+/// This function will return an error if the proof or public inputs fail to
+/// parse, if a proof point is not on the curve or not in the prime order
+/// subgroup, or if the proof can't be checked against the verifying key. A
+/// well-formed proof that doesn't verify returns `Ok(false)`.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::{prepare_verification_key, verify_proof};
+/// use electron_testkit::circom;
+///
+/// let pvk = prepare_verification_key(circom::vkey().to_string()).unwrap();
+/// let proof = circom::proof().to_string();
+/// let inputs = circom::public_inputs().to_string();
+/// assert!(verify_proof(pvk.clone(), proof.clone(), inputs).unwrap());
+///
+/// let wrong_inputs = circom::public_inputs().replacen("\"1\"", "\"2\"", 1);
+/// assert!(!verify_proof(pvk, proof, wrong_inputs).unwrap());
+/// ```
 pub fn verify_proof(
     pvk: PreparedVerifyingKey,
     proof_str: String,
     pub_inputs_str: String,
 ) -> Result<bool> {
-    let proof = parse_circom_proof(proof_str)?;
+    let proof = parse_proof(proof_str)?;
     let pub_inputs = parse_public_inputs(pub_inputs_str)?;
-    let ark_pub_inputs: Vec<ark_bn254::Fr> = pub_inputs.into_iter().map(fr_from_str).collect();
-
-    // TODO: Convert this to a proper error type of Bolt-rs
-    let res = ark_groth16::verify_proof(&pvk.into(), &proof.into(), &ark_pub_inputs[..]).unwrap();
+    let ark_pub_inputs: Vec<ark_bn254::Fr> = inputs_from_str(&pub_inputs)?;
 
-    Ok(res)
+    Ok(verify_prepared(&pvk, &proof, &ark_pub_inputs)?)
 }
 
-fn fq_from_str(s: String) -> ark_bn254::Fq {
-    ark_bn254::Fq::from_str(&s).unwrap()
+/// Converts circom proof and public inputs json into the borsh types taken
+/// by `verify_proof_borsh`. Meant to run off chain, so that the contract
+/// doesn't pay for parsing json.
+///
+/// # Errors
+/// VerifierError::ParseError, VerifierError::InvalidProof,
+/// VerifierError::InvalidFieldElement, VerifierError::InvalidPoint,
+/// VerifierError::InvalidSubgroupPoint
+///
+/// This function will return an error if the proof or public inputs fail to
+/// parse, or if a proof point is invalid.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::core::verify_proof_borsh;
+/// use electron_rs::verifier::near::{prepare_verification_key, proof_to_borsh};
+/// use electron_testkit::circom;
+///
+/// let (proof, inputs) =
+///     proof_to_borsh(circom::proof().to_string(), circom::public_inputs().to_string()).unwrap();
+/// let pvk = prepare_verification_key(circom::vkey().to_string()).unwrap();
+/// assert!(verify_proof_borsh(pvk, proof, inputs).unwrap());
+/// ```
+pub fn proof_to_borsh(proof_str: String, pub_inputs_str: String) -> Result<(Proof, Vec<Fr>)> {
+    let proof = parse_proof(proof_str)?;
+    let pub_inputs = parse_public_inputs(pub_inputs_str)?;
+    let ark_pub_inputs: Vec<ark_bn254::Fr> = inputs_from_str(&pub_inputs)?;
+    Ok((
+        proof.into(),
+        ark_pub_inputs.into_iter().map(Fr::from).collect(),
+    ))
 }
 
-pub fn fr_from_str(s: String) -> ark_bn254::Fr {
-    ark_bn254::Fr::from_str(&s).unwrap()
+/// Parses a scalar written in decimal or `0x` hex, which must be below
+/// the scalar modulus in either encoding.
+///
+/// # Errors
+/// VerifierError::InvalidFieldElement
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::fr_from_str;
+///
+/// let decimal = fr_from_str("255".to_string()).unwrap();
+/// assert_eq!(decimal, fr_from_str("0xff".to_string()).unwrap());
+/// assert!(fr_from_str("abc".to_string()).is_err());
+/// ```
+pub fn fr_from_str(s: String) -> Result<ark_bn254::Fr, VerifierError> {
+    field_from_str(&s, || format!("{:?}", s))
 }
 
-fn g1_from_str(g1: &[String]) -> ark_bn254::G1Affine {
-    let x = fq_from_str(g1[0].clone());
-    let y = fq_from_str(g1[1].clone());
-    let z = fq_from_str(g1[2].clone());
-    ark_bn254::G1Affine::from(ark_bn254::G1Projective::new(x, y, z))
+/// Decimal string of a field element, as used in circom json.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::{fr_from_str, fr_to_string};
+///
+/// let fr = fr_from_str("0xff".to_string()).unwrap();
+/// assert_eq!(fr_to_string(fr), "255");
+/// ```
+pub fn fr_to_string(fr: ark_bn254::Fr) -> String {
+    repr_to_string(&fr.into_repr().0)
 }
 
-fn g2_from_str(g2: &[Vec<String>]) -> ark_bn254::G2Affine {
-    let c0 = fq_from_str(g2[0][0].clone());
-    let c1 = fq_from_str(g2[0][1].clone());
-    let x = ark_bn254::Fq2::new(c0, c1);
-
-    let c0 = fq_from_str(g2[1][0].clone());
-    let c1 = fq_from_str(g2[1][1].clone());
-    let y = ark_bn254::Fq2::new(c0, c1);
-
-    let c0 = fq_from_str(g2[2][0].clone());
-    let c1 = fq_from_str(g2[2][1].clone());
-    let z = ark_bn254::Fq2::new(c0, c1);
-
-    ark_bn254::G2Affine::from(ark_bn254::G2Projective::new(x, y, z))
+/// Decimal string of little-endian 64-bit limbs.
+pub(crate) fn repr_to_string(repr: &[u64]) -> String {
+    const CHUNK: u128 = 10_000_000_000_000_000_000;
+    let mut limbs = repr.to_vec();
+    let mut chunks = Vec::new();
+    while limbs.iter().any(|limb| *limb != 0) {
+        let mut rem: u128 = 0;
+        for limb in limbs.iter_mut().rev() {
+            let cur = (rem << 64) | *limb as u128;
+            *limb = (cur / CHUNK) as u64;
+            rem = cur % CHUNK;
+        }
+        chunks.push(rem as u64);
+    }
+    match chunks.split_last() {
+        None => "0".to_string(),
+        Some((first, rest)) => rest.iter().rev().fold(first.to_string(), |mut s, chunk| {
+            s.push_str(&format!("{:019}", chunk));
+            s
+        }),
+    }
 }
 
-impl From<VerificationKeyJson> for ark_groth16::VerifyingKey<ark_bn254::Bn254> {
-    fn from(src: VerificationKeyJson) -> Self {
-        let alpha_g1_ = g1_from_str(&src.vk_alpha_1);
-        let beta_g2_ = g2_from_str(&src.vk_beta_2);
-        let gamma_g2_ = g2_from_str(&src.vk_gamma_2);
-        let delta_g2_ = g2_from_str(&src.vk_delta_2);
+impl TryFrom<VerificationKeyJson> for ark_groth16::VerifyingKey<ark_bn254::Bn254> {
+    type Error = VerifierError;
 
-        let gamma_abc_g1_: Vec<ark_bn254::G1Affine> =
-            src.ic.iter().map(|x| g1_from_str(x)).collect();
+    fn try_from(src: VerificationKeyJson) -> Result<Self, VerifierError> {
+        let alpha_g1_ = g1_from_str(&src.vk_alpha_1, "vk_alpha_1")?;
+        let beta_g2_ = g2_from_str(&src.vk_beta_2, "vk_beta_2")?;
+        let gamma_g2_ = g2_from_str(&src.vk_gamma_2, "vk_gamma_2")?;
+        let delta_g2_ = g2_from_str(&src.vk_delta_2, "vk_delta_2")?;
 
-        ark_groth16::VerifyingKey {
+        let gamma_abc_g1_: Vec<ark_bn254::G1Affine> = src
+            .ic
+            .iter()
+            .enumerate()
+            .map(|(i, x)| g1_from_str(x, &format!("IC[{}]", i)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(ark_groth16::VerifyingKey {
             alpha_g1: alpha_g1_,
             beta_g2: beta_g2_,
             gamma_g2: gamma_g2_,
             delta_g2: delta_g2_,
             gamma_abc_g1: gamma_abc_g1_,
-        }
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    fn get_vkey() -> &'static str {
-        r#"
-        {
-            "protocol": "groth16",
-            "curve": "bn128",
-            "nPublic": 21,
-            "vk_alpha_1": [
-             "20491192805390485299153009773594534940189261866228447918068658471970481763042",
-             "9383485363053290200918347156157836566562967994039712273449902621266178545958",
-             "1"
-            ],
-            "vk_beta_2": [
-             [
-              "6375614351688725206403948262868962793625744043794305715222011528459656738731",
-              "4252822878758300859123897981450591353533073413197771768651442665752259397132"
-             ],
-             [
-              "10505242626370262277552901082094356697409835680220590971873171140371331206856",
-              "21847035105528745403288232691147584728191162732299865338377159692350059136679"
-             ],
-             [
-              "1",
-              "0"
-             ]
-            ],
-            "vk_gamma_2": [
-             [
-              "10857046999023057135944570762232829481370756359578518086990519993285655852781",
-              "11559732032986387107991004021392285783925812861821192530917403151452391805634"
-             ],
-             [
-              "8495653923123431417604973247489272438418190587263600148770280649306958101930",
-              "4082367875863433681332203403145435568316851327593401208105741076214120093531"
-             ],
-             [
-              "1",
-              "0"
-             ]
-            ],
-            "vk_delta_2": [
-             [
-              "166438788818422684353143109466712365495487529761282054253940311767202847529",
-              "14821889692288092546390398853883577003395705920427691037003877337111307008319"
-             ],
-             [
-              "5211044291848451570308359449705497730711843248959818951644537468318735026319",
-              "3349759874590271776701023934351541831283252450166481144436728710799565826635"
-             ],
-             [
-              "1",
-              "0"
-             ]
-            ],
-            "vk_alphabeta_12": [
-             [
-              [
-               "2029413683389138792403550203267699914886160938906632433982220835551125967885",
-               "21072700047562757817161031222997517981543347628379360635925549008442030252106"
-              ],
-              [
-               "5940354580057074848093997050200682056184807770593307860589430076672439820312",
-               "12156638873931618554171829126792193045421052652279363021382169897324752428276"
-              ],
-              [
-               "7898200236362823042373859371574133993780991612861777490112507062703164551277",
-               "7074218545237549455313236346927434013100842096812539264420499035217050630853"
-              ]
-             ],
-             [
-              [
-               "7077479683546002997211712695946002074877511277312570035766170199895071832130",
-               "10093483419865920389913245021038182291233451549023025229112148274109565435465"
-              ],
-              [
-               "4595479056700221319381530156280926371456704509942304414423590385166031118820",
-               "19831328484489333784475432780421641293929726139240675179672856274388269393268"
-              ],
-              [
-               "11934129596455521040620786944827826205713621633706285934057045369193958244500",
-               "8037395052364110730298837004334506829870972346962140206007064471173334027475"
-              ]
-             ]
-            ],
-            "IC": [
-             [
-              "19975645442203377055504350944199411205645925605842881710313661501103970826593",
-              "17515161622283010384423259590087060433422690594791060414171309961412819784969",
-              "1"
-             ],
-             [
-              "8314529012362679498714409542216060373647165806213078732764739247682086265767",
-              "121366207716244222195924313927761544312158108247873731042786280646943184074",
-              "1"
-             ],
-             [
-              "16709720837782968526180617884167855231344603866174025119200385206304701258678",
-              "3147822512060247213265367088074297137791420360497197470911250310113275037763",
-              "1"
-             ],
-             [
-              "14216723210244410575876418879665374598747581482663712212010511617392597830954",
-              "15811996758528967218865995673654714048570588460636125402018277656651434631576",
-              "1"
-             ],
-             [
-              "7348238908009886871059992732128931157271697524606274111411455960455037416413",
-              "14001472805890407823397893627240743988837305207489952388063413323698861707624",
-              "1"
-             ],
-             [
-              "2138882192497635891459717929673559440104769163700828386965661447497938982721",
-              "5186793583243682306353927402481196491547812815293709454908025411581465445004",
-              "1"
-             ],
-             [
-              "2116764452247307873087707246637130330345204236852642632713114592476993977670",
-              "14896161713831569254989869822450928542555444355351318861266435690413316845347",
-              "1"
-             ],
-             [
-              "16392430006950202355682918247811738427580100868571691215288876389925500647279",
-              "19437084047439114680241004405825353549565621104782399561893962443338240135858",
-              "1"
-             ],
-             [
-              "16963065381115919041780779888616737843143206987161162977928288398707149790618",
-              "9087066945988971374305861013885116715721320414719802148300649773920118102481",
-              "1"
-             ],
-             [
-              "13714673228950478504452201663230221577251226934030004828193127473877480610295",
-              "9332072320101623120415187992550525752876274301602491265535702933221101004380",
-              "1"
-             ],
-             [
-              "1064045990922553586834518447367936820175319540784875187573912133883165188670",
-              "18287981330912970040426745735838860702735392209815444404076135459948276202848",
-              "1"
-             ],
-             [
-              "9210826867500141415001909980706988517816622370128886786816673451224513701503",
-              "3651094788905360180553273507287364045940819368096000322156684552199804097143",
-              "1"
-             ],
-             [
-              "17720362295505313322759315353391656693108343058592864160681048989141882794083",
-              "10097671657793855671159749436121468469201270375403582850205385628210921488731",
-              "1"
-             ],
-             [
-              "9801543874486422221954003660705098546171144064277720948049325854942931758306",
-              "20479944074043794678092216875190551894013835948904068657881623722226189539016",
-              "1"
-             ],
-             [
-              "5374663040433250412848838440386505484894911153493652424898166227177046711199",
-              "13679665179607144765496503536099360866217236185602567461732884358192393872279",
-              "1"
-             ],
-             [
-              "1064329530975255434535409396597644022861254752006703233721201637345800440139",
-              "5140009461438788926486789050955593582109349287858692508879168080077367120629",
-              "1"
-             ],
-             [
-              "15366436033551689602012357199098419434258945123964889817106842055644617190504",
-              "898268788386333715715903230667785887632210104432209295828625929694299885006",
-              "1"
-             ],
-             [
-              "5625417729666095139456177838606211212046421091440422619829111829213675828978",
-              "18455517249670178543137281808225159109856379895586238312217422816116366743603",
-              "1"
-             ],
-             [
-              "17537235019815029148949517328224734386526017513684721827218738801833451783210",
-              "2342105886191919519714066767578407697780765722350456533494274069027087830216",
-              "1"
-             ],
-             [
-              "8512191115799353035296472708809096858085180357544392842547774011355858433041",
-              "2541245043439530389724749443817975569327264943016202232800605721736943199048",
-              "1"
-             ],
-             [
-              "19224585989189727449965872368330162278522031170641583311558474979239173678715",
-              "18166021891232232834725962994255689261693690030629187665379835418854223722023",
-              "1"
-             ],
-             [
-              "14017181509831449693830612331037537298674425286306310710534048602053149127774",
-              "330831566870832606085453648362982294226755734586757078631724647552023101374",
-              "1"
-             ]
-            ]
-        }
-        "#
-    }
+    use crate::verifier::core::{BigInteger256, G1Affine};
+    use crate::verifier::json::JsonError;
+    use ark_ff::{BigInteger, Field};
+    use borsh::{BorshDeserialize, BorshSerialize};
 
     #[test]
     fn test_parse_valid_verification_key() {
-        let vkey_str = get_vkey();
+        let vkey_str = fixtures::vkey();
         let vkey = parse_verification_key(vkey_str.to_string()).unwrap();
         assert_eq!(vkey.protocol, "groth16");
         assert_eq!(vkey.curve, "bn128");
@@ -804,9 +652,9 @@ mod tests {
 
     #[test]
     fn test_prepared_verification_key() {
-        let vkey_str = get_vkey();
+        let vkey_str = fixtures::vkey();
         let vkey = parse_verification_key(vkey_str.to_string()).unwrap();
-        let prepared_vkey = get_prepared_verifying_key(vkey);
+        let prepared_vkey = get_prepared_verifying_key(vkey).unwrap();
         let x: BigInteger256 = BigInteger256::new([
             129941079445278231,
             14986904513597369283,
@@ -824,6 +672,97 @@ mod tests {
         assert_eq!(g1, prepared_vkey.vk.alpha_g1);
     }
 
+    #[test]
+    fn test_bls12_381_key_is_not_prepared_as_bn128() {
+        let (vkey_str, _, _) = bls12_381::test_json::circom_json(bls12_381::CURVE);
+        let vkey = parse_verification_key(vkey_str.clone()).unwrap();
+        let unsupported = "Unsupported curve bls12381";
+        let res = get_prepared_verifying_key(vkey.clone());
+        assert_eq!(res.err().unwrap().to_string(), unsupported);
+        let res = compress_verification_key(vkey);
+        assert_eq!(res.err().unwrap().to_string(), unsupported);
+        let res = prepare_verification_key(vkey_str);
+        assert_eq!(res.err().unwrap().to_string(), unsupported);
+    }
+
+    #[test]
+    fn test_prepared_verification_key_fingerprint() {
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        let prepared_vkey = get_prepared_verifying_key(vkey.clone()).unwrap();
+        let other = get_prepared_verifying_key(vkey).unwrap();
+        assert_eq!(prepared_vkey.fingerprint(), other.fingerprint());
+        assert_ne!(prepared_vkey.fingerprint(), [0u8; 32]);
+    }
+
+    #[test]
+    fn test_parse_verification_key_lenient() {
+        let extended = fixtures::vkey().replacen('{', r#"{"nVars": 42, "power": 7,"#, 1);
+        let vkey = parse_verification_key_lenient(extended, PointChecks::Full).unwrap();
+        let expected = get_prepared_verifying_key(
+            parse_verification_key(fixtures::vkey().to_string()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            get_prepared_verifying_key(vkey).unwrap().fingerprint(),
+            expected.fingerprint()
+        );
+
+        let missing = fixtures::vkey().replace("vk_delta_2", "vk_delta");
+        let res = parse_verification_key_lenient(missing, PointChecks::Full);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Verification key is missing field vk_delta_2"
+        );
+    }
+
+    #[test]
+    fn test_verification_key_without_alphabeta() {
+        let full = fixtures::vkey();
+        let start = full.find("\"vk_alphabeta_12\"").unwrap();
+        let end = full.find("\"IC\"").unwrap();
+        let trimmed = format!("{}{}", &full[..start], &full[end..]);
+
+        let vkey = parse_verification_key(trimmed).unwrap();
+        assert!(vkey.vk_alphabeta_12.is_none());
+        let expected =
+            get_prepared_verifying_key(parse_verification_key(full.to_string()).unwrap()).unwrap();
+        assert_eq!(
+            get_prepared_verifying_key(vkey).unwrap().fingerprint(),
+            expected.fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_prepared_verification_key_compressed() {
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        let compressed = compress_verification_key(vkey.clone()).unwrap();
+        let stored =
+            CompressedVerifyingKey::try_from_slice(&compressed.try_to_vec().unwrap()).unwrap();
+        let prepared_vkey = get_prepared_verifying_key_compressed(&stored).unwrap();
+        assert_eq!(prepared_vkey, get_prepared_verifying_key(vkey).unwrap());
+
+        let res = verify_proof(
+            prepared_vkey,
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string(),
+        )
+        .unwrap();
+        assert!(res);
+    }
+
+    #[test]
+    fn test_fr_to_string() {
+        for s in [
+            "0",
+            "1",
+            "10000000000000000000",
+            "277989581668086710587965336712738880284",
+            "21888242871839275222246405745257275088548364400416034343698204186575808495616",
+        ] {
+            assert_eq!(fr_to_string(fr_from_str(s.to_string()).unwrap()), s);
+        }
+    }
+
     #[test]
     fn test_parse_public_input() {
         let pub_input_str = r#"[
@@ -839,71 +778,175 @@ mod tests {
 
     #[test]
     fn test_valid_proof_snarkjs() {
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        let prepared_vkey = get_prepared_verifying_key(vkey).unwrap();
+
+        let res = verify_proof(
+            prepared_vkey,
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string(),
+        );
+        assert!(res.unwrap());
+    }
+
+    #[test]
+    fn test_malformed_proof() {
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        let prepared_vkey = get_prepared_verifying_key(vkey).unwrap();
         let proof_str = r#"
         {
-            "pi_a": [
-              "20198676790799425245595459194274498752473994950719073183074649501711660535595",
-              "12758475309915023533579531485441554907458299575042834087971469653289637732346",
-              "1"
-            ],
-            "pi_b": [
-              [
-                "13742117572560123711123425096963974481037753438772131102525214062174465939468",
-                "9217768357543713672348398426848893195759877300475465964741673960918197283129"
-              ],
-              [
-                "13388985823083338129254299703944286332336674476925977438789020739020226493083",
-                "13389941977815367065802562753053209214146349395284722106316234427940539426898"
-              ],
-              [
-                "1",
-                "0"
-              ]
-            ],
-            "pi_c": [
-              "5988936190268741469108357726405145464702633179533876088993318355641592876129",
-              "15053058905266236652562457399329328685910831643948235107886315836157181001907",
-              "1"
-            ],
-            "protocol": "groth16",
-            "curve": "bn128"
+            "pi_a": ["1", "2"],
+            "pi_b": [["1", "0"], ["1", "0"], ["1", "0"]],
+            "pi_c": ["1", "2", "1"],
+            "protocol": "groth16"
         }
         "#;
-        let pub_input_str = r#"
-        [
-            "1",
-            "139034790179591340742761703217010858871",
-            "178747724383637324525799708680472596098",
-            "249730154399878769526315894913495941533",
-            "339453732354324016397146782775657558721",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052"
-        ]
-        "#;
-        let vkey_str = get_vkey();
-        let vkey = parse_verification_key(vkey_str.to_string()).unwrap();
-        let prepared_vkey = get_prepared_verifying_key(vkey);
 
         let res = verify_proof(
             prepared_vkey,
             proof_str.to_string(),
-            pub_input_str.to_string(),
+            fixtures::public_inputs().to_string(),
         );
-        assert!(res.unwrap());
+        assert_eq!(res.err().unwrap().to_string(), "Invalid proof: pi_a");
+    }
+
+    #[test]
+    fn test_input_count_mismatch() {
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        let prepared_vkey = get_prepared_verifying_key(vkey).unwrap();
+
+        let res = verify_proof(
+            prepared_vkey,
+            fixtures::proof().to_string(),
+            r#"["1", "2"]"#.to_string(),
+        );
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Verifying key takes 21 public inputs, got 2"
+        );
+    }
+
+    #[test]
+    fn test_hex_field_elements() {
+        let fifteen = ark_bn254::Fr::from(15u64);
+        assert_eq!(fr_from_str("0x0f".to_string()).unwrap(), fifteen);
+        assert_eq!(fr_from_str("0xF".to_string()).unwrap(), fifteen);
+        assert_eq!(fr_from_str("0x000f".to_string()).unwrap(), fifteen);
+        assert!(fr_from_str("0x".to_string()).is_err());
+        assert!(fr_from_str("0xfg".to_string()).is_err());
+        // The modulus itself is not reduced.
+        let modulus: String = ark_bn254::Fr::characteristic()
+            .iter()
+            .rev()
+            .map(|limb| format!("{:016x}", limb))
+            .collect();
+        assert!(fr_from_str(format!("0x{}", modulus)).is_err());
+
+        let dec = "20198676790799425245595459194274498752473994950719073183074649501711660535595";
+        let fq: ark_bn254::Fq = field_from_str(dec, String::new).unwrap();
+        let hex = format!("0x{}", hex::encode(fq.into_repr().to_bytes_be()));
+        let proof_str = fixtures::proof().replace(dec, &hex);
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        let res = verify_proof(
+            get_prepared_verifying_key(vkey).unwrap(),
+            proof_str,
+            fixtures::public_inputs().to_string(),
+        )
+        .unwrap();
+        assert!(res);
+    }
+
+    #[test]
+    fn test_decimal_field_elements_are_not_reduced() {
+        let r = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+        let r_plus_one =
+            "21888242871839275222246405745257275088548364400416034343698204186575808495618";
+        let r_minus_one =
+            "21888242871839275222246405745257275088548364400416034343698204186575808495616";
+        assert!(fr_from_str(r.to_string()).is_err());
+        // Used to be reduced to 1.
+        assert!(fr_from_str(r_plus_one.to_string()).is_err());
+        assert_eq!(
+            fr_from_str(r_minus_one.to_string()).unwrap(),
+            -ark_bn254::Fr::from(1u64)
+        );
+        assert!(fr_from_str("-1".to_string()).is_err());
+        assert!(fr_from_str("".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_malformed_field_element() {
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        let prepared_vkey = get_prepared_verifying_key(vkey).unwrap();
+        let proof_str = fixtures::proof().replace(
+            "12758475309915023533579531485441554907458299575042834087971469653289637732346",
+            "12758475309915023533579531485441554907458299575042834087971469653289637732x46",
+        );
+        let res = verify_proof(
+            prepared_vkey.clone(),
+            proof_str,
+            fixtures::public_inputs().to_string(),
+        );
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Invalid field element pi_a[1]"
+        );
+
+        let mut inputs = parse_public_inputs(fixtures::public_inputs().to_string()).unwrap();
+        inputs[3] = "abc".to_string();
+        let res = verify_proof(
+            prepared_vkey,
+            fixtures::proof().to_string(),
+            serde_json_wasm::to_string(&inputs).unwrap(),
+        );
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Invalid field element public input 3"
+        );
+
+        let vkey_str = fixtures::vkey().replace(
+            "9383485363053290200918347156157836566562967994039712273449902621266178545958",
+            "",
+        );
+        let res = parse_verification_key(vkey_str);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Invalid field element vk_alpha_1[1]"
+        );
+    }
+
+    #[test]
+    fn test_proof_point_not_on_curve() {
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        let prepared_vkey = get_prepared_verifying_key(vkey).unwrap();
+        let proof_str = fixtures::proof().replace(
+            "12758475309915023533579531485441554907458299575042834087971469653289637732346",
+            "1",
+        );
+
+        let res = verify_proof(
+            prepared_vkey,
+            proof_str,
+            fixtures::public_inputs().to_string(),
+        );
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Point pi_a is not on the curve"
+        );
+    }
+
+    #[test]
+    fn test_vkey_point_not_on_curve() {
+        let vkey_str = fixtures::vkey().replace(
+            "9383485363053290200918347156157836566562967994039712273449902621266178545958",
+            "1",
+        );
+        let res = parse_verification_key(vkey_str.clone());
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Point vk_alpha_1 is not on the curve"
+        );
+        assert!(parse_verification_key_with(vkey_str, PointChecks::Skip).is_ok());
     }
 
     #[test]
@@ -937,40 +980,75 @@ mod tests {
             "protocol": "groth16"
         }
         "#;
-        let pub_input_str = r#"
-        [
-            "1",
-            "139034790179591340742761703217010858871",
-            "178747724383637324525799708680472596098",
-            "249730154399878769526315894913495941533",
-            "339453732354324016397146782775657558721",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052",
-            "208326850591216812292393721318634961999",
-            "28902942442541169865286267622270965052"
-        ]
-        "#;
-        let vkey_str = get_vkey();
+        let vkey_str = fixtures::vkey();
         let vkey = parse_verification_key(vkey_str.to_string()).unwrap();
-        let prepared_vkey = get_prepared_verifying_key(vkey);
+        let prepared_vkey = get_prepared_verifying_key(vkey).unwrap();
 
         let res = verify_proof(
             prepared_vkey,
             proof_str.to_string(),
-            pub_input_str.to_string(),
+            fixtures::public_inputs().to_string(),
         );
         assert!(res.unwrap());
     }
+
+    #[test]
+    fn test_valid_proof_testkit_multiplier() {
+        let mut multiplier = electron_testkit::multiplier::Multiplier::setup();
+        let vkey = parse_verification_key(multiplier.vkey_json()).unwrap();
+        let prepared_vkey = get_prepared_verifying_key(vkey).unwrap();
+
+        let (proof_str, inputs_str) = multiplier.prove(3, 5);
+        assert!(verify_proof(prepared_vkey.clone(), proof_str.clone(), inputs_str).unwrap());
+        assert!(!verify_proof(prepared_vkey, proof_str, r#"["16"]"#.to_string()).unwrap());
+    }
+
+    #[test]
+    fn test_valid_proof_borsh() {
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        let prepared_vkey = get_prepared_verifying_key(vkey).unwrap();
+
+        let (proof, pub_inputs) = proof_to_borsh(
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string(),
+        )
+        .unwrap();
+        let bytes = (proof, pub_inputs).try_to_vec().unwrap();
+        let (proof, pub_inputs) = <(Proof, Vec<Fr>)>::try_from_slice(&bytes).unwrap();
+        assert!(verify_proof_borsh(prepared_vkey, proof, pub_inputs).unwrap());
+    }
+
+    #[test]
+    fn test_fixtures_fit_default_limits() {
+        for json in [
+            fixtures::vkey(),
+            fixtures::proof(),
+            fixtures::public_inputs(),
+        ] {
+            assert!(JsonLimits::DEFAULT.check(json.as_bytes(), "test").is_ok());
+        }
+    }
+
+    #[test]
+    fn test_parsers_reject_deep_nesting() {
+        // Deep enough to overflow the stack of a recursive parser.
+        let payload = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        let err = parse_public_inputs(payload.clone()).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<JsonError>(),
+            Some(JsonError::TooDeep(_, _))
+        ));
+        assert!(parse_proof(format!(r#"{{"pi_a": {}}}"#, payload)).is_err());
+        assert!(parse_verification_key(payload).is_err());
+    }
+
+    #[test]
+    fn test_parsers_reject_large_payloads() {
+        let payload = format!("[\"{}\"]", "1".repeat(JsonLimits::DEFAULT.max_len));
+        let err = parse_public_inputs(payload).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<JsonError>(),
+            Some(JsonError::TooLarge(_, _))
+        ));
+    }
 }
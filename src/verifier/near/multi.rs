@@ -0,0 +1,237 @@
+// Copyright © 2022, Electron Labs
+
+//! Keys of different curves side by side in one contract.
+//!
+//! [`AnyPreparedVerifyingKey`] holds a prepared BN254 or BLS12-381 key and
+//! dispatches on its curve, so registries, storage and events don't need a
+//! copy per curve. Its borsh encoding starts with the curve tag, followed by
+//! the key, and the key json picks the curve:
+//!
+//! ```ignore
+//! let pvk = prepare_any_verification_key(vkey_str)?;
+//! let verified = pvk.verify_proof(proof_str, inputs_str)?;
+//! log_verification("age", &pvk, verified);
+//! ```
+
+use super::{bls12_381, get_prepared_verifying_key, parse_verification_key, VerifierError, CURVE};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::env;
+use near_sdk::serde_json::json;
+
+/// Curves a contract can verify proofs over.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Curve {
+    Bn254,
+    Bls12_381,
+}
+
+impl Curve {
+    /// Maps the `curve` of snarkjs json onto a curve.
+    ///
+    /// # Errors
+    /// VerifierError::UnsupportedCurve
+    pub fn from_snarkjs(curve: &str) -> Result<Curve, VerifierError> {
+        match curve {
+            CURVE => Ok(Curve::Bn254),
+            bls12_381::CURVE => Ok(Curve::Bls12_381),
+            _ => Err(VerifierError::UnsupportedCurve(curve.to_string())),
+        }
+    }
+
+    /// The curve name snarkjs writes, used as the tag in events.
+    pub fn snarkjs_name(&self) -> &'static str {
+        match self {
+            Curve::Bn254 => CURVE,
+            Curve::Bls12_381 => bls12_381::CURVE,
+        }
+    }
+}
+
+/// A prepared verifying key over any supported curve.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub enum AnyPreparedVerifyingKey {
+    Bn254(super::PreparedVerifyingKey),
+    Bls12_381(bls12_381::PreparedVerifyingKey),
+}
+
+impl From<super::PreparedVerifyingKey> for AnyPreparedVerifyingKey {
+    fn from(pvk: super::PreparedVerifyingKey) -> Self {
+        AnyPreparedVerifyingKey::Bn254(pvk)
+    }
+}
+
+impl From<bls12_381::PreparedVerifyingKey> for AnyPreparedVerifyingKey {
+    fn from(pvk: bls12_381::PreparedVerifyingKey) -> Self {
+        AnyPreparedVerifyingKey::Bls12_381(pvk)
+    }
+}
+
+impl AnyPreparedVerifyingKey {
+    pub fn curve(&self) -> Curve {
+        match self {
+            AnyPreparedVerifyingKey::Bn254(_) => Curve::Bn254,
+            AnyPreparedVerifyingKey::Bls12_381(_) => Curve::Bls12_381,
+        }
+    }
+
+    /// Fingerprint of the key itself, the same as that of the key of the
+    /// curve, so BN254 fingerprints don't change when a contract adds
+    /// BLS12-381 keys.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        match self {
+            AnyPreparedVerifyingKey::Bn254(pvk) => pvk.fingerprint(),
+            AnyPreparedVerifyingKey::Bls12_381(pvk) => pvk.fingerprint(),
+        }
+    }
+
+    /// Verifies circom proof and public inputs json with the verifier of
+    /// the key's curve.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the cases of
+    /// `verifier::near::verify_proof` and `bls12_381::verify_proof`.
+    pub fn verify_proof(&self, proof_str: String, pub_inputs_str: String) -> Result<bool> {
+        match self {
+            AnyPreparedVerifyingKey::Bn254(pvk) => {
+                super::verify_proof(pvk.clone(), proof_str, pub_inputs_str)
+            }
+            AnyPreparedVerifyingKey::Bls12_381(pvk) => {
+                bls12_381::verify_proof(pvk.clone(), proof_str, pub_inputs_str)
+            }
+        }
+    }
+}
+
+/// A helper function to parse verification key json over any supported
+/// curve into a prepared key.
+///
+/// # Errors
+/// VerifierError::ParseError, VerifierError::UnsupportedCurve,
+/// VerifierError::InvalidFieldElement, VerifierError::InvalidPoint,
+/// VerifierError::InvalidSubgroupPoint
+pub fn prepare_any_verification_key(vkey_str: String) -> Result<AnyPreparedVerifyingKey> {
+    let vkey = parse_verification_key(vkey_str)?;
+    Ok(match Curve::from_snarkjs(&vkey.curve)? {
+        Curve::Bn254 => get_prepared_verifying_key(vkey)?.into(),
+        Curve::Bls12_381 => bls12_381::get_prepared_verifying_key(vkey)?.into(),
+    })
+}
+
+/// The NEP-297 event logged by `log_verification`.
+pub fn verification_event(
+    circuit_id: &str,
+    pvk: &AnyPreparedVerifyingKey,
+    verified: bool,
+) -> String {
+    let event = json!({
+        "standard": "groth16",
+        "version": "1.0.0",
+        "event": "proof_verified",
+        "data": [{
+            "circuit_id": circuit_id,
+            "curve": pvk.curve().snarkjs_name(),
+            "fingerprint": hex::encode(pvk.fingerprint()),
+            "verified": verified,
+        }],
+    });
+    format!("EVENT_JSON:{}", event)
+}
+
+/// Logs a verification as a NEP-297 event tagged with the curve, so
+/// indexers can tell the verifiers of one contract apart.
+pub fn log_verification(circuit_id: &str, pvk: &AnyPreparedVerifyingKey, verified: bool) {
+    env::log_str(&verification_event(circuit_id, pvk, verified));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures;
+    use super::*;
+    use crate::components::registry::VkeyRegistry;
+    use crate::hash::limbs::LimbLayout;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{near_bindgen, testing_env, PanicOnDefault};
+
+    /// One deployment verifying both curves through one registry.
+    #[near_bindgen]
+    #[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
+    struct MultiCurveContract {
+        registry: VkeyRegistry,
+    }
+
+    #[near_bindgen]
+    impl MultiCurveContract {
+        #[init]
+        pub fn new() -> Self {
+            MultiCurveContract {
+                registry: VkeyRegistry::new(b"r".to_vec()),
+            }
+        }
+
+        pub fn register(&mut self, circuit_id: String, vkey: String) {
+            self.registry
+                .register_json(&circuit_id, vkey, LimbLayout::default())
+                .unwrap();
+        }
+
+        pub fn verify(&self, circuit_id: String, proof: String, inputs: String) -> bool {
+            let entry = self.registry.get(&circuit_id).unwrap();
+            let verified = entry.pvk.verify_proof(proof, inputs).unwrap();
+            log_verification(&circuit_id, &entry.pvk, verified);
+            verified
+        }
+    }
+
+    #[test]
+    fn test_curve_names() {
+        assert_eq!(Curve::from_snarkjs("bn128").unwrap(), Curve::Bn254);
+        assert_eq!(Curve::from_snarkjs("bls12381").unwrap(), Curve::Bls12_381);
+        assert!(Curve::from_snarkjs("bls12377").is_err());
+        assert_eq!(Curve::Bls12_381.snarkjs_name(), "bls12381");
+    }
+
+    #[test]
+    fn test_both_curves_in_one_contract() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut contract = MultiCurveContract::new();
+        let (bls_vkey, bls_proof, bls_inputs) = bls12_381::test_json::circom_json(bls12_381::CURVE);
+        contract.register("age".to_string(), fixtures::vkey().to_string());
+        contract.register("vote".to_string(), bls_vkey);
+
+        assert!(contract.verify(
+            "age".to_string(),
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string()
+        ));
+        assert!(contract.verify("vote".to_string(), bls_proof.clone(), bls_inputs));
+        assert!(!contract.verify("vote".to_string(), bls_proof, r#"["16"]"#.to_string()));
+
+        let events: Vec<near_sdk::serde_json::Value> = get_logs()
+            .iter()
+            .map(|log| near_sdk::serde_json::from_str(&log["EVENT_JSON:".len()..]).unwrap())
+            .collect();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0]["data"][0]["circuit_id"], "age");
+        assert_eq!(events[0]["data"][0]["curve"], "bn128");
+        assert_eq!(events[1]["data"][0]["curve"], "bls12381");
+        assert_eq!(events[2]["data"][0]["verified"], false);
+    }
+
+    #[test]
+    fn test_borsh_starts_with_curve_tag() {
+        let bn = prepare_any_verification_key(fixtures::vkey().to_string()).unwrap();
+        let (bls_vkey, _, _) = bls12_381::test_json::circom_json(bls12_381::CURVE);
+        let bls = prepare_any_verification_key(bls_vkey).unwrap();
+        assert_eq!(bn.try_to_vec().unwrap()[0], 0);
+        assert_eq!(bls.try_to_vec().unwrap()[0], 1);
+
+        let restored = AnyPreparedVerifyingKey::try_from_slice(&bls.try_to_vec().unwrap()).unwrap();
+        assert_eq!(restored.curve(), Curve::Bls12_381);
+        assert_eq!(restored.fingerprint(), bls.fingerprint());
+        if let AnyPreparedVerifyingKey::Bn254(pvk) = &bn {
+            assert_eq!(bn.fingerprint(), pvk.fingerprint());
+        }
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright © 2022, Electron Labs
+
+//! Normalization of verification keys exported by different snarkjs
+//! versions.
+//!
+//! Older releases spell `alpha` as `alfa`, name the protocol `groth`, and
+//! don't always emit `curve`, `nPublic` or `vk_alphabeta_12`. All of these
+//! are mapped onto the canonical [`VerificationKeyJson`].
+
+use super::{json, VerificationKeyJson, VerifierError};
+use anyhow::Result;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct RawVerificationKeyJson {
+    protocol: String,
+    #[serde(default)]
+    curve: Option<String>,
+    #[serde(rename = "nPublic", default)]
+    num_public: Option<u64>,
+    #[serde(alias = "vk_alfa_1")]
+    vk_alpha_1: Vec<String>,
+    vk_beta_2: Vec<Vec<String>>,
+    vk_gamma_2: Vec<Vec<String>>,
+    vk_delta_2: Vec<Vec<String>>,
+    #[serde(alias = "vk_alfabeta_12", default)]
+    vk_alphabeta_12: Option<Vec<Vec<Vec<String>>>>,
+    #[serde(rename = "IC")]
+    ic: Vec<Vec<String>>,
+}
+
+/// A helper function to parse a verification key json exported by any
+/// snarkjs version into the canonical representation.
+///
+/// # Errors
+///
+/// This function will return an error if the json fails to parse, or if the
+/// key is not a groth16 key over bn128.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::normalize::normalize_verification_key;
+///
+/// let legacy = electron_testkit::circom::vkey().replace("vk_alpha_1", "vk_alfa_1");
+/// let vkey = normalize_verification_key(legacy).unwrap();
+/// ```
+pub fn normalize_verification_key(vkey_str: String) -> Result<VerificationKeyJson> {
+    let raw: RawVerificationKeyJson = json::from_slice(vkey_str.as_bytes(), "verification key")?;
+
+    let protocol = match raw.protocol.to_lowercase().as_str() {
+        "groth" | "groth16" => "groth16".to_string(),
+        _ => return Err(VerifierError::UnsupportedProtocol(raw.protocol).into()),
+    };
+    let curve = match raw.curve {
+        None => "bn128".to_string(),
+        Some(curve) => match curve.to_lowercase().as_str() {
+            "bn128" | "bn254" | "alt_bn128" => "bn128".to_string(),
+            _ => return Err(VerifierError::UnsupportedCurve(curve).into()),
+        },
+    };
+    let num_public = match raw.num_public {
+        Some(num_public) => num_public,
+        None => raw.ic.len().saturating_sub(1) as u64,
+    };
+
+    Ok(VerificationKeyJson {
+        protocol,
+        curve,
+        num_public,
+        vk_alpha_1: raw.vk_alpha_1,
+        vk_beta_2: raw.vk_beta_2,
+        vk_gamma_2: raw.vk_gamma_2,
+        vk_delta_2: raw.vk_delta_2,
+        vk_alphabeta_12: raw.vk_alphabeta_12,
+        ic: raw.ic,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures;
+    use super::super::get_prepared_verifying_key;
+    use super::*;
+
+    fn canonical_fingerprint() -> [u8; 32] {
+        let vkey = normalize_verification_key(fixtures::vkey().to_string()).unwrap();
+        get_prepared_verifying_key(vkey).unwrap().fingerprint()
+    }
+
+    #[test]
+    fn test_normalize_canonical_key() {
+        let vkey = normalize_verification_key(fixtures::vkey().to_string()).unwrap();
+        assert_eq!(vkey.protocol, "groth16");
+        assert_eq!(vkey.curve, "bn128");
+        assert_eq!(vkey.num_public, 21);
+    }
+
+    #[test]
+    fn test_normalize_legacy_key() {
+        let legacy = fixtures::vkey()
+            .replace("\"groth16\"", "\"groth\"")
+            .replace("\"curve\": \"bn128\",", "")
+            .replace("\"nPublic\": 21,", "")
+            .replace("vk_alpha_1", "vk_alfa_1")
+            .replace("vk_alphabeta_12", "vk_alfabeta_12");
+        let vkey = normalize_verification_key(legacy).unwrap();
+        assert_eq!(vkey.protocol, "groth16");
+        assert_eq!(vkey.curve, "bn128");
+        assert_eq!(vkey.num_public, 21);
+        assert_eq!(
+            get_prepared_verifying_key(vkey).unwrap().fingerprint(),
+            canonical_fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_normalize_casing_and_curve_alias() {
+        let vkey_str = fixtures::vkey()
+            .replace("\"groth16\"", "\"Groth16\"")
+            .replace("\"bn128\"", "\"BN254\"");
+        let vkey = normalize_verification_key(vkey_str).unwrap();
+        assert_eq!(vkey.protocol, "groth16");
+        assert_eq!(vkey.curve, "bn128");
+    }
+
+    #[test]
+    fn test_normalize_unsupported_protocol() {
+        let vkey_str = fixtures::vkey().replace("\"groth16\"", "\"plonk\"");
+        let err = normalize_verification_key(vkey_str).err().unwrap();
+        assert_eq!(err.to_string(), "Unsupported protocol plonk");
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright © 2022, Electron Labs
+
+//! Declarative pre-verification mappers for public inputs.
+//!
+//! A pipeline is a list of [`InputMapper`]s applied in order to the raw
+//! public inputs before verification, so one deployed verifier can accept
+//! inputs from clients that encode them differently. Pipelines serialize
+//! with both borsh and json, so they can be stored next to a key.
+
+use super::{
+    fr_to_string, json, parse_public_inputs, verify_proof, PreparedVerifyingKey, VerifierError,
+};
+use crate::verifier::circom::field_from_decimal;
+use anyhow::Result;
+use ark_ff::{BigInteger, BigInteger256, Field, PrimeField};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+pub enum InputMapper {
+    /// Accepts `0x`-prefixed big-endian hex inputs.
+    HexToFr,
+    /// Reverses the bytes of every input read as a 32-byte integer.
+    SwapEndianness,
+    /// Merges every `count` consecutive limbs of `bits` bits, most
+    /// significant first, into one input.
+    MergeLimbs { bits: u32, count: u32 },
+    /// Rejects inputs that don't fit in `bits` bits.
+    CheckBits { bits: u32 },
+}
+
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+pub struct InputPipeline {
+    pub mappers: Vec<InputMapper>,
+}
+
+impl InputPipeline {
+    pub fn new(mappers: Vec<InputMapper>) -> Self {
+        InputPipeline { mappers }
+    }
+
+    /// Applies every mapper in order and returns the inputs as decimal
+    /// strings.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error naming the first input a mapper
+    /// can't be applied to.
+    pub fn apply(&self, mut inputs: Vec<String>) -> Result<Vec<String>> {
+        for mapper in &self.mappers {
+            inputs = mapper.apply(inputs)?;
+        }
+        Ok(inputs)
+    }
+
+    /// Maps the public inputs json through the pipeline, then verifies.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the inputs fail to parse or
+    /// map, or if verification fails with an error.
+    pub fn verify(
+        &self,
+        pvk: PreparedVerifyingKey,
+        proof_str: String,
+        pub_inputs_str: String,
+    ) -> Result<bool> {
+        let inputs = self.apply(parse_public_inputs(pub_inputs_str)?)?;
+        verify_proof(pvk, proof_str, json::to_string(&inputs, "public inputs")?)
+    }
+}
+
+impl InputMapper {
+    fn apply(&self, inputs: Vec<String>) -> Result<Vec<String>, VerifierError> {
+        match self {
+            InputMapper::HexToFr => inputs
+                .iter()
+                .enumerate()
+                .map(|(i, input)| match input.strip_prefix("0x") {
+                    Some(digits) => Ok(fr_to_string(fr_from_hex(i, digits)?)),
+                    None => Ok(input.clone()),
+                })
+                .collect(),
+            InputMapper::SwapEndianness => inputs
+                .iter()
+                .enumerate()
+                .map(|(i, input)| {
+                    let mut bytes = parse_fr(i, input)?.into_repr().to_bytes_be();
+                    bytes.reverse();
+                    Ok(fr_to_string(fr_from_be_bytes(i, &bytes)?))
+                })
+                .collect(),
+            InputMapper::MergeLimbs { bits, count } => {
+                let (bits, count) = (*bits, *count as usize);
+                if count == 0 || bits as usize * count > 253 || inputs.len() % count != 0 {
+                    return Err(mapping_error(0, "limb layout doesn't fit the inputs"));
+                }
+                let shift = ark_bn254::Fr::from(2u64).pow([bits as u64]);
+                let mut merged = Vec::with_capacity(inputs.len() / count);
+                for (group, limbs) in inputs.chunks(count).enumerate() {
+                    let mut acc = ark_bn254::Fr::from(0u64);
+                    for (j, limb) in limbs.iter().enumerate() {
+                        let i = group * count + j;
+                        let limb = check_bits(i, parse_fr(i, limb)?, bits)?;
+                        acc = acc * shift + limb;
+                    }
+                    merged.push(fr_to_string(acc));
+                }
+                Ok(merged)
+            }
+            InputMapper::CheckBits { bits } => {
+                for (i, input) in inputs.iter().enumerate() {
+                    check_bits(i, parse_fr(i, input)?, *bits)?;
+                }
+                Ok(inputs)
+            }
+        }
+    }
+}
+
+fn mapping_error(index: usize, reason: &str) -> VerifierError {
+    VerifierError::InputMapping(index, reason.to_string())
+}
+
+fn parse_fr(index: usize, s: &str) -> Result<ark_bn254::Fr, VerifierError> {
+    field_from_decimal(s).ok_or_else(|| mapping_error(index, "not a decimal field element"))
+}
+
+fn check_bits(index: usize, fr: ark_bn254::Fr, bits: u32) -> Result<ark_bn254::Fr, VerifierError> {
+    if fr.into_repr().num_bits() > bits {
+        return Err(mapping_error(index, "input exceeds the limb size"));
+    }
+    Ok(fr)
+}
+
+fn fr_from_hex(index: usize, digits: &str) -> Result<ark_bn254::Fr, VerifierError> {
+    let padded = if digits.len() % 2 == 1 {
+        format!("0{}", digits)
+    } else {
+        digits.to_string()
+    };
+    let bytes = hex::decode(padded).map_err(|_| mapping_error(index, "invalid hex"))?;
+    fr_from_be_bytes(index, &bytes)
+}
+
+fn fr_from_be_bytes(index: usize, bytes: &[u8]) -> Result<ark_bn254::Fr, VerifierError> {
+    let bytes = match bytes.iter().position(|b| *b != 0) {
+        Some(start) => &bytes[start..],
+        None => &[],
+    };
+    if bytes.len() > 32 {
+        return Err(mapping_error(index, "input exceeds the field modulus"));
+    }
+    let mut limbs = [0u64; 4];
+    for (k, byte) in bytes.iter().rev().enumerate() {
+        limbs[k / 8] |= (*byte as u64) << (8 * (k % 8));
+    }
+    ark_bn254::Fr::from_repr(BigInteger256::new(limbs))
+        .ok_or_else(|| mapping_error(index, "input exceeds the field modulus"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(inputs: &[&str]) -> Vec<String> {
+        inputs.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_hex_to_fr() {
+        let pipeline = InputPipeline::new(vec![InputMapper::HexToFr]);
+        let inputs = pipeline.apply(strings(&["0x0a", "0xFf", "7"])).unwrap();
+        assert_eq!(inputs, strings(&["10", "255", "7"]));
+    }
+
+    #[test]
+    fn test_swap_endianness() {
+        let pipeline = InputPipeline::new(vec![InputMapper::SwapEndianness]);
+        // 2^248 is 0x01 followed by 31 zero bytes.
+        let inputs = pipeline.apply(strings(&["1"])).unwrap();
+        assert_eq!(
+            inputs,
+            strings(&[
+                "452312848583266388373324160190187140051835877600158453279131187530910662656"
+            ])
+        );
+        assert_eq!(pipeline.apply(inputs).unwrap(), strings(&["1"]));
+    }
+
+    #[test]
+    fn test_merge_limbs() {
+        let pipeline = InputPipeline::new(vec![InputMapper::MergeLimbs { bits: 8, count: 2 }]);
+        let inputs = pipeline.apply(strings(&["1", "2", "0", "255"])).unwrap();
+        assert_eq!(inputs, strings(&["258", "255"]));
+        assert!(pipeline.apply(strings(&["256", "0"])).is_err());
+        assert!(pipeline.apply(strings(&["1"])).is_err());
+    }
+
+    #[test]
+    fn test_check_bits() {
+        let pipeline = InputPipeline::new(vec![InputMapper::CheckBits { bits: 128 }]);
+        assert!(pipeline
+            .apply(strings(&["340282366920938463463374607431768211455"]))
+            .is_ok());
+        let err = pipeline
+            .apply(strings(&["1", "340282366920938463463374607431768211456"]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Failed to map public input 1: input exceeds the limb size"
+        );
+    }
+
+    #[test]
+    fn test_pipeline_json() {
+        let pipeline = InputPipeline::new(vec![
+            InputMapper::HexToFr,
+            InputMapper::MergeLimbs {
+                bits: 128,
+                count: 2,
+            },
+        ]);
+        let json = serde_json_wasm::to_string(&pipeline).unwrap();
+        assert_eq!(
+            json,
+            r#"{"mappers":[{"type":"hex_to_fr"},{"type":"merge_limbs","bits":128,"count":2}]}"#
+        );
+        let parsed: InputPipeline = serde_json_wasm::from_str(&json).unwrap();
+        assert_eq!(parsed, pipeline);
+    }
+}
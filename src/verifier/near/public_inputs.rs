@@ -0,0 +1,200 @@
+// Copyright © 2022, Electron Labs
+
+use super::{fr_to_string, inputs_from_str, json, parse_public_inputs};
+use crate::hash::limbs::LimbLayout;
+use anyhow::Result;
+use ark_ff::{BigInteger, PrimeField};
+use sha3::{Digest, Keccak256};
+
+/// Public inputs of a proof as field elements.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PublicInputs(Vec<ark_bn254::Fr>);
+
+impl PublicInputs {
+    pub fn new(inputs: Vec<ark_bn254::Fr>) -> Self {
+        PublicInputs(inputs)
+    }
+
+    /// Parses the public inputs json returned by circom.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the json or one of the inputs
+    /// fails to parse.
+    pub fn parse(pub_inputs_str: String) -> Result<Self> {
+        let pub_inputs = parse_public_inputs(pub_inputs_str)?;
+        Ok(PublicInputs(inputs_from_str(&pub_inputs)?))
+    }
+
+    pub fn as_slice(&self) -> &[ark_bn254::Fr] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Canonical encoding: every input as a 32-byte big-endian integer, in
+    /// order. This matches `abi.encodePacked(uint256[])` on Ethereum.
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        self.0
+            .iter()
+            .flat_map(|input| input.into_repr().to_bytes_be())
+            .collect()
+    }
+
+    /// Chain-agnostic 32-byte id of the statement: the keccak256 of the
+    /// canonical encoding.
+    pub fn message_id(&self) -> [u8; 32] {
+        Keccak256::digest(&self.to_bytes_be()).into()
+    }
+}
+
+impl From<Vec<ark_bn254::Fr>> for PublicInputs {
+    fn from(src: Vec<ark_bn254::Fr>) -> Self {
+        PublicInputs::new(src)
+    }
+}
+
+/// Builds public inputs from typed values, in circuit order.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::public_inputs::PublicInputsBuilder;
+///
+/// let mut builder = PublicInputsBuilder::new();
+/// builder.push_bool(true).push_u128(7).push_hash_as_limbs(&[0u8; 32]);
+/// assert_eq!(builder.to_json().unwrap(), r#"["1","7","0","0"]"#);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct PublicInputsBuilder {
+    inputs: Vec<ark_bn254::Fr>,
+    layout: LimbLayout,
+}
+
+impl PublicInputsBuilder {
+    pub fn new() -> Self {
+        PublicInputsBuilder::default()
+    }
+
+    /// A builder packing hashes in the limb layout of the target circuit.
+    pub fn with_layout(layout: LimbLayout) -> Self {
+        PublicInputsBuilder {
+            inputs: Vec::new(),
+            layout,
+        }
+    }
+
+    pub fn push_fr(&mut self, fr: ark_bn254::Fr) -> &mut Self {
+        self.inputs.push(fr);
+        self
+    }
+
+    pub fn push_u128(&mut self, value: u128) -> &mut Self {
+        self.push_fr(value.into())
+    }
+
+    /// A 32-byte hash as two 128-bit limbs in the builder's layout, since a
+    /// hash doesn't fit in one BN254 scalar.
+    pub fn push_hash_as_limbs(&mut self, hash: &[u8; 32]) -> &mut Self {
+        self.inputs.extend_from_slice(&self.layout.split(hash));
+        self
+    }
+
+    /// A 20-byte Ethereum address as one input.
+    pub fn push_address(&mut self, address: &[u8; 20]) -> &mut Self {
+        self.push_fr(ark_bn254::Fr::from_be_bytes_mod_order(address))
+    }
+
+    pub fn push_bool(&mut self, value: bool) -> &mut Self {
+        self.push_u128(value as u128)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    pub fn build(&self) -> PublicInputs {
+        PublicInputs::new(self.inputs.clone())
+    }
+
+    /// Public inputs as the json string accepted by `verify_proof`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the inputs fail to serialize.
+    pub fn to_json(&self) -> Result<String> {
+        let inputs: Vec<String> = self.inputs.iter().copied().map(fr_to_string).collect();
+        Ok(json::to_string(&inputs, "public inputs")?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::limbs::{Endianness, LimbOrder};
+
+    #[test]
+    fn test_canonical_encoding() {
+        let inputs = PublicInputs::parse(r#"["1", "2"]"#.to_string()).unwrap();
+        let bytes = inputs.to_bytes_be();
+        assert_eq!(bytes.len(), 64);
+        assert_eq!(bytes[31], 1);
+        assert_eq!(bytes[63], 2);
+        assert!(bytes[..31].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_message_id() {
+        let inputs = PublicInputs::parse(r#"["1", "2"]"#.to_string()).unwrap();
+        assert_eq!(
+            hex::encode(inputs.message_id()),
+            "e90b7bceb6e7df5418fb78d8ee546e97c83a08bbccc01a0644d599ccd2a7c2e0"
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let mut hash = [0u8; 32];
+        hash[15] = 1;
+        hash[31] = 2;
+        let mut address = [0u8; 20];
+        address[19] = 0xff;
+
+        let mut builder = PublicInputsBuilder::new();
+        builder
+            .push_u128(u128::MAX)
+            .push_hash_as_limbs(&hash)
+            .push_address(&address)
+            .push_bool(true);
+        assert_eq!(builder.len(), 5);
+        assert_eq!(
+            builder.to_json().unwrap(),
+            r#"["340282366920938463463374607431768211455","1","2","255","1"]"#
+        );
+        assert_eq!(
+            PublicInputs::parse(builder.to_json().unwrap()).unwrap(),
+            builder.build()
+        );
+    }
+
+    #[test]
+    fn test_builder_layout() {
+        let mut hash = [0u8; 32];
+        hash[15] = 1;
+        hash[31] = 2;
+        let mut builder =
+            PublicInputsBuilder::with_layout(LimbLayout::new(LimbOrder::LowHigh, Endianness::Big));
+        builder.push_hash_as_limbs(&hash);
+        assert_eq!(builder.to_json().unwrap(), r#"["2","1"]"#);
+    }
+}
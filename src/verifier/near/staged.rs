@@ -0,0 +1,201 @@
+// Copyright © 2022, Electron Labs
+
+//! Groth16 verification split into stages that can run in separate
+//! transactions.
+//!
+//! [`ChunkedVerification`] splits the miller loop but prepares all public
+//! inputs up front, which is a scalar multiplication per input and doesn't
+//! fit in one call for circuits with many inputs. [`StagedVerification`]
+//! also folds the inputs in chunks:
+//!
+//! ```ignore
+//! let mut staged = StagedVerification::new(&pvk, proof, inputs)?;
+//! while !staged.prepare_inputs(&pvk, 8) {}          // one call each
+//! while !staged.miller_loop_part(&pvk, 16)? {}     // one call each
+//! let valid = staged.final_exponentiation(&pvk)?;
+//! ```
+//!
+//! The state is borsh-serializable and can be stored between calls.
+
+use super::chunked::ChunkedVerification;
+use super::{inputs_from_str, parse_proof, parse_public_inputs};
+use crate::verifier::core::{
+    check_input_count, Fr, G1Affine, G2Affine, PreparedVerifyingKey, VerifierError,
+};
+use anyhow::Result;
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::PrimeField;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct StagedVerification {
+    a: G1Affine,
+    b: G2Affine,
+    c: G1Affine,
+    pub_inputs: Vec<Fr>,
+    acc: G1Affine,
+    next_input: u32,
+    miller_loop: Option<ChunkedVerification>,
+}
+
+impl StagedVerification {
+    /// Parses the proof and public inputs.
+    ///
+    /// # Errors
+    /// VerifierError::ParseError, VerifierError::InvalidProof,
+    /// VerifierError::InvalidFieldElement, VerifierError::InputCountMismatch
+    ///
+    /// This function will return an error if the proof or public inputs fail
+    /// to parse, or if the number of inputs doesn't match the key.
+    pub fn new(
+        pvk: &PreparedVerifyingKey,
+        proof_str: String,
+        pub_inputs_str: String,
+    ) -> Result<Self> {
+        let proof = parse_proof(proof_str)?;
+        let pub_inputs = parse_public_inputs(pub_inputs_str)?;
+        check_input_count(pvk.vk.gamma_abc_g1.len(), pub_inputs.len())?;
+        let ark_pub_inputs: Vec<ark_bn254::Fr> = inputs_from_str(&pub_inputs)?;
+
+        Ok(StagedVerification {
+            a: proof.a.into(),
+            b: proof.b.into(),
+            c: proof.c.into(),
+            pub_inputs: ark_pub_inputs.into_iter().map(Fr::from).collect(),
+            acc: pvk.vk.gamma_abc_g1[0].clone(),
+            next_input: 0,
+            miller_loop: None,
+        })
+    }
+
+    /// Folds at most `max_inputs` further public inputs into the prepared
+    /// inputs point and returns whether all inputs are folded.
+    pub fn prepare_inputs(&mut self, pvk: &PreparedVerifyingKey, max_inputs: u32) -> bool {
+        if self.miller_loop.is_some() {
+            return true;
+        }
+        let start = self.next_input as usize;
+        let end = (start + max_inputs as usize).min(self.pub_inputs.len());
+
+        let acc: ark_bn254::G1Affine = self.acc.clone().into();
+        let mut acc = acc.into_projective();
+        for i in start..end {
+            // Written by `new` from parsed field elements.
+            let input = ark_bn254::Fr::try_from(self.pub_inputs[i].clone())
+                .expect("Staged public inputs are field elements");
+            let base: ark_bn254::G1Affine = pvk.vk.gamma_abc_g1[i + 1].clone().into();
+            acc += &base.mul(input.into_repr());
+        }
+        let acc = acc.into_affine();
+        self.acc = acc.into();
+        self.next_input = end as u32;
+
+        if end == self.pub_inputs.len() {
+            let proof = ark_groth16::Proof {
+                a: self.a.clone().into(),
+                b: self.b.clone().into(),
+                c: self.c.clone().into(),
+            };
+            self.miller_loop = Some(ChunkedVerification::from_prepared_inputs(&proof, acc));
+            return true;
+        }
+        false
+    }
+
+    /// Runs at most `max_steps` further steps of the miller loop and returns
+    /// whether the loop is complete.
+    ///
+    /// # Errors
+    /// VerifierError::IncompleteInputPreparation
+    ///
+    /// This function will return an error if the inputs aren't prepared yet.
+    pub fn miller_loop_part(&mut self, pvk: &PreparedVerifyingKey, max_steps: u32) -> Result<bool> {
+        let miller_loop = self
+            .miller_loop
+            .as_mut()
+            .ok_or(VerifierError::IncompleteInputPreparation)?;
+        Ok(miller_loop.resume(pvk, max_steps))
+    }
+
+    /// Runs the final exponentiation and checks the result against the key.
+    ///
+    /// # Errors
+    /// VerifierError::IncompleteInputPreparation,
+    /// VerifierError::IncompleteMillerLoop
+    ///
+    /// This function will return an error if an earlier stage isn't
+    /// complete.
+    pub fn final_exponentiation(&self, pvk: &PreparedVerifyingKey) -> Result<bool> {
+        let miller_loop = self
+            .miller_loop
+            .as_ref()
+            .ok_or(VerifierError::IncompleteInputPreparation)?;
+        miller_loop.finish(pvk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures;
+    use super::*;
+
+    fn round_trip(staged: StagedVerification) -> StagedVerification {
+        let bytes = staged.try_to_vec().unwrap();
+        StagedVerification::try_from_slice(&bytes).unwrap()
+    }
+
+    fn run(pvk: &PreparedVerifyingKey, inputs: String) -> bool {
+        let mut staged =
+            StagedVerification::new(pvk, fixtures::proof().to_string(), inputs).unwrap();
+        while !staged.prepare_inputs(pvk, 5) {
+            staged = round_trip(staged);
+        }
+        while !staged.miller_loop_part(pvk, 20).unwrap() {
+            staged = round_trip(staged);
+        }
+        round_trip(staged).final_exponentiation(pvk).unwrap()
+    }
+
+    #[test]
+    fn test_staged_verification() {
+        let pvk = fixtures::pvk();
+        assert!(run(&pvk, fixtures::public_inputs().to_string()));
+    }
+
+    #[test]
+    fn test_staged_verification_wrong_input() {
+        let pvk = fixtures::pvk();
+        let inputs = fixtures::public_inputs().replacen("\"1\"", "\"2\"", 1);
+        assert!(!run(&pvk, inputs));
+    }
+
+    #[test]
+    fn test_stages_out_of_order() {
+        let pvk = fixtures::pvk();
+        let mut staged = StagedVerification::new(
+            &pvk,
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string(),
+        )
+        .unwrap();
+        assert!(!staged.prepare_inputs(&pvk, 1));
+        assert_eq!(
+            staged.miller_loop_part(&pvk, 1).err().unwrap().to_string(),
+            "Public inputs are not fully prepared"
+        );
+        assert!(staged.prepare_inputs(&pvk, 100));
+        assert!(!staged.miller_loop_part(&pvk, 1).unwrap());
+        assert_eq!(
+            staged.final_exponentiation(&pvk).err().unwrap().to_string(),
+            "Miller loop is not complete"
+        );
+    }
+
+    #[test]
+    fn test_input_count_mismatch() {
+        let pvk = fixtures::pvk();
+        let res =
+            StagedVerification::new(&pvk, fixtures::proof().to_string(), r#"["1"]"#.to_string());
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright © 2022, Electron Labs
+
+//! Verification with a committed trace of the intermediate values.
+//!
+//! Every intermediate value of the pairing check is hashed into a sha256
+//! chain seeded with the key fingerprint. Two parties that disagree on a
+//! verification can compare commitments to find the first step they
+//! disagree on and only recompute that step.
+
+use super::{inputs_from_str, parse_proof, parse_public_inputs};
+use crate::verifier::core::{
+    check_input_count, Fq12, G1Affine, PreparedVerifyingKey, VerifierError,
+};
+use anyhow::Result;
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::One;
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha2::{Digest, Sha256};
+
+type G1Prepared = ark_ec::bn::G1Prepared<ark_bn254::Parameters>;
+type G2Prepared = ark_ec::bn::G2Prepared<ark_bn254::Parameters>;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone, Copy)]
+pub enum TraceStep {
+    /// The public inputs folded into one G1 point.
+    PreparedInputs,
+    /// The miller loop of one pairing: 0 for `(a, b)`, 1 for the prepared
+    /// inputs against gamma, 2 for `c` against delta.
+    MillerLoop(u8),
+    /// The product of the three miller loops.
+    MillerProduct,
+    /// The final exponentiation of the product.
+    FinalExponentiation,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct TraceEntry {
+    pub step: TraceStep,
+    /// Borsh encoding of the value, a `G1Affine` for the prepared inputs
+    /// and an `Fq12` otherwise.
+    pub value: Vec<u8>,
+    pub commitment: [u8; 32],
+}
+
+impl TraceEntry {
+    fn new(prev: &[u8; 32], step: TraceStep, value: Vec<u8>) -> Self {
+        let commitment = commit(prev, step, &value);
+        TraceEntry {
+            step,
+            value,
+            commitment,
+        }
+    }
+
+    /// Checks that the entry's commitment extends `prev`.
+    pub fn follows(&self, prev: &[u8; 32]) -> bool {
+        commit(prev, self.step, &self.value) == self.commitment
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct VerificationTrace {
+    pub entries: Vec<TraceEntry>,
+    pub valid: bool,
+}
+
+impl VerificationTrace {
+    /// The last commitment of the chain, which commits to the whole trace.
+    pub fn root(&self) -> [u8; 32] {
+        self.entries
+            .last()
+            .expect("A trace always has entries")
+            .commitment
+    }
+}
+
+fn commit(prev: &[u8; 32], step: TraceStep, value: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(prev);
+    hasher.update(step.try_to_vec().expect("Failed to serialize trace step"));
+    hasher.update(value);
+    hasher.finalize().into()
+}
+
+fn to_bytes<T: BorshSerialize>(value: T) -> Vec<u8> {
+    value.try_to_vec().expect("Failed to serialize trace value")
+}
+
+/// Verifies a proof like `verify_proof` and also returns the committed
+/// trace of the pairing check. The chain starts from the fingerprint of
+/// `pvk`.
+///
+/// # Errors
+/// VerifierError::ParseError, VerifierError::InvalidProof,
+/// VerifierError::InputCountMismatch, VerifierError::SynthesisError
+///
+/// This function will return an error in the same cases as `verify_proof`.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::prepare_verification_key;
+/// use electron_rs::verifier::near::trace::verify_with_trace;
+/// use electron_testkit::circom;
+///
+/// let pvk = prepare_verification_key(circom::vkey().to_string()).unwrap();
+/// let proof = circom::proof().to_string();
+/// let trace = verify_with_trace(&pvk, proof, circom::public_inputs().to_string()).unwrap();
+/// assert!(trace.valid);
+/// ```
+pub fn verify_with_trace(
+    pvk: &PreparedVerifyingKey,
+    proof_str: String,
+    pub_inputs_str: String,
+) -> Result<VerificationTrace> {
+    let proof = parse_proof(proof_str)?;
+    let pub_inputs = parse_public_inputs(pub_inputs_str)?;
+    check_input_count(pvk.vk.gamma_abc_g1.len(), pub_inputs.len())?;
+    let ark_pub_inputs: Vec<ark_bn254::Fr> = inputs_from_str(&pub_inputs)?;
+
+    let ark_pvk: ark_groth16::PreparedVerifyingKey<ark_bn254::Bn254> = pvk.clone().into();
+    let prepared_inputs = ark_groth16::prepare_inputs(&ark_pvk, &ark_pub_inputs)
+        .map_err(|e| VerifierError::SynthesisError(format!("{:?}", e)))?
+        .into_affine();
+
+    let mut entries = Vec::new();
+    let mut prev = pvk.fingerprint();
+    let mut push = |step: TraceStep, value: Vec<u8>| {
+        let entry = TraceEntry::new(&prev, step, value);
+        prev = entry.commitment;
+        entries.push(entry);
+    };
+
+    push(
+        TraceStep::PreparedInputs,
+        to_bytes(G1Affine::from(prepared_inputs)),
+    );
+
+    let pairs: [(G1Prepared, G2Prepared); 3] = [
+        (proof.a.into(), proof.b.into()),
+        (prepared_inputs.into(), ark_pvk.gamma_g2_neg_pc.clone()),
+        (proof.c.into(), ark_pvk.delta_g2_neg_pc.clone()),
+    ];
+    let mut product = ark_bn254::Fq12::one();
+    for (i, pair) in pairs.iter().enumerate() {
+        let f = ark_bn254::Bn254::miller_loop(std::iter::once(pair));
+        product *= &f;
+        push(TraceStep::MillerLoop(i as u8), to_bytes(Fq12::from(f)));
+    }
+    push(TraceStep::MillerProduct, to_bytes(Fq12::from(product)));
+
+    let test = ark_bn254::Bn254::final_exponentiation(&product)
+        .ok_or_else(|| VerifierError::SynthesisError("UnexpectedIdentity".to_string()))?;
+    push(TraceStep::FinalExponentiation, to_bytes(Fq12::from(test)));
+
+    Ok(VerificationTrace {
+        entries,
+        valid: test == ark_pvk.alpha_g1_beta_g2,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures;
+    use super::super::verify_proof;
+    use super::*;
+
+    #[test]
+    fn test_trace_matches_verification() {
+        let pvk = fixtures::pvk();
+        let trace = verify_with_trace(
+            &pvk,
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string(),
+        )
+        .unwrap();
+        assert!(trace.valid);
+        assert!(verify_proof(
+            pvk.clone(),
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string()
+        )
+        .unwrap());
+
+        assert_eq!(trace.entries.len(), 6);
+        let mut prev = pvk.fingerprint();
+        for entry in &trace.entries {
+            assert!(entry.follows(&prev));
+            prev = entry.commitment;
+        }
+        assert_eq!(trace.root(), prev);
+    }
+
+    #[test]
+    fn test_trace_pinpoints_divergence() {
+        let pvk = fixtures::pvk();
+        let honest = verify_with_trace(
+            &pvk,
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string(),
+        )
+        .unwrap();
+        let inputs = fixtures::public_inputs().replacen("\"1\"", "\"2\"", 1);
+        let dishonest = verify_with_trace(&pvk, fixtures::proof().to_string(), inputs).unwrap();
+        assert!(!dishonest.valid);
+
+        // The proof points are the same, so only the prepared inputs and
+        // the pairing that consumes them differ before the product.
+        let differs: Vec<bool> = honest
+            .entries
+            .iter()
+            .zip(&dishonest.entries)
+            .map(|(a, b)| a.value != b.value)
+            .collect();
+        assert_eq!(differs, vec![true, false, true, false, true, true]);
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright © 2022, Electron Labs
+
+//! Newtypes separating keys set by a trusted party from data submitted by
+//! users.
+//!
+//! A contract that verifies against a key passed in by the caller proves
+//! nothing, since the caller can generate a key for any statement.
+//! [`verify_untrusted`] only accepts a [`TrustedVkey`], which can't be
+//! built from call arguments by accident: it has no `Deserialize` impl and
+//! is only created by an explicit constructor.
+
+use super::{get_prepared_verifying_key, parse_verification_key, verify_proof};
+use super::{PreparedVerifyingKey, VerifierError};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// A verifying key set by the contract owner or compiled into the contract.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct TrustedVkey(PreparedVerifyingKey);
+
+impl TrustedVkey {
+    /// Marks a prepared key as trusted. Only call this with keys from the
+    /// contract owner or from the contract code, never with arguments of a
+    /// method callable by anyone.
+    pub fn from_admin(pvk: PreparedVerifyingKey) -> Self {
+        TrustedVkey(pvk)
+    }
+
+    /// Parses and prepares a trusted circom verification key json.
+    ///
+    /// # Errors
+    /// VerifierError::ParseError, VerifierError::UnsupportedCurve
+    ///
+    /// This function will return an error if the json fails to parse, or
+    /// if the key is not a bn128 key.
+    pub fn from_admin_json(vkey_str: String) -> Result<Self> {
+        let vkey = parse_verification_key(vkey_str)?;
+        Ok(TrustedVkey(get_prepared_verifying_key(vkey)?))
+    }
+
+    pub fn prepared_key(&self) -> &PreparedVerifyingKey {
+        &self.0
+    }
+}
+
+/// Circom proof json as submitted by a user.
+#[derive(Debug, PartialEq, Clone)]
+pub struct UntrustedProofBytes(Vec<u8>);
+
+impl From<Vec<u8>> for UntrustedProofBytes {
+    fn from(src: Vec<u8>) -> Self {
+        UntrustedProofBytes(src)
+    }
+}
+
+impl From<String> for UntrustedProofBytes {
+    fn from(src: String) -> Self {
+        UntrustedProofBytes(src.into_bytes())
+    }
+}
+
+/// Public inputs json as submitted by a user.
+#[derive(Debug, PartialEq, Clone)]
+pub struct UntrustedInputs(String);
+
+impl From<String> for UntrustedInputs {
+    fn from(src: String) -> Self {
+        UntrustedInputs(src)
+    }
+}
+
+/// Verifies a user-submitted proof and public inputs against a trusted key.
+///
+/// # Errors
+/// VerifierError::ParseError, VerifierError::InvalidProof,
+/// VerifierError::SynthesisError
+///
+/// This function will return an error if the proof is not utf-8 or in any
+/// case `verify_proof` returns one.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::trust::{
+///     verify_untrusted, TrustedVkey, UntrustedInputs, UntrustedProofBytes,
+/// };
+/// use electron_testkit::circom;
+///
+/// let vkey = TrustedVkey::from_admin_json(circom::vkey().to_string()).unwrap();
+/// let proof = UntrustedProofBytes::from(circom::proof().to_string());
+/// let inputs = UntrustedInputs::from(circom::public_inputs().to_string());
+/// assert!(verify_untrusted(&vkey, proof, inputs).unwrap());
+/// ```
+pub fn verify_untrusted(
+    vkey: &TrustedVkey,
+    proof: UntrustedProofBytes,
+    inputs: UntrustedInputs,
+) -> Result<bool> {
+    let proof_str =
+        String::from_utf8(proof.0).map_err(|_| VerifierError::ParseError("proof".to_string()))?;
+    verify_proof(vkey.0.clone(), proof_str, inputs.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures;
+    use super::*;
+
+    #[test]
+    fn test_verify_untrusted() {
+        let vkey = TrustedVkey::from_admin_json(fixtures::vkey().to_string()).unwrap();
+        let proof = UntrustedProofBytes::from(fixtures::proof().to_string());
+        let inputs = UntrustedInputs::from(fixtures::public_inputs().to_string());
+        assert!(verify_untrusted(&vkey, proof, inputs).unwrap());
+    }
+
+    #[test]
+    fn test_verify_untrusted_invalid_utf8() {
+        let vkey = TrustedVkey::from_admin_json(fixtures::vkey().to_string()).unwrap();
+        let proof = UntrustedProofBytes::from(vec![0xff, 0xfe]);
+        let inputs = UntrustedInputs::from(fixtures::public_inputs().to_string());
+        let res = verify_untrusted(&vkey, proof, inputs);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Failed to parse circom proof json"
+        );
+    }
+}
@@ -0,0 +1,307 @@
+// Copyright © 2022, Electron Labs
+
+//! Uploading a verification key across several transactions.
+//!
+//! A key with hundreds of public inputs doesn't fit in the arguments of one
+//! call. The client splits it with [`split_verification_key`] into a
+//! [`VerifyingKeyPart1`] with everything but the IC points, and a list of
+//! [`VerifyingKeyIcChunk`]s. The contract stores the part in a
+//! [`VerifyingKeyUpload`], adds the chunks in order, one or more per call,
+//! and prepares the key with [`assemble_verifying_key`] once all chunks are
+//! in.
+
+use super::{
+    g1_from_str, g2_from_str, json, AdapterError, PreparedVerifyingKey, VerificationKeyJson,
+};
+use crate::verifier::core::{check_point, G1Affine, G2Affine, VerifyingKey};
+use anyhow::Result;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct VerifyingKeyPart1Json {
+    #[serde(rename = "nPublic")]
+    num_public: u32,
+    vk_alpha_1: Vec<String>,
+    vk_beta_2: Vec<Vec<String>>,
+    vk_gamma_2: Vec<Vec<String>>,
+    vk_delta_2: Vec<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+struct VerifyingKeyIcChunkJson {
+    start: u32,
+    #[serde(rename = "IC")]
+    ic: Vec<Vec<String>>,
+}
+
+/// Everything in a verification key but the IC points.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct VerifyingKeyPart1 {
+    alpha_g1: G1Affine,
+    beta_g2: G2Affine,
+    gamma_g2: G2Affine,
+    delta_g2: G2Affine,
+    ic_len: u32,
+}
+
+impl VerifyingKeyPart1 {
+    /// Parses the json written by `split_verification_key`. A full circom
+    /// verification key json is also accepted, its IC is ignored.
+    ///
+    /// # Errors
+    /// VerifierError::ParseError, VerifierError::InvalidFieldElement,
+    /// VerifierError::InvalidPoint, VerifierError::InvalidSubgroupPoint
+    pub fn from_json(part_str: String) -> Result<Self> {
+        let part: VerifyingKeyPart1Json =
+            json::from_slice(part_str.as_bytes(), "verification key part")?;
+        let alpha_g1 = g1_from_str(&part.vk_alpha_1, "vk_alpha_1")?;
+        check_point(&alpha_g1, "vk_alpha_1")?;
+        let beta_g2 = g2_from_str(&part.vk_beta_2, "vk_beta_2")?;
+        check_point(&beta_g2, "vk_beta_2")?;
+        let gamma_g2 = g2_from_str(&part.vk_gamma_2, "vk_gamma_2")?;
+        check_point(&gamma_g2, "vk_gamma_2")?;
+        let delta_g2 = g2_from_str(&part.vk_delta_2, "vk_delta_2")?;
+        check_point(&delta_g2, "vk_delta_2")?;
+        Ok(VerifyingKeyPart1 {
+            alpha_g1: alpha_g1.into(),
+            beta_g2: beta_g2.into(),
+            gamma_g2: gamma_g2.into(),
+            delta_g2: delta_g2.into(),
+            ic_len: part.num_public + 1,
+        })
+    }
+
+    /// Number of IC points the key has.
+    pub fn ic_len(&self) -> u32 {
+        self.ic_len
+    }
+
+    /// Number of chunks needed to upload the IC with at most
+    /// `points_per_chunk` points per chunk.
+    pub fn chunks_needed(&self, points_per_chunk: u32) -> u32 {
+        let (len, per_chunk) = (self.ic_len as usize, points_per_chunk as usize);
+        ((len + per_chunk - 1) / per_chunk) as u32
+    }
+}
+
+/// A run of consecutive IC points starting at index `start`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct VerifyingKeyIcChunk {
+    start: u32,
+    points: Vec<G1Affine>,
+}
+
+impl VerifyingKeyIcChunk {
+    /// Parses the json written by `split_verification_key`.
+    ///
+    /// # Errors
+    /// VerifierError::ParseError, VerifierError::InvalidFieldElement,
+    /// VerifierError::InvalidPoint, VerifierError::InvalidSubgroupPoint
+    pub fn from_json(chunk_str: String) -> Result<Self> {
+        let chunk: VerifyingKeyIcChunkJson =
+            json::from_slice(chunk_str.as_bytes(), "verification key IC chunk")?;
+        let mut points = Vec::with_capacity(chunk.ic.len());
+        for (i, p) in chunk.ic.iter().enumerate() {
+            let name = format!("IC[{}]", chunk.start as usize + i);
+            let point = g1_from_str(p, &name)?;
+            check_point(&point, &name)?;
+            points.push(point.into());
+        }
+        Ok(VerifyingKeyIcChunk {
+            start: chunk.start,
+            points,
+        })
+    }
+}
+
+/// A verification key being uploaded.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone)]
+pub struct VerifyingKeyUpload {
+    part1: VerifyingKeyPart1,
+    ic: Vec<G1Affine>,
+}
+
+impl VerifyingKeyUpload {
+    pub fn new(part1: VerifyingKeyPart1) -> Self {
+        VerifyingKeyUpload {
+            part1,
+            ic: Vec::new(),
+        }
+    }
+
+    /// Appends a chunk, which must start right after the points received so
+    /// far.
+    ///
+    /// # Errors
+    /// AdapterError::UnexpectedIcChunk
+    ///
+    /// This function will return an error if the chunk is out of order or
+    /// goes past the number of IC points in the key.
+    pub fn add_chunk(&mut self, chunk: VerifyingKeyIcChunk) -> Result<()> {
+        let received = self.ic.len() as u32;
+        let end = chunk.start as usize + chunk.points.len();
+        if chunk.start != received || end > self.part1.ic_len as usize {
+            return Err(AdapterError::UnexpectedIcChunk(chunk.start, received).into());
+        }
+        self.ic.extend(chunk.points);
+        Ok(())
+    }
+
+    /// Number of IC points received so far.
+    pub fn received(&self) -> u32 {
+        self.ic.len() as u32
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.received() == self.part1.ic_len
+    }
+}
+
+/// Prepares the key of a complete upload.
+///
+/// # Errors
+/// AdapterError::IncompleteUpload
+///
+/// This function will return an error if some IC points are missing.
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::upload::{
+///     assemble_verifying_key, split_verification_key, VerifyingKeyIcChunk, VerifyingKeyPart1,
+///     VerifyingKeyUpload,
+/// };
+/// use electron_rs::verifier::near::{parse_verification_key, prepare_verification_key};
+/// use electron_testkit::circom;
+///
+/// let vkey = parse_verification_key(circom::vkey().to_string()).unwrap();
+/// let (part1, chunks) = split_verification_key(vkey, 8).unwrap();
+/// let mut upload = VerifyingKeyUpload::new(VerifyingKeyPart1::from_json(part1).unwrap());
+/// for chunk in chunks {
+///     upload.add_chunk(VerifyingKeyIcChunk::from_json(chunk).unwrap()).unwrap();
+/// }
+/// let pvk = assemble_verifying_key(upload).unwrap();
+/// let expected = prepare_verification_key(circom::vkey().to_string()).unwrap();
+/// assert_eq!(pvk.fingerprint(), expected.fingerprint());
+/// ```
+pub fn assemble_verifying_key(upload: VerifyingKeyUpload) -> Result<PreparedVerifyingKey> {
+    if !upload.is_complete() {
+        return Err(AdapterError::IncompleteUpload(upload.received(), upload.part1.ic_len).into());
+    }
+    let vk = VerifyingKey {
+        alpha_g1: upload.part1.alpha_g1,
+        beta_g2: upload.part1.beta_g2,
+        gamma_g2: upload.part1.gamma_g2,
+        delta_g2: upload.part1.delta_g2,
+        gamma_abc_g1: upload.ic,
+    };
+    let ark_vk: ark_groth16::VerifyingKey<ark_bn254::Bn254> = vk.into();
+    Ok(ark_groth16::prepare_verifying_key(&ark_vk).into())
+}
+
+/// Splits a circom verification key json into the part 1 json and IC chunk
+/// jsons of at most `points_per_chunk` points each, for a client to upload.
+///
+/// # Errors
+/// VerifierError::ParseError
+///
+/// # Examples
+///
+/// ```
+/// use electron_rs::verifier::near::parse_verification_key;
+/// use electron_rs::verifier::near::upload::split_verification_key;
+///
+/// let vkey = parse_verification_key(electron_testkit::circom::vkey().to_string()).unwrap();
+/// let (part1, chunks) = split_verification_key(vkey, 8).unwrap();
+/// assert!(part1.contains("vk_alpha_1"));
+/// assert_eq!(chunks.len(), 3);
+/// ```
+pub fn split_verification_key(
+    vkey: VerificationKeyJson,
+    points_per_chunk: usize,
+) -> Result<(String, Vec<String>)> {
+    let part1 = VerifyingKeyPart1Json {
+        num_public: vkey.ic.len() as u32 - 1,
+        vk_alpha_1: vkey.vk_alpha_1,
+        vk_beta_2: vkey.vk_beta_2,
+        vk_gamma_2: vkey.vk_gamma_2,
+        vk_delta_2: vkey.vk_delta_2,
+    };
+    let part1 = json::to_string(&part1, "verification key")?;
+
+    let mut chunks = Vec::with_capacity((vkey.ic.len() + points_per_chunk - 1) / points_per_chunk);
+    for (i, points) in vkey.ic.chunks(points_per_chunk).enumerate() {
+        let chunk = VerifyingKeyIcChunkJson {
+            start: (i * points_per_chunk) as u32,
+            ic: points.to_vec(),
+        };
+        chunks.push(json::to_string(&chunk, "verification key")?);
+    }
+    Ok((part1, chunks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures;
+    use super::super::{get_prepared_verifying_key, parse_verification_key};
+    use super::*;
+
+    fn split(points_per_chunk: usize) -> (String, Vec<String>) {
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        split_verification_key(vkey, points_per_chunk).unwrap()
+    }
+
+    #[test]
+    fn test_chunked_upload() {
+        let (part1, chunks) = split(5);
+        let part1 = VerifyingKeyPart1::from_json(part1).unwrap();
+        assert_eq!(part1.ic_len(), 22);
+        assert_eq!(part1.chunks_needed(5), 5);
+        assert_eq!(chunks.len(), 5);
+
+        let mut upload = VerifyingKeyUpload::new(part1);
+        for chunk in chunks {
+            assert!(!upload.is_complete());
+            let bytes = upload.try_to_vec().unwrap();
+            upload = VerifyingKeyUpload::try_from_slice(&bytes).unwrap();
+            upload
+                .add_chunk(VerifyingKeyIcChunk::from_json(chunk).unwrap())
+                .unwrap();
+        }
+        assert!(upload.is_complete());
+
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        assert_eq!(
+            assemble_verifying_key(upload).unwrap(),
+            get_prepared_verifying_key(vkey).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_chunk_out_of_order() {
+        let (part1, chunks) = split(5);
+        let mut upload = VerifyingKeyUpload::new(VerifyingKeyPart1::from_json(part1).unwrap());
+        let err = upload
+            .add_chunk(VerifyingKeyIcChunk::from_json(chunks[1].clone()).unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Unexpected IC chunk starting at 5, expected 0"
+        );
+    }
+
+    #[test]
+    fn test_assemble_incomplete() {
+        let (part1, chunks) = split(5);
+        let mut upload = VerifyingKeyUpload::new(VerifyingKeyPart1::from_json(part1).unwrap());
+        upload
+            .add_chunk(VerifyingKeyIcChunk::from_json(chunks[0].clone()).unwrap())
+            .unwrap();
+        let err = assemble_verifying_key(upload).err().unwrap();
+        assert_eq!(err.to_string(), "Received 5 of 22 IC points");
+    }
+}
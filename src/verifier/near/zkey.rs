@@ -0,0 +1,294 @@
+// Copyright © 2022, Electron Labs
+
+//! Verification keys read straight from snarkjs `.zkey` files.
+//!
+//! A zkey is the binary proving key snarkjs writes after the setup. It is a
+//! list of sections, and the Groth16 header (section 2) and the IC points
+//! (section 3) hold the whole verification key, so there is no need to run
+//! `snarkjs zkey export verificationkey` first:
+//!
+//! ```ignore
+//! let vkey = parse_zkey_vkey(&std::fs::read("circuit_final.zkey")?)?;
+//! let pvk = get_prepared_verifying_key(vkey)?;
+//! ```
+//!
+//! Coordinates are little-endian and in Montgomery form. Only Groth16 zkeys
+//! over bn128 are supported.
+
+use super::{
+    check_vkey_json, repr_to_string, AdapterError, PointChecks, VerificationKeyJson, VerifierError,
+};
+use anyhow::Result;
+use ark_ff::{BigInteger, BigInteger256, PrimeField, Zero};
+use std::convert::{TryFrom, TryInto};
+
+const MAGIC: &[u8; 4] = b"zkey";
+const HEADER: u32 = 1;
+const GROTH16_HEADER: u32 = 2;
+const IC: u32 = 3;
+const GROTH16: u32 = 1;
+const FQ_LEN: usize = 32;
+
+fn invalid(what: &str) -> AdapterError {
+    AdapterError::InvalidZkey(what.to_string())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], AdapterError> {
+        if self.bytes.len() < len {
+            return Err(invalid("file is truncated"));
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, AdapterError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, AdapterError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// A length-prefixed field modulus.
+    fn modulus(&mut self) -> Result<&'a [u8], AdapterError> {
+        let len = self.u32()?;
+        self.take(len as usize)
+    }
+
+    fn fq(&mut self) -> Result<ark_bn254::Fq, AdapterError> {
+        let mut limbs = [0u64; 4];
+        for (limb, chunk) in limbs.iter_mut().zip(self.take(FQ_LEN)?.chunks(8)) {
+            *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        let repr = BigInteger256::new(limbs);
+        if repr >= <ark_bn254::Fq as PrimeField>::Params::MODULUS {
+            return Err(invalid("coordinate is not in the field"));
+        }
+        // snarkjs and arkworks both keep bn128 elements in Montgomery form
+        // with R = 2^256, so the limbs are the element as stored.
+        Ok(ark_bn254::Fq::new(repr))
+    }
+
+    fn g1(&mut self) -> Result<Vec<String>, AdapterError> {
+        let x = self.fq()?;
+        let y = self.fq()?;
+        if x.is_zero() && y.is_zero() {
+            return Ok(vec!["0".to_string(), "1".to_string(), "0".to_string()]);
+        }
+        Ok(vec![fq_to_string(x), fq_to_string(y), "1".to_string()])
+    }
+
+    fn g2(&mut self) -> Result<Vec<Vec<String>>, AdapterError> {
+        let coords = [self.fq()?, self.fq()?, self.fq()?, self.fq()?];
+        if coords.iter().all(|c| c.is_zero()) {
+            return Ok(vec![
+                vec!["0".to_string(), "0".to_string()],
+                vec!["1".to_string(), "0".to_string()],
+                vec!["0".to_string(), "0".to_string()],
+            ]);
+        }
+        Ok(vec![
+            vec![fq_to_string(coords[0]), fq_to_string(coords[1])],
+            vec![fq_to_string(coords[2]), fq_to_string(coords[3])],
+            vec!["1".to_string(), "0".to_string()],
+        ])
+    }
+}
+
+fn fq_to_string(fq: ark_bn254::Fq) -> String {
+    repr_to_string(&fq.into_repr().0)
+}
+
+fn read_sections(bytes: &[u8]) -> Result<Vec<(u32, &[u8])>, AdapterError> {
+    let mut reader = Reader { bytes };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(invalid("not a zkey file"));
+    }
+    let _version = reader.u32()?;
+    let count = reader.u32()?;
+    let mut sections = Vec::new();
+    for _ in 0..count {
+        let id = reader.u32()?;
+        let len = usize::try_from(reader.u64()?).map_err(|_| invalid("file is truncated"))?;
+        sections.push((id, reader.take(len)?));
+    }
+    Ok(sections)
+}
+
+fn section<'a>(sections: &[(u32, &'a [u8])], id: u32) -> Result<Reader<'a>, AdapterError> {
+    sections
+        .iter()
+        .find(|(section_id, _)| *section_id == id)
+        .map(|(_, bytes)| Reader { bytes })
+        .ok_or_else(|| AdapterError::InvalidZkey(format!("section {} is missing", id)))
+}
+
+/// A helper function to read the verification key of a snarkjs `.zkey`
+/// file, as `snarkjs zkey export verificationkey` would write it.
+///
+/// # Errors
+/// AdapterError::InvalidZkey, VerifierError::UnsupportedProtocol,
+/// VerifierError::UnsupportedCurve, VerifierError::InvalidPoint,
+/// VerifierError::InvalidSubgroupPoint
+///
+/// This function will return an error if the file is not a zkey or is
+/// truncated, if it is not a Groth16 key over bn128, or if a point of the
+/// key is not on the curve or not in the prime order subgroup.
+pub fn parse_zkey_vkey(bytes: &[u8]) -> Result<VerificationKeyJson> {
+    let sections = read_sections(bytes)?;
+
+    let protocol = section(&sections, HEADER)?.u32()?;
+    if protocol != GROTH16 {
+        return Err(VerifierError::UnsupportedProtocol(protocol_name(protocol)).into());
+    }
+
+    let mut header = section(&sections, GROTH16_HEADER)?;
+    let q = header.modulus()?;
+    let bn128_q = <ark_bn254::Fq as PrimeField>::Params::MODULUS.to_bytes_le();
+    if q != bn128_q.as_slice() {
+        let curve = format!("with modulus 0x{}", hex_be(q));
+        return Err(VerifierError::UnsupportedCurve(curve).into());
+    }
+    let _r = header.modulus()?;
+    let _num_vars = header.u32()?;
+    let num_public = header.u32()?;
+    let _domain_size = header.u32()?;
+    let vk_alpha_1 = header.g1()?;
+    let _beta_1 = header.g1()?;
+    let vk_beta_2 = header.g2()?;
+    let vk_gamma_2 = header.g2()?;
+    let _delta_1 = header.g1()?;
+    let vk_delta_2 = header.g2()?;
+
+    let mut ic_section = section(&sections, IC)?;
+    let ic = (0..=num_public)
+        .map(|_| ic_section.g1())
+        .collect::<Result<Vec<_>, _>>()?;
+    if !ic_section.bytes.is_empty() {
+        return Err(invalid("IC section is longer than nPublic + 1 points"));
+    }
+
+    let vkey = VerificationKeyJson {
+        protocol: "groth16".to_string(),
+        curve: "bn128".to_string(),
+        num_public: num_public as u64,
+        vk_alpha_1,
+        vk_beta_2,
+        vk_gamma_2,
+        vk_delta_2,
+        vk_alphabeta_12: None,
+        ic,
+    };
+    check_vkey_json(&vkey, PointChecks::Full)?;
+    Ok(vkey)
+}
+
+/// Names of the other protocols snarkjs writes zkeys for.
+fn protocol_name(protocol: u32) -> String {
+    match protocol {
+        2 => "plonk".to_string(),
+        10 => "fflonk".to_string(),
+        _ => protocol.to_string(),
+    }
+}
+
+fn hex_be(bytes: &[u8]) -> String {
+    let mut be = bytes.to_vec();
+    be.reverse();
+    hex::encode(be)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::{
+        get_prepared_verifying_key, prepare_verification_key, verify_proof,
+    };
+    use electron_testkit::multiplier::Multiplier;
+
+    fn push_fq(out: &mut Vec<u8>, fq: ark_bn254::Fq) {
+        out.extend(fq.0.to_bytes_le());
+    }
+
+    fn push_g1(out: &mut Vec<u8>, p: ark_bn254::G1Affine) {
+        push_fq(out, p.x);
+        push_fq(out, p.y);
+    }
+
+    fn push_g2(out: &mut Vec<u8>, p: ark_bn254::G2Affine) {
+        push_fq(out, p.x.c0);
+        push_fq(out, p.x.c1);
+        push_fq(out, p.y.c0);
+        push_fq(out, p.y.c1);
+    }
+
+    fn push_section(out: &mut Vec<u8>, id: u32, data: &[u8]) {
+        out.extend(id.to_le_bytes());
+        out.extend((data.len() as u64).to_le_bytes());
+        out.extend(data);
+    }
+
+    /// The sections of a zkey that `parse_zkey_vkey` reads, laid out as
+    /// snarkjs writes them.
+    fn zkey(vk: &ark_groth16::VerifyingKey<ark_bn254::Bn254>, protocol: u32) -> Vec<u8> {
+        let q = <ark_bn254::Fq as PrimeField>::Params::MODULUS.to_bytes_le();
+        let r = <ark_bn254::Fr as PrimeField>::Params::MODULUS.to_bytes_le();
+        let mut header = Vec::new();
+        header.extend(32u32.to_le_bytes());
+        header.extend(&q);
+        header.extend(32u32.to_le_bytes());
+        header.extend(&r);
+        header.extend(4u32.to_le_bytes());
+        header.extend((vk.gamma_abc_g1.len() as u32 - 1).to_le_bytes());
+        header.extend(4u32.to_le_bytes());
+        push_g1(&mut header, vk.alpha_g1);
+        push_g1(&mut header, vk.alpha_g1);
+        push_g2(&mut header, vk.beta_g2);
+        push_g2(&mut header, vk.gamma_g2);
+        push_g1(&mut header, vk.alpha_g1);
+        push_g2(&mut header, vk.delta_g2);
+        let mut ic = Vec::new();
+        for p in &vk.gamma_abc_g1 {
+            push_g1(&mut ic, *p);
+        }
+
+        let mut out = MAGIC.to_vec();
+        out.extend(1u32.to_le_bytes());
+        out.extend(3u32.to_le_bytes());
+        push_section(&mut out, HEADER, &protocol.to_le_bytes());
+        push_section(&mut out, GROTH16_HEADER, &header);
+        push_section(&mut out, IC, &ic);
+        out
+    }
+
+    #[test]
+    fn test_zkey_matches_exported_key() {
+        let mut multiplier = Multiplier::setup();
+        let vkey = parse_zkey_vkey(&zkey(multiplier.verifying_key(), GROTH16)).unwrap();
+        assert_eq!(vkey.num_public, 1);
+
+        let pvk = get_prepared_verifying_key(vkey).unwrap();
+        let exported = prepare_verification_key(multiplier.vkey_json()).unwrap();
+        assert_eq!(pvk.fingerprint(), exported.fingerprint());
+        let (proof, inputs) = multiplier.prove(3, 11);
+        assert!(verify_proof(pvk, proof, inputs).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_zkeys() {
+        let multiplier = Multiplier::setup();
+        let bytes = zkey(multiplier.verifying_key(), GROTH16);
+
+        let truncated = parse_zkey_vkey(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(truncated.to_string(), "Invalid zkey: file is truncated");
+        let plonk = parse_zkey_vkey(&zkey(multiplier.verifying_key(), 2)).unwrap_err();
+        assert_eq!(plonk.to_string(), "Unsupported protocol plonk");
+        assert!(parse_zkey_vkey(b"{\"protocol\": \"groth16\"}").is_err());
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright © 2022, Electron Labs
+
+//! Groth16 verification in Solana programs, with the BN254 arithmetic done
+//! by the `alt_bn128` syscalls instead of arkworks.
+//!
+//! Programs take the borsh types of `verifier::core` as instruction data
+//! and keep the prepared key in an account, so no json is parsed on chain.
+//! Points are passed to the syscalls in the EIP-197 encoding of
+//! `verifier::core::eip197`. Off chain, solana-program runs the same
+//! operations natively, so programs can be tested with `cargo test`.
+
+use crate::verifier::core::backend::PairingBackend;
+use crate::verifier::core::eip197::{
+    decode_g1, encode_fr, encode_g1, encode_pairing_input, FP_LEN, G1_LEN,
+};
+use crate::verifier::core::{
+    check_input_count, check_proof, PreparedVerifyingKey, Proof, VerifierError,
+};
+use alloc::format;
+use alloc::vec::Vec;
+use solana_program::alt_bn128::prelude::{
+    alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing,
+};
+
+fn syscall_error<E: core::fmt::Debug>(e: E) -> VerifierError {
+    VerifierError::InvalidProof(format!("alt_bn128 syscall failed: {:?}", e))
+}
+
+/// Pairing checks through the `alt_bn128_pairing` syscall.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AltBn128Backend;
+
+impl PairingBackend<ark_bn254::Bn254> for AltBn128Backend {
+    fn pairing_check(&self, pairs: &[(ark_bn254::G1Affine, ark_bn254::G2Affine)]) -> bool {
+        match alt_bn128_pairing(&encode_pairing_input(pairs)) {
+            Ok(out) => {
+                out.len() == FP_LEN
+                    && out[..FP_LEN - 1].iter().all(|b| *b == 0)
+                    && out[FP_LEN - 1] == 1
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// `IC[0] + sum(inputs[i] * IC[i + 1])`, computed with the `alt_bn128`
+/// addition and multiplication syscalls.
+///
+/// # Errors
+/// VerifierError::InputCountMismatch, VerifierError::InvalidProof
+pub fn prepare_inputs(
+    gamma_abc_g1: &[ark_bn254::G1Affine],
+    pub_inputs: &[ark_bn254::Fr],
+) -> Result<ark_bn254::G1Affine, VerifierError> {
+    check_input_count(gamma_abc_g1.len(), pub_inputs.len())?;
+    let mut acc = encode_g1(&gamma_abc_g1[0]);
+    for (input, base) in pub_inputs.iter().zip(&gamma_abc_g1[1..]) {
+        let mut mul = Vec::with_capacity(G1_LEN + FP_LEN);
+        mul.extend(encode_g1(base));
+        mul.extend_from_slice(&encode_fr(input));
+        let product = alt_bn128_multiplication(&mul).map_err(syscall_error)?;
+        acc.extend_from_slice(&product);
+        acc = alt_bn128_addition(&acc).map_err(syscall_error)?;
+    }
+    decode_g1(&acc)
+}
+
+/// Verifies a proof with the `alt_bn128` syscalls. The points of the proof
+/// are checked with `check_proof` before anything is passed to a syscall.
+///
+/// # Errors
+/// VerifierError::InvalidFieldElement, VerifierError::InvalidPoint,
+/// VerifierError::InvalidSubgroupPoint, VerifierError::InputCountMismatch,
+/// VerifierError::InvalidProof
+///
+/// This function will return an error if a coordinate of the proof is not
+/// below the modulus, if a proof point is not on the curve or not in the
+/// prime order subgroup, if the number of inputs doesn't match the key, or
+/// if a syscall rejects a point.
+pub fn verify_proof(
+    pvk: &PreparedVerifyingKey,
+    proof: &Proof,
+    pub_inputs: &[ark_bn254::Fr],
+) -> Result<bool, VerifierError> {
+    let proof: ark_groth16::Proof<ark_bn254::Bn254> = proof.clone().try_into()?;
+    check_proof(&proof)?;
+
+    let pvk: ark_groth16::PreparedVerifyingKey<ark_bn254::Bn254> = pvk.clone().into();
+    let vk = &pvk.vk;
+    let prepared_inputs = prepare_inputs(&vk.gamma_abc_g1, pub_inputs)?;
+
+    // e(A, B) * e(-PI, gamma) * e(-C, delta) * e(-alpha, beta) == 1
+    let pairs = [
+        (proof.a, proof.b),
+        (-prepared_inputs, vk.gamma_g2),
+        (-proof.c, vk.delta_g2),
+        (-vk.alpha_g1, vk.beta_g2),
+    ];
+    Ok(AltBn128Backend.pairing_check(&pairs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::core::backend::{verify_with_backend, ArkworksBackend};
+    use crate::verifier::core::test_circuit;
+    use ark_ec::AffineCurve;
+    use ark_ff::Zero;
+
+    #[test]
+    fn test_alt_bn128_matches_arkworks() {
+        let (vk, proofs) = test_circuit::prove_many::<ark_bn254::Bn254>(&[(3, 5), (7, 11)]);
+        let pvk: PreparedVerifyingKey = ark_groth16::prepare_verifying_key(&vk).into();
+        for (proof, c) in &proofs {
+            let wrapped: Proof = proof.clone().into();
+            for input in [*c, *c + ark_bn254::Fr::from(1u64)] {
+                let ark = verify_with_backend(&ArkworksBackend, &pvk, proof, &[input]).unwrap();
+                assert_eq!(verify_proof(&pvk, &wrapped, &[input]).unwrap(), ark);
+            }
+        }
+        assert!(verify_proof(&pvk, &proofs[0].0.clone().into(), &[]).is_err());
+
+        let mut off_curve = proofs[0].0.clone();
+        off_curve.a = ark_bn254::G1Affine::new(1u64.into(), 1u64.into(), false);
+        assert!(matches!(
+            verify_proof(&pvk, &off_curve.into(), &[proofs[0].1]),
+            Err(VerifierError::InvalidPoint(_))
+        ));
+    }
+
+    #[test]
+    fn test_pairing_check_of_cancelling_pairs() {
+        let g1 = ark_bn254::G1Affine::prime_subgroup_generator();
+        let g2 = ark_bn254::G2Affine::prime_subgroup_generator();
+        assert!(AltBn128Backend.pairing_check(&[(g1, g2), (-g1, g2)]));
+        assert!(!AltBn128Backend.pairing_check(&[(g1, g2)]));
+        assert!(AltBn128Backend.pairing_check(&[(ark_bn254::G1Affine::zero(), g2)]));
+    }
+}
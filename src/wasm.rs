@@ -0,0 +1,92 @@
+// Copyright © 2022, Electron Labs
+
+//! `wasm-bindgen` bindings, so frontends can check a proof with the code
+//! the contract runs before submitting it.
+//!
+//! ```js
+//! import { parseVerificationKey, getPreparedVerifyingKey, verifyProof } from "electron-rs";
+//!
+//! const pvk = getPreparedVerifyingKey(parseVerificationKey(vkeyJson));
+//! if (!verifyProof(pvk, proofJson, inputsJson)) {
+//!     throw new Error("proof does not verify");
+//! }
+//! ```
+//!
+//! Errors are thrown as strings with the message of the Rust error.
+
+use crate::verifier::near::{self, PreparedVerifyingKey, VerificationKeyJson};
+use borsh::BorshSerialize;
+use wasm_bindgen::prelude::*;
+
+/// Parsed verification key json.
+#[wasm_bindgen]
+pub struct VerificationKey(VerificationKeyJson);
+
+/// A prepared verification key, reusable across proofs.
+#[wasm_bindgen]
+pub struct PreparedKey(PreparedVerifyingKey);
+
+#[wasm_bindgen]
+impl PreparedKey {
+    /// Hex fingerprint of the key, as reported by contracts.
+    pub fn fingerprint(&self) -> String {
+        hex::encode(self.0.fingerprint())
+    }
+
+    /// The borsh encoding of the key, as contracts store it.
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.try_to_vec().unwrap()
+    }
+}
+
+fn to_js<T>(res: anyhow::Result<T>) -> Result<T, JsValue> {
+    res.map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// See `verifier::near::parse_verification_key`.
+#[wasm_bindgen(js_name = parseVerificationKey)]
+pub fn parse_verification_key(vkey: String) -> Result<VerificationKey, JsValue> {
+    to_js(near::parse_verification_key(vkey)).map(VerificationKey)
+}
+
+/// See `verifier::near::get_prepared_verifying_key`.
+#[wasm_bindgen(js_name = getPreparedVerifyingKey)]
+pub fn get_prepared_verifying_key(vkey: &VerificationKey) -> Result<PreparedKey, JsValue> {
+    to_js(near::get_prepared_verifying_key(vkey.0.clone())).map(PreparedKey)
+}
+
+/// See `verifier::near::verify_proof`.
+#[wasm_bindgen(js_name = verifyProof)]
+pub fn verify_proof(pvk: &PreparedKey, proof: String, inputs: String) -> Result<bool, JsValue> {
+    to_js(near::verify_proof(pvk.0.clone(), proof, inputs))
+}
+
+// JsValue can't be built off wasm32, so these only cover the success path.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::near::fixtures;
+
+    #[test]
+    fn test_same_path_as_contract() {
+        let vkey = parse_verification_key(fixtures::vkey().to_string()).unwrap();
+        let pvk = get_prepared_verifying_key(&vkey).unwrap();
+        let expected = near::prepare_verification_key(fixtures::vkey().to_string()).unwrap();
+        assert_eq!(pvk.fingerprint(), hex::encode(expected.fingerprint()));
+        assert_eq!(pvk.to_bytes(), expected.try_to_vec().unwrap());
+
+        assert!(verify_proof(
+            &pvk,
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().to_string()
+        )
+        .unwrap());
+        assert!(!verify_proof(
+            &pvk,
+            fixtures::proof().to_string(),
+            fixtures::public_inputs().replacen("\"1\"", "\"2\"", 1)
+        )
+        .unwrap());
+    }
+}
@@ -0,0 +1,12 @@
+pragma circom 2.0.0;
+
+// Proves knowledge of a and b with a * b = c, c public.
+template Multiplier() {
+    signal input a;
+    signal input b;
+    signal output c;
+
+    c <== a * b;
+}
+
+component main = Multiplier();
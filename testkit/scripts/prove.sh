@@ -0,0 +1,23 @@
+#!/usr/bin/env bash
+# Compiles a circuit from circuits/ and writes its key, proof and public
+# inputs to fixtures/<circuit>/ with snarkjs. Needs circom 2 and snarkjs on
+# PATH, and a powers of tau file large enough for the circuit.
+#
+#   scripts/prove.sh <circuit> <input.json> <pot.ptau>
+set -euo pipefail
+
+circuit="$1"
+input="$2"
+ptau="$3"
+
+root="$(cd "$(dirname "$0")/.." && pwd)"
+build="$(mktemp -d)"
+out="$root/fixtures/$circuit"
+mkdir -p "$out"
+
+circom "$root/circuits/$circuit.circom" --r1cs --wasm -o "$build"
+snarkjs groth16 setup "$build/$circuit.r1cs" "$ptau" "$build/$circuit.zkey"
+snarkjs zkey export verificationkey "$build/$circuit.zkey" "$out/verification_key.json"
+snarkjs wtns calculate "$build/${circuit}_js/$circuit.wasm" "$input" "$build/witness.wtns"
+snarkjs groth16 prove "$build/$circuit.zkey" "$build/witness.wtns" "$out/proof.json" "$out/public.json"
+snarkjs groth16 verify "$out/verification_key.json" "$out/public.json" "$out/proof.json"
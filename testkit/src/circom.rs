@@ -0,0 +1,17 @@
+// Copyright © 2022, Electron Labs
+
+//! A snarkjs Groth16 proof over bn128 with 21 public inputs.
+//!
+//! The files live in `fixtures/bn128` with the names snarkjs gives them.
+
+pub fn vkey() -> &'static str {
+    include_str!("../fixtures/bn128/verification_key.json")
+}
+
+pub fn proof() -> &'static str {
+    include_str!("../fixtures/bn128/proof.json")
+}
+
+pub fn public_inputs() -> &'static str {
+    include_str!("../fixtures/bn128/public.json")
+}
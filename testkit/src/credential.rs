@@ -0,0 +1,107 @@
+// Copyright © 2022, Electron Labs
+
+//! A credential circuit with selective disclosure.
+//!
+//! For every attribute the circuit has two public inputs: a reveal flag,
+//! constrained to be 0 or 1, and the attribute value times the flag. A
+//! hidden attribute therefore shows up as `0, 0`.
+
+use crate::json;
+use ark_ff::Field;
+use ark_relations::lc;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable};
+use ark_std::rand::rngs::StdRng;
+use ark_std::rand::SeedableRng;
+
+pub struct CredentialCircuit<F: Field> {
+    pub values: Vec<Option<F>>,
+    pub reveal: Vec<Option<bool>>,
+}
+
+impl<F: Field> ConstraintSynthesizer<F> for CredentialCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        for (value, reveal) in self.values.into_iter().zip(self.reveal) {
+            let flag_value = reveal.map(|r| if r { F::one() } else { F::zero() });
+            let flag =
+                cs.new_input_variable(|| flag_value.ok_or(SynthesisError::AssignmentMissing))?;
+            let disclosed = cs.new_input_variable(|| {
+                let value = value.ok_or(SynthesisError::AssignmentMissing)?;
+                let flag = flag_value.ok_or(SynthesisError::AssignmentMissing)?;
+                Ok(value * flag)
+            })?;
+            let v = cs.new_witness_variable(|| value.ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce_constraint(lc!() + flag, lc!() + Variable::One - flag, lc!())?;
+            cs.enforce_constraint(lc!() + flag, lc!() + v, lc!() + disclosed)?;
+        }
+        Ok(())
+    }
+}
+
+/// A `CredentialCircuit` setup over bn128 with a deterministic rng.
+pub struct Credential {
+    num_attributes: usize,
+    params: ark_groth16::ProvingKey<ark_bn254::Bn254>,
+    rng: StdRng,
+}
+
+impl Credential {
+    pub fn setup(num_attributes: usize) -> Self {
+        let mut rng = StdRng::seed_from_u64(0);
+        let empty = CredentialCircuit::<ark_bn254::Fr> {
+            values: vec![None; num_attributes],
+            reveal: vec![None; num_attributes],
+        };
+        let params =
+            ark_groth16::generate_random_parameters::<ark_bn254::Bn254, _, _>(empty, &mut rng)
+                .expect("Failed to run the credential setup");
+        Credential {
+            num_attributes,
+            params,
+            rng,
+        }
+    }
+
+    /// The verification key in the snarkjs json layout.
+    pub fn vkey_json(&self) -> String {
+        json::vkey_json(&self.params.vk)
+    }
+
+    /// Proves a credential with the given attribute values, revealing the
+    /// attributes whose flag is set, and returns the proof and public
+    /// inputs json.
+    pub fn prove(&mut self, values: &[u64], reveal: &[bool]) -> (String, String) {
+        assert_eq!(values.len(), self.num_attributes);
+        assert_eq!(reveal.len(), self.num_attributes);
+        let values: Vec<ark_bn254::Fr> = values.iter().map(|v| ark_bn254::Fr::from(*v)).collect();
+        let circuit = CredentialCircuit {
+            values: values.iter().copied().map(Some).collect(),
+            reveal: reveal.iter().copied().map(Some).collect(),
+        };
+        let proof = ark_groth16::create_random_proof(circuit, &self.params, &mut self.rng)
+            .expect("Failed to prove the credential");
+        let inputs: Vec<ark_bn254::Fr> = values
+            .iter()
+            .zip(reveal)
+            .flat_map(|(value, reveal)| {
+                if *reveal {
+                    vec![ark_bn254::Fr::from(1u64), *value]
+                } else {
+                    vec![ark_bn254::Fr::from(0u64), ark_bn254::Fr::from(0u64)]
+                }
+            })
+            .collect();
+        (json::proof_json(&proof), json::inputs_json(&inputs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credential_inputs() {
+        let mut credential = Credential::setup(2);
+        let (_, inputs) = credential.prove(&[30, 7], &[false, true]);
+        assert_eq!(inputs, r#"["0", "0", "1", "7"]"#);
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright © 2022, Electron Labs
+
+//! Rendering arkworks keys and proofs in the snarkjs json layout.
+
+use ark_ec::PairingEngine;
+use ark_ff::{PrimeField, Zero};
+
+/// A verification key in the snarkjs json layout.
+pub fn vkey_json(vk: &ark_groth16::VerifyingKey<ark_bn254::Bn254>) -> String {
+    let alphabeta = ark_bn254::Bn254::pairing(vk.alpha_g1, vk.beta_g2);
+    let ic: Vec<String> = vk.gamma_abc_g1.iter().map(g1_json).collect();
+    format!(
+        r#"{{"protocol": "groth16", "curve": "bn128", "nPublic": {}, "vk_alpha_1": {}, "vk_beta_2": {}, "vk_gamma_2": {}, "vk_delta_2": {}, "vk_alphabeta_12": {}, "IC": [{}]}}"#,
+        ic.len() - 1,
+        g1_json(&vk.alpha_g1),
+        g2_json(&vk.beta_g2),
+        g2_json(&vk.gamma_g2),
+        g2_json(&vk.delta_g2),
+        fq12_json(&alphabeta),
+        ic.join(", ")
+    )
+}
+
+/// A proof in the snarkjs json layout.
+pub fn proof_json(proof: &ark_groth16::Proof<ark_bn254::Bn254>) -> String {
+    format!(
+        r#"{{"pi_a": {}, "pi_b": {}, "pi_c": {}, "protocol": "groth16", "curve": "bn128"}}"#,
+        g1_json(&proof.a),
+        g2_json(&proof.b),
+        g1_json(&proof.c)
+    )
+}
+
+/// Public inputs as a json array of decimal strings.
+pub fn inputs_json(inputs: &[ark_bn254::Fr]) -> String {
+    let inputs: Vec<String> = inputs
+        .iter()
+        .map(|input| format!(r#""{}""#, to_decimal(*input)))
+        .collect();
+    format!("[{}]", inputs.join(", "))
+}
+
+/// Decimal string of a field element, as snarkjs writes it.
+pub fn to_decimal<F: PrimeField>(f: F) -> String {
+    const CHUNK: u128 = 10_000_000_000_000_000_000;
+    let mut limbs = f.into_repr().as_ref().to_vec();
+    let mut chunks = Vec::new();
+    while limbs.iter().any(|limb| *limb != 0) {
+        let mut rem: u128 = 0;
+        for limb in limbs.iter_mut().rev() {
+            let cur = (rem << 64) | *limb as u128;
+            *limb = (cur / CHUNK) as u64;
+            rem = cur % CHUNK;
+        }
+        chunks.push(rem as u64);
+    }
+    match chunks.split_last() {
+        None => "0".to_string(),
+        Some((first, rest)) => rest.iter().rev().fold(first.to_string(), |mut s, chunk| {
+            s.push_str(&format!("{:019}", chunk));
+            s
+        }),
+    }
+}
+
+fn g1_json(p: &ark_bn254::G1Affine) -> String {
+    if p.is_zero() {
+        return r#"["0", "1", "0"]"#.to_string();
+    }
+    format!(r#"["{}", "{}", "1"]"#, to_decimal(p.x), to_decimal(p.y))
+}
+
+fn fq2_json(f: &ark_bn254::Fq2) -> String {
+    format!(r#"["{}", "{}"]"#, to_decimal(f.c0), to_decimal(f.c1))
+}
+
+fn g2_json(p: &ark_bn254::G2Affine) -> String {
+    format!(r#"[{}, {}, ["1", "0"]]"#, fq2_json(&p.x), fq2_json(&p.y))
+}
+
+fn fq12_json(f: &ark_bn254::Fq12) -> String {
+    let fq6 = |f: &ark_bn254::Fq6| {
+        format!(
+            "[{}, {}, {}]",
+            fq2_json(&f.c0),
+            fq2_json(&f.c1),
+            fq2_json(&f.c2)
+        )
+    };
+    format!("[{}, {}]", fq6(&f.c0), fq6(&f.c1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_decimal() {
+        assert_eq!(to_decimal(ark_bn254::Fr::from(0u64)), "0");
+        assert_eq!(
+            to_decimal(-ark_bn254::Fr::from(1u64)),
+            "21888242871839275222246405745257275088548364400416034343698204186575808495616"
+        );
+    }
+
+    #[test]
+    fn test_inputs_json() {
+        let inputs = [ark_bn254::Fr::from(1u64), ark_bn254::Fr::from(15u64)];
+        assert_eq!(inputs_json(&inputs), r#"["1", "15"]"#);
+    }
+}
@@ -0,0 +1,23 @@
+// Copyright © 2022, Electron Labs
+
+//! Fixtures for testing code built on electron-rs.
+//!
+//! - [`circom`] has a real circom circuit with 21 public inputs, its
+//!   verification key and a snarkjs proof, as the json strings the verifier
+//!   takes.
+//! - [`multiplier`] proves `a * b = c` with arkworks and renders keys and
+//!   proofs in the same json layout, for tests that need fresh proofs or
+//!   many of them.
+//! - [`wide`] has any number of public inputs, for benchmarks.
+//! - [`credential`] reveals a chosen subset of its attributes, for
+//!   selective disclosure.
+//!
+//! There are no Poseidon preimage or Merkle membership circuits. Circom
+//! fixtures are generated with `scripts/prove.sh`, and none has been
+//! generated for them.
+
+pub mod circom;
+pub mod credential;
+pub mod json;
+pub mod multiplier;
+pub mod wide;
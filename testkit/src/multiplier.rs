@@ -0,0 +1,101 @@
+// Copyright © 2022, Electron Labs
+
+//! The circuit of `circuits/multiplier.circom`, proven with arkworks.
+//!
+//! ```ignore
+//! let multiplier = Multiplier::setup();
+//! let (proof, inputs) = multiplier.prove(3, 5);
+//! verify_proof(pvk_from(multiplier.vkey_json()), proof, inputs)
+//! ```
+
+use crate::json;
+use ark_ff::Field;
+use ark_relations::lc;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::rand::rngs::StdRng;
+use ark_std::rand::SeedableRng;
+
+/// Proves knowledge of `a` and `b` such that `a * b = c`, with `c` public.
+pub struct MulCircuit<F: Field> {
+    pub a: Option<F>,
+    pub b: Option<F>,
+}
+
+impl<F: Field> ConstraintSynthesizer<F> for MulCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let a = cs.new_witness_variable(|| self.a.ok_or(SynthesisError::AssignmentMissing))?;
+        let b = cs.new_witness_variable(|| self.b.ok_or(SynthesisError::AssignmentMissing))?;
+        let c = cs.new_input_variable(|| {
+            let a = self.a.ok_or(SynthesisError::AssignmentMissing)?;
+            let b = self.b.ok_or(SynthesisError::AssignmentMissing)?;
+            Ok(a * b)
+        })?;
+        cs.enforce_constraint(lc!() + a, lc!() + b, lc!() + c)?;
+        Ok(())
+    }
+}
+
+/// A multiplier setup over bn128 with a deterministic rng, so keys are the
+/// same across runs.
+pub struct Multiplier {
+    params: ark_groth16::ProvingKey<ark_bn254::Bn254>,
+    rng: StdRng,
+}
+
+impl Multiplier {
+    pub fn setup() -> Self {
+        let mut rng = StdRng::seed_from_u64(0);
+        let empty = MulCircuit::<ark_bn254::Fr> { a: None, b: None };
+        let params =
+            ark_groth16::generate_random_parameters::<ark_bn254::Bn254, _, _>(empty, &mut rng)
+                .expect("Failed to run the multiplier setup");
+        Multiplier { params, rng }
+    }
+
+    pub fn verifying_key(&self) -> &ark_groth16::VerifyingKey<ark_bn254::Bn254> {
+        &self.params.vk
+    }
+
+    /// The verification key in the snarkjs json layout.
+    pub fn vkey_json(&self) -> String {
+        json::vkey_json(&self.params.vk)
+    }
+
+    /// Proves `a * b` and returns the proof and public inputs json.
+    pub fn prove(&mut self, a: u64, b: u64) -> (String, String) {
+        self.prove_fr(ark_bn254::Fr::from(a), ark_bn254::Fr::from(b))
+    }
+
+    /// Like `prove`, for arbitrary field elements, e.g. to make the public
+    /// input a given value with `b = 1`.
+    pub fn prove_fr(&mut self, a: ark_bn254::Fr, b: ark_bn254::Fr) -> (String, String) {
+        let circuit = MulCircuit {
+            a: Some(a),
+            b: Some(b),
+        };
+        let proof = ark_groth16::create_random_proof(circuit, &self.params, &mut self.rng)
+            .expect("Failed to prove the multiplier");
+        (json::proof_json(&proof), json::inputs_json(&[a * b]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiplier_is_deterministic() {
+        assert_eq!(
+            Multiplier::setup().vkey_json(),
+            Multiplier::setup().vkey_json()
+        );
+    }
+
+    #[test]
+    fn test_multiplier_prove() {
+        let mut multiplier = Multiplier::setup();
+        let (proof_json, inputs_json) = multiplier.prove(3, 5);
+        assert!(proof_json.contains(r#""protocol": "groth16""#));
+        assert_eq!(inputs_json, r#"["15"]"#);
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright © 2022, Electron Labs
+
+//! A circuit with a configurable number of public inputs, for measuring how
+//! verification scales with the input count.
+
+use crate::json;
+use ark_ff::Field;
+use ark_relations::lc;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::rand::rngs::StdRng;
+use ark_std::rand::SeedableRng;
+
+/// Proves knowledge of the square of every public input. `inputs` sets the
+/// number of public inputs, with `None` values for the setup.
+pub struct WideCircuit<F: Field> {
+    pub inputs: Vec<Option<F>>,
+}
+
+impl<F: Field> ConstraintSynthesizer<F> for WideCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        for input in self.inputs {
+            let x = cs.new_input_variable(|| input.ok_or(SynthesisError::AssignmentMissing))?;
+            let square = cs.new_witness_variable(|| {
+                input
+                    .map(|x| x.square())
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            cs.enforce_constraint(lc!() + x, lc!() + x, lc!() + square)?;
+        }
+        Ok(())
+    }
+}
+
+/// A `WideCircuit` setup over bn128 with a deterministic rng.
+pub struct Wide {
+    num_inputs: usize,
+    params: ark_groth16::ProvingKey<ark_bn254::Bn254>,
+    rng: StdRng,
+}
+
+impl Wide {
+    pub fn setup(num_inputs: usize) -> Self {
+        let mut rng = StdRng::seed_from_u64(0);
+        let empty = WideCircuit::<ark_bn254::Fr> {
+            inputs: vec![None; num_inputs],
+        };
+        let params =
+            ark_groth16::generate_random_parameters::<ark_bn254::Bn254, _, _>(empty, &mut rng)
+                .expect("Failed to run the wide circuit setup");
+        Wide {
+            num_inputs,
+            params,
+            rng,
+        }
+    }
+
+    /// The verification key in the snarkjs json layout.
+    pub fn vkey_json(&self) -> String {
+        json::vkey_json(&self.params.vk)
+    }
+
+    /// Proves the circuit with inputs `1..=num_inputs` and returns the proof
+    /// and public inputs json.
+    pub fn prove(&mut self) -> (String, String) {
+        let inputs: Vec<ark_bn254::Fr> = (1..=self.num_inputs as u64)
+            .map(ark_bn254::Fr::from)
+            .collect();
+        self.prove_inputs(&inputs)
+    }
+
+    /// Like `prove`, with the given public inputs, so the circuit can stand
+    /// in for another circuit with as many inputs.
+    pub fn prove_inputs(&mut self, inputs: &[ark_bn254::Fr]) -> (String, String) {
+        assert_eq!(inputs.len(), self.num_inputs, "Wrong number of inputs");
+        let circuit = WideCircuit {
+            inputs: inputs.iter().copied().map(Some).collect(),
+        };
+        let proof = ark_groth16::create_random_proof(circuit, &self.params, &mut self.rng)
+            .expect("Failed to prove the wide circuit");
+        (json::proof_json(&proof), json::inputs_json(inputs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wide_inputs() {
+        let mut wide = Wide::setup(3);
+        assert!(wide.vkey_json().contains(r#""nPublic": 3"#));
+        let (_, inputs) = wide.prove();
+        assert_eq!(inputs, r#"["1", "2", "3"]"#);
+    }
+}
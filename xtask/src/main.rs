@@ -0,0 +1,236 @@
+// Copyright © 2022, Electron Labs
+
+//! Workspace tasks, run with `cargo xtask <task>`.
+//!
+//! `features` builds and lints electron-rs under every supported
+//! combination of features and targets, and runs the tests where the target
+//! can run them.
+//! Feature-gated backends only break each other in combinations that no
+//! single `cargo test` covers, so CI runs the whole matrix.
+//!
+//! `msrv` checks every native feature set of the matrix, run by CI with the
+//! toolchain of `rust-version`.
+//!
+//! `ffi-header` writes `include/electron_rs.h` from `src/ffi.rs`.
+
+use std::env;
+use std::path::Path;
+use std::process::{exit, Command};
+
+const WASM: &str = "wasm32-unknown-unknown";
+/// The target of the WebAssembly component.
+const WASI: &str = "wasm32-wasip1";
+/// A target without std, to check that the core verifier stays `no_std`.
+const NO_STD: &str = "thumbv7em-none-eabi";
+
+/// One cell of the matrix: the cargo feature flags, the target (native if
+/// `None`) and whether the tests run or the crate is only checked.
+struct Cell {
+    flags: &'static [&'static str],
+    target: Option<&'static str>,
+    test: bool,
+}
+
+const MATRIX: &[Cell] = &[
+    Cell {
+        flags: &[],
+        target: None,
+        test: true,
+    },
+    Cell {
+        flags: &["--features", "reproducible"],
+        target: None,
+        test: true,
+    },
+    Cell {
+        flags: &["--features", "blst"],
+        target: None,
+        test: true,
+    },
+    Cell {
+        flags: &["--features", "codegen"],
+        target: None,
+        test: true,
+    },
+    Cell {
+        flags: &["--features", "solana"],
+        target: None,
+        test: true,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "ink"],
+        target: None,
+        test: true,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "audit-min"],
+        target: None,
+        test: true,
+    },
+    Cell {
+        flags: &["--features", "simd"],
+        target: None,
+        test: true,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "cosmwasm"],
+        target: None,
+        test: true,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "native"],
+        target: None,
+        test: false,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "ffi"],
+        target: None,
+        test: true,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "python"],
+        target: None,
+        test: false,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "component"],
+        target: None,
+        test: true,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "ssz"],
+        target: Some(WASM),
+        test: false,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "cli"],
+        target: None,
+        test: true,
+    },
+    Cell {
+        flags: &["--no-default-features"],
+        target: None,
+        test: false,
+    },
+    Cell {
+        flags: &["--no-default-features"],
+        target: Some(NO_STD),
+        test: false,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "ink"],
+        target: Some(NO_STD),
+        test: false,
+    },
+    Cell {
+        flags: &[],
+        target: Some(WASM),
+        test: false,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "audit-min"],
+        target: Some(WASM),
+        test: false,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "simd"],
+        target: Some(WASM),
+        test: false,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "cosmwasm"],
+        target: Some(WASM),
+        test: false,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "native,wasm-bindgen"],
+        target: Some(WASM),
+        test: false,
+    },
+    Cell {
+        flags: &["--no-default-features", "--features", "component"],
+        target: Some(WASI),
+        test: false,
+    },
+];
+
+/// Runs `subcommand` on the cell, with `extra` after the cargo arguments.
+fn cargo(subcommand: &str, cell: &Cell, extra: &[&str]) -> bool {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let mut cmd = Command::new(cargo);
+    cmd.arg(subcommand)
+        .args(["--package", "electron-rs"])
+        .args(cell.flags);
+    if let Some(target) = cell.target {
+        cmd.args(["--lib", "--target", target]);
+    } else if subcommand != "test" {
+        // `--all-targets` would skip the doc tests.
+        cmd.arg("--all-targets");
+    }
+    cmd.args(extra);
+    println!("==> {:?}", cmd);
+    matches!(cmd.status(), Ok(status) if status.success())
+}
+
+fn run(cell: &Cell) -> bool {
+    let built = cargo(if cell.test { "test" } else { "check" }, cell, &[]);
+    built && cargo("clippy", cell, &["--", "-D", "warnings"])
+}
+
+fn features() {
+    let failed: Vec<String> = MATRIX
+        .iter()
+        .filter(|cell| !run(cell))
+        .map(|cell| {
+            format!(
+                "{} {}",
+                cell.target.unwrap_or("native"),
+                cell.flags.join(" ")
+            )
+        })
+        .collect();
+    report(&failed);
+}
+
+fn report(failed: &[String]) {
+    if !failed.is_empty() {
+        eprintln!("Failed feature combinations:");
+        for cell in failed {
+            eprintln!("  {}", cell);
+        }
+        exit(1);
+    }
+}
+
+/// Features whose dependencies need a newer compiler than `rust-version`.
+const NEWER_THAN_MSRV: &[&str] = &["component"];
+
+fn msrv() {
+    let failed: Vec<String> = MATRIX
+        .iter()
+        .filter(|cell| cell.target.is_none())
+        .filter(|cell| !cell.flags.iter().any(|flag| NEWER_THAN_MSRV.contains(flag)))
+        .filter(|cell| !cargo("check", cell, &[]))
+        .map(|cell| cell.flags.join(" "))
+        .collect();
+    report(&failed);
+}
+
+fn ffi_header() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    cbindgen::generate(crate_dir)
+        .expect("Unable to generate the C header")
+        .write_to_file(crate_dir.join("include/electron_rs.h"));
+}
+
+fn main() {
+    match env::args().nth(1).as_deref() {
+        Some("features") => features(),
+        Some("msrv") => msrv(),
+        Some("ffi-header") => ffi_header(),
+        _ => {
+            eprintln!("Usage: cargo xtask <features|msrv|ffi-header>");
+            exit(2);
+        }
+    }
+}